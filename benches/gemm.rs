@@ -0,0 +1,57 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use linalg::gemm::{self, Transpose};
+use linalg::matrix::Mat;
+
+// The i-j-k loop that `Mul` used before the blocked kernel.
+fn naive<const N: usize>(a: &Mat<f64, N, N>, b: &Mat<f64, N, N>) -> Mat<f64, N, N> {
+    let mut out = Mat::zero();
+    for i in 0..N {
+        for j in 0..N {
+            let mut sum = 0.0;
+            for k in 0..N {
+                sum += a[i][k] * b[k][j];
+            }
+            out[i][j] = sum;
+        }
+    }
+    out
+}
+
+fn bench_size<const N: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gemm");
+    let a = Mat::<f64, N, N>::from_slice(&(0..N * N).map(|i| i as f64).collect::<Vec<_>>());
+    let b = a.transpose();
+    group.bench_with_input(BenchmarkId::new("naive", N), &N, |bench, _| {
+        bench.iter(|| naive(black_box(&a), black_box(&b)))
+    });
+    // the kernel itself, whatever the size; `Mul` only switches to it at
+    // 48^3 multiply-adds, so it runs the naive loop at N = 32
+    group.bench_with_input(BenchmarkId::new("blocked", N), &N, |bench, _| {
+        bench.iter(|| {
+            let mut c = Mat::<f64, N, N>::zero();
+            gemm::gemm(
+                1.0,
+                black_box(&a),
+                Transpose::No,
+                black_box(&b),
+                Transpose::No,
+                0.0,
+                &mut c,
+            );
+            c
+        })
+    });
+    group.bench_with_input(BenchmarkId::new("mul", N), &N, |bench, _| {
+        bench.iter(|| black_box(a) * black_box(b))
+    });
+    group.finish();
+}
+
+fn gemm(c: &mut Criterion) {
+    bench_size::<32>(c);
+    bench_size::<64>(c);
+    bench_size::<128>(c);
+}
+
+criterion_group!(benches, gemm);
+criterion_main!(benches);
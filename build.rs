@@ -0,0 +1,102 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+// Links a BLAS/LAPACK for the `lapack` feature.
+//
+// `LINALG_LAPACK_LIBS` names the libraries to link, separated by commas (e.g.
+// `openblas` or `lapacke,lapack,cblas`), and `LINALG_LAPACK_DIR` adds a
+// directory to search. Setting `LINALG_LAPACK_LIBS` to an empty string links
+// nothing, for builds that provide the library some other way, such as an
+// `openblas-src` dependency of the final binary. Without it, the usual
+// library directories are searched for OpenBLAS or for LAPACKE with a CBLAS.
+//
+// When nothing is linked the crate still builds, and only the tests that
+// call into LAPACK are left out, so `--all-features` works on machines
+// without one.
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(lapack_linked)");
+    println!("cargo:rerun-if-env-changed=LINALG_LAPACK_LIBS");
+    println!("cargo:rerun-if-env-changed=LINALG_LAPACK_DIR");
+    if env::var_os("CARGO_FEATURE_LAPACK").is_none() {
+        return;
+    }
+
+    let mut dirs = Vec::new();
+    if let Some(dir) = env::var_os("LINALG_LAPACK_DIR") {
+        let dir = PathBuf::from(dir);
+        println!("cargo:rustc-link-search=native={}", dir.display());
+        dirs.push(dir);
+    }
+    let libs = match env::var("LINALG_LAPACK_LIBS") {
+        Ok(libs) => libs
+            .split(',')
+            .map(str::trim)
+            .filter(|lib| !lib.is_empty())
+            .map(String::from)
+            .collect(),
+        Err(_) => {
+            dirs.extend(default_dirs());
+            probe(&dirs)
+        }
+    };
+
+    if libs.is_empty() {
+        if env::var_os("LINALG_LAPACK_LIBS").is_none() {
+            println!(
+                "cargo:warning=no BLAS/LAPACK library found for the `lapack` feature; \
+                 set LINALG_LAPACK_LIBS (and LINALG_LAPACK_DIR) to link one"
+            );
+        }
+        return;
+    }
+    for lib in &libs {
+        println!("cargo:rustc-link-lib={}", lib);
+    }
+    println!("cargo:rustc-cfg=lapack_linked");
+}
+
+// OpenBLAS bundles CBLAS and LAPACKE; otherwise both have to be found
+// separately, along with the LAPACK and BLAS they wrap.
+fn probe(dirs: &[PathBuf]) -> Vec<String> {
+    let find = |name: &str| dirs.iter().find(|dir| has_lib(dir, name));
+    let candidates: [&[&str]; 3] = [
+        &["openblas"],
+        &["lapacke", "lapack", "cblas"],
+        &["lapacke", "lapack", "blas"],
+    ];
+    for libs in candidates {
+        let found: Vec<_> = libs.iter().map_while(|lib| find(lib)).collect();
+        if found.len() == libs.len() {
+            for dir in found {
+                println!("cargo:rustc-link-search=native={}", dir.display());
+            }
+            return libs.iter().map(|&lib| lib.into()).collect();
+        }
+    }
+    Vec::new()
+}
+
+fn has_lib(dir: &Path, name: &str) -> bool {
+    ["so", "a", "dylib"]
+        .iter()
+        .any(|ext| dir.join(format!("lib{}.{}", name, ext)).exists())
+}
+
+fn default_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = env::var_os("LIBRARY_PATH")
+        .map(|paths| env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    for dir in [
+        "/usr/lib".into(),
+        "/usr/lib64".into(),
+        "/usr/local/lib".into(),
+        format!("/usr/lib/{}-linux-gnu", arch),
+        "/usr/lib/openblas-base".into(),
+        "/opt/homebrew/opt/openblas/lib".into(),
+        "/usr/local/opt/openblas/lib".into(),
+    ] {
+        dirs.push(dir.into());
+    }
+    dirs
+}
@@ -0,0 +1,739 @@
+#[cfg(feature = "alloc")]
+use super::dmatrix::DMat;
+use super::dual::{DualN, HyperDual};
+use super::mat3a::Mat3A;
+use super::matrix::Mat;
+#[cfg(feature = "alloc")]
+use super::sparse::CscMatrix;
+use super::vector::{vec2::Vec2, vec3::Vec3, vec3a::Vec3A, vec4::Vec4};
+use core::fmt;
+use num::Float;
+
+pub trait AbsDiffEq<Rhs = Self> {
+    type Epsilon: Copy;
+
+    fn default_epsilon() -> Self::Epsilon;
+
+    fn abs_diff_eq(&self, other: &Rhs, epsilon: Self::Epsilon) -> bool;
+
+    fn abs_diff_ne(&self, other: &Rhs, epsilon: Self::Epsilon) -> bool {
+        !self.abs_diff_eq(other, epsilon)
+    }
+}
+
+pub trait RelativeEq<Rhs = Self>: AbsDiffEq<Rhs> {
+    fn default_max_relative() -> Self::Epsilon;
+
+    fn relative_eq(&self, other: &Rhs, epsilon: Self::Epsilon, max_relative: Self::Epsilon)
+        -> bool;
+
+    fn relative_ne(
+        &self,
+        other: &Rhs,
+        epsilon: Self::Epsilon,
+        max_relative: Self::Epsilon,
+    ) -> bool {
+        !self.relative_eq(other, epsilon, max_relative)
+    }
+}
+
+pub trait UlpsEq<Rhs = Self>: AbsDiffEq<Rhs> {
+    fn default_max_ulps() -> u32;
+
+    fn ulps_eq(&self, other: &Rhs, epsilon: Self::Epsilon, max_ulps: u32) -> bool;
+
+    fn ulps_ne(&self, other: &Rhs, epsilon: Self::Epsilon, max_ulps: u32) -> bool {
+        !self.ulps_eq(other, epsilon, max_ulps)
+    }
+}
+
+macro_rules! impl_float {
+    ($t:ty, $bits:ty) => {
+        impl AbsDiffEq for $t {
+            type Epsilon = $t;
+
+            fn default_epsilon() -> $t {
+                <$t>::EPSILON
+            }
+
+            fn abs_diff_eq(&self, other: &$t, epsilon: $t) -> bool {
+                // handles infinities, which would otherwise subtract to NaN
+                self == other || (self - other).abs() <= epsilon
+            }
+        }
+
+        impl RelativeEq for $t {
+            fn default_max_relative() -> $t {
+                <$t>::EPSILON
+            }
+
+            fn relative_eq(&self, other: &$t, epsilon: $t, max_relative: $t) -> bool {
+                if self == other {
+                    return true;
+                }
+                if self.is_infinite() || other.is_infinite() {
+                    return false;
+                }
+                let diff = (self - other).abs();
+                if diff <= epsilon {
+                    return true;
+                }
+                diff <= self.abs().max(other.abs()) * max_relative
+            }
+        }
+
+        impl UlpsEq for $t {
+            fn default_max_ulps() -> u32 {
+                4
+            }
+
+            fn ulps_eq(&self, other: &$t, epsilon: $t, max_ulps: u32) -> bool {
+                if self.abs_diff_eq(other, epsilon) {
+                    return true;
+                }
+                // a NaN's bits are zero ULPs from themselves
+                if self.is_nan() || other.is_nan() {
+                    return false;
+                }
+                if self.is_sign_positive() != other.is_sign_positive() {
+                    return false;
+                }
+                let a = self.to_bits() as $bits;
+                let b = other.to_bits() as $bits;
+                a.abs_diff(b) <= max_ulps as _
+            }
+        }
+
+        impl Entries for $t {
+            type Scalar = $t;
+
+            fn visit_entries(&self, other: &$t, f: &mut dyn FnMut(EntryIndex, $t, $t)) {
+                f(EntryIndex::Scalar, *self, *other)
+            }
+        }
+    };
+}
+
+impl_float!(f32, i32);
+impl_float!(f64, i64);
+
+// Walks corresponding scalar entries of two values, so that a failed
+// comparison can report exactly which entries differ.
+pub trait Entries {
+    type Scalar: Copy;
+
+    fn visit_entries(
+        &self,
+        other: &Self,
+        f: &mut dyn FnMut(EntryIndex, Self::Scalar, Self::Scalar),
+    );
+
+    // The two shapes when they differ, for types whose shape is only known at
+    // run time. Entries are only visited for equal shapes.
+    fn shapes(&self, _other: &Self) -> Option<((usize, usize), (usize, usize))> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryIndex {
+    Scalar,
+    Component(&'static str),
+    Element(usize, usize),
+    Derivative(usize),
+}
+
+impl fmt::Display for EntryIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntryIndex::Scalar => write!(f, "value"),
+            EntryIndex::Component(name) => write!(f, ".{}", name),
+            EntryIndex::Element(i, j) => write!(f, "[({}, {})]", i, j),
+            EntryIndex::Derivative(i) => write!(f, ".eps[{}]", i),
+        }
+    }
+}
+
+impl<T: Float + AbsDiffEq, const R: usize, const C: usize> AbsDiffEq for Mat<T, R, C> {
+    type Epsilon = T::Epsilon;
+
+    fn default_epsilon() -> Self::Epsilon {
+        T::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+        (0..R).all(|i| (0..C).all(|j| self[i][j].abs_diff_eq(&other[i][j], epsilon)))
+    }
+}
+
+impl<T: Float + RelativeEq, const R: usize, const C: usize> RelativeEq for Mat<T, R, C> {
+    fn default_max_relative() -> Self::Epsilon {
+        T::default_max_relative()
+    }
+
+    fn relative_eq(
+        &self,
+        other: &Self,
+        epsilon: Self::Epsilon,
+        max_relative: Self::Epsilon,
+    ) -> bool {
+        (0..R).all(|i| (0..C).all(|j| self[i][j].relative_eq(&other[i][j], epsilon, max_relative)))
+    }
+}
+
+impl<T: Float + UlpsEq, const R: usize, const C: usize> UlpsEq for Mat<T, R, C> {
+    fn default_max_ulps() -> u32 {
+        T::default_max_ulps()
+    }
+
+    fn ulps_eq(&self, other: &Self, epsilon: Self::Epsilon, max_ulps: u32) -> bool {
+        (0..R).all(|i| (0..C).all(|j| self[i][j].ulps_eq(&other[i][j], epsilon, max_ulps)))
+    }
+}
+
+impl<T: Float, const R: usize, const C: usize> Entries for Mat<T, R, C> {
+    type Scalar = T;
+
+    fn visit_entries(&self, other: &Self, f: &mut dyn FnMut(EntryIndex, T, T)) {
+        for i in 0..R {
+            for j in 0..C {
+                f(EntryIndex::Element(i, j), self[i][j], other[i][j]);
+            }
+        }
+    }
+}
+
+macro_rules! impl_vec {
+    ($v:ident, $($c:ident),+) => {
+        impl<T: Float + AbsDiffEq> AbsDiffEq for $v<T> {
+            type Epsilon = T::Epsilon;
+
+            fn default_epsilon() -> Self::Epsilon {
+                T::default_epsilon()
+            }
+
+            fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+                $(self.$c.abs_diff_eq(&other.$c, epsilon))&&+
+            }
+        }
+
+        impl<T: Float + RelativeEq> RelativeEq for $v<T> {
+            fn default_max_relative() -> Self::Epsilon {
+                T::default_max_relative()
+            }
+
+            fn relative_eq(
+                &self,
+                other: &Self,
+                epsilon: Self::Epsilon,
+                max_relative: Self::Epsilon,
+            ) -> bool {
+                $(self.$c.relative_eq(&other.$c, epsilon, max_relative))&&+
+            }
+        }
+
+        impl<T: Float + UlpsEq> UlpsEq for $v<T> {
+            fn default_max_ulps() -> u32 {
+                T::default_max_ulps()
+            }
+
+            fn ulps_eq(&self, other: &Self, epsilon: Self::Epsilon, max_ulps: u32) -> bool {
+                $(self.$c.ulps_eq(&other.$c, epsilon, max_ulps))&&+
+            }
+        }
+
+        impl<T: Float> Entries for $v<T> {
+            type Scalar = T;
+
+            fn visit_entries(&self, other: &Self, f: &mut dyn FnMut(EntryIndex, T, T)) {
+                $(f(EntryIndex::Component(stringify!($c)), self.$c, other.$c);)+
+            }
+        }
+    };
+}
+
+impl_vec!(Vec2, x, y);
+impl_vec!(Vec3, x, y, z);
+impl_vec!(Vec3A, x, y, z);
+impl_vec!(Vec4, x, y, z, w);
+impl_vec!(HyperDual, re, e1, e2, e12);
+
+// The comparisons for the remaining types hold when the shapes agree and the
+// scalar comparison holds for every pair of entries from `Entries`.
+fn all_entries<A: Entries>(a: &A, b: &A, eq: impl Fn(A::Scalar, A::Scalar) -> bool) -> bool {
+    if a.shapes(b).is_some() {
+        return false;
+    }
+    let mut all = true;
+    a.visit_entries(b, &mut |_, l, r| all = all && eq(l, r));
+    all
+}
+
+macro_rules! impl_by_entries {
+    ([$($generics:tt)*] $ty:ty) => {
+        impl<$($generics)*> AbsDiffEq for $ty
+        where
+            T: AbsDiffEq,
+        {
+            type Epsilon = T::Epsilon;
+
+            fn default_epsilon() -> Self::Epsilon {
+                T::default_epsilon()
+            }
+
+            fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+                all_entries(self, other, |l, r| l.abs_diff_eq(&r, epsilon))
+            }
+        }
+
+        impl<$($generics)*> RelativeEq for $ty
+        where
+            T: RelativeEq,
+        {
+            fn default_max_relative() -> Self::Epsilon {
+                T::default_max_relative()
+            }
+
+            fn relative_eq(
+                &self,
+                other: &Self,
+                epsilon: Self::Epsilon,
+                max_relative: Self::Epsilon,
+            ) -> bool {
+                all_entries(self, other, |l, r| l.relative_eq(&r, epsilon, max_relative))
+            }
+        }
+
+        impl<$($generics)*> UlpsEq for $ty
+        where
+            T: UlpsEq,
+        {
+            fn default_max_ulps() -> u32 {
+                T::default_max_ulps()
+            }
+
+            fn ulps_eq(&self, other: &Self, epsilon: Self::Epsilon, max_ulps: u32) -> bool {
+                all_entries(self, other, |l, r| l.ulps_eq(&r, epsilon, max_ulps))
+            }
+        }
+    };
+}
+
+impl_by_entries!([T: Float, const N: usize] DualN<T, N>);
+impl_by_entries!([T: Float] Mat3A<T>);
+#[cfg(feature = "alloc")]
+impl_by_entries!([T: Float] DMat<T>);
+#[cfg(feature = "alloc")]
+impl_by_entries!([T: Float] CscMatrix<T>);
+
+impl<T: Float, const N: usize> Entries for DualN<T, N> {
+    type Scalar = T;
+
+    fn visit_entries(&self, other: &Self, f: &mut dyn FnMut(EntryIndex, T, T)) {
+        f(EntryIndex::Component("re"), self.re, other.re);
+        for i in 0..N {
+            f(EntryIndex::Derivative(i), self.eps[i], other.eps[i]);
+        }
+    }
+}
+
+impl<T: Float> Entries for Mat3A<T> {
+    type Scalar = T;
+
+    fn visit_entries(&self, other: &Self, f: &mut dyn FnMut(EntryIndex, T, T)) {
+        for i in 0..3 {
+            for j in 0..3 {
+                let (l, r) = (self.cols[j].as_array(), other.cols[j].as_array());
+                f(EntryIndex::Element(i, j), l[i], r[i]);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Float> Entries for DMat<T> {
+    type Scalar = T;
+
+    fn visit_entries(&self, other: &Self, f: &mut dyn FnMut(EntryIndex, T, T)) {
+        if self.shape() != other.shape() {
+            return;
+        }
+        let cols = self.ncols();
+        for (k, (&l, &r)) in self.as_slice().iter().zip(other.as_slice()).enumerate() {
+            f(EntryIndex::Element(k / cols, k % cols), l, r);
+        }
+    }
+
+    fn shapes(&self, other: &Self) -> Option<((usize, usize), (usize, usize))> {
+        (self.shape() != other.shape()).then(|| (self.shape(), other.shape()))
+    }
+}
+
+// Entries outside the pattern of one matrix are compared as zeros, so two
+// matrices that only differ in explicitly stored zeros compare equal.
+#[cfg(feature = "alloc")]
+impl<T: Float> Entries for CscMatrix<T> {
+    type Scalar = T;
+
+    fn visit_entries(&self, other: &Self, f: &mut dyn FnMut(EntryIndex, T, T)) {
+        if self.shapes(other).is_some() {
+            return;
+        }
+        for j in 0..self.ncols() {
+            let ((li, lv), (ri, rv)) = (self.col(j), other.col(j));
+            let (mut p, mut q) = (0, 0);
+            while p < li.len() || q < ri.len() {
+                let i = li.get(p).copied().unwrap_or(usize::MAX);
+                let k = ri.get(q).copied().unwrap_or(usize::MAX);
+                let (l, r) = (
+                    if i <= k { lv[p] } else { T::zero() },
+                    if k <= i { rv[q] } else { T::zero() },
+                );
+                f(EntryIndex::Element(i.min(k), j), l, r);
+                p += usize::from(i <= k);
+                q += usize::from(k <= i);
+            }
+        }
+    }
+
+    fn shapes(&self, other: &Self) -> Option<((usize, usize), (usize, usize))> {
+        let shape = |m: &Self| (m.nrows(), m.ncols());
+        (shape(self) != shape(other)).then(|| (shape(self), shape(other)))
+    }
+}
+
+// Comparison settings used by the `*_eq!` macros, e.g.
+// `Relative::default().epsilon(1e-12).max_relative(1e-9).eq(&a, &b)`.
+
+pub struct AbsDiff<A: AbsDiffEq> {
+    pub epsilon: A::Epsilon,
+}
+
+impl<A: AbsDiffEq> Default for AbsDiff<A> {
+    fn default() -> Self {
+        Self {
+            epsilon: A::default_epsilon(),
+        }
+    }
+}
+
+impl<A: AbsDiffEq> AbsDiff<A> {
+    pub fn epsilon(self, epsilon: A::Epsilon) -> Self {
+        Self { epsilon }
+    }
+
+    pub fn eq(&self, a: &A, b: &A) -> bool {
+        a.abs_diff_eq(b, self.epsilon)
+    }
+
+    pub fn ne(&self, a: &A, b: &A) -> bool {
+        !self.eq(a, b)
+    }
+}
+
+pub struct Relative<A: RelativeEq> {
+    pub epsilon: A::Epsilon,
+    pub max_relative: A::Epsilon,
+}
+
+impl<A: RelativeEq> Default for Relative<A> {
+    fn default() -> Self {
+        Self {
+            epsilon: A::default_epsilon(),
+            max_relative: A::default_max_relative(),
+        }
+    }
+}
+
+impl<A: RelativeEq> Relative<A> {
+    pub fn epsilon(self, epsilon: A::Epsilon) -> Self {
+        Self { epsilon, ..self }
+    }
+
+    pub fn max_relative(self, max_relative: A::Epsilon) -> Self {
+        Self {
+            max_relative,
+            ..self
+        }
+    }
+
+    pub fn eq(&self, a: &A, b: &A) -> bool {
+        a.relative_eq(b, self.epsilon, self.max_relative)
+    }
+
+    pub fn ne(&self, a: &A, b: &A) -> bool {
+        !self.eq(a, b)
+    }
+}
+
+pub struct Ulps<A: UlpsEq> {
+    pub epsilon: A::Epsilon,
+    pub max_ulps: u32,
+}
+
+impl<A: UlpsEq> Default for Ulps<A> {
+    fn default() -> Self {
+        Self {
+            epsilon: A::default_epsilon(),
+            max_ulps: A::default_max_ulps(),
+        }
+    }
+}
+
+impl<A: UlpsEq> Ulps<A> {
+    pub fn epsilon(self, epsilon: A::Epsilon) -> Self {
+        Self { epsilon, ..self }
+    }
+
+    pub fn max_ulps(self, max_ulps: u32) -> Self {
+        Self { max_ulps, ..self }
+    }
+
+    pub fn eq(&self, a: &A, b: &A) -> bool {
+        a.ulps_eq(b, self.epsilon, self.max_ulps)
+    }
+
+    pub fn ne(&self, a: &A, b: &A) -> bool {
+        !self.eq(a, b)
+    }
+}
+
+// Lists the entries for which `eq` fails, one per line. Used in the panic
+// messages of the `assert_*_eq!` macros.
+pub struct Mismatches<'a, A: Entries, F> {
+    left: &'a A,
+    right: &'a A,
+    eq: F,
+}
+
+impl<'a, A: Entries, F: Fn(&A::Scalar, &A::Scalar) -> bool> Mismatches<'a, A, F> {
+    pub fn new(left: &'a A, right: &'a A, eq: F) -> Self {
+        Self { left, right, eq }
+    }
+}
+
+impl<A, F> fmt::Display for Mismatches<'_, A, F>
+where
+    A: Entries,
+    A::Scalar: fmt::Debug + Float,
+    F: Fn(&A::Scalar, &A::Scalar) -> bool,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(((lr, lc), (rr, rc))) = self.left.shapes(self.right) {
+            return writeln!(f, "    shape: {}x{} != {}x{}", lr, lc, rr, rc);
+        }
+        let mut result = Ok(());
+        self.left.visit_entries(self.right, &mut |index, l, r| {
+            if result.is_ok() && !(self.eq)(&l, &r) {
+                result = writeln!(
+                    f,
+                    "    {}: {:?} != {:?} (diff {:?})",
+                    index,
+                    l,
+                    r,
+                    (l - r).abs()
+                );
+            }
+        });
+        result
+    }
+}
+
+#[macro_export]
+macro_rules! abs_diff_eq {
+    ($left:expr, $right:expr $(, $opt:ident = $val:expr)* $(,)?) => {
+        $crate::approx::AbsDiff::default()$(.$opt($val))*.eq(&$left, &$right)
+    };
+}
+
+#[macro_export]
+macro_rules! relative_eq {
+    ($left:expr, $right:expr $(, $opt:ident = $val:expr)* $(,)?) => {
+        $crate::approx::Relative::default()$(.$opt($val))*.eq(&$left, &$right)
+    };
+}
+
+#[macro_export]
+macro_rules! ulps_eq {
+    ($left:expr, $right:expr $(, $opt:ident = $val:expr)* $(,)?) => {
+        $crate::approx::Ulps::default()$(.$opt($val))*.eq(&$left, &$right)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_approx {
+    ($cmp:ident, $name:literal, $left:expr, $right:expr $(, $opt:ident = $val:expr)*) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let cmp = $crate::approx::$cmp::default()$(.$opt($val))*;
+                if !cmp.eq(left, right) {
+                    panic!(
+                        "assertion failed: `{}!(left, right{})`\n  left: {:?}\n right: {:?}\nmismatched entries:\n{}",
+                        $name,
+                        concat!($(", ", stringify!($opt), " = ", stringify!($val)),*),
+                        left,
+                        right,
+                        $crate::approx::Mismatches::new(left, right, |l, r| {
+                            $crate::approx::$cmp::default()$(.$opt($val))*.eq(l, r)
+                        }),
+                    );
+                }
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! assert_abs_diff_eq {
+    ($left:expr, $right:expr $(, $opt:ident = $val:expr)* $(,)?) => {
+        $crate::__assert_approx!(AbsDiff, "abs_diff_eq", $left, $right $(, $opt = $val)*)
+    };
+}
+
+#[macro_export]
+macro_rules! assert_relative_eq {
+    ($left:expr, $right:expr $(, $opt:ident = $val:expr)* $(,)?) => {
+        $crate::__assert_approx!(Relative, "relative_eq", $left, $right $(, $opt = $val)*)
+    };
+}
+
+#[macro_export]
+macro_rules! assert_ulps_eq {
+    ($left:expr, $right:expr $(, $opt:ident = $val:expr)* $(,)?) => {
+        $crate::__assert_approx!(Ulps, "ulps_eq", $left, $right $(, $opt = $val)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_abs_diff() {
+        assert!(1.0f64.abs_diff_eq(&1.05, 0.1));
+        assert!(!1.0f64.abs_diff_eq(&1.2, 0.1));
+        assert!(f64::INFINITY.abs_diff_eq(&f64::INFINITY, 0.0));
+        assert!(!f64::NAN.abs_diff_eq(&f64::NAN, 1.0));
+    }
+
+    #[test]
+    fn float_relative() {
+        assert!(1e10f64.relative_eq(&(1e10 + 1.0), 0.0, 1e-9));
+        assert!(!1e-10f64.relative_eq(&2e-10, 0.0, 1e-9));
+        assert!(1e-10f64.relative_eq(&2e-10, 1e-9, 0.0));
+    }
+
+    #[test]
+    fn float_ulps() {
+        let a = 1.0f32;
+        let b = f32::from_bits(a.to_bits() + 3);
+        assert!(a.ulps_eq(&b, 0.0, 4));
+        assert!(!a.ulps_eq(&b, 0.0, 2));
+        assert!(!1.0f64.ulps_eq(&-1.0, 0.0, u32::MAX));
+    }
+
+    #[test]
+    fn float_ulps_nan() {
+        assert!(!f64::NAN.ulps_eq(&f64::NAN, 0.0, 4));
+        assert!(!f32::NAN.ulps_eq(&f32::NAN, f32::INFINITY, u32::MAX));
+        assert!(!f64::NAN.ulps_eq(&1.0, 0.0, u32::MAX));
+        assert!(!ulps_eq!(
+            Vec2::new(1.0, f64::NAN),
+            Vec2::new(1.0, f64::NAN)
+        ));
+    }
+
+    #[test]
+    fn rotation_roundoff() {
+        let (s, c) = std::f64::consts::FRAC_PI_6.sin_cos();
+        let rot = Mat::new(&[[c, -s], [s, c]]);
+        let rot3 = rot * rot * rot;
+        assert_ne!(rot3, Mat::new(&[[0.0, -1.0], [1.0, 0.0]]));
+        assert_relative_eq!(rot3, Mat::new(&[[0.0, -1.0], [1.0, 0.0]]), epsilon = 1e-15);
+        assert_ulps_eq!(
+            rot3 * Vec2::new(1.0, 0.0),
+            Mat::new(&[[0.0], [1.0]]),
+            epsilon = 1e-15
+        );
+    }
+
+    #[test]
+    fn vectors() {
+        assert_abs_diff_eq!(Vec2::new(1.0, 2.0), Vec2::new(1.001, 1.999), epsilon = 0.01);
+        assert!(relative_eq!(
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(1.0, 2.0, 3.0)
+        ));
+        assert!(!ulps_eq!(
+            Vec4::new(1.0, 2.0, 3.0, 4.0),
+            Vec4::new(1.0, 2.0, 3.0, 4.1)
+        ));
+    }
+
+    #[test]
+    fn mismatches() {
+        let a = Mat::new(&[[1.0, 2.0], [3.0, 4.0]]);
+        let b = Mat::new(&[[1.0, 2.5], [3.0, 4.0]]);
+        let diff = Mismatches::new(&a, &b, |l, r| l.abs_diff_eq(r, 0.1)).to_string();
+        assert_eq!(diff, "    [(0, 1)]: 2.0 != 2.5 (diff 0.5)\n");
+
+        let diff =
+            Mismatches::new(&Vec2::new(0.0, 1.0), &Vec2::new(0.5, 1.0), |l, r| l == r).to_string();
+        assert_eq!(diff, "    .x: 0.0 != 0.5 (diff 0.5)\n");
+    }
+
+    #[test]
+    fn dual_and_padded() {
+        use crate::dual::Dual;
+
+        let x = Dual::new(2.0, [1.0]);
+        assert_abs_diff_eq!(x, Dual::new(2.0, [1.0 + 1e-12]), epsilon = 1e-9);
+        assert!(!relative_eq!(x, Dual::constant(2.0)));
+        let diff = Mismatches::new(&x, &Dual::constant(2.0), |l, r| l == r).to_string();
+        assert_eq!(diff, "    .eps[0]: 1.0 != 0.0 (diff 1.0)\n");
+
+        let m = Mat3A::from(Mat::new(&[
+            [1.0, 2.0, 3.0],
+            [4.0, 5.0, 6.0],
+            [7.0, 8.0, 9.0],
+        ]));
+        let mut n = m;
+        n.cols[2].x += 1e-3;
+        assert_abs_diff_eq!(m, n, epsilon = 1e-2);
+        let diff = Mismatches::new(&m, &n, |l, r| l == r).to_string();
+        assert!(diff.starts_with("    [(0, 2)]: 3.0 != 3.001"));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn dynamic_and_sparse() {
+        let a = DMat::from_vec(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        let b = DMat::from_vec(2, 2, vec![1.0, 2.0, 3.0, 4.0 + 1e-15]);
+        assert_ulps_eq!(a, b);
+        assert!(!abs_diff_eq!(a, a.transpose()));
+        let wide = DMat::from_vec(1, 4, vec![1.0, 2.0, 3.0, 4.0]);
+        assert!(!abs_diff_eq!(a, wide, epsilon = 1.0));
+        let diff = Mismatches::new(&a, &wide, |l, r| l == r).to_string();
+        assert_eq!(diff, "    shape: 2x2 != 1x4\n");
+
+        // an explicitly stored zero matches a missing entry
+        let s = CscMatrix::new(2, 2, vec![0, 2, 3], vec![0, 1, 1], vec![1.0, 0.0, 2.0]);
+        let t = CscMatrix::from_triplets(2, 2, &[(0, 0, 1.0), (1, 1, 2.0 + 1e-12)]);
+        assert_relative_eq!(s, t, epsilon = 1e-9);
+        let u = CscMatrix::from_triplets(2, 2, &[(0, 0, 1.0), (0, 1, 0.5), (1, 1, 2.0)]);
+        let diff = Mismatches::new(&s, &u, |l, r| l == r).to_string();
+        assert_eq!(diff, "    [(0, 1)]: 0.0 != 0.5 (diff 0.5)\n");
+        assert!(!abs_diff_eq!(s, CscMatrix::zero(2, 3), epsilon = 10.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "[(1, 0)]: 3.0 != 3.1")]
+    fn assert_reports_entry() {
+        assert_relative_eq!(
+            Mat::new(&[[1.0, 2.0], [3.0, 4.0]]),
+            Mat::new(&[[1.0, 2.0], [3.1, 4.0]]),
+            max_relative = 1e-3,
+        );
+    }
+}
@@ -0,0 +1,526 @@
+use super::{Backend, Diag, Side, Transpose, Uplo};
+use crate::error::LinalgError;
+use core::ffi::{c_char, c_int};
+
+// Forwards to a vendor BLAS/LAPACK through the CBLAS and LAPACKE C
+// interfaces, which take row-major matrices directly. The build script links
+// OpenBLAS or LAPACKE and CBLAS when it finds them, or the libraries named in
+// `LINALG_LAPACK_LIBS`; see build.rs. LAPACKE is assumed to use 32-bit
+// integers (the LP64 interface).
+//
+// The slice lengths are checked before each call, since the C routines would
+// otherwise read or write out of bounds.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lapack;
+
+const ROW_MAJOR: c_int = 101;
+const NO_TRANS: c_int = 111;
+const TRANS: c_int = 112;
+const UPPER: c_int = 121;
+const LOWER: c_int = 122;
+const NON_UNIT: c_int = 131;
+const UNIT: c_int = 132;
+const LEFT: c_int = 141;
+const RIGHT: c_int = 142;
+
+#[allow(clippy::too_many_arguments)]
+extern "C" {
+    fn cblas_sgemm(
+        layout: c_int,
+        ta: c_int,
+        tb: c_int,
+        m: c_int,
+        n: c_int,
+        k: c_int,
+        alpha: f32,
+        a: *const f32,
+        lda: c_int,
+        b: *const f32,
+        ldb: c_int,
+        beta: f32,
+        c: *mut f32,
+        ldc: c_int,
+    );
+    fn cblas_dgemm(
+        layout: c_int,
+        ta: c_int,
+        tb: c_int,
+        m: c_int,
+        n: c_int,
+        k: c_int,
+        alpha: f64,
+        a: *const f64,
+        lda: c_int,
+        b: *const f64,
+        ldb: c_int,
+        beta: f64,
+        c: *mut f64,
+        ldc: c_int,
+    );
+    fn cblas_sgemv(
+        layout: c_int,
+        ta: c_int,
+        m: c_int,
+        n: c_int,
+        alpha: f32,
+        a: *const f32,
+        lda: c_int,
+        x: *const f32,
+        incx: c_int,
+        beta: f32,
+        y: *mut f32,
+        incy: c_int,
+    );
+    fn cblas_dgemv(
+        layout: c_int,
+        ta: c_int,
+        m: c_int,
+        n: c_int,
+        alpha: f64,
+        a: *const f64,
+        lda: c_int,
+        x: *const f64,
+        incx: c_int,
+        beta: f64,
+        y: *mut f64,
+        incy: c_int,
+    );
+    fn cblas_strsm(
+        layout: c_int,
+        side: c_int,
+        uplo: c_int,
+        ta: c_int,
+        diag: c_int,
+        m: c_int,
+        n: c_int,
+        alpha: f32,
+        a: *const f32,
+        lda: c_int,
+        b: *mut f32,
+        ldb: c_int,
+    );
+    fn cblas_dtrsm(
+        layout: c_int,
+        side: c_int,
+        uplo: c_int,
+        ta: c_int,
+        diag: c_int,
+        m: c_int,
+        n: c_int,
+        alpha: f64,
+        a: *const f64,
+        lda: c_int,
+        b: *mut f64,
+        ldb: c_int,
+    );
+
+    fn LAPACKE_sgetrf(
+        layout: c_int,
+        m: c_int,
+        n: c_int,
+        a: *mut f32,
+        lda: c_int,
+        ipiv: *mut c_int,
+    ) -> c_int;
+    fn LAPACKE_dgetrf(
+        layout: c_int,
+        m: c_int,
+        n: c_int,
+        a: *mut f64,
+        lda: c_int,
+        ipiv: *mut c_int,
+    ) -> c_int;
+    fn LAPACKE_spotrf(layout: c_int, uplo: c_char, n: c_int, a: *mut f32, lda: c_int) -> c_int;
+    fn LAPACKE_dpotrf(layout: c_int, uplo: c_char, n: c_int, a: *mut f64, lda: c_int) -> c_int;
+    fn LAPACKE_sgeqrf(
+        layout: c_int,
+        m: c_int,
+        n: c_int,
+        a: *mut f32,
+        lda: c_int,
+        tau: *mut f32,
+    ) -> c_int;
+    fn LAPACKE_dgeqrf(
+        layout: c_int,
+        m: c_int,
+        n: c_int,
+        a: *mut f64,
+        lda: c_int,
+        tau: *mut f64,
+    ) -> c_int;
+    fn LAPACKE_ssyevd(
+        layout: c_int,
+        jobz: c_char,
+        uplo: c_char,
+        n: c_int,
+        a: *mut f32,
+        lda: c_int,
+        w: *mut f32,
+    ) -> c_int;
+    fn LAPACKE_dsyevd(
+        layout: c_int,
+        jobz: c_char,
+        uplo: c_char,
+        n: c_int,
+        a: *mut f64,
+        lda: c_int,
+        w: *mut f64,
+    ) -> c_int;
+    fn LAPACKE_sgesdd(
+        layout: c_int,
+        jobz: c_char,
+        m: c_int,
+        n: c_int,
+        a: *mut f32,
+        lda: c_int,
+        s: *mut f32,
+        u: *mut f32,
+        ldu: c_int,
+        vt: *mut f32,
+        ldvt: c_int,
+    ) -> c_int;
+    fn LAPACKE_dgesdd(
+        layout: c_int,
+        jobz: c_char,
+        m: c_int,
+        n: c_int,
+        a: *mut f64,
+        lda: c_int,
+        s: *mut f64,
+        u: *mut f64,
+        ldu: c_int,
+        vt: *mut f64,
+        ldvt: c_int,
+    ) -> c_int;
+}
+
+fn int(n: usize) -> c_int {
+    c_int::try_from(n).expect("dimension does not fit in a LAPACK integer")
+}
+
+// Checks that a rows x cols matrix with leading dimension `ld` fits in a
+// slice of length `len`, and returns `ld` for LAPACK, which requires it to be
+// at least 1.
+fn check(name: &str, len: usize, rows: usize, cols: usize, ld: usize) -> c_int {
+    assert!(
+        ld >= cols.max(1),
+        "{}: leading dimension {} < {}",
+        name,
+        ld,
+        cols
+    );
+    if rows > 0 && cols > 0 {
+        assert!(
+            len >= (rows - 1) * ld + cols,
+            "{}: slice of length {} is too short for a {}x{} matrix",
+            name,
+            len,
+            rows,
+            cols
+        );
+    }
+    int(ld)
+}
+
+fn trans(t: Transpose) -> c_int {
+    match t {
+        Transpose::No => NO_TRANS,
+        Transpose::Yes => TRANS,
+    }
+}
+
+fn dims(t: Transpose, rows: usize, cols: usize) -> (usize, usize) {
+    match t {
+        Transpose::No => (rows, cols),
+        Transpose::Yes => (cols, rows),
+    }
+}
+
+fn uplo_char(uplo: Uplo) -> c_char {
+    match uplo {
+        Uplo::Lower => b'L' as c_char,
+        Uplo::Upper => b'U' as c_char,
+    }
+}
+
+// A positive `info` is reported as `err`; a negative one means an argument
+// was rejected, which the checks above should have prevented. LAPACK does not
+// report iteration counts, so `NoConvergence` has 0 iterations.
+fn info(name: &str, info: c_int, err: LinalgError) -> Result<(), LinalgError> {
+    match info {
+        0 => Ok(()),
+        i if i > 0 => Err(err),
+        i => panic!("{}: argument {} is invalid", name, -i),
+    }
+}
+
+const NO_CONVERGENCE: LinalgError = LinalgError::NoConvergence { iterations: 0 };
+
+macro_rules! impl_lapack {
+    ($t:ty, $gemm:ident, $gemv:ident, $trsm:ident, $getrf:ident, $potrf:ident, $geqrf:ident, $syevd:ident, $gesdd:ident) => {
+        #[allow(clippy::too_many_arguments)]
+        impl Backend<$t> for Lapack {
+            fn gemm(
+                &self,
+                ta: Transpose,
+                tb: Transpose,
+                m: usize,
+                n: usize,
+                k: usize,
+                alpha: $t,
+                a: &[$t],
+                lda: usize,
+                b: &[$t],
+                ldb: usize,
+                beta: $t,
+                c: &mut [$t],
+                ldc: usize,
+            ) {
+                let (ar, ac) = dims(ta, m, k);
+                let (br, bc) = dims(tb, k, n);
+                let lda = check("gemm", a.len(), ar, ac, lda);
+                let ldb = check("gemm", b.len(), br, bc, ldb);
+                let ldc = check("gemm", c.len(), m, n, ldc);
+                // SAFETY: the slices hold the matrices, as checked above
+                unsafe {
+                    $gemm(
+                        ROW_MAJOR,
+                        trans(ta),
+                        trans(tb),
+                        int(m),
+                        int(n),
+                        int(k),
+                        alpha,
+                        a.as_ptr(),
+                        lda,
+                        b.as_ptr(),
+                        ldb,
+                        beta,
+                        c.as_mut_ptr(),
+                        ldc,
+                    )
+                }
+            }
+
+            fn gemv(
+                &self,
+                ta: Transpose,
+                m: usize,
+                n: usize,
+                alpha: $t,
+                a: &[$t],
+                lda: usize,
+                x: &[$t],
+                beta: $t,
+                y: &mut [$t],
+            ) {
+                let lda = check("gemv", a.len(), m, n, lda);
+                let (rows, cols) = dims(ta, m, n);
+                assert!(x.len() >= cols && y.len() >= rows, "gemv: vector is too short");
+                // SAFETY: the slices hold the matrix and vectors, as checked
+                // above
+                unsafe {
+                    $gemv(
+                        ROW_MAJOR,
+                        trans(ta),
+                        int(m),
+                        int(n),
+                        alpha,
+                        a.as_ptr(),
+                        lda,
+                        x.as_ptr(),
+                        1,
+                        beta,
+                        y.as_mut_ptr(),
+                        1,
+                    )
+                }
+            }
+
+            fn trsm(
+                &self,
+                side: Side,
+                uplo: Uplo,
+                ta: Transpose,
+                diag: Diag,
+                m: usize,
+                n: usize,
+                alpha: $t,
+                a: &[$t],
+                lda: usize,
+                b: &mut [$t],
+                ldb: usize,
+            ) {
+                let order = match side {
+                    Side::Left => m,
+                    Side::Right => n,
+                };
+                let lda = check("trsm", a.len(), order, order, lda);
+                let ldb = check("trsm", b.len(), m, n, ldb);
+                // SAFETY: the slices hold the matrices, as checked above
+                unsafe {
+                    $trsm(
+                        ROW_MAJOR,
+                        match side {
+                            Side::Left => LEFT,
+                            Side::Right => RIGHT,
+                        },
+                        match uplo {
+                            Uplo::Lower => LOWER,
+                            Uplo::Upper => UPPER,
+                        },
+                        trans(ta),
+                        match diag {
+                            Diag::NonUnit => NON_UNIT,
+                            Diag::Unit => UNIT,
+                        },
+                        int(m),
+                        int(n),
+                        alpha,
+                        a.as_ptr(),
+                        lda,
+                        b.as_mut_ptr(),
+                        ldb,
+                    )
+                }
+            }
+
+            fn getrf(
+                &self,
+                m: usize,
+                n: usize,
+                a: &mut [$t],
+                lda: usize,
+                ipiv: &mut [usize],
+            ) -> Result<(), LinalgError> {
+                let lda = check("getrf", a.len(), m, n, lda);
+                let k = m.min(n);
+                assert!(ipiv.len() >= k, "getrf: ipiv is too short");
+                let mut pivots = alloc::vec![0; k];
+                // SAFETY: `a` holds the matrix, as checked above, and
+                // `pivots` has room for min(m, n) pivots
+                let i = unsafe {
+                    $getrf(ROW_MAJOR, int(m), int(n), a.as_mut_ptr(), lda, pivots.as_mut_ptr())
+                };
+                // an invalid argument leaves the pivots unset, so check first;
+                // a singular matrix is still fully factored
+                let result = info("getrf", i, LinalgError::Singular);
+                // LAPACK pivots are 1-based
+                for (p, &q) in ipiv.iter_mut().zip(&pivots) {
+                    *p = q as usize - 1;
+                }
+                result
+            }
+
+            fn potrf(
+                &self,
+                uplo: Uplo,
+                n: usize,
+                a: &mut [$t],
+                lda: usize,
+            ) -> Result<(), LinalgError> {
+                let lda = check("potrf", a.len(), n, n, lda);
+                // SAFETY: `a` holds the matrix, as checked above
+                let i = unsafe { $potrf(ROW_MAJOR, uplo_char(uplo), int(n), a.as_mut_ptr(), lda) };
+                info("potrf", i, LinalgError::NotPositiveDefinite)
+            }
+
+            fn geqrf(&self, m: usize, n: usize, a: &mut [$t], lda: usize, tau: &mut [$t]) {
+                let lda = check("geqrf", a.len(), m, n, lda);
+                assert!(tau.len() >= m.min(n), "geqrf: tau is too short");
+                // SAFETY: `a` holds the matrix, as checked above, and `tau`
+                // has room for min(m, n) scalars
+                let i = unsafe {
+                    $geqrf(ROW_MAJOR, int(m), int(n), a.as_mut_ptr(), lda, tau.as_mut_ptr())
+                };
+                info("geqrf", i, NO_CONVERGENCE).unwrap();
+            }
+
+            fn syevd(
+                &self,
+                uplo: Uplo,
+                n: usize,
+                a: &mut [$t],
+                lda: usize,
+                w: &mut [$t],
+            ) -> Result<(), LinalgError> {
+                let lda = check("syevd", a.len(), n, n, lda);
+                assert!(w.len() >= n, "syevd: w is too short");
+                // SAFETY: `a` holds the matrix, as checked above, and `w` has
+                // room for n eigenvalues
+                let i = unsafe {
+                    $syevd(
+                        ROW_MAJOR,
+                        b'V' as c_char,
+                        uplo_char(uplo),
+                        int(n),
+                        a.as_mut_ptr(),
+                        lda,
+                        w.as_mut_ptr(),
+                    )
+                };
+                info("syevd", i, NO_CONVERGENCE)
+            }
+
+            fn gesdd(
+                &self,
+                m: usize,
+                n: usize,
+                a: &mut [$t],
+                lda: usize,
+                s: &mut [$t],
+                u: &mut [$t],
+                ldu: usize,
+                vt: &mut [$t],
+                ldvt: usize,
+            ) -> Result<(), LinalgError> {
+                let k = m.min(n);
+                let lda = check("gesdd", a.len(), m, n, lda);
+                let ldu = check("gesdd", u.len(), m, k, ldu);
+                let ldvt = check("gesdd", vt.len(), k, n, ldvt);
+                assert!(s.len() >= k, "gesdd: s is too short");
+                // SAFETY: the slices hold the matrices and singular values,
+                // as checked above
+                let i = unsafe {
+                    $gesdd(
+                        ROW_MAJOR,
+                        b'S' as c_char,
+                        int(m),
+                        int(n),
+                        a.as_mut_ptr(),
+                        lda,
+                        s.as_mut_ptr(),
+                        u.as_mut_ptr(),
+                        ldu,
+                        vt.as_mut_ptr(),
+                        ldvt,
+                    )
+                };
+                info("gesdd", i, NO_CONVERGENCE)
+            }
+        }
+    };
+}
+
+impl_lapack!(
+    f32,
+    cblas_sgemm,
+    cblas_sgemv,
+    cblas_strsm,
+    LAPACKE_sgetrf,
+    LAPACKE_spotrf,
+    LAPACKE_sgeqrf,
+    LAPACKE_ssyevd,
+    LAPACKE_sgesdd
+);
+impl_lapack!(
+    f64,
+    cblas_dgemm,
+    cblas_dgemv,
+    cblas_dtrsm,
+    LAPACKE_dgetrf,
+    LAPACKE_dpotrf,
+    LAPACKE_dgeqrf,
+    LAPACKE_dsyevd,
+    LAPACKE_dgesdd
+);
@@ -0,0 +1,925 @@
+#[cfg(feature = "alloc")]
+use super::dmatrix::DMat;
+use super::error::LinalgError;
+use super::matrix::Mat;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+use num::Float;
+
+#[cfg(feature = "lapack")]
+pub mod lapack;
+pub mod native;
+
+#[cfg(feature = "lapack")]
+pub use lapack::Lapack;
+pub use native::Native;
+
+// Dense kernels behind a common interface, so that code written against
+// `Backend` can run on the crate's own pure-Rust kernels (`Native`) or on a
+// vendor BLAS/LAPACK (`Lapack`, behind the `lapack` feature).
+//
+// The routines follow their BLAS/LAPACK namesakes, except that matrices are
+// row-major: element (i, j) of a matrix with leading dimension `ld` is at
+// `i * ld + j`, and pivot indices are 0-based. Invalid dimensions or slices
+// that are too short are programming errors and panic; the `Err` cases are the
+// ones LAPACK reports with a positive `info`.
+//
+// The trait works on slices, so that it serves `Mat` and `DMat` alike. The
+// factorizations of `DMat` and the solvers in `lstsq`, `equations` and
+// `nonlinear` take the backend to use through their `_with` variants.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transpose {
+    No,
+    Yes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Uplo {
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diag {
+    NonUnit,
+    Unit,
+}
+
+// A row-major matrix stored in a slice with a row stride, read through an
+// optional transpose.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct View<'a, T> {
+    pub data: &'a [T],
+    pub stride: usize,
+    pub trans: Transpose,
+}
+
+impl<T: Float> View<'_, T> {
+    pub fn at(&self, i: usize, j: usize) -> T {
+        match self.trans {
+            Transpose::No => self.data[i * self.stride + j],
+            Transpose::Yes => self.data[j * self.stride + i],
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub trait Backend<T: Float> {
+    // C = alpha op(A) op(B) + beta C, where C is m x n and op(A) is m x k. C
+    // is not read when beta is zero.
+    fn gemm(
+        &self,
+        ta: Transpose,
+        tb: Transpose,
+        m: usize,
+        n: usize,
+        k: usize,
+        alpha: T,
+        a: &[T],
+        lda: usize,
+        b: &[T],
+        ldb: usize,
+        beta: T,
+        c: &mut [T],
+        ldc: usize,
+    );
+
+    // y = alpha op(A) x + beta y, where A is m x n. y is not read when beta
+    // is zero.
+    fn gemv(
+        &self,
+        ta: Transpose,
+        m: usize,
+        n: usize,
+        alpha: T,
+        a: &[T],
+        lda: usize,
+        x: &[T],
+        beta: T,
+        y: &mut [T],
+    );
+
+    // Solves op(A) X = alpha B (`Side::Left`) or X op(A) = alpha B
+    // (`Side::Right`) for the m x n matrix X, overwriting B. Only the `uplo`
+    // triangle of A is read, and its diagonal is taken as ones for
+    // `Diag::Unit`.
+    fn trsm(
+        &self,
+        side: Side,
+        uplo: Uplo,
+        ta: Transpose,
+        diag: Diag,
+        m: usize,
+        n: usize,
+        alpha: T,
+        a: &[T],
+        lda: usize,
+        b: &mut [T],
+        ldb: usize,
+    );
+
+    // LU factorization with partial pivoting of the m x n matrix A. L (with a
+    // unit diagonal) and U overwrite A, and row i was swapped with row
+    // `ipiv[i]` for i < min(m, n). `Singular` means U has a zero on its
+    // diagonal; the factorization is still completed.
+    fn getrf(
+        &self,
+        m: usize,
+        n: usize,
+        a: &mut [T],
+        lda: usize,
+        ipiv: &mut [usize],
+    ) -> Result<(), LinalgError>;
+
+    // Cholesky factorization A = L L^T (`Uplo::Lower`) or A = U^T U
+    // (`Uplo::Upper`), overwriting that triangle of A. The other triangle is
+    // neither read nor written.
+    fn potrf(&self, uplo: Uplo, n: usize, a: &mut [T], lda: usize) -> Result<(), LinalgError>;
+
+    // QR factorization of the m x n matrix A. R overwrites the upper
+    // triangle, and Q = H(0) H(1) ... H(k - 1) with k = min(m, n) is stored as
+    // Householder reflectors H(i) = I - tau[i] v v^T, where v[i] = 1 and the
+    // rest of v is below the diagonal in column i.
+    fn geqrf(&self, m: usize, n: usize, a: &mut [T], lda: usize, tau: &mut [T]);
+
+    // Eigenvalues (in ascending order, into `w`) and eigenvectors (into the
+    // columns of A) of the symmetric matrix whose `uplo` triangle is in A.
+    fn syevd(
+        &self,
+        uplo: Uplo,
+        n: usize,
+        a: &mut [T],
+        lda: usize,
+        w: &mut [T],
+    ) -> Result<(), LinalgError>;
+
+    // Thin singular value decomposition A = U diag(s) V^T of the m x n
+    // matrix A, with k = min(m, n) singular values in descending order, U
+    // m x k and V^T k x n. A is overwritten.
+    fn gesdd(
+        &self,
+        m: usize,
+        n: usize,
+        a: &mut [T],
+        lda: usize,
+        s: &mut [T],
+        u: &mut [T],
+        ldu: usize,
+        vt: &mut [T],
+        ldvt: usize,
+    ) -> Result<(), LinalgError>;
+}
+
+// Solves A X = B through `getrf`.
+pub(crate) fn lu_solve<T: Float, const N: usize, const K: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, N, N>,
+    b: &Mat<T, N, K>,
+) -> Result<Mat<T, N, K>, LinalgError> {
+    let (mut lu, mut x) = (*a, *b);
+    lu_solve_in_place(
+        backend,
+        N,
+        K,
+        lu.as_mut_slice(),
+        &mut [0; N],
+        x.as_mut_slice(),
+    )?;
+    Ok(x)
+}
+
+// `lu_solve` for the dynamic solvers. The shapes have to match.
+#[cfg(feature = "alloc")]
+pub(crate) fn lu_solve_dynamic<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+) -> Result<DMat<T>, LinalgError> {
+    let (n, k) = b.shape();
+    assert_eq!(a.shape(), (n, n));
+    let (mut lu, mut x) = (a.clone(), b.clone());
+    let mut ipiv = vec![0; n];
+    lu_solve_in_place(
+        backend,
+        n,
+        k,
+        lu.as_mut_slice(),
+        &mut ipiv,
+        x.as_mut_slice(),
+    )?;
+    Ok(x)
+}
+
+// Factors the n x n `lu` in place and overwrites the n x k `x` with the
+// solution.
+pub(crate) fn lu_solve_in_place<T: Float>(
+    backend: &impl Backend<T>,
+    n: usize,
+    k: usize,
+    lu: &mut [T],
+    ipiv: &mut [usize],
+    x: &mut [T],
+) -> Result<(), LinalgError> {
+    backend.getrf(n, n, lu, n, ipiv)?;
+    for (i, &p) in ipiv.iter().enumerate() {
+        for j in 0..k {
+            x.swap(i * k + j, p * k + j);
+        }
+    }
+    for (uplo, diag) in [(Uplo::Lower, Diag::Unit), (Uplo::Upper, Diag::NonUnit)] {
+        backend.trsm(
+            Side::Left,
+            uplo,
+            Transpose::No,
+            diag,
+            n,
+            k,
+            T::one(),
+            lu,
+            n,
+            x,
+            k,
+        );
+    }
+    Ok(())
+}
+
+// Solves A X = B for symmetric positive definite A through `potrf`.
+pub(crate) fn cholesky_solve<T: Float, const N: usize, const K: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, N, N>,
+    b: &Mat<T, N, K>,
+) -> Result<Mat<T, N, K>, LinalgError> {
+    let (mut l, mut x) = (*a, *b);
+    cholesky_solve_in_place(backend, N, K, l.as_mut_slice(), x.as_mut_slice())?;
+    Ok(x)
+}
+
+// `cholesky_solve` for the dynamic solvers. The shapes have to match.
+#[cfg(feature = "alloc")]
+pub(crate) fn cholesky_solve_dynamic<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+) -> Result<DMat<T>, LinalgError> {
+    let (n, k) = b.shape();
+    assert_eq!(a.shape(), (n, n));
+    let (mut l, mut x) = (a.clone(), b.clone());
+    cholesky_solve_in_place(backend, n, k, l.as_mut_slice(), x.as_mut_slice())?;
+    Ok(x)
+}
+
+fn cholesky_solve_in_place<T: Float>(
+    backend: &impl Backend<T>,
+    n: usize,
+    k: usize,
+    l: &mut [T],
+    x: &mut [T],
+) -> Result<(), LinalgError> {
+    backend.potrf(Uplo::Lower, n, l, n)?;
+    for ta in [Transpose::No, Transpose::Yes] {
+        backend.trsm(
+            Side::Left,
+            Uplo::Lower,
+            ta,
+            Diag::NonUnit,
+            n,
+            k,
+            T::one(),
+            l,
+            n,
+            x,
+            k,
+        );
+    }
+    Ok(())
+}
+
+// A backend that counts the calls made to it and passes them on to `Native`,
+// for checking that code dispatches through the backend it was given.
+#[cfg(test)]
+pub(crate) mod test_util {
+    use super::*;
+    use core::cell::Cell;
+    use std::rc::Rc;
+
+    #[derive(Debug, Clone, Default)]
+    pub struct Counting(Rc<Cell<usize>>);
+
+    impl Counting {
+        pub fn calls(&self) -> usize {
+            self.0.get()
+        }
+
+        fn count(&self) -> Native {
+            self.0.set(self.0.get() + 1);
+            Native
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    impl<T: Float> Backend<T> for Counting {
+        fn gemm(
+            &self,
+            ta: Transpose,
+            tb: Transpose,
+            m: usize,
+            n: usize,
+            k: usize,
+            alpha: T,
+            a: &[T],
+            lda: usize,
+            b: &[T],
+            ldb: usize,
+            beta: T,
+            c: &mut [T],
+            ldc: usize,
+        ) {
+            self.count()
+                .gemm(ta, tb, m, n, k, alpha, a, lda, b, ldb, beta, c, ldc)
+        }
+
+        fn gemv(
+            &self,
+            ta: Transpose,
+            m: usize,
+            n: usize,
+            alpha: T,
+            a: &[T],
+            lda: usize,
+            x: &[T],
+            beta: T,
+            y: &mut [T],
+        ) {
+            self.count().gemv(ta, m, n, alpha, a, lda, x, beta, y)
+        }
+
+        fn trsm(
+            &self,
+            side: Side,
+            uplo: Uplo,
+            ta: Transpose,
+            diag: Diag,
+            m: usize,
+            n: usize,
+            alpha: T,
+            a: &[T],
+            lda: usize,
+            b: &mut [T],
+            ldb: usize,
+        ) {
+            self.count()
+                .trsm(side, uplo, ta, diag, m, n, alpha, a, lda, b, ldb)
+        }
+
+        fn getrf(
+            &self,
+            m: usize,
+            n: usize,
+            a: &mut [T],
+            lda: usize,
+            ipiv: &mut [usize],
+        ) -> Result<(), LinalgError> {
+            self.count().getrf(m, n, a, lda, ipiv)
+        }
+
+        fn potrf(&self, uplo: Uplo, n: usize, a: &mut [T], lda: usize) -> Result<(), LinalgError> {
+            self.count().potrf(uplo, n, a, lda)
+        }
+
+        fn geqrf(&self, m: usize, n: usize, a: &mut [T], lda: usize, tau: &mut [T]) {
+            self.count().geqrf(m, n, a, lda, tau)
+        }
+
+        fn syevd(
+            &self,
+            uplo: Uplo,
+            n: usize,
+            a: &mut [T],
+            lda: usize,
+            w: &mut [T],
+        ) -> Result<(), LinalgError> {
+            self.count().syevd(uplo, n, a, lda, w)
+        }
+
+        fn gesdd(
+            &self,
+            m: usize,
+            n: usize,
+            a: &mut [T],
+            lda: usize,
+            s: &mut [T],
+            u: &mut [T],
+            ldu: usize,
+            vt: &mut [T],
+            ldvt: usize,
+        ) -> Result<(), LinalgError> {
+            self.count().gesdd(m, n, a, lda, s, u, ldu, vt, ldvt)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec;
+    use std::vec::Vec;
+
+    // Every backend is run against the same cases, so that swapping one for
+    // another is safe.
+    macro_rules! backend_tests {
+        ($name:ident, $backend:expr) => {
+            mod $name {
+                use super::*;
+
+                #[test]
+                fn gemm() {
+                    check_gemm(&$backend);
+                }
+
+                #[test]
+                fn gemv() {
+                    check_gemv(&$backend);
+                }
+
+                #[test]
+                fn trsm() {
+                    check_trsm(&$backend);
+                }
+
+                #[test]
+                fn getrf() {
+                    check_getrf(&$backend);
+                }
+
+                #[test]
+                fn potrf() {
+                    check_potrf(&$backend);
+                }
+
+                #[test]
+                fn geqrf() {
+                    check_geqrf(&$backend);
+                }
+
+                #[test]
+                fn syevd() {
+                    check_syevd(&$backend);
+                }
+
+                #[test]
+                fn gesdd() {
+                    check_gesdd(&$backend);
+                }
+            }
+        };
+    }
+
+    backend_tests!(native, Native);
+    // only when the build script found a library to link
+    #[cfg(all(feature = "lapack", lapack_linked))]
+    backend_tests!(lapack, Lapack);
+
+    const TOL: f64 = 1e-12;
+
+    fn values(seed: u64, n: usize) -> Vec<f64> {
+        let mut state = seed;
+        (0..n)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect()
+    }
+
+    fn assert_close(a: &[f64], b: &[f64], tol: f64) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() <= tol, "{:?} != {:?}", a, b);
+        }
+    }
+
+    // The dense m x n product of two row-major matrices.
+    fn mul(m: usize, n: usize, k: usize, a: &[f64], b: &[f64]) -> Vec<f64> {
+        let mut c = vec![0.0; m * n];
+        for i in 0..m {
+            for j in 0..n {
+                c[i * n + j] = (0..k).map(|p| a[i * k + p] * b[p * n + j]).sum();
+            }
+        }
+        c
+    }
+
+    fn transpose(m: usize, n: usize, a: &[f64]) -> Vec<f64> {
+        let mut t = vec![0.0; m * n];
+        for i in 0..m {
+            for j in 0..n {
+                t[j * m + i] = a[i * n + j];
+            }
+        }
+        t
+    }
+
+    fn identity(n: usize) -> Vec<f64> {
+        let mut a = vec![0.0; n * n];
+        for i in 0..n {
+            a[i * n + i] = 1.0;
+        }
+        a
+    }
+
+    // Copies an m x n matrix into storage with leading dimension `ld`,
+    // filling the padding with NaN so that reading it is noticed.
+    fn padded(m: usize, n: usize, a: &[f64], ld: usize) -> Vec<f64> {
+        let mut out = vec![f64::NAN; m * ld];
+        for i in 0..m {
+            out[i * ld..i * ld + n].copy_from_slice(&a[i * n..i * n + n]);
+        }
+        out
+    }
+
+    fn unpadded(m: usize, n: usize, a: &[f64], ld: usize) -> Vec<f64> {
+        (0..m)
+            .flat_map(|i| a[i * ld..i * ld + n].to_vec())
+            .collect()
+    }
+
+    fn op(t: Transpose, m: usize, n: usize, a: &[f64]) -> Vec<f64> {
+        match t {
+            Transpose::No => a.to_vec(),
+            Transpose::Yes => transpose(m, n, a),
+        }
+    }
+
+    fn check_gemm(b: &impl Backend<f64>) {
+        let (m, n, k) = (7, 5, 6);
+        let x = values(1, m * k);
+        let y = values(2, k * n);
+        let c0 = values(3, m * n);
+        let expected: Vec<f64> = mul(m, n, k, &x, &y)
+            .iter()
+            .zip(&c0)
+            .map(|(p, c)| 2.0 * p - 0.5 * c)
+            .collect();
+
+        for ta in [Transpose::No, Transpose::Yes] {
+            for tb in [Transpose::No, Transpose::Yes] {
+                // store op(A) and op(B) so that the product is still X Y
+                let (ar, ac) = if ta == Transpose::No { (m, k) } else { (k, m) };
+                let (br, bc) = if tb == Transpose::No { (k, n) } else { (n, k) };
+                let a = padded(ar, ac, &op(ta, m, k, &x), ac + 2);
+                let bb = padded(br, bc, &op(tb, k, n, &y), bc + 1);
+                let mut c = padded(m, n, &c0, n + 3);
+                b.gemm(
+                    ta,
+                    tb,
+                    m,
+                    n,
+                    k,
+                    2.0,
+                    &a,
+                    ac + 2,
+                    &bb,
+                    bc + 1,
+                    -0.5,
+                    &mut c,
+                    n + 3,
+                );
+                assert_close(&unpadded(m, n, &c, n + 3), &expected, TOL);
+            }
+        }
+
+        // C is not read when beta is zero
+        let mut c = vec![f64::NAN; m * n];
+        b.gemm(
+            Transpose::No,
+            Transpose::No,
+            m,
+            n,
+            k,
+            1.0,
+            &x,
+            k,
+            &y,
+            n,
+            0.0,
+            &mut c,
+            n,
+        );
+        assert_close(&c, &mul(m, n, k, &x, &y), TOL);
+    }
+
+    fn check_gemv(b: &impl Backend<f64>) {
+        let (m, n) = (6, 4);
+        let a = values(4, m * n);
+        for ta in [Transpose::No, Transpose::Yes] {
+            let (rows, cols) = if ta == Transpose::No { (m, n) } else { (n, m) };
+            let x = values(5, cols);
+            let y0 = values(6, rows);
+            let expected: Vec<f64> = mul(rows, 1, cols, &op(ta, m, n, &a), &x)
+                .iter()
+                .zip(&y0)
+                .map(|(p, y)| 3.0 * p + 2.0 * y)
+                .collect();
+            let mut y = y0.clone();
+            b.gemv(
+                ta,
+                m,
+                n,
+                3.0,
+                &padded(m, n, &a, n + 1),
+                n + 1,
+                &x,
+                2.0,
+                &mut y,
+            );
+            assert_close(&y, &expected, TOL);
+
+            let mut y = vec![f64::NAN; rows];
+            b.gemv(ta, m, n, 3.0, &a, n, &x, 0.0, &mut y);
+            assert_close(
+                &y,
+                &expected
+                    .iter()
+                    .zip(&y0)
+                    .map(|(e, y)| e - 2.0 * y)
+                    .collect::<Vec<_>>(),
+                TOL,
+            );
+        }
+    }
+
+    fn check_trsm(b: &impl Backend<f64>) {
+        let (m, n) = (5, 3);
+        for side in [Side::Left, Side::Right] {
+            let order = if side == Side::Left { m } else { n };
+            for uplo in [Uplo::Lower, Uplo::Upper] {
+                for ta in [Transpose::No, Transpose::Yes] {
+                    for diag in [Diag::NonUnit, Diag::Unit] {
+                        // the unused triangle is NaN, and so is the diagonal
+                        // when it is taken as ones
+                        let mut a = values(7, order * order);
+                        let mut dense = a.clone();
+                        for i in 0..order {
+                            for j in 0..order {
+                                let lower = uplo == Uplo::Lower;
+                                if (lower && j > i) || (!lower && j < i) {
+                                    a[i * order + j] = f64::NAN;
+                                    dense[i * order + j] = 0.0;
+                                }
+                            }
+                            a[i * order + i] = 2.0 + i as f64;
+                            dense[i * order + i] = 2.0 + i as f64;
+                            if diag == Diag::Unit {
+                                a[i * order + i] = f64::NAN;
+                                dense[i * order + i] = 1.0;
+                            }
+                        }
+                        let opa = op(ta, order, order, &dense);
+
+                        let x = values(8, m * n);
+                        let mut rhs = match side {
+                            Side::Left => mul(m, n, m, &opa, &x),
+                            Side::Right => mul(m, n, n, &x, &opa),
+                        };
+                        for v in &mut rhs {
+                            *v /= 2.0;
+                        }
+                        let mut bb = padded(m, n, &rhs, n + 2);
+                        b.trsm(side, uplo, ta, diag, m, n, 2.0, &a, order, &mut bb, n + 2);
+                        assert_close(&unpadded(m, n, &bb, n + 2), &x, 1e-10);
+                    }
+                }
+            }
+        }
+    }
+
+    // Applies the row swaps from `getrf` to the m x n matrix `a`.
+    fn permute(m: usize, n: usize, a: &[f64], ipiv: &[usize]) -> Vec<f64> {
+        let mut a = a.to_vec();
+        for (i, &p) in ipiv.iter().enumerate() {
+            assert!(p >= i && p < m);
+            for j in 0..n {
+                a.swap(i * n + j, p * n + j);
+            }
+        }
+        a
+    }
+
+    fn check_getrf(b: &impl Backend<f64>) {
+        for (m, n) in [(6, 6), (7, 4), (4, 7)] {
+            let a0 = values(9, m * n);
+            let k = m.min(n);
+            let mut a = padded(m, n, &a0, n + 1);
+            let mut ipiv = vec![0; k];
+            b.getrf(m, n, &mut a, n + 1, &mut ipiv).unwrap();
+            let lu = unpadded(m, n, &a, n + 1);
+
+            let mut l = vec![0.0; m * k];
+            let mut u = vec![0.0; k * n];
+            for i in 0..m {
+                for j in 0..n {
+                    let v = lu[i * n + j];
+                    if j < i && j < k {
+                        l[i * k + j] = v;
+                        // partial pivoting bounds the multipliers
+                        assert!(v.abs() <= 1.0);
+                    } else if i < k {
+                        u[i * n + j] = v;
+                    }
+                }
+                if i < k {
+                    l[i * k + i] = 1.0;
+                }
+            }
+            assert_close(&mul(m, n, k, &l, &u), &permute(m, n, &a0, &ipiv), TOL);
+        }
+
+        // the second column is a multiple of the first
+        let mut a = vec![1.0, 2.0, 0.0, 2.0, 4.0, 1.0, 3.0, 6.0, 5.0];
+        let mut ipiv = [0; 3];
+        assert_eq!(
+            b.getrf(3, 3, &mut a, 3, &mut ipiv),
+            Err(LinalgError::Singular)
+        );
+    }
+
+    fn spd(n: usize) -> Vec<f64> {
+        let x = values(10, n * n);
+        let mut a = mul(n, n, n, &x, &transpose(n, n, &x));
+        for i in 0..n {
+            a[i * n + i] += 1.0;
+        }
+        a
+    }
+
+    fn check_potrf(b: &impl Backend<f64>) {
+        let n = 6;
+        let a0 = spd(n);
+        for uplo in [Uplo::Lower, Uplo::Upper] {
+            let mut a = a0.clone();
+            for i in 0..n {
+                for j in 0..n {
+                    if (uplo == Uplo::Lower && j > i) || (uplo == Uplo::Upper && j < i) {
+                        a[i * n + j] = f64::NAN;
+                    }
+                }
+            }
+            b.potrf(uplo, n, &mut a, n).unwrap();
+            for v in &mut a {
+                if v.is_nan() {
+                    *v = 0.0;
+                }
+            }
+            let product = match uplo {
+                Uplo::Lower => mul(n, n, n, &a, &transpose(n, n, &a)),
+                Uplo::Upper => mul(n, n, n, &transpose(n, n, &a), &a),
+            };
+            assert_close(&product, &a0, TOL);
+        }
+
+        let mut a = vec![1.0, 2.0, 2.0, 1.0];
+        assert_eq!(
+            b.potrf(Uplo::Lower, 2, &mut a, 2),
+            Err(LinalgError::NotPositiveDefinite)
+        );
+    }
+
+    fn check_geqrf(b: &impl Backend<f64>) {
+        for (m, n) in [(7, 4), (4, 6), (5, 5)] {
+            let a0 = values(11, m * n);
+            let k = m.min(n);
+            let mut a = padded(m, n, &a0, n + 1);
+            let mut tau = vec![0.0; k];
+            b.geqrf(m, n, &mut a, n + 1, &mut tau);
+            let qr = unpadded(m, n, &a, n + 1);
+
+            // Q = H(0) ... H(k - 1), applied to the identity from the right
+            let mut q = identity(m);
+            for i in 0..k {
+                let mut v = vec![0.0; m];
+                v[i] = 1.0;
+                for r in i + 1..m {
+                    v[r] = qr[r * n + i];
+                }
+                for row in 0..m {
+                    let dot: f64 = (0..m).map(|c| q[row * m + c] * v[c]).sum();
+                    for c in 0..m {
+                        q[row * m + c] -= tau[i] * dot * v[c];
+                    }
+                }
+            }
+            assert_close(&mul(m, m, m, &transpose(m, m, &q), &q), &identity(m), TOL);
+
+            let mut r = vec![0.0; m * n];
+            for i in 0..k {
+                for j in i..n {
+                    r[i * n + j] = qr[i * n + j];
+                }
+            }
+            assert_close(&mul(m, n, m, &q, &r), &a0, TOL);
+        }
+    }
+
+    fn check_syevd(b: &impl Backend<f64>) {
+        let n = 6;
+        let x = values(12, n * n);
+        let a0: Vec<f64> = (0..n * n).map(|i| x[i] + x[(i % n) * n + i / n]).collect();
+        for uplo in [Uplo::Lower, Uplo::Upper] {
+            let mut a = a0.clone();
+            for i in 0..n {
+                for j in 0..n {
+                    if (uplo == Uplo::Lower && j > i) || (uplo == Uplo::Upper && j < i) {
+                        a[i * n + j] = f64::NAN;
+                    }
+                }
+            }
+            let mut w = vec![0.0; n];
+            b.syevd(uplo, n, &mut a, n, &mut w).unwrap();
+            assert!(w.windows(2).all(|p| p[0] <= p[1]));
+            assert_close(&mul(n, n, n, &transpose(n, n, &a), &a), &identity(n), TOL);
+
+            let mut scaled = a.clone();
+            for i in 0..n {
+                for j in 0..n {
+                    scaled[i * n + j] *= w[j];
+                }
+            }
+            assert_close(&mul(n, n, n, &a0, &a), &scaled, TOL);
+        }
+
+        // eigenvalues of a diagonal matrix come back sorted
+        let mut a = vec![3.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 2.0];
+        let mut w = [0.0; 3];
+        b.syevd(Uplo::Lower, 3, &mut a, 3, &mut w).unwrap();
+        assert_eq!(w, [-1.0, 2.0, 3.0]);
+
+        // a repeated eigenvalue, whose eigenvectors are only fixed up to a
+        // rotation within its eigenspace
+        let a0 = [2.0, 1.0, 1.0, 1.0, 2.0, 1.0, 1.0, 1.0, 2.0];
+        let mut a = a0.to_vec();
+        b.syevd(Uplo::Upper, 3, &mut a, 3, &mut w).unwrap();
+        assert_close(&w, &[1.0, 1.0, 4.0], TOL);
+        assert_close(&mul(3, 3, 3, &transpose(3, 3, &a), &a), &identity(3), TOL);
+        let mut scaled = a.clone();
+        for i in 0..3 {
+            for j in 0..3 {
+                scaled[i * 3 + j] *= w[j];
+            }
+        }
+        assert_close(&mul(3, 3, 3, &a0, &a), &scaled, TOL);
+
+        let mut a = vec![0.0; 4];
+        b.syevd(Uplo::Lower, 2, &mut a, 2, &mut w[..2]).unwrap();
+        assert_eq!(a, identity(2));
+    }
+
+    fn check_svd(b: &impl Backend<f64>, m: usize, n: usize, a0: &[f64]) {
+        let k = m.min(n);
+        let mut a = padded(m, n, a0, n + 1);
+        let mut s = vec![0.0; k];
+        let mut u = vec![0.0; m * k];
+        let mut vt = vec![0.0; k * n];
+        b.gesdd(m, n, &mut a, n + 1, &mut s, &mut u, k, &mut vt, n)
+            .unwrap();
+
+        assert!(s.windows(2).all(|p| p[0] >= p[1]));
+        assert!(s.iter().all(|&x| x >= 0.0));
+        assert_close(&mul(k, k, m, &transpose(m, k, &u), &u), &identity(k), TOL);
+        assert_close(&mul(k, k, n, &vt, &transpose(k, n, &vt)), &identity(k), TOL);
+
+        let mut us = u.clone();
+        for i in 0..m {
+            for j in 0..k {
+                us[i * k + j] *= s[j];
+            }
+        }
+        assert_close(&mul(m, n, k, &us, &vt), a0, TOL);
+    }
+
+    fn check_gesdd(b: &impl Backend<f64>) {
+        check_svd(b, 7, 4, &values(13, 28));
+        check_svd(b, 3, 5, &values(14, 15));
+
+        // rank 2
+        let x = values(15, 8);
+        let y = values(16, 10);
+        check_svd(b, 4, 5, &mul(4, 5, 2, &x, &y));
+
+        let mut a = vec![0.0, 3.0, 4.0, 0.0];
+        let mut s = [0.0; 2];
+        let (mut u, mut vt) = ([0.0; 4], [0.0; 4]);
+        b.gesdd(2, 2, &mut a, 2, &mut s, &mut u, 2, &mut vt, 2)
+            .unwrap();
+        assert_close(&s, &[4.0, 3.0], TOL);
+    }
+}
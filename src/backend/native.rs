@@ -0,0 +1,615 @@
+use super::{Backend, Diag, Side, Transpose, Uplo, View};
+use crate::error::LinalgError;
+#[cfg(feature = "alloc")]
+use crate::gemm;
+use num::Float;
+
+// The crate's own kernels. GEMM is the blocked kernel from `gemm`, or a plain
+// loop without `alloc`; the factorizations are unblocked. The symmetric
+// eigensolver and the SVD use Jacobi rotations, which are slower than
+// LAPACK's divide and conquer but simple and accurate. Everything works in
+// the slices it is given, so none of the kernels allocate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Native;
+
+// Jacobi sweeps before giving up. Cyclic Jacobi converges quadratically, so
+// this is only reached for matrices with NaN or infinite entries.
+const MAX_SWEEPS: usize = 64;
+
+#[allow(clippy::too_many_arguments)]
+impl<T: Float> Backend<T> for Native {
+    fn gemm(
+        &self,
+        ta: Transpose,
+        tb: Transpose,
+        m: usize,
+        n: usize,
+        k: usize,
+        alpha: T,
+        a: &[T],
+        lda: usize,
+        b: &[T],
+        ldb: usize,
+        beta: T,
+        c: &mut [T],
+        ldc: usize,
+    ) {
+        let a = View {
+            data: a,
+            stride: lda,
+            trans: ta,
+        };
+        let b = View {
+            data: b,
+            stride: ldb,
+            trans: tb,
+        };
+        #[cfg(feature = "alloc")]
+        gemm::gemm_strided(m, n, k, alpha, a, b, beta, c, ldc);
+        // the blocked kernel packs its operands into allocated panels
+        #[cfg(not(feature = "alloc"))]
+        for i in 0..m {
+            for j in 0..n {
+                let mut sum = T::zero();
+                for p in 0..k {
+                    sum = sum + a.at(i, p) * b.at(p, j);
+                }
+                let c = &mut c[i * ldc + j];
+                *c = if beta == T::zero() {
+                    alpha * sum
+                } else {
+                    alpha * sum + beta * *c
+                };
+            }
+        }
+    }
+
+    #[allow(clippy::needless_range_loop)]
+    fn gemv(
+        &self,
+        ta: Transpose,
+        m: usize,
+        n: usize,
+        alpha: T,
+        a: &[T],
+        lda: usize,
+        x: &[T],
+        beta: T,
+        y: &mut [T],
+    ) {
+        let a = View {
+            data: a,
+            stride: lda,
+            trans: ta,
+        };
+        let (rows, cols) = match ta {
+            Transpose::No => (m, n),
+            Transpose::Yes => (n, m),
+        };
+        for i in 0..rows {
+            let mut sum = T::zero();
+            for j in 0..cols {
+                sum = sum + a.at(i, j) * x[j];
+            }
+            y[i] = if beta == T::zero() {
+                alpha * sum
+            } else {
+                alpha * sum + beta * y[i]
+            };
+        }
+    }
+
+    fn trsm(
+        &self,
+        side: Side,
+        uplo: Uplo,
+        ta: Transpose,
+        diag: Diag,
+        m: usize,
+        n: usize,
+        alpha: T,
+        a: &[T],
+        lda: usize,
+        b: &mut [T],
+        ldb: usize,
+    ) {
+        let a = View {
+            data: a,
+            stride: lda,
+            trans: ta,
+        };
+        // whether op(A) is lower triangular
+        let lower = (uplo == Uplo::Lower) == (ta == Transpose::No);
+        let unit = diag == Diag::Unit;
+
+        for i in 0..m {
+            for x in &mut b[i * ldb..i * ldb + n] {
+                *x = alpha * *x;
+            }
+        }
+        match side {
+            // each column of B is solved on its own
+            Side::Left => {
+                for j in 0..n {
+                    tri_solve(m, lower, unit, |i, k| a.at(i, k), &mut b[j..], ldb);
+                }
+            }
+            // x op(A) = b is op(A)^T x^T = b^T for each row of B
+            Side::Right => {
+                for i in 0..m {
+                    tri_solve(n, !lower, unit, |r, k| a.at(k, r), &mut b[i * ldb..], 1);
+                }
+            }
+        }
+    }
+
+    fn getrf(
+        &self,
+        m: usize,
+        n: usize,
+        a: &mut [T],
+        lda: usize,
+        ipiv: &mut [usize],
+    ) -> Result<(), LinalgError> {
+        let mut singular = false;
+        for k in 0..m.min(n) {
+            // the first entry of largest magnitude, as in LAPACK
+            let mut p = k;
+            for i in k + 1..m {
+                if a[i * lda + k].abs() > a[p * lda + k].abs() {
+                    p = i;
+                }
+            }
+            ipiv[k] = p;
+            let pivot = a[p * lda + k];
+            if pivot == T::zero() {
+                singular = true;
+                continue;
+            }
+            if p != k {
+                for j in 0..n {
+                    a.swap(k * lda + j, p * lda + j);
+                }
+            }
+            for i in k + 1..m {
+                let l = a[i * lda + k] / pivot;
+                a[i * lda + k] = l;
+                for j in k + 1..n {
+                    a[i * lda + j] = a[i * lda + j] - l * a[k * lda + j];
+                }
+            }
+        }
+        if singular {
+            Err(LinalgError::Singular)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn potrf(&self, uplo: Uplo, n: usize, a: &mut [T], lda: usize) -> Result<(), LinalgError> {
+        // works on L, which for `Uplo::Upper` is the transpose of U
+        let at = |i: usize, j: usize| match uplo {
+            Uplo::Lower => i * lda + j,
+            Uplo::Upper => j * lda + i,
+        };
+        for j in 0..n {
+            let mut d = a[at(j, j)];
+            for k in 0..j {
+                d = d - a[at(j, k)] * a[at(j, k)];
+            }
+            if d <= T::zero() || d.is_nan() {
+                return Err(LinalgError::NotPositiveDefinite);
+            }
+            let d = d.sqrt();
+            a[at(j, j)] = d;
+            for i in j + 1..n {
+                let mut sum = a[at(i, j)];
+                for k in 0..j {
+                    sum = sum - a[at(i, k)] * a[at(j, k)];
+                }
+                a[at(i, j)] = sum / d;
+            }
+        }
+        Ok(())
+    }
+
+    fn geqrf(&self, m: usize, n: usize, a: &mut [T], lda: usize, tau: &mut [T]) {
+        for j in 0..m.min(n) {
+            let alpha = a[j * lda + j];
+            let mut norm = T::zero();
+            for i in j + 1..m {
+                norm = norm.hypot(a[i * lda + j]);
+            }
+            if norm == T::zero() {
+                // already upper triangular in this column, so H(j) = I
+                tau[j] = T::zero();
+                continue;
+            }
+
+            let beta = -alpha.signum() * alpha.hypot(norm);
+            tau[j] = (beta - alpha) / beta;
+            let scale = T::one() / (alpha - beta);
+            for i in j + 1..m {
+                a[i * lda + j] = a[i * lda + j] * scale;
+            }
+            a[j * lda + j] = beta;
+
+            for c in j + 1..n {
+                let mut w = a[j * lda + c];
+                for i in j + 1..m {
+                    w = w + a[i * lda + j] * a[i * lda + c];
+                }
+                let w = tau[j] * w;
+                a[j * lda + c] = a[j * lda + c] - w;
+                for i in j + 1..m {
+                    a[i * lda + c] = a[i * lda + c] - w * a[i * lda + j];
+                }
+            }
+        }
+    }
+
+    fn syevd(
+        &self,
+        uplo: Uplo,
+        n: usize,
+        a: &mut [T],
+        lda: usize,
+        w: &mut [T],
+    ) -> Result<(), LinalgError> {
+        // entry (i, j) of the `uplo` triangle for i >= j, or of the other
+        // triangle for i < j
+        let tri = |i: usize, j: usize| match uplo {
+            Uplo::Lower => i * lda + j,
+            Uplo::Upper => j * lda + i,
+        };
+
+        // The eigenvalues come from cyclic Jacobi on a copy of A kept in the
+        // other triangle, with its diagonal in `w`, so A itself is still there
+        // for the eigenvectors.
+        for i in 0..n {
+            w[i] = a[i * lda + i];
+            for j in 0..i {
+                a[tri(j, i)] = a[tri(i, j)];
+            }
+        }
+        jacobi_eigen(n, a, |p, q| tri(p.min(q), p.max(q)), w)?;
+
+        let min = w.iter().fold(T::infinity(), |acc, &x| acc.min(x));
+        let norm = w.iter().fold(T::zero(), |acc, &x| acc.hypot(x));
+        if norm == T::zero() {
+            for i in 0..n {
+                for j in 0..n {
+                    a[i * lda + j] = if i == j { T::one() } else { T::zero() };
+                }
+            }
+            return Ok(());
+        }
+
+        // With mu below every eigenvalue, G = A - mu I is positive definite,
+        // and once its rows have been rotated to be orthogonal, row i is
+        // (lambda_i - mu) times an eigenvector. Taking mu one norm of A below
+        // the smallest eigenvalue keeps the condition number of G at most 3.
+        let mu = min - norm;
+        for i in 0..n {
+            a[i * lda + i] = a[i * lda + i] - mu;
+            for j in 0..i {
+                a[tri(j, i)] = a[tri(i, j)];
+            }
+        }
+        // the rows of G are the columns of this view
+        let mut g = Strided {
+            data: &mut *a,
+            rs: 1,
+            cs: lda,
+        };
+        jacobi_columns(n, n, &mut g, |_, _, _| {})?;
+        for j in 0..n {
+            let mut smallest = (j, g.norm(n, j));
+            for k in j + 1..n {
+                let norm = g.norm(n, k);
+                if norm < smallest.1 {
+                    smallest = (k, norm);
+                }
+            }
+            g.swap(n, j, smallest.0);
+            for i in 0..n {
+                g.set(i, j, g.at(i, j) / smallest.1);
+            }
+        }
+        for i in 0..n {
+            for j in 0..i {
+                a.swap(i * lda + j, j * lda + i);
+            }
+        }
+        w.sort_unstable_by(|x, y| x.partial_cmp(y).unwrap());
+        Ok(())
+    }
+
+    #[allow(clippy::needless_range_loop)]
+    fn gesdd(
+        &self,
+        m: usize,
+        n: usize,
+        a: &mut [T],
+        lda: usize,
+        s: &mut [T],
+        u: &mut [T],
+        ldu: usize,
+        vt: &mut [T],
+        ldvt: usize,
+    ) -> Result<(), LinalgError> {
+        // One-sided Jacobi orthogonalizes the columns of a tall matrix, so a
+        // wide A is handled through A^T = V diag(s) U^T. G is A or A^T where
+        // it lies, `right` is whichever of V and U that makes the right
+        // singular vectors of G, and `left` is the other.
+        let (rows, cols) = (m.max(n), m.min(n));
+        let (mut g, mut right, mut left) = if m >= n {
+            (
+                Strided {
+                    data: a,
+                    rs: lda,
+                    cs: 1,
+                },
+                Strided {
+                    data: vt,
+                    rs: 1,
+                    cs: ldvt,
+                },
+                Strided {
+                    data: u,
+                    rs: ldu,
+                    cs: 1,
+                },
+            )
+        } else {
+            (
+                Strided {
+                    data: a,
+                    rs: 1,
+                    cs: lda,
+                },
+                Strided {
+                    data: u,
+                    rs: ldu,
+                    cs: 1,
+                },
+                Strided {
+                    data: vt,
+                    rs: 1,
+                    cs: ldvt,
+                },
+            )
+        };
+        for i in 0..cols {
+            for j in 0..cols {
+                right.set(i, j, if i == j { T::one() } else { T::zero() });
+            }
+        }
+        jacobi_columns(rows, cols, &mut g, |p, q, r| right.rotate(cols, p, q, r))?;
+
+        // the singular values are the column norms, sorted by swapping
+        // columns of G and V alike
+        for j in 0..cols {
+            let mut largest = (j, g.norm(rows, j));
+            for k in j + 1..cols {
+                let norm = g.norm(rows, k);
+                if norm > largest.1 {
+                    largest = (k, norm);
+                }
+            }
+            g.swap(rows, j, largest.0);
+            right.swap(cols, j, largest.0);
+            s[j] = largest.1;
+            for i in 0..rows {
+                let x = if s[j] == T::zero() {
+                    T::zero()
+                } else {
+                    g.at(i, j) / s[j]
+                };
+                left.set(i, j, x);
+            }
+        }
+        // columns for zero singular values are only fixed by orthogonality
+        for j in 0..cols {
+            if s[j] == T::zero() {
+                complete_column(rows, cols, &mut left, j);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Solves the n x n triangular system A x = x in place, where element i of x
+// is at `x[i * stride]`.
+fn tri_solve<T: Float>(
+    n: usize,
+    lower: bool,
+    unit: bool,
+    a: impl Fn(usize, usize) -> T,
+    x: &mut [T],
+    stride: usize,
+) {
+    let mut solve = |i: usize, range: core::ops::Range<usize>| {
+        let mut sum = x[i * stride];
+        for k in range {
+            sum = sum - a(i, k) * x[k * stride];
+        }
+        x[i * stride] = if unit { sum } else { sum / a(i, i) };
+    };
+    if lower {
+        for i in 0..n {
+            solve(i, 0..i);
+        }
+    } else {
+        for i in (0..n).rev() {
+            solve(i, i + 1..n);
+        }
+    }
+}
+
+// A matrix in a slice with element (i, j) at `i * rs + j * cs`, so that the
+// Jacobi sweeps can run over the rows or the columns of an argument in place.
+struct Strided<'a, T> {
+    data: &'a mut [T],
+    rs: usize,
+    cs: usize,
+}
+
+impl<T: Float> Strided<'_, T> {
+    fn at(&self, i: usize, j: usize) -> T {
+        self.data[i * self.rs + j * self.cs]
+    }
+
+    fn set(&mut self, i: usize, j: usize, x: T) {
+        self.data[i * self.rs + j * self.cs] = x;
+    }
+
+    fn norm(&self, rows: usize, j: usize) -> T {
+        (0..rows).fold(T::zero(), |acc, i| acc.hypot(self.at(i, j)))
+    }
+
+    fn swap(&mut self, rows: usize, p: usize, q: usize) {
+        for i in 0..rows {
+            self.data
+                .swap(i * self.rs + p * self.cs, i * self.rs + q * self.cs);
+        }
+    }
+
+    // Rotates columns p and q.
+    fn rotate(&mut self, rows: usize, p: usize, q: usize, (c, s): (T, T)) {
+        for i in 0..rows {
+            let (xp, xq) = (self.at(i, p), self.at(i, q));
+            self.set(i, p, c * xp - s * xq);
+            self.set(i, q, s * xp + c * xq);
+        }
+    }
+}
+
+// The rotation (c, s) that zeroes the off-diagonal entry of the symmetric
+// 2 x 2 matrix [[app, apq], [apq, aqq]].
+fn rotation<T: Float>(app: T, aqq: T, apq: T) -> (T, T) {
+    let two = T::one() + T::one();
+    let theta = (aqq - app) / (two * apq);
+    // the smaller of the two angles, which keeps the rotation stable
+    let t = theta.signum() / (theta.abs() + theta.hypot(T::one()));
+    let c = T::one() / t.hypot(T::one());
+    (c, t * c)
+}
+
+// Cyclic Jacobi on the symmetric n x n matrix with diagonal `d` and entry
+// (p, q) for p < q at `s[at(p, q)]`, which is reduced to diagonal form. Only
+// the eigenvalues are kept, in `d`.
+fn jacobi_eigen<T: Float>(
+    n: usize,
+    s: &mut [T],
+    at: impl Fn(usize, usize) -> usize,
+    d: &mut [T],
+) -> Result<(), LinalgError> {
+    let off = |s: &[T]| {
+        let mut off = T::zero();
+        for p in 0..n {
+            for q in p + 1..n {
+                off = off.hypot(s[at(p, q)]);
+            }
+        }
+        off
+    };
+    // the entries off the diagonal count twice in the norm
+    let two = T::one() + T::one();
+    let norm = d.iter().fold(off(s) * two.sqrt(), |acc, &x| acc.hypot(x));
+    let tol = T::epsilon() * norm;
+    for _ in 0..MAX_SWEEPS {
+        if off(s) <= tol {
+            return Ok(());
+        }
+
+        for p in 0..n {
+            for q in p + 1..n {
+                let apq = s[at(p, q)];
+                if apq == T::zero() {
+                    continue;
+                }
+                let (c, sn) = rotation(d[p], d[q], apq);
+                for k in (0..n).filter(|&k| k != p && k != q) {
+                    let (kp, kq) = (at(k.min(p), k.max(p)), at(k.min(q), k.max(q)));
+                    let (xp, xq) = (s[kp], s[kq]);
+                    s[kp] = c * xp - sn * xq;
+                    s[kq] = sn * xp + c * xq;
+                }
+                let t = sn / c;
+                d[p] = d[p] - t * apq;
+                d[q] = d[q] + t * apq;
+                s[at(p, q)] = T::zero();
+            }
+        }
+    }
+    Err(LinalgError::NoConvergence {
+        iterations: MAX_SWEEPS,
+    })
+}
+
+// One-sided Jacobi on the rows x cols matrix `g`: columns are rotated in
+// pairs until they are mutually orthogonal, and each rotation is passed on to
+// `rotated` along with the columns it applies to.
+fn jacobi_columns<T: Float>(
+    rows: usize,
+    cols: usize,
+    g: &mut Strided<T>,
+    mut rotated: impl FnMut(usize, usize, (T, T)),
+) -> Result<(), LinalgError> {
+    for _ in 0..MAX_SWEEPS {
+        let mut any = false;
+        for p in 0..cols {
+            for q in p + 1..cols {
+                let (mut app, mut aqq, mut apq) = (T::zero(), T::zero(), T::zero());
+                for i in 0..rows {
+                    let (xp, xq) = (g.at(i, p), g.at(i, q));
+                    app = app + xp * xp;
+                    aqq = aqq + xq * xq;
+                    apq = apq + xp * xq;
+                }
+                if apq.abs() <= T::epsilon() * (app * aqq).sqrt() {
+                    continue;
+                }
+                any = true;
+                let r = rotation(app, aqq, apq);
+                g.rotate(rows, p, q, r);
+                rotated(p, q, r);
+            }
+        }
+        if !any {
+            return Ok(());
+        }
+    }
+    Err(LinalgError::NoConvergence {
+        iterations: MAX_SWEEPS,
+    })
+}
+
+// Replaces column j of the rows x cols matrix `u` with a unit vector
+// orthogonal to its other nonzero columns, found by Gram-Schmidt on the
+// standard basis.
+fn complete_column<T: Float>(rows: usize, cols: usize, u: &mut Strided<T>, j: usize) {
+    let half = T::one() / (T::one() + T::one());
+    for e in 0..rows {
+        for i in 0..rows {
+            u.set(i, j, if i == e { T::one() } else { T::zero() });
+        }
+        // twice, for orthogonality to working precision
+        for _ in 0..2 {
+            for k in (0..cols).filter(|&k| k != j) {
+                let dot = (0..rows).fold(T::zero(), |acc, i| acc + u.at(i, k) * u.at(i, j));
+                for i in 0..rows {
+                    u.set(i, j, u.at(i, j) - dot * u.at(i, k));
+                }
+            }
+        }
+        let norm = u.norm(rows, j);
+        if norm > half {
+            for i in 0..rows {
+                u.set(i, j, u.at(i, j) / norm);
+            }
+            return;
+        }
+    }
+}
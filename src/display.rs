@@ -0,0 +1,422 @@
+#[cfg(feature = "alloc")]
+use super::dmatrix::DMat;
+use super::mat3a::Mat3A;
+use super::matrix::Mat;
+#[cfg(feature = "alloc")]
+use super::sparse::CscMatrix;
+use super::vector::{vec2::Vec2, vec3::Vec3, vec3a::Vec3A, vec4::Vec4};
+#[cfg(feature = "alloc")]
+use alloc::vec;
+use core::fmt::{self, Alignment, Display, Formatter, Write};
+use num::Float;
+
+// Everything here writes straight to the `Formatter`, so the fixed-size types
+// display without `alloc`. Entries are formatted twice, once to measure them
+// and once to write them.
+
+// The precision and sign flags of a `Formatter`, which apply to each entry on
+// its own. Width and alignment are applied separately by `pad`, since they
+// depend on the other entries being laid out.
+#[derive(Clone, Copy)]
+struct Spec {
+    precision: Option<usize>,
+    plus: bool,
+}
+
+impl Spec {
+    fn of(f: &Formatter) -> Self {
+        Spec {
+            precision: f.precision(),
+            plus: f.sign_plus(),
+        }
+    }
+
+    fn write<T: Display>(self, out: &mut impl Write, x: &T) -> fmt::Result {
+        match (self.precision, self.plus) {
+            (Some(p), true) => write!(out, "{:+.*}", p, x),
+            (Some(p), false) => write!(out, "{:.*}", p, x),
+            (None, true) => write!(out, "{:+}", x),
+            (None, false) => write!(out, "{}", x),
+        }
+    }
+
+    // The number of characters `write` produces.
+    fn len<T: Display>(self, x: &T) -> usize {
+        let mut count = Count(0);
+        let _ = self.write(&mut count, x);
+        count.0
+    }
+}
+
+struct Count(usize);
+
+impl Write for Count {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
+
+fn repeat(f: &mut Formatter, c: char, n: usize) -> fmt::Result {
+    for _ in 0..n {
+        f.write_char(c)?;
+    }
+    Ok(())
+}
+
+fn pad<T: Display>(f: &mut Formatter, spec: Spec, x: &T, width: usize) -> fmt::Result {
+    let fill = width.saturating_sub(spec.len(x));
+    let (left, right) = match f.align() {
+        Some(Alignment::Left) => (0, fill),
+        Some(Alignment::Center) => (fill / 2, fill - fill / 2),
+        Some(Alignment::Right) | None => (fill, 0),
+    };
+    repeat(f, f.fill(), left)?;
+    spec.write(f, x)?;
+    repeat(f, f.fill(), right)
+}
+
+// Fills `widths` with the width of each column: the widest entry, or the
+// width of `f` if that is larger.
+fn column_widths<T: Display>(
+    f: &Formatter,
+    rows: usize,
+    get: impl Fn(usize, usize) -> T,
+    widths: &mut [usize],
+) {
+    let spec = Spec::of(f);
+    widths.fill(f.width().unwrap_or(0));
+    for i in 0..rows {
+        for (j, w) in widths.iter_mut().enumerate() {
+            *w = usize::max(*w, spec.len(&get(i, j)));
+        }
+    }
+}
+
+fn widths<T: Float + Display, const R: usize, const C: usize>(
+    f: &Formatter,
+    mat: &Mat<T, R, C>,
+) -> [usize; C] {
+    let mut widths = [0; C];
+    column_widths(f, R, |i, j| mat[i][j], &mut widths);
+    widths
+}
+
+// Renders as a box with right-aligned columns:
+//
+// ┌         ┐
+// │ 1.5  -2 │
+// │  10   4 │
+// └         ┘
+fn draw_box<T: Display>(
+    f: &mut Formatter,
+    rows: usize,
+    get: impl Fn(usize, usize) -> T,
+    widths: &[usize],
+) -> fmt::Result {
+    let spec = Spec::of(f);
+    let inner: usize = widths.iter().sum::<usize>() + 2 * widths.len().saturating_sub(1) + 2;
+
+    f.write_str("┌")?;
+    repeat(f, ' ', inner)?;
+    writeln!(f, "┐")?;
+    for i in 0..rows {
+        f.write_str("│ ")?;
+        for (j, &w) in widths.iter().enumerate() {
+            if j > 0 {
+                f.write_str("  ")?;
+            }
+            pad(f, spec, &get(i, j), w)?;
+        }
+        writeln!(f, " │")?;
+    }
+    f.write_str("└")?;
+    repeat(f, ' ', inner)?;
+    f.write_str("┘")
+}
+
+impl<T: Float + Display, const R: usize, const C: usize> Display for Mat<T, R, C> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let widths = widths(f, self);
+        draw_box(f, R, |i, j| self[i][j], &widths)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Float + Display> Display for DMat<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (rows, cols) = self.shape();
+        let mut widths = vec![0; cols];
+        column_widths(f, rows, |i, j| self[(i, j)], &mut widths);
+        draw_box(f, rows, |i, j| self[(i, j)], &widths)
+    }
+}
+
+impl<T: Float + Display> Display for Mat3A<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&Mat::from(*self), f)
+    }
+}
+
+// Drawn densely, with the unstored entries as zeros, so that the output
+// parses back as a `DMat`. Only meant for small matrices.
+#[cfg(feature = "alloc")]
+impl<T: Float + Display> Display for CscMatrix<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut widths = vec![0; self.ncols()];
+        column_widths(f, self.nrows(), |i, j| self.get(i, j), &mut widths);
+        draw_box(f, self.nrows(), |i, j| self.get(i, j), &widths)
+    }
+}
+
+macro_rules! impl_display_vec {
+    ($v:ident, $first:ident $(, $c:ident)*) => {
+        impl<T: Float + Display> Display for $v<T> {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                let (spec, width) = (Spec::of(f), f.width().unwrap_or(0));
+                f.write_str("(")?;
+                pad(f, spec, &self.$first, width)?;
+                $(
+                    f.write_str(", ")?;
+                    pad(f, spec, &self.$c, width)?;
+                )*
+                f.write_str(")")
+            }
+        }
+    };
+}
+
+impl_display_vec!(Vec2, x, y);
+impl_display_vec!(Vec3, x, y, z);
+impl_display_vec!(Vec3A, x, y, z);
+impl_display_vec!(Vec4, x, y, z, w);
+
+// Alternate renderings. Each one honours the same precision, sign and width
+// flags as the default `Display` impl, e.g. `format!("{:.3}", m.latex())`.
+
+pub struct Latex<'a, T: Float, const R: usize, const C: usize>(&'a Mat<T, R, C>);
+pub struct NumPy<'a, T: Float, const R: usize, const C: usize>(&'a Mat<T, R, C>);
+pub struct Matlab<'a, T: Float, const R: usize, const C: usize>(&'a Mat<T, R, C>);
+pub struct Markdown<'a, T: Float, const R: usize, const C: usize>(&'a Mat<T, R, C>);
+
+impl<T: Float, const R: usize, const C: usize> Mat<T, R, C> {
+    pub fn latex(&self) -> Latex<'_, T, R, C> {
+        Latex(self)
+    }
+
+    pub fn numpy(&self) -> NumPy<'_, T, R, C> {
+        NumPy(self)
+    }
+
+    pub fn matlab(&self) -> Matlab<'_, T, R, C> {
+        Matlab(self)
+    }
+
+    pub fn markdown(&self) -> Markdown<'_, T, R, C> {
+        Markdown(self)
+    }
+}
+
+// \begin{bmatrix}
+// 1 & 2 \\
+// 3 & 4
+// \end{bmatrix}
+impl<T: Float + Display, const R: usize, const C: usize> Display for Latex<'_, T, R, C> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (spec, widths) = (Spec::of(f), widths(f, self.0));
+        writeln!(f, "\\begin{{bmatrix}}")?;
+        for (i, row) in self.0 .0.iter().enumerate() {
+            for (j, x) in row.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(" & ")?;
+                }
+                pad(f, spec, x, widths[j])?;
+            }
+            if i + 1 < R {
+                f.write_str(" \\\\")?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "\\end{{bmatrix}}")
+    }
+}
+
+// np.array([[1, 2], [3, 4]])
+impl<T: Float + Display, const R: usize, const C: usize> Display for NumPy<'_, T, R, C> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (spec, widths) = (Spec::of(f), widths(f, self.0));
+        f.write_str("np.array([")?;
+        for (i, row) in self.0 .0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str("[")?;
+            for (j, x) in row.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(", ")?;
+                }
+                pad(f, spec, x, widths[j])?;
+            }
+            f.write_str("]")?;
+        }
+        f.write_str("])")
+    }
+}
+
+// [1 2; 3 4]
+impl<T: Float + Display, const R: usize, const C: usize> Display for Matlab<'_, T, R, C> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (spec, widths) = (Spec::of(f), widths(f, self.0));
+        f.write_str("[")?;
+        for (i, row) in self.0 .0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            for (j, x) in row.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(" ")?;
+                }
+                pad(f, spec, x, widths[j])?;
+            }
+        }
+        f.write_str("]")
+    }
+}
+
+// Markdown tables need a header row, which is left empty.
+//
+// |   |   |
+// |--:|--:|
+// | 1 | 2 |
+// | 3 | 4 |
+impl<T: Float + Display, const R: usize, const C: usize> Display for Markdown<'_, T, R, C> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (spec, widths) = (Spec::of(f), widths(f, self.0));
+        f.write_str("|")?;
+        for w in widths {
+            f.write_str(" ")?;
+            repeat(f, ' ', w)?;
+            f.write_str(" |")?;
+        }
+        writeln!(f)?;
+        f.write_str("|")?;
+        for w in widths {
+            repeat(f, '-', w + 1)?;
+            f.write_str(":|")?;
+        }
+        for row in &self.0 .0 {
+            writeln!(f)?;
+            f.write_str("|")?;
+            for (j, x) in row.iter().enumerate() {
+                f.write_str(" ")?;
+                pad(f, spec, x, widths[j])?;
+                f.write_str(" |")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mat() {
+        let mat = Mat::new(&[[1.5, -2.0], [10.0, 4.0]]);
+        assert_eq!(
+            mat.to_string(),
+            "┌         ┐\n│ 1.5  -2 │\n│  10   4 │\n└         ┘"
+        );
+    }
+
+    #[test]
+    fn mat_precision() {
+        let mat = Mat::new(&[[1.0, 2.0], [3.0, 4.0]]);
+        assert_eq!(
+            format!("{:.2}", mat),
+            "┌            ┐\n│ 1.00  2.00 │\n│ 3.00  4.00 │\n└            ┘"
+        );
+    }
+
+    #[test]
+    fn mat_width() {
+        let mat = Mat::new(&[[1.0, 2.0]]);
+        assert_eq!(
+            format!("{:6.1}", mat),
+            "┌                ┐\n│    1.0     2.0 │\n└                ┘"
+        );
+        assert_eq!(
+            format!("{:<4}", mat),
+            "┌            ┐\n│ 1     2    │\n└            ┘"
+        );
+    }
+
+    #[test]
+    fn mat_sign() {
+        let mat = Mat::new(&[[1.0, -2.0]]);
+        assert_eq!(format!("{:+}", mat.matlab()), "[+1 -2]");
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn dynamic() {
+        let mat = Mat::new(&[[1.5, -2.0], [10.0, 4.0]]);
+        assert_eq!(DMat::from_mat(&mat).to_string(), mat.to_string());
+        assert_eq!(
+            format!("{:.1}", DMat::from_mat(&mat)),
+            format!("{:.1}", mat)
+        );
+        assert_eq!(DMat::<f64>::zero(0, 0).to_string(), "┌  ┐\n└  ┘");
+
+        let sym = Mat::new(&[[1.0, 0.0, 2.0], [0.0, 3.0, 0.0], [2.0, 0.0, 4.0]]);
+        assert_eq!(Mat3A::from(sym).to_string(), sym.to_string());
+        assert_eq!(CscMatrix::from_dense(&sym).to_string(), sym.to_string());
+    }
+
+    #[test]
+    fn vec() {
+        assert_eq!(Vec2::new(1.0, 2.5).to_string(), "(1, 2.5)");
+        assert_eq!(
+            format!("{:.1}", Vec3::new(1.0, 2.0, 3.0)),
+            "(1.0, 2.0, 3.0)"
+        );
+        assert_eq!(
+            format!("{:5.2}", Vec4::new(1.0, -2.0, 3.0, 4.0)),
+            "( 1.00, -2.00,  3.00,  4.00)"
+        );
+    }
+
+    #[test]
+    fn latex() {
+        let mat = Mat::new(&[[1.0, 2.0], [3.0, 4.0]]);
+        assert_eq!(
+            mat.latex().to_string(),
+            "\\begin{bmatrix}\n1 & 2 \\\\\n3 & 4\n\\end{bmatrix}"
+        );
+    }
+
+    #[test]
+    fn numpy() {
+        let mat = Mat::new(&[[1.0, 2.0], [3.0, 4.0]]);
+        assert_eq!(
+            format!("{:.1}", mat.numpy()),
+            "np.array([[1.0, 2.0], [3.0, 4.0]])"
+        );
+    }
+
+    #[test]
+    fn matlab() {
+        let mat = Mat::new(&[[1.0, 2.0], [3.0, 40.0]]);
+        assert_eq!(mat.matlab().to_string(), "[1  2; 3 40]");
+    }
+
+    #[test]
+    fn markdown() {
+        let mat = Mat::new(&[[1.0, 2.0], [3.0, 40.0]]);
+        assert_eq!(
+            mat.markdown().to_string(),
+            "|   |    |\n|--:|---:|\n| 1 |  2 |\n| 3 | 40 |"
+        );
+    }
+}
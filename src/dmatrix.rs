@@ -0,0 +1,1216 @@
+use super::backend::{Backend, Diag, Native, Side, Transpose, Uplo};
+use super::error::LinalgError;
+use super::gemm;
+use super::matrix::Mat;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{Add, Index, IndexMut, Mul, MulAssign, Neg, Sub};
+use num::Float;
+
+// A heap-allocated matrix whose shape is only known at run time, for problems
+// too large for the stack or sized by data. Entries are row-major like `Mat`,
+// so the storage goes to a `Backend` as-is.
+//
+// The factorizations run on `Native`, or on the backend passed to their
+// `_with` variants (e.g. `Lapack`). Each factorization keeps a copy of its
+// backend for the solves that follow.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DMat<T: Float> {
+    rows: usize,
+    cols: usize,
+    data: Vec<T>,
+}
+
+impl<T: Float> DMat<T> {
+    pub fn zero(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            data: vec![T::zero(); rows * cols],
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut m = Self::zero(n, n);
+        for i in 0..n {
+            m[(i, i)] = T::one();
+        }
+        m
+    }
+
+    pub fn from_fn(rows: usize, cols: usize, mut f: impl FnMut(usize, usize) -> T) -> Self {
+        let data = (0..rows * cols).map(|k| f(k / cols, k % cols)).collect();
+        Self { rows, cols, data }
+    }
+
+    // Panics on the `DimensionMismatch` from `try_from_vec`.
+    pub fn from_vec(rows: usize, cols: usize, data: Vec<T>) -> Self {
+        Self::try_from_vec(rows, cols, data).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Takes the entries in row-major order.
+    pub fn try_from_vec(rows: usize, cols: usize, data: Vec<T>) -> Result<Self, LinalgError> {
+        if data.len() != rows * cols {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (rows * cols, 1),
+                found: (data.len(), 1),
+            });
+        }
+        Ok(Self { rows, cols, data })
+    }
+
+    pub fn from_mat<const R: usize, const C: usize>(m: &Mat<T, R, C>) -> Self {
+        Self {
+            rows: R,
+            cols: C,
+            data: m.as_slice().to_vec(),
+        }
+    }
+
+    // Panics on the `DimensionMismatch` from `try_to_mat`.
+    pub fn to_mat<const R: usize, const C: usize>(&self) -> Mat<T, R, C> {
+        self.try_to_mat().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_to_mat<const R: usize, const C: usize>(&self) -> Result<Mat<T, R, C>, LinalgError> {
+        self.check_shape((R, C))?;
+        Ok(Mat::from_slice(&self.data))
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    // Entries in row-major order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.data[i * self.cols..(i + 1) * self.cols]
+    }
+
+    pub fn row_mut(&mut self, i: usize) -> &mut [T] {
+        &mut self.data[i * self.cols..(i + 1) * self.cols]
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Option<&T> {
+        if i < self.rows && j < self.cols {
+            self.data.get(i * self.cols + j)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, i: usize, j: usize) -> Option<&mut T> {
+        if i < self.rows && j < self.cols {
+            self.data.get_mut(i * self.cols + j)
+        } else {
+            None
+        }
+    }
+
+    pub fn swap_rows(&mut self, a: usize, b: usize) {
+        for j in 0..self.cols {
+            self.data.swap(a * self.cols + j, b * self.cols + j);
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_fn(self.cols, self.rows, |i, j| self[(j, i)])
+    }
+
+    // Runtime-shaped counterparts of the shape methods on `Mat`. Each `try_`
+    // method returns `DimensionMismatch` where `Mat` fails to compile or
+    // panics; for offsets and indices that are out of bounds, `expected` is
+    // the smallest shape they would fit in. The plain methods panic on that
+    // error.
+
+    pub fn hstack(&self, rhs: &Self) -> Self {
+        self.try_hstack(rhs).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_hstack(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        rhs.check_shape((self.rows, rhs.cols))?;
+        Ok(Self::from_fn(self.rows, self.cols + rhs.cols, |i, j| {
+            if j < self.cols {
+                self[(i, j)]
+            } else {
+                rhs[(i, j - self.cols)]
+            }
+        }))
+    }
+
+    pub fn vstack(&self, rhs: &Self) -> Self {
+        self.try_vstack(rhs).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_vstack(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        rhs.check_shape((rhs.rows, self.cols))?;
+        let mut data = self.data.clone();
+        data.extend_from_slice(&rhs.data);
+        Ok(Self {
+            rows: self.rows + rhs.rows,
+            cols: self.cols,
+            data,
+        })
+    }
+
+    pub fn block(&self, i: usize, j: usize, rows: usize, cols: usize) -> Self {
+        self.try_block(i, j, rows, cols)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // The rows x cols block whose top-left entry is at (i, j).
+    pub fn try_block(
+        &self,
+        i: usize,
+        j: usize,
+        rows: usize,
+        cols: usize,
+    ) -> Result<Self, LinalgError> {
+        self.check_fits(i, j, (rows, cols))?;
+        Ok(Self::from_fn(rows, cols, |r, c| self[(i + r, j + c)]))
+    }
+
+    pub fn set_block(&mut self, i: usize, j: usize, block: &Self) {
+        self.try_set_block(i, j, block)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Overwrites the block whose top-left entry is at (i, j).
+    pub fn try_set_block(&mut self, i: usize, j: usize, block: &Self) -> Result<(), LinalgError> {
+        self.check_fits(i, j, block.shape())?;
+        for r in 0..block.rows {
+            let start = (i + r) * self.cols + j;
+            self.data[start..start + block.cols].copy_from_slice(block.row(r));
+        }
+        Ok(())
+    }
+
+    pub fn reshape(&self, rows: usize, cols: usize) -> Self {
+        self.try_reshape(rows, cols)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Reinterprets the entries, in row-major order, as a rows x cols matrix.
+    // The entry counts are reported as in `try_from_vec`.
+    pub fn try_reshape(&self, rows: usize, cols: usize) -> Result<Self, LinalgError> {
+        Self::try_from_vec(rows, cols, self.data.clone())
+    }
+
+    pub fn insert_row(&self, i: usize, row: &[T]) -> Self {
+        self.try_insert_row(i, row)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Inserts `row` before row i, so that it becomes row i. `i == nrows()`
+    // appends. `row` is reported as a 1 x n matrix.
+    pub fn try_insert_row(&self, i: usize, row: &[T]) -> Result<Self, LinalgError> {
+        self.check_fits(i, 0, (0, self.cols))?;
+        if row.len() != self.cols {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (1, self.cols),
+                found: (1, row.len()),
+            });
+        }
+        let mut data = Vec::with_capacity(self.data.len() + self.cols);
+        data.extend_from_slice(&self.data[..i * self.cols]);
+        data.extend_from_slice(row);
+        data.extend_from_slice(&self.data[i * self.cols..]);
+        Ok(Self {
+            rows: self.rows + 1,
+            cols: self.cols,
+            data,
+        })
+    }
+
+    pub fn remove_row(&self, i: usize) -> Self {
+        self.try_remove_row(i)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_remove_row(&self, i: usize) -> Result<Self, LinalgError> {
+        self.check_fits(i, 0, (1, self.cols))?;
+        let mut data = self.data.clone();
+        data.drain(i * self.cols..(i + 1) * self.cols);
+        Ok(Self {
+            rows: self.rows - 1,
+            cols: self.cols,
+            data,
+        })
+    }
+
+    pub fn insert_column(&self, j: usize, column: &[T]) -> Self {
+        self.try_insert_column(j, column)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Inserts `column` before column j, so that it becomes column j.
+    // `j == ncols()` appends.
+    pub fn try_insert_column(&self, j: usize, column: &[T]) -> Result<Self, LinalgError> {
+        self.check_fits(0, j, (self.rows, 0))?;
+        if column.len() != self.rows {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (self.rows, 1),
+                found: (column.len(), 1),
+            });
+        }
+        Ok(Self::from_fn(self.rows, self.cols + 1, |r, c| {
+            match c.cmp(&j) {
+                core::cmp::Ordering::Less => self[(r, c)],
+                core::cmp::Ordering::Equal => column[r],
+                core::cmp::Ordering::Greater => self[(r, c - 1)],
+            }
+        }))
+    }
+
+    pub fn remove_column(&self, j: usize) -> Self {
+        self.try_remove_column(j)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_remove_column(&self, j: usize) -> Result<Self, LinalgError> {
+        self.check_fits(0, j, (self.rows, 1))?;
+        Ok(Self::from_fn(self.rows, self.cols - 1, |r, c| {
+            self[(r, if c < j { c } else { c + 1 })]
+        }))
+    }
+
+    // The product with the same dispatch as for `Mat`: the naive loop for
+    // small products and the blocked kernel from `gemm` above
+    // `gemm::BLOCKED_THRESHOLD`. `*` panics on the `DimensionMismatch`.
+    pub fn try_mul(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        if self.cols != rhs.rows {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (self.cols, rhs.cols),
+                found: rhs.shape(),
+            });
+        }
+        let (m, n, k) = (self.rows, rhs.cols, self.cols);
+        let mut out = Self::zero(m, n);
+        gemm::mul_into(m, n, k, &self.data, &rhs.data, &mut out.data);
+        Ok(out)
+    }
+
+    // `+` and `-` panic on the `DimensionMismatch`.
+    pub fn try_add(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        self.zip_with(rhs, |a, b| a + b)
+    }
+
+    pub fn try_sub(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        self.zip_with(rhs, |a, b| a - b)
+    }
+
+    fn zip_with(&self, rhs: &Self, f: impl Fn(T, T) -> T) -> Result<Self, LinalgError> {
+        rhs.check_shape(self.shape())?;
+        let data = self.data.iter().zip(&rhs.data).map(|(&a, &b)| f(a, b));
+        Ok(Self {
+            rows: self.rows,
+            cols: self.cols,
+            data: data.collect(),
+        })
+    }
+
+    // Panics on the `Singular` or `DimensionMismatch` from `try_lu`.
+    pub fn lu(&self) -> Lu<T> {
+        self.try_lu().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // LU factorization with partial pivoting of a square matrix. Fails with
+    // `Singular` when U has a zero on its diagonal.
+    pub fn try_lu(&self) -> Result<Lu<T>, LinalgError> {
+        self.try_lu_with(&Native)
+    }
+
+    pub fn try_lu_with<B: Backend<T> + Clone>(&self, backend: &B) -> Result<Lu<T, B>, LinalgError> {
+        let n = self.square()?;
+        let mut lu = self.clone();
+        let mut ipiv = vec![0; n];
+        backend.getrf(n, n, &mut lu.data, n, &mut ipiv)?;
+        Ok(Lu {
+            lu,
+            ipiv,
+            backend: backend.clone(),
+        })
+    }
+
+    // Panics on the `NotPositiveDefinite` or `DimensionMismatch` from
+    // `try_cholesky`.
+    pub fn cholesky(&self) -> Cholesky<T> {
+        self.try_cholesky().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // A = L L^T for symmetric positive definite A. Only the lower triangle is
+    // read.
+    pub fn try_cholesky(&self) -> Result<Cholesky<T>, LinalgError> {
+        self.try_cholesky_with(&Native)
+    }
+
+    pub fn try_cholesky_with<B: Backend<T> + Clone>(
+        &self,
+        backend: &B,
+    ) -> Result<Cholesky<T, B>, LinalgError> {
+        let n = self.square()?;
+        let mut l = self.clone();
+        backend.potrf(Uplo::Lower, n, &mut l.data, n)?;
+        for i in 0..n {
+            for x in &mut l.row_mut(i)[i + 1..] {
+                *x = T::zero();
+            }
+        }
+        Ok(Cholesky {
+            l,
+            backend: backend.clone(),
+        })
+    }
+
+    // Householder QR of a matrix of any shape.
+    pub fn qr(&self) -> Qr<T> {
+        self.qr_with(&Native)
+    }
+
+    pub fn qr_with<B: Backend<T> + Clone>(&self, backend: &B) -> Qr<T, B> {
+        let mut qr = self.clone();
+        let mut tau = vec![T::zero(); self.rows.min(self.cols)];
+        backend.geqrf(self.rows, self.cols, &mut qr.data, self.cols, &mut tau);
+        Qr {
+            qr,
+            tau,
+            backend: backend.clone(),
+        }
+    }
+
+    // Panics on the `NoConvergence` or `DimensionMismatch` from `try_eigh`.
+    pub fn eigh(&self) -> Eigh<T> {
+        self.try_eigh().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Eigenvalues and eigenvectors of a symmetric matrix, of which only the
+    // lower triangle is read.
+    pub fn try_eigh(&self) -> Result<Eigh<T>, LinalgError> {
+        self.try_eigh_with(&Native)
+    }
+
+    pub fn try_eigh_with(&self, backend: &impl Backend<T>) -> Result<Eigh<T>, LinalgError> {
+        let n = self.square()?;
+        let mut vectors = self.clone();
+        let mut values = vec![T::zero(); n];
+        backend.syevd(Uplo::Lower, n, &mut vectors.data, n, &mut values)?;
+        Ok(Eigh { values, vectors })
+    }
+
+    // Panics on the `NoConvergence` from `try_svd`.
+    pub fn svd(&self) -> Svd<T> {
+        self.try_svd().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // The thin singular value decomposition.
+    pub fn try_svd(&self) -> Result<Svd<T>, LinalgError> {
+        self.try_svd_with(&Native)
+    }
+
+    pub fn try_svd_with(&self, backend: &impl Backend<T>) -> Result<Svd<T>, LinalgError> {
+        let (m, n) = self.shape();
+        let k = m.min(n);
+        let (mut u, mut s, mut vt) = (Self::zero(m, k), vec![T::zero(); k], Self::zero(k, n));
+        backend.gesdd(
+            m,
+            n,
+            &mut self.data.clone(),
+            n,
+            &mut s,
+            &mut u.data,
+            k,
+            &mut vt.data,
+            n,
+        )?;
+        Ok(Svd { u, s, vt })
+    }
+
+    // Panics on the `Singular` or `DimensionMismatch` from `try_solve`.
+    pub fn solve(&self, b: &Self) -> Self {
+        self.try_solve(b).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Solves A X = B through the LU factorization of A.
+    pub fn try_solve(&self, b: &Self) -> Result<Self, LinalgError> {
+        self.try_solve_with(&Native, b)
+    }
+
+    pub fn try_solve_with<B: Backend<T> + Clone>(
+        &self,
+        backend: &B,
+        b: &Self,
+    ) -> Result<Self, LinalgError> {
+        self.try_lu_with(backend)?.try_solve(b)
+    }
+
+    pub(crate) fn square(&self) -> Result<usize, LinalgError> {
+        self.check_shape((self.rows, self.rows))?;
+        Ok(self.rows)
+    }
+
+    // Whether a block of the given shape fits with its top-left entry at
+    // (i, j).
+    fn check_fits(&self, i: usize, j: usize, shape: (usize, usize)) -> Result<(), LinalgError> {
+        let needed = (i.saturating_add(shape.0), j.saturating_add(shape.1));
+        if needed.0 > self.rows || needed.1 > self.cols {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (needed.0.max(self.rows), needed.1.max(self.cols)),
+                found: self.shape(),
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_shape(&self, expected: (usize, usize)) -> Result<(), LinalgError> {
+        if self.shape() != expected {
+            return Err(LinalgError::DimensionMismatch {
+                expected,
+                found: self.shape(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<T: Float, const R: usize, const C: usize> From<Mat<T, R, C>> for DMat<T> {
+    fn from(m: Mat<T, R, C>) -> Self {
+        Self::from_mat(&m)
+    }
+}
+
+impl<T: Float> Index<(usize, usize)> for DMat<T> {
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        assert!(i < self.rows && j < self.cols, "index out of bounds");
+        &self.data[i * self.cols + j]
+    }
+}
+
+impl<T: Float> IndexMut<(usize, usize)> for DMat<T> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Self::Output {
+        assert!(i < self.rows && j < self.cols, "index out of bounds");
+        &mut self.data[i * self.cols + j]
+    }
+}
+
+impl<T: Float> Mul<&DMat<T>> for &DMat<T> {
+    type Output = DMat<T>;
+
+    fn mul(self, rhs: &DMat<T>) -> Self::Output {
+        self.try_mul(rhs).unwrap_or_else(|_| {
+            panic!(
+                "cannot multiply {}x{} by {}x{}",
+                self.rows, self.cols, rhs.rows, rhs.cols
+            )
+        })
+    }
+}
+
+impl<T: Float> Add<&DMat<T>> for &DMat<T> {
+    type Output = DMat<T>;
+
+    fn add(self, rhs: &DMat<T>) -> Self::Output {
+        self.try_add(rhs).unwrap_or_else(|_| {
+            panic!(
+                "cannot add {}x{} and {}x{}",
+                self.rows, self.cols, rhs.rows, rhs.cols
+            )
+        })
+    }
+}
+
+impl<T: Float> Sub<&DMat<T>> for &DMat<T> {
+    type Output = DMat<T>;
+
+    fn sub(self, rhs: &DMat<T>) -> Self::Output {
+        self.try_sub(rhs).unwrap_or_else(|_| {
+            panic!(
+                "cannot subtract {}x{} from {}x{}",
+                rhs.rows, rhs.cols, self.rows, self.cols
+            )
+        })
+    }
+}
+
+// The operators above for owned operands, so that expressions can be chained
+// without borrowing every intermediate result.
+macro_rules! impl_owned_ops {
+    ($($op:ident $f:ident),*) => {
+        $(
+            impl<T: Float> $op for DMat<T> {
+                type Output = DMat<T>;
+
+                fn $f(self, rhs: DMat<T>) -> Self::Output {
+                    (&self).$f(&rhs)
+                }
+            }
+
+            impl<T: Float> $op<&DMat<T>> for DMat<T> {
+                type Output = DMat<T>;
+
+                fn $f(self, rhs: &DMat<T>) -> Self::Output {
+                    (&self).$f(rhs)
+                }
+            }
+
+            impl<T: Float> $op<DMat<T>> for &DMat<T> {
+                type Output = DMat<T>;
+
+                fn $f(self, rhs: DMat<T>) -> Self::Output {
+                    self.$f(&rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_owned_ops!(Add add, Sub sub, Mul mul);
+
+impl<T: Float> Neg for &DMat<T> {
+    type Output = DMat<T>;
+
+    fn neg(self) -> Self::Output {
+        DMat {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.data.iter().map(|&x| -x).collect(),
+        }
+    }
+}
+
+impl<T: Float> Neg for DMat<T> {
+    type Output = DMat<T>;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
+
+impl<T: Float> MulAssign<&DMat<T>> for DMat<T> {
+    fn mul_assign(&mut self, rhs: &DMat<T>) {
+        *self = &*self * rhs;
+    }
+}
+
+// P A = L U, with L and U packed into one matrix as `getrf` leaves them.
+#[derive(Debug, Clone)]
+pub struct Lu<T: Float, B = Native> {
+    pub(crate) lu: DMat<T>,
+    pub(crate) ipiv: Vec<usize>,
+    pub(crate) backend: B,
+}
+
+impl<T: Float, B: Backend<T>> Lu<T, B> {
+    // Row i was swapped with row `pivots()[i]`, in order.
+    pub fn pivots(&self) -> &[usize] {
+        &self.ipiv
+    }
+
+    // The unit lower triangular factor.
+    pub fn l(&self) -> DMat<T> {
+        let n = self.lu.rows;
+        DMat::from_fn(n, n, |i, j| match i.cmp(&j) {
+            core::cmp::Ordering::Greater => self.lu[(i, j)],
+            core::cmp::Ordering::Equal => T::one(),
+            core::cmp::Ordering::Less => T::zero(),
+        })
+    }
+
+    pub fn u(&self) -> DMat<T> {
+        let n = self.lu.rows;
+        DMat::from_fn(
+            n,
+            n,
+            |i, j| if i <= j { self.lu[(i, j)] } else { T::zero() },
+        )
+    }
+
+    pub fn determinant(&self) -> T {
+        let swaps = self.ipiv.iter().enumerate().filter(|&(i, &p)| i != p);
+        let sign = if swaps.count() % 2 == 0 {
+            T::one()
+        } else {
+            -T::one()
+        };
+        (0..self.lu.rows).fold(sign, |acc, i| acc * self.lu[(i, i)])
+    }
+
+    // Panics on the `DimensionMismatch` from `try_solve`.
+    pub fn solve(&self, b: &DMat<T>) -> DMat<T> {
+        self.try_solve(b).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_solve(&self, b: &DMat<T>) -> Result<DMat<T>, LinalgError> {
+        let n = self.lu.rows;
+        rows_match(n, b)?;
+        let mut x = b.clone();
+        for (i, &p) in self.ipiv.iter().enumerate() {
+            x.swap_rows(i, p);
+        }
+        let k = x.cols;
+        for (uplo, diag) in [(Uplo::Lower, Diag::Unit), (Uplo::Upper, Diag::NonUnit)] {
+            self.backend.trsm(
+                Side::Left,
+                uplo,
+                Transpose::No,
+                diag,
+                n,
+                k,
+                T::one(),
+                &self.lu.data,
+                n,
+                &mut x.data,
+                k,
+            );
+        }
+        Ok(x)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cholesky<T: Float, B = Native> {
+    pub(crate) l: DMat<T>,
+    pub(crate) backend: B,
+}
+
+impl<T: Float, B: Backend<T>> Cholesky<T, B> {
+    // The lower triangular factor, with zeros above the diagonal.
+    pub fn l(&self) -> &DMat<T> {
+        &self.l
+    }
+
+    // Panics on the `DimensionMismatch` from `try_solve`.
+    pub fn solve(&self, b: &DMat<T>) -> DMat<T> {
+        self.try_solve(b).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_solve(&self, b: &DMat<T>) -> Result<DMat<T>, LinalgError> {
+        let n = self.l.rows;
+        rows_match(n, b)?;
+        let mut x = b.clone();
+        let k = x.cols;
+        for ta in [Transpose::No, Transpose::Yes] {
+            self.backend.trsm(
+                Side::Left,
+                Uplo::Lower,
+                ta,
+                Diag::NonUnit,
+                n,
+                k,
+                T::one(),
+                &self.l.data,
+                n,
+                &mut x.data,
+                k,
+            );
+        }
+        Ok(x)
+    }
+}
+
+// A = Q R for an m x n A, with the Householder reflectors stored as `geqrf`
+// leaves them.
+#[derive(Debug, Clone)]
+pub struct Qr<T: Float, B = Native> {
+    qr: DMat<T>,
+    tau: Vec<T>,
+    backend: B,
+}
+
+impl<T: Float, B: Backend<T>> Qr<T, B> {
+    // The min(m, n) x n upper triangular factor.
+    pub fn r(&self) -> DMat<T> {
+        let (k, n) = (self.tau.len(), self.qr.cols);
+        DMat::from_fn(
+            k,
+            n,
+            |i, j| if i <= j { self.qr[(i, j)] } else { T::zero() },
+        )
+    }
+
+    // The m x min(m, n) factor with orthonormal columns, formed by applying
+    // the reflectors to the leading columns of the identity.
+    pub fn q(&self) -> DMat<T> {
+        let (m, k) = (self.qr.rows, self.tau.len());
+        let mut q = DMat::from_fn(m, k, |i, j| if i == j { T::one() } else { T::zero() });
+        for i in (0..k).rev() {
+            for c in 0..k {
+                let dot = (i + 1..m).fold(q[(i, c)], |acc, r| acc + self.qr[(r, i)] * q[(r, c)]);
+                let f = self.tau[i] * dot;
+                q[(i, c)] = q[(i, c)] - f;
+                for r in i + 1..m {
+                    q[(r, c)] = q[(r, c)] - f * self.qr[(r, i)];
+                }
+            }
+        }
+        q
+    }
+
+    // Panics on the `Singular` or `DimensionMismatch` from `try_solve`.
+    pub fn solve(&self, b: &DMat<T>) -> DMat<T> {
+        self.try_solve(b).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // The least squares solution of A X = B for A with at least as many rows
+    // as columns. Fails with `Singular` when R has a zero on its diagonal.
+    pub fn try_solve(&self, b: &DMat<T>) -> Result<DMat<T>, LinalgError> {
+        let (m, n) = self.qr.shape();
+        rows_match(m, b)?;
+        if m < n {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (n, n),
+                found: (m, n),
+            });
+        }
+        if (0..n).any(|i| self.qr[(i, i)] == T::zero()) {
+            return Err(LinalgError::Singular);
+        }
+        // X = R^-1 Q^T B
+        let k = b.cols;
+        let mut x = DMat::zero(n, k);
+        self.backend.gemm(
+            Transpose::Yes,
+            Transpose::No,
+            n,
+            k,
+            m,
+            T::one(),
+            &self.q().data,
+            n,
+            &b.data,
+            k,
+            T::zero(),
+            &mut x.data,
+            k,
+        );
+        self.backend.trsm(
+            Side::Left,
+            Uplo::Upper,
+            Transpose::No,
+            Diag::NonUnit,
+            n,
+            k,
+            T::one(),
+            &self.qr.data,
+            n,
+            &mut x.data,
+            k,
+        );
+        Ok(x)
+    }
+}
+
+// A = V diag(values) V^T, with the eigenvalues in ascending order and the
+// eigenvectors in the columns of V.
+#[derive(Debug, Clone)]
+pub struct Eigh<T: Float> {
+    pub values: Vec<T>,
+    pub vectors: DMat<T>,
+}
+
+// A = U diag(s) V^T for an m x n A, with k = min(m, n) singular values in
+// descending order, U m x k and V^T k x n.
+#[derive(Debug, Clone)]
+pub struct Svd<T: Float> {
+    pub u: DMat<T>,
+    pub s: Vec<T>,
+    pub vt: DMat<T>,
+}
+
+fn rows_match<T: Float>(n: usize, b: &DMat<T>) -> Result<(), LinalgError> {
+    if b.rows != n {
+        return Err(LinalgError::DimensionMismatch {
+            expected: (n, b.cols),
+            found: b.shape(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::Counting;
+    use crate::mat;
+
+    const TOL: f64 = 1e-12;
+
+    fn random(seed: u64, rows: usize, cols: usize) -> DMat<f64> {
+        let mut state = seed;
+        DMat::from_fn(rows, cols, |_, _| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+        })
+    }
+
+    // A^T A + n I, which is symmetric positive definite.
+    fn spd(seed: u64, n: usize) -> DMat<f64> {
+        let a = random(seed, n, n);
+        DMat::from_fn(n, n, |i, j| {
+            let dot: f64 = (0..n).map(|k| a[(k, i)] * a[(k, j)]).sum();
+            if i == j {
+                dot + n as f64
+            } else {
+                dot
+            }
+        })
+    }
+
+    fn mul(a: &DMat<f64>, b: &DMat<f64>) -> DMat<f64> {
+        DMat::from_fn(a.nrows(), b.ncols(), |i, j| {
+            (0..a.ncols()).map(|k| a[(i, k)] * b[(k, j)]).sum()
+        })
+    }
+
+    fn assert_close(a: &DMat<f64>, b: &DMat<f64>, tol: f64) {
+        assert_eq!(a.shape(), b.shape());
+        for (x, y) in a.as_slice().iter().zip(b.as_slice()) {
+            assert!((x - y).abs() <= tol, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn construction() {
+        let m = mat![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+        let d = DMat::<f64>::from(m);
+        assert_eq!(d.shape(), (2, 3));
+        assert_eq!(d[(1, 0)], 4.0);
+        assert_eq!(d.row(1), &[4.0, 5.0, 6.0]);
+        assert_eq!(d.get(2, 0), None);
+        assert_eq!(d.to_mat::<2, 3>(), m);
+        assert_eq!(d.transpose().to_mat::<3, 2>(), m.transpose());
+        assert_eq!(
+            d.try_to_mat::<3, 2>(),
+            Err(LinalgError::DimensionMismatch {
+                expected: (3, 2),
+                found: (2, 3)
+            })
+        );
+        assert_eq!(
+            DMat::try_from_vec(2, 2, vec![1.0; 3]),
+            Err(LinalgError::DimensionMismatch {
+                expected: (4, 1),
+                found: (3, 1)
+            })
+        );
+        assert_eq!(DMat::<f64>::identity(2).as_slice(), &[1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn shape_methods() {
+        let m = mat![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+        let d = DMat::<f64>::from(m);
+        let e = DMat::from_mat(&mat![7.0; 8.0]);
+        assert_eq!(d.hstack(&e).to_mat::<2, 4>(), m.hstack(&mat![7.0; 8.0]));
+        assert_eq!(
+            d.vstack(&d.block(1, 0, 1, 3)).to_mat::<3, 3>(),
+            m.vstack(&mat![4.0, 5.0, 6.0])
+        );
+        assert_eq!(
+            d.try_hstack(&d.transpose()),
+            Err(LinalgError::DimensionMismatch {
+                expected: (2, 2),
+                found: (3, 2)
+            })
+        );
+        assert_eq!(
+            d.try_vstack(&e),
+            Err(LinalgError::DimensionMismatch {
+                expected: (2, 3),
+                found: (2, 1)
+            })
+        );
+
+        assert_eq!(d.block(0, 1, 2, 2).to_mat::<2, 2>(), m.fixed_block(0, 1));
+        assert_eq!(
+            d.try_block(1, 1, 2, 2),
+            Err(LinalgError::DimensionMismatch {
+                expected: (3, 3),
+                found: (2, 3)
+            })
+        );
+        let mut s = d.clone();
+        s.set_block(0, 2, &e);
+        let mut expected = m;
+        expected.set_block(0, 2, &mat![7.0; 8.0]);
+        assert_eq!(s.to_mat::<2, 3>(), expected);
+        assert!(s.try_set_block(1, 2, &e).is_err());
+        assert!(s.try_set_block(usize::MAX, 0, &e).is_err());
+
+        assert_eq!(d.reshape(3, 2).to_mat::<3, 2>(), m.reshape());
+        assert_eq!(
+            d.try_reshape(4, 2),
+            Err(LinalgError::DimensionMismatch {
+                expected: (8, 1),
+                found: (6, 1)
+            })
+        );
+
+        assert_eq!(
+            d.insert_row(1, &[0.0; 3]).to_mat::<3, 3>(),
+            m.insert_row(1, [0.0; 3])
+        );
+        assert_eq!(d.insert_row(2, &[0.0; 3]).row(2), &[0.0; 3]);
+        assert_eq!(
+            d.try_insert_row(0, &[0.0; 2]),
+            Err(LinalgError::DimensionMismatch {
+                expected: (1, 3),
+                found: (1, 2)
+            })
+        );
+        assert!(d.try_insert_row(3, &[0.0; 3]).is_err());
+        assert_eq!(d.remove_row(0).to_mat::<1, 3>(), m.remove_row(0));
+        assert!(d.try_remove_row(2).is_err());
+
+        assert_eq!(
+            d.insert_column(3, &[9.0, 9.0]).to_mat::<2, 4>(),
+            m.insert_column(3, [9.0, 9.0])
+        );
+        assert_eq!(
+            d.try_insert_column(1, &[9.0; 3]),
+            Err(LinalgError::DimensionMismatch {
+                expected: (2, 1),
+                found: (3, 1)
+            })
+        );
+        assert_eq!(d.remove_column(1).to_mat::<2, 2>(), m.remove_column(1));
+        assert_eq!(
+            d.try_remove_column(3),
+            Err(LinalgError::DimensionMismatch {
+                expected: (2, 4),
+                found: (2, 3)
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a 3x3 matrix, found 2x3")]
+    fn block_out_of_bounds() {
+        DMat::<f64>::zero(2, 3).block(2, 0, 1, 1);
+    }
+
+    #[test]
+    fn product() {
+        let a = mat![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+        let b = mat![1.0, 0.0; 0.0, 1.0; 1.0, 1.0];
+        let (da, db) = (DMat::<f64>::from(a), DMat::<f64>::from(b));
+        assert_eq!((&da * &db).to_mat::<2, 2>(), a * b);
+        assert_eq!(
+            da.try_mul(&da).unwrap_err(),
+            LinalgError::DimensionMismatch {
+                expected: (3, 3),
+                found: (2, 3)
+            }
+        );
+
+        // large enough for the blocked kernel
+        assert!(gemm::use_blocked(60, 50, 70));
+        let (a, b) = (random(15, 60, 70), random(16, 70, 50));
+        assert_close(&(&a * &b), &mul(&a, &b), 1e-12);
+
+        let mut c = random(17, 60, 60);
+        let expected = &c * &c;
+        c *= &c.clone();
+        assert_eq!(c, expected);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = mat![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+        let b = mat![0.5, 0.0, -1.0; 2.0, 1.0, 0.0];
+        let (da, db) = (DMat::<f64>::from(a), DMat::<f64>::from(b));
+        assert_eq!((&da + &db).to_mat::<2, 3>(), a + b);
+        assert_eq!((da.clone() - &db).to_mat::<2, 3>(), a - b);
+        assert_eq!((-&da).to_mat::<2, 3>(), -a);
+        assert_eq!(
+            (da.clone() * db.transpose() + &da * &db.transpose()).to_mat::<2, 2>(),
+            a * b.transpose() + a * b.transpose()
+        );
+        assert_eq!(
+            da.try_add(&db.transpose()),
+            Err(LinalgError::DimensionMismatch {
+                expected: (2, 3),
+                found: (3, 2)
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot subtract 3x2 from 2x3")]
+    fn sub_mismatch() {
+        let a = DMat::<f64>::zero(2, 3);
+        let _ = &a - &a.transpose();
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot multiply 2x3 by 2x3")]
+    fn product_mismatch() {
+        let a = DMat::<f64>::zero(2, 3);
+        let _ = a.clone() * a;
+    }
+
+    #[test]
+    fn lu() {
+        let a = random(1, 20, 20);
+        let lu = a.lu();
+        let mut pa = a.clone();
+        for (i, &p) in lu.pivots().iter().enumerate() {
+            pa.swap_rows(i, p);
+        }
+        assert_close(&mul(&lu.l(), &lu.u()), &pa, TOL);
+
+        let b = random(2, 20, 3);
+        assert_close(&mul(&a, &a.solve(&b)), &b, 1e-10);
+
+        let small = mat![2.0, 1.0; 1.0, 3.0];
+        assert!((DMat::<f64>::from(small).lu().determinant() - 5.0).abs() < TOL);
+    }
+
+    #[test]
+    fn lu_errors() {
+        let singular = DMat::<f64>::from(mat![1.0, 2.0; 2.0, 4.0]);
+        assert_eq!(singular.try_lu().unwrap_err(), LinalgError::Singular);
+        assert_eq!(
+            random(1, 2, 3).try_lu().unwrap_err(),
+            LinalgError::DimensionMismatch {
+                expected: (2, 2),
+                found: (2, 3)
+            }
+        );
+        assert_eq!(
+            DMat::<f64>::identity(2)
+                .lu()
+                .try_solve(&DMat::zero(3, 1))
+                .unwrap_err(),
+            LinalgError::DimensionMismatch {
+                expected: (2, 1),
+                found: (3, 1)
+            }
+        );
+    }
+
+    #[test]
+    fn cholesky() {
+        let a = spd(3, 15);
+        let chol = a.cholesky();
+        assert_close(&mul(chol.l(), &chol.l().transpose()), &a, 1e-10);
+        let b = random(4, 15, 2);
+        assert_close(&mul(&a, &chol.solve(&b)), &b, 1e-10);
+
+        let indefinite = DMat::<f64>::from(mat![1.0, 2.0; 2.0, 1.0]);
+        assert_eq!(
+            indefinite.try_cholesky().unwrap_err(),
+            LinalgError::NotPositiveDefinite
+        );
+    }
+
+    #[test]
+    fn qr() {
+        for (m, n) in [(12, 5), (5, 12), (7, 7)] {
+            let a = random(5, m, n);
+            let qr = a.qr();
+            let q = qr.q();
+            assert_close(&mul(&q, &qr.r()), &a, TOL);
+            assert_close(&mul(&q.transpose(), &q), &DMat::identity(m.min(n)), TOL);
+        }
+
+        // the least squares solution satisfies the normal equations
+        let (a, b) = (random(6, 12, 5), random(7, 12, 1));
+        let x = a.qr().solve(&b);
+        let r = DMat::from_fn(12, 1, |i, _| b[(i, 0)] - mul(&a, &x)[(i, 0)]);
+        let at_r = mul(&a.transpose(), &r);
+        assert!(at_r.as_slice().iter().all(|x| x.abs() < 1e-12));
+
+        assert_eq!(
+            random(8, 3, 5)
+                .qr()
+                .try_solve(&DMat::zero(3, 1))
+                .unwrap_err(),
+            LinalgError::DimensionMismatch {
+                expected: (5, 5),
+                found: (3, 5)
+            }
+        );
+    }
+
+    #[test]
+    fn eigh() {
+        let a = spd(9, 10);
+        let eigh = a.eigh();
+        assert!(eigh.values.windows(2).all(|w| w[0] <= w[1]));
+        let v = &eigh.vectors;
+        let lambda = DMat::from_fn(10, 10, |i, j| if i == j { eigh.values[i] } else { 0.0 });
+        assert_close(&mul(&mul(v, &lambda), &v.transpose()), &a, 1e-10);
+    }
+
+    #[test]
+    fn svd() {
+        for (m, n) in [(9, 4), (4, 9)] {
+            let a = random(10, m, n);
+            let svd = a.svd();
+            assert!(svd.s.windows(2).all(|w| w[0] >= w[1]));
+            let k = m.min(n);
+            let sigma = DMat::from_fn(k, k, |i, j| if i == j { svd.s[i] } else { 0.0 });
+            assert_close(&mul(&mul(&svd.u, &sigma), &svd.vt), &a, 1e-12);
+        }
+    }
+
+    #[test]
+    fn dispatches_to_backend() {
+        let backend = Counting::default();
+        let a = spd(11, 6);
+        let b = random(12, 6, 1);
+
+        let lu = a.try_lu_with(&backend).unwrap();
+        assert_eq!(backend.calls(), 1);
+        lu.solve(&b);
+        assert_eq!(backend.calls(), 3);
+
+        let x = a.try_solve_with(&backend, &b).unwrap();
+        assert_eq!(backend.calls(), 6);
+        assert_close(&x, &a.solve(&b), TOL);
+
+        a.try_cholesky_with(&backend).unwrap().solve(&b);
+        a.qr_with(&backend).solve(&b);
+        a.try_eigh_with(&backend).unwrap();
+        a.try_svd_with(&backend).unwrap();
+        assert_eq!(backend.calls(), 14);
+    }
+
+    #[cfg(all(feature = "lapack", lapack_linked))]
+    #[test]
+    fn lapack_matches_native() {
+        use crate::backend::Lapack;
+
+        let a = spd(13, 30);
+        let b = random(14, 30, 2);
+        assert_close(&a.try_solve_with(&Lapack, &b).unwrap(), &a.solve(&b), 1e-10);
+        let (x, y) = (a.try_eigh_with(&Lapack).unwrap(), a.eigh());
+        for (x, y) in x.values.iter().zip(&y.values) {
+            assert!((x - y).abs() < 1e-10);
+        }
+    }
+}
@@ -0,0 +1,1325 @@
+use super::matrix::Mat;
+use core::cmp::Ordering;
+use core::fmt::{self, Display};
+use core::num::FpCategory;
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, Sub, SubAssign};
+use num::{Float, Num, NumCast, One, ToPrimitive, Zero};
+
+// Forward-mode automatic differentiation. A dual number a + b1 e1 + ... +
+// bN eN with ei ej = 0 carries a value together with its derivatives in N
+// directions, and every operation applies the chain rule to them:
+//
+//   f(a + b e) = f(a) + f'(a) b e
+//
+// `DualN` implements `num::Float`, so it can be used as the scalar of `Mat`
+// and the vector types, and code written against them can be differentiated
+// unchanged. Seeding each of N inputs with its own direction gives a whole
+// Jacobian in one evaluation; `Dual<T>` is the single-direction case.
+//
+// `HyperDual` adds a second pair of directions and their cross term,
+// a + b e1 + c e2 + d e1e2 with e1^2 = e2^2 = 0, which carries second
+// derivatives exactly:
+//
+//   f(a + e1 + e2) = f(a) + f'(a) e1 + f'(a) e2 + f''(a) e1e2
+//
+// Comparisons, like the rest of `Float` (`classify`, `floor`,
+// `integer_decode`, ...), only look at the value, so generic code branches
+// the same way it would on plain numbers and differentiates the branch it
+// takes; use the approximate comparisons in `approx` to compare derivatives
+// too. Functions that are piecewise constant (floor, round, signum, ...) have
+// zero derivatives.
+#[derive(Debug, Clone, Copy)]
+pub struct DualN<T: Float, const N: usize> {
+    pub re: T,
+    pub eps: [T; N],
+}
+
+pub type Dual<T> = DualN<T, 1>;
+
+impl<T: Float, const N: usize> DualN<T, N> {
+    pub const fn new(re: T, eps: [T; N]) -> Self {
+        Self { re, eps }
+    }
+
+    // A value with zero derivatives.
+    pub fn constant(re: T) -> Self {
+        Self::new(re, [T::zero(); N])
+    }
+
+    // The input variable for direction i: a derivative of one there and zero
+    // elsewhere.
+    pub fn variable(re: T, i: usize) -> Self {
+        let mut eps = [T::zero(); N];
+        eps[i] = T::one();
+        Self::new(re, eps)
+    }
+
+    // f(self), given f(re) and f'(re).
+    fn chain(self, value: T, derivative: T) -> Self {
+        Self::new(value, self.eps.map(|e| e * derivative))
+    }
+}
+
+// f(x) and f'(x) for a scalar function of one variable.
+pub fn derivative<T: Float>(f: impl Fn(Dual<T>) -> Dual<T>, x: T) -> (T, T) {
+    let y = f(Dual::variable(x, 0));
+    (y.re, y.eps[0])
+}
+
+// The M x N Jacobian of f at x, from a single evaluation of f.
+pub fn jacobian<T: Float, const M: usize, const N: usize>(
+    f: impl Fn(&Mat<DualN<T, N>, N, 1>) -> Mat<DualN<T, N>, M, 1>,
+    x: &Mat<T, N, 1>,
+) -> Mat<T, M, N> {
+    let y = f(&seed(x));
+    let mut out = Mat::zero();
+    for i in 0..M {
+        out[i] = y[i][0].eps;
+    }
+    out
+}
+
+// The gradient of the scalar function f at x, from a single evaluation of f.
+pub fn gradient<T: Float, const N: usize>(
+    f: impl Fn(&Mat<DualN<T, N>, N, 1>) -> DualN<T, N>,
+    x: &Mat<T, N, 1>,
+) -> Mat<T, N, 1> {
+    Mat::from_slice(&f(&seed(x)).eps)
+}
+
+fn seed<T: Float, const N: usize>(x: &Mat<T, N, 1>) -> Mat<DualN<T, N>, N, 1> {
+    let mut out = Mat::zero();
+    for j in 0..N {
+        out[j][0] = DualN::variable(x[j][0], j);
+    }
+    out
+}
+
+impl<T: Float, const N: usize> Default for DualN<T, N> {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<T: Float + Display, const N: usize> Display for DualN<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.re)?;
+        for (i, e) in self.eps.iter().enumerate() {
+            if N == 1 {
+                write!(f, " + {}ε", e)?;
+            } else {
+                write!(f, " + {}ε{}", e, i)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Float, const N: usize> PartialEq for DualN<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.re == other.re
+    }
+}
+
+impl<T: Float, const N: usize> PartialOrd for DualN<T, N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.re.partial_cmp(&other.re)
+    }
+}
+
+impl<T: Float, const N: usize> Neg for DualN<T, N> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, self.eps.map(|e| -e))
+    }
+}
+
+impl<T: Float, const N: usize> Add for DualN<T, N> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let mut eps = self.eps;
+        for (e, &r) in eps.iter_mut().zip(&rhs.eps) {
+            *e = *e + r;
+        }
+        Self::new(self.re + rhs.re, eps)
+    }
+}
+
+impl<T: Float, const N: usize> Sub for DualN<T, N> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl<T: Float, const N: usize> Mul for DualN<T, N> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let mut eps = self.eps;
+        for (e, &r) in eps.iter_mut().zip(&rhs.eps) {
+            *e = *e * rhs.re + self.re * r;
+        }
+        Self::new(self.re * rhs.re, eps)
+    }
+}
+
+impl<T: Float, const N: usize> Div for DualN<T, N> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let re = self.re / rhs.re;
+        let mut eps = self.eps;
+        for (e, &r) in eps.iter_mut().zip(&rhs.eps) {
+            *e = (*e - re * r) / rhs.re;
+        }
+        Self::new(re, eps)
+    }
+}
+
+// a % b = a - b trunc(a / b), where the truncation is locally constant
+impl<T: Float, const N: usize> Rem for DualN<T, N> {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self {
+        let q = (self.re / rhs.re).trunc();
+        let mut eps = self.eps;
+        for (e, &r) in eps.iter_mut().zip(&rhs.eps) {
+            *e = *e - q * r;
+        }
+        Self::new(self.re % rhs.re, eps)
+    }
+}
+
+macro_rules! impl_assign {
+    ($($trait:ident $method:ident $op:tt),*) => {
+        $(
+            impl<T: Float, const N: usize> $trait for DualN<T, N> {
+                fn $method(&mut self, rhs: Self) {
+                    *self = *self $op rhs;
+                }
+            }
+        )*
+    };
+}
+
+impl_assign!(AddAssign add_assign +, SubAssign sub_assign -, MulAssign mul_assign *, DivAssign div_assign /);
+
+impl<T: Float, const N: usize> Zero for DualN<T, N> {
+    fn zero() -> Self {
+        Self::constant(T::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.re.is_zero()
+    }
+}
+
+impl<T: Float, const N: usize> One for DualN<T, N> {
+    fn one() -> Self {
+        Self::constant(T::one())
+    }
+}
+
+impl<T: Float, const N: usize> Num for DualN<T, N> {
+    type FromStrRadixErr = T::FromStrRadixErr;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        T::from_str_radix(s, radix).map(Self::constant)
+    }
+}
+
+impl<T: Float, const N: usize> ToPrimitive for DualN<T, N> {
+    fn to_i64(&self) -> Option<i64> {
+        self.re.to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.re.to_u64()
+    }
+
+    fn to_f32(&self) -> Option<f32> {
+        self.re.to_f32()
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        self.re.to_f64()
+    }
+}
+
+impl<T: Float, const N: usize> NumCast for DualN<T, N> {
+    fn from<U: ToPrimitive>(n: U) -> Option<Self> {
+        T::from(n).map(Self::constant)
+    }
+}
+
+impl<T: Float, const N: usize> Float for DualN<T, N> {
+    fn nan() -> Self {
+        Self::constant(T::nan())
+    }
+
+    fn infinity() -> Self {
+        Self::constant(T::infinity())
+    }
+
+    fn neg_infinity() -> Self {
+        Self::constant(T::neg_infinity())
+    }
+
+    fn neg_zero() -> Self {
+        Self::constant(T::neg_zero())
+    }
+
+    fn min_value() -> Self {
+        Self::constant(T::min_value())
+    }
+
+    fn min_positive_value() -> Self {
+        Self::constant(T::min_positive_value())
+    }
+
+    fn epsilon() -> Self {
+        Self::constant(T::epsilon())
+    }
+
+    fn max_value() -> Self {
+        Self::constant(T::max_value())
+    }
+
+    fn is_nan(self) -> bool {
+        self.re.is_nan()
+    }
+
+    fn is_infinite(self) -> bool {
+        self.re.is_infinite()
+    }
+
+    fn is_finite(self) -> bool {
+        self.re.is_finite()
+    }
+
+    fn is_normal(self) -> bool {
+        self.re.is_normal()
+    }
+
+    fn classify(self) -> FpCategory {
+        self.re.classify()
+    }
+
+    fn floor(self) -> Self {
+        Self::constant(self.re.floor())
+    }
+
+    fn ceil(self) -> Self {
+        Self::constant(self.re.ceil())
+    }
+
+    fn round(self) -> Self {
+        Self::constant(self.re.round())
+    }
+
+    fn trunc(self) -> Self {
+        Self::constant(self.re.trunc())
+    }
+
+    fn fract(self) -> Self {
+        Self::new(self.re.fract(), self.eps)
+    }
+
+    fn abs(self) -> Self {
+        if self.re.is_sign_negative() {
+            -self
+        } else {
+            self
+        }
+    }
+
+    fn signum(self) -> Self {
+        Self::constant(self.re.signum())
+    }
+
+    fn is_sign_positive(self) -> bool {
+        self.re.is_sign_positive()
+    }
+
+    fn is_sign_negative(self) -> bool {
+        self.re.is_sign_negative()
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+
+    fn recip(self) -> Self {
+        let re = self.re.recip();
+        self.chain(re, -re * re)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        if n == 0 {
+            return Self::one();
+        }
+        let value = self.re.powi(n);
+        self.chain(value, T::from(n).unwrap() * self.re.powi(n - 1))
+    }
+
+    // The ln(x) term only enters through derivatives of the exponent, so a
+    // negative base with a constant exponent stays finite.
+    #[allow(clippy::needless_range_loop)]
+    fn powf(self, n: Self) -> Self {
+        let value = self.re.powf(n.re);
+        let base = n.re * self.re.powf(n.re - T::one());
+        let mut eps = [T::zero(); N];
+        for i in 0..N {
+            if self.eps[i] != T::zero() {
+                eps[i] = self.eps[i] * base;
+            }
+            if n.eps[i] != T::zero() {
+                eps[i] = eps[i] + n.eps[i] * value * self.re.ln();
+            }
+        }
+        Self::new(value, eps)
+    }
+
+    fn sqrt(self) -> Self {
+        let value = self.re.sqrt();
+        self.chain(value, (value + value).recip())
+    }
+
+    fn exp(self) -> Self {
+        let value = self.re.exp();
+        self.chain(value, value)
+    }
+
+    fn exp2(self) -> Self {
+        let value = self.re.exp2();
+        self.chain(value, value * T::from(2).unwrap().ln())
+    }
+
+    fn ln(self) -> Self {
+        self.chain(self.re.ln(), self.re.recip())
+    }
+
+    fn log(self, base: Self) -> Self {
+        self.ln() / base.ln()
+    }
+
+    fn log2(self) -> Self {
+        let ln2 = T::from(2).unwrap().ln();
+        self.chain(self.re.log2(), (self.re * ln2).recip())
+    }
+
+    fn log10(self) -> Self {
+        let ln10 = T::from(10).unwrap().ln();
+        self.chain(self.re.log10(), (self.re * ln10).recip())
+    }
+
+    fn max(self, other: Self) -> Self {
+        if self.re.is_nan() || other.re > self.re {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if self.re.is_nan() || other.re < self.re {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        (self - other).max(Self::zero())
+    }
+
+    fn cbrt(self) -> Self {
+        let value = self.re.cbrt();
+        self.chain(value, (T::from(3).unwrap() * value * value).recip())
+    }
+
+    #[allow(clippy::needless_range_loop)]
+    fn hypot(self, other: Self) -> Self {
+        let value = self.re.hypot(other.re);
+        let mut eps = [T::zero(); N];
+        if value != T::zero() {
+            for i in 0..N {
+                eps[i] = (self.re * self.eps[i] + other.re * other.eps[i]) / value;
+            }
+        }
+        Self::new(value, eps)
+    }
+
+    fn sin(self) -> Self {
+        self.chain(self.re.sin(), self.re.cos())
+    }
+
+    fn cos(self) -> Self {
+        self.chain(self.re.cos(), -self.re.sin())
+    }
+
+    fn tan(self) -> Self {
+        let value = self.re.tan();
+        self.chain(value, T::one() + value * value)
+    }
+
+    fn asin(self) -> Self {
+        let d = (T::one() - self.re * self.re).sqrt().recip();
+        self.chain(self.re.asin(), d)
+    }
+
+    fn acos(self) -> Self {
+        let d = -(T::one() - self.re * self.re).sqrt().recip();
+        self.chain(self.re.acos(), d)
+    }
+
+    fn atan(self) -> Self {
+        self.chain(self.re.atan(), (T::one() + self.re * self.re).recip())
+    }
+
+    // atan2(y, x) with self = y, differentiated in both arguments
+    #[allow(clippy::needless_range_loop)]
+    fn atan2(self, other: Self) -> Self {
+        let (y, x) = (self.re, other.re);
+        let r2 = x * x + y * y;
+        let mut eps = [T::zero(); N];
+        for i in 0..N {
+            eps[i] = (x * self.eps[i] - y * other.eps[i]) / r2;
+        }
+        Self::new(y.atan2(x), eps)
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        (self.sin(), self.cos())
+    }
+
+    fn exp_m1(self) -> Self {
+        self.chain(self.re.exp_m1(), self.re.exp())
+    }
+
+    fn ln_1p(self) -> Self {
+        self.chain(self.re.ln_1p(), (T::one() + self.re).recip())
+    }
+
+    fn sinh(self) -> Self {
+        self.chain(self.re.sinh(), self.re.cosh())
+    }
+
+    fn cosh(self) -> Self {
+        self.chain(self.re.cosh(), self.re.sinh())
+    }
+
+    fn tanh(self) -> Self {
+        let value = self.re.tanh();
+        self.chain(value, T::one() - value * value)
+    }
+
+    fn asinh(self) -> Self {
+        let d = (self.re * self.re + T::one()).sqrt().recip();
+        self.chain(self.re.asinh(), d)
+    }
+
+    fn acosh(self) -> Self {
+        let d = (self.re * self.re - T::one()).sqrt().recip();
+        self.chain(self.re.acosh(), d)
+    }
+
+    fn atanh(self) -> Self {
+        self.chain(self.re.atanh(), (T::one() - self.re * self.re).recip())
+    }
+
+    fn integer_decode(self) -> (u64, i16, i8) {
+        self.re.integer_decode()
+    }
+}
+
+// A hyper-dual number re + e1 ε1 + e2 ε2 + e12 ε1ε2, for exact second
+// derivatives. Seeding e1 and e2 with the same direction gives f'' in e12, and
+// two different directions give a mixed partial derivative.
+#[derive(Debug, Clone, Copy)]
+pub struct HyperDual<T: Float> {
+    pub re: T,
+    pub e1: T,
+    pub e2: T,
+    pub e12: T,
+}
+
+impl<T: Float> HyperDual<T> {
+    pub const fn new(re: T, e1: T, e2: T, e12: T) -> Self {
+        Self { re, e1, e2, e12 }
+    }
+
+    // A value with zero derivatives.
+    pub fn constant(re: T) -> Self {
+        let zero = T::zero();
+        Self::new(re, zero, zero, zero)
+    }
+
+    // The input variable for a second derivative: a derivative of one in
+    // both directions.
+    pub fn variable(re: T) -> Self {
+        let (zero, one) = (T::zero(), T::one());
+        Self::new(re, one, one, zero)
+    }
+
+    // f(self), given f(re), f'(re) and f''(re).
+    fn chain(self, value: T, first: T, second: T) -> Self {
+        Self::new(
+            value,
+            first * self.e1,
+            first * self.e2,
+            first * self.e12 + second * self.e1 * self.e2,
+        )
+    }
+
+    fn is_constant(self) -> bool {
+        self.e1 == T::zero() && self.e2 == T::zero() && self.e12 == T::zero()
+    }
+}
+
+// f(x), f'(x) and f''(x) for a scalar function of one variable.
+pub fn second_derivative<T: Float>(f: impl Fn(HyperDual<T>) -> HyperDual<T>, x: T) -> (T, T, T) {
+    let y = f(HyperDual::variable(x));
+    (y.re, y.e1, y.e12)
+}
+
+// The N x N Hessian of the scalar function f at x, from N (N + 1) / 2
+// evaluations of f, one per entry on and above the diagonal.
+pub fn hessian<T: Float, const N: usize>(
+    f: impl Fn(&Mat<HyperDual<T>, N, 1>) -> HyperDual<T>,
+    x: &Mat<T, N, 1>,
+) -> Mat<T, N, N> {
+    let (zero, one) = (T::zero(), T::one());
+    let mut out = Mat::zero();
+    for i in 0..N {
+        for j in i..N {
+            let mut seeded = Mat::zero();
+            for k in 0..N {
+                let e1 = if k == i { one } else { zero };
+                let e2 = if k == j { one } else { zero };
+                seeded[k][0] = HyperDual::new(x[k][0], e1, e2, zero);
+            }
+            let h = f(&seeded).e12;
+            out[i][j] = h;
+            out[j][i] = h;
+        }
+    }
+    out
+}
+
+impl<T: Float> Default for HyperDual<T> {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<T: Float + Display> Display for HyperDual<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} + {}ε1 + {}ε2 + {}ε1ε2",
+            self.re, self.e1, self.e2, self.e12
+        )
+    }
+}
+
+impl<T: Float> PartialEq for HyperDual<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.re == other.re
+    }
+}
+
+impl<T: Float> PartialOrd for HyperDual<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.re.partial_cmp(&other.re)
+    }
+}
+
+impl<T: Float> Neg for HyperDual<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.e1, -self.e2, -self.e12)
+    }
+}
+
+impl<T: Float> Add for HyperDual<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(
+            self.re + rhs.re,
+            self.e1 + rhs.e1,
+            self.e2 + rhs.e2,
+            self.e12 + rhs.e12,
+        )
+    }
+}
+
+impl<T: Float> Sub for HyperDual<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl<T: Float> Mul for HyperDual<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re,
+            self.e1 * rhs.re + self.re * rhs.e1,
+            self.e2 * rhs.re + self.re * rhs.e2,
+            self.e12 * rhs.re + self.e1 * rhs.e2 + self.e2 * rhs.e1 + self.re * rhs.e12,
+        )
+    }
+}
+
+impl<T: Float> Div for HyperDual<T> {
+    type Output = Self;
+
+    // solves self = out * rhs term by term
+    fn div(self, rhs: Self) -> Self {
+        let re = self.re / rhs.re;
+        let e1 = (self.e1 - re * rhs.e1) / rhs.re;
+        let e2 = (self.e2 - re * rhs.e2) / rhs.re;
+        let e12 = (self.e12 - re * rhs.e12 - e1 * rhs.e2 - e2 * rhs.e1) / rhs.re;
+        Self::new(re, e1, e2, e12)
+    }
+}
+
+// a % b = a - b trunc(a / b), where the truncation is locally constant
+impl<T: Float> Rem for HyperDual<T> {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self {
+        let q = (self.re / rhs.re).trunc();
+        Self::new(
+            self.re % rhs.re,
+            self.e1 - q * rhs.e1,
+            self.e2 - q * rhs.e2,
+            self.e12 - q * rhs.e12,
+        )
+    }
+}
+
+macro_rules! impl_hyper_assign {
+    ($($trait:ident $method:ident $op:tt),*) => {
+        $(
+            impl<T: Float> $trait for HyperDual<T> {
+                fn $method(&mut self, rhs: Self) {
+                    *self = *self $op rhs;
+                }
+            }
+        )*
+    };
+}
+
+impl_hyper_assign!(AddAssign add_assign +, SubAssign sub_assign -, MulAssign mul_assign *, DivAssign div_assign /);
+
+impl<T: Float> Zero for HyperDual<T> {
+    fn zero() -> Self {
+        Self::constant(T::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.re.is_zero()
+    }
+}
+
+impl<T: Float> One for HyperDual<T> {
+    fn one() -> Self {
+        Self::constant(T::one())
+    }
+}
+
+impl<T: Float> Num for HyperDual<T> {
+    type FromStrRadixErr = T::FromStrRadixErr;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        T::from_str_radix(s, radix).map(Self::constant)
+    }
+}
+
+impl<T: Float> ToPrimitive for HyperDual<T> {
+    fn to_i64(&self) -> Option<i64> {
+        self.re.to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.re.to_u64()
+    }
+
+    fn to_f32(&self) -> Option<f32> {
+        self.re.to_f32()
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        self.re.to_f64()
+    }
+}
+
+impl<T: Float> NumCast for HyperDual<T> {
+    fn from<U: ToPrimitive>(n: U) -> Option<Self> {
+        T::from(n).map(Self::constant)
+    }
+}
+
+impl<T: Float> Float for HyperDual<T> {
+    fn nan() -> Self {
+        Self::constant(T::nan())
+    }
+
+    fn infinity() -> Self {
+        Self::constant(T::infinity())
+    }
+
+    fn neg_infinity() -> Self {
+        Self::constant(T::neg_infinity())
+    }
+
+    fn neg_zero() -> Self {
+        Self::constant(T::neg_zero())
+    }
+
+    fn min_value() -> Self {
+        Self::constant(T::min_value())
+    }
+
+    fn min_positive_value() -> Self {
+        Self::constant(T::min_positive_value())
+    }
+
+    fn epsilon() -> Self {
+        Self::constant(T::epsilon())
+    }
+
+    fn max_value() -> Self {
+        Self::constant(T::max_value())
+    }
+
+    fn is_nan(self) -> bool {
+        self.re.is_nan()
+    }
+
+    fn is_infinite(self) -> bool {
+        self.re.is_infinite()
+    }
+
+    fn is_finite(self) -> bool {
+        self.re.is_finite()
+    }
+
+    fn is_normal(self) -> bool {
+        self.re.is_normal()
+    }
+
+    fn classify(self) -> FpCategory {
+        self.re.classify()
+    }
+
+    fn floor(self) -> Self {
+        Self::constant(self.re.floor())
+    }
+
+    fn ceil(self) -> Self {
+        Self::constant(self.re.ceil())
+    }
+
+    fn round(self) -> Self {
+        Self::constant(self.re.round())
+    }
+
+    fn trunc(self) -> Self {
+        Self::constant(self.re.trunc())
+    }
+
+    fn fract(self) -> Self {
+        Self {
+            re: self.re.fract(),
+            ..self
+        }
+    }
+
+    fn abs(self) -> Self {
+        if self.re.is_sign_negative() {
+            -self
+        } else {
+            self
+        }
+    }
+
+    fn signum(self) -> Self {
+        Self::constant(self.re.signum())
+    }
+
+    fn is_sign_positive(self) -> bool {
+        self.re.is_sign_positive()
+    }
+
+    fn is_sign_negative(self) -> bool {
+        self.re.is_sign_negative()
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+
+    fn recip(self) -> Self {
+        let re = self.re.recip();
+        self.chain(re, -re * re, (re + re) * re * re)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        match n {
+            0 => Self::one(),
+            1 => self,
+            _ => {
+                let k = T::from(n).unwrap();
+                let x = self.re;
+                let second = k * (k - T::one()) * x.powi(n - 2);
+                self.chain(x.powi(n), k * x.powi(n - 1), second)
+            }
+        }
+    }
+
+    // As for `DualN`, the ln(x) terms only enter through derivatives of the
+    // exponent, so a negative base with a constant exponent stays finite.
+    fn powf(self, n: Self) -> Self {
+        let (x, p) = (self.re, n.re);
+        if n.is_constant() {
+            let second = p * (p - T::one()) * x.powf(p - T::one() - T::one());
+            self.chain(x.powf(p), p * x.powf(p - T::one()), second)
+        } else if self.is_constant() {
+            let (value, ln) = (x.powf(p), x.ln());
+            n.chain(value, value * ln, value * ln * ln)
+        } else {
+            (n * self.ln()).exp()
+        }
+    }
+
+    fn sqrt(self) -> Self {
+        let value = self.re.sqrt();
+        let first = (value + value).recip();
+        self.chain(value, first, -first / (self.re + self.re))
+    }
+
+    fn exp(self) -> Self {
+        let value = self.re.exp();
+        self.chain(value, value, value)
+    }
+
+    fn exp2(self) -> Self {
+        let value = self.re.exp2();
+        let ln2 = T::from(2).unwrap().ln();
+        self.chain(value, value * ln2, value * ln2 * ln2)
+    }
+
+    fn ln(self) -> Self {
+        let first = self.re.recip();
+        self.chain(self.re.ln(), first, -first * first)
+    }
+
+    fn log(self, base: Self) -> Self {
+        self.ln() / base.ln()
+    }
+
+    fn log2(self) -> Self {
+        let ln2 = T::from(2).unwrap().ln();
+        self.ln().chain(self.re.log2(), ln2.recip(), T::zero())
+    }
+
+    fn log10(self) -> Self {
+        let ln10 = T::from(10).unwrap().ln();
+        self.ln().chain(self.re.log10(), ln10.recip(), T::zero())
+    }
+
+    fn max(self, other: Self) -> Self {
+        if self.re.is_nan() || other.re > self.re {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if self.re.is_nan() || other.re < self.re {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        (self - other).max(Self::zero())
+    }
+
+    fn cbrt(self) -> Self {
+        let value = self.re.cbrt();
+        let first = (T::from(3).unwrap() * value * value).recip();
+        let second = -(first + first) / (T::from(3).unwrap() * self.re);
+        self.chain(value, first, second)
+    }
+
+    // Scaled by the value, so that neither the squares nor their derivatives
+    // overflow. The derivatives are undefined at the origin and left at zero.
+    fn hypot(self, other: Self) -> Self {
+        let value = self.re.hypot(other.re);
+        if value == T::zero() || !value.is_finite() {
+            return Self::constant(value);
+        }
+        let scale = Self::constant(value);
+        let (x, y) = (self / scale, other / scale);
+        let mut out = (x * x + y * y).sqrt() * scale;
+        out.re = value;
+        out
+    }
+
+    fn sin(self) -> Self {
+        let (sin, cos) = self.re.sin_cos();
+        self.chain(sin, cos, -sin)
+    }
+
+    fn cos(self) -> Self {
+        let (sin, cos) = self.re.sin_cos();
+        self.chain(cos, -sin, -cos)
+    }
+
+    fn tan(self) -> Self {
+        let value = self.re.tan();
+        let first = T::one() + value * value;
+        self.chain(value, first, (value + value) * first)
+    }
+
+    fn asin(self) -> Self {
+        let first = (T::one() - self.re * self.re).sqrt().recip();
+        self.chain(self.re.asin(), first, self.re * first * first * first)
+    }
+
+    fn acos(self) -> Self {
+        let first = (T::one() - self.re * self.re).sqrt().recip();
+        self.chain(self.re.acos(), -first, -self.re * first * first * first)
+    }
+
+    fn atan(self) -> Self {
+        let first = (T::one() + self.re * self.re).recip();
+        self.chain(self.re.atan(), first, -(self.re + self.re) * first * first)
+    }
+
+    // atan2(y, x) with self = y, differentiated in both arguments. Rotating
+    // (x, y) by -atan2(y, x) puts it on the positive x axis, where atan(y / x)
+    // carries the derivatives; they are undefined at the origin and left at
+    // zero there.
+    fn atan2(self, other: Self) -> Self {
+        let angle = self.re.atan2(other.re);
+        if self.re == T::zero() && other.re == T::zero() {
+            return Self::constant(angle);
+        }
+        let (sin, cos) = (Self::constant(angle.sin()), Self::constant(angle.cos()));
+        let x = other * cos + self * sin;
+        let y = self * cos - other * sin;
+        let mut out = (y / x).atan();
+        out.re = angle;
+        out
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        (self.sin(), self.cos())
+    }
+
+    fn exp_m1(self) -> Self {
+        let exp = self.re.exp();
+        self.chain(self.re.exp_m1(), exp, exp)
+    }
+
+    fn ln_1p(self) -> Self {
+        let first = (T::one() + self.re).recip();
+        self.chain(self.re.ln_1p(), first, -first * first)
+    }
+
+    fn sinh(self) -> Self {
+        let (sinh, cosh) = (self.re.sinh(), self.re.cosh());
+        self.chain(sinh, cosh, sinh)
+    }
+
+    fn cosh(self) -> Self {
+        let (sinh, cosh) = (self.re.sinh(), self.re.cosh());
+        self.chain(cosh, sinh, cosh)
+    }
+
+    fn tanh(self) -> Self {
+        let value = self.re.tanh();
+        let first = T::one() - value * value;
+        self.chain(value, first, -(value + value) * first)
+    }
+
+    fn asinh(self) -> Self {
+        let first = (self.re * self.re + T::one()).sqrt().recip();
+        self.chain(self.re.asinh(), first, -self.re * first * first * first)
+    }
+
+    fn acosh(self) -> Self {
+        let first = (self.re * self.re - T::one()).sqrt().recip();
+        self.chain(self.re.acosh(), first, -self.re * first * first * first)
+    }
+
+    fn atanh(self) -> Self {
+        let first = (T::one() - self.re * self.re).recip();
+        self.chain(self.re.atanh(), first, (self.re + self.re) * first * first)
+    }
+
+    fn integer_decode(self) -> (u64, i16, i8) {
+        self.re.integer_decode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::vec3::Vec3;
+    use crate::{assert_abs_diff_eq, mat, relative_eq};
+
+    // checks the derivative of f against central differences of its value
+    fn check(f: impl Fn(Dual<f64>) -> Dual<f64>, x: f64) {
+        let (_, d) = derivative(&f, x);
+        let h = 1e-6;
+        let numeric = (f(Dual::constant(x + h)).re - f(Dual::constant(x - h)).re) / (2.0 * h);
+        assert!(
+            (d - numeric).abs() < 1e-6 * numeric.abs().max(1.0),
+            "{} vs {}",
+            d,
+            numeric
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        let (y, d) = derivative(|x| x * x * x - x / (x + Dual::one()), 2.0);
+        assert_eq!(y, 8.0 - 2.0 / 3.0);
+        assert_abs_diff_eq!(d, 12.0 - 1.0 / 9.0, epsilon = 1e-15);
+
+        let a = DualN::new(3.0, [1.0, 2.0]);
+        let b = DualN::new(2.0, [0.5, -1.0]);
+        assert_eq!((a * b).eps, [3.5, 1.0]);
+        assert_eq!((a % b).eps, [0.5, 3.0]);
+        let mut c = a;
+        c -= b;
+        assert_eq!(c.eps, [0.5, 3.0]);
+        assert!(b < a);
+        assert!(DualN::<f64, 2>::zero().is_zero());
+
+        // comparisons only look at the value, like the rest of `Float`
+        let x = Dual::variable(1.0, 0);
+        let y = Dual::constant(1.0);
+        assert_eq!(x, y);
+        assert_eq!(x.partial_cmp(&y), Some(Ordering::Equal));
+        assert_eq!(x.classify(), y.classify());
+        assert_eq!(x.integer_decode(), y.integer_decode());
+        assert!(!relative_eq!(x, y));
+        assert_eq!(
+            Dual::new(0.0, [f64::NAN]).partial_cmp(&x),
+            Some(Ordering::Less)
+        );
+        assert!(Dual::new(0.0, [1.0]).is_zero());
+        assert_eq!(
+            <DualN<f64, 2> as NumCast>::from(2.5).unwrap().eps,
+            [0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn functions() {
+        let unary: [fn(Dual<f64>) -> Dual<f64>; 22] = [
+            Float::sqrt,
+            Float::cbrt,
+            Float::exp,
+            Float::exp2,
+            Float::exp_m1,
+            Float::ln,
+            Float::ln_1p,
+            Float::log2,
+            Float::log10,
+            Float::recip,
+            Float::sin,
+            Float::cos,
+            Float::tan,
+            Float::asin,
+            Float::acos,
+            Float::atan,
+            Float::sinh,
+            Float::cosh,
+            Float::tanh,
+            Float::asinh,
+            Float::atanh,
+            Float::abs,
+        ];
+        for f in unary {
+            check(f, 0.3);
+        }
+        check(Float::acosh, 1.7);
+        check(|x| x.powi(5), -1.3);
+        check(|x| x.powf(Dual::constant(2.5)), 1.3);
+        check(|x| Dual::constant(1.5).powf(x), 0.7);
+        check(|x| x.powf(x), 0.7);
+        check(|x| x.log(Dual::constant(3.0)), 2.0);
+        check(|x| x.hypot(Dual::constant(2.0) * x), 1.5);
+        check(|x| x.atan2(x * x - Dual::one()), 0.4);
+        check(|x| x.to_degrees(), 0.4);
+        check(Float::abs, -0.3);
+
+        // a constant exponent keeps a negative base finite
+        let (_, d) = derivative(|x| x.powf(Dual::constant(2.0)), -3.0);
+        assert_abs_diff_eq!(d, -6.0, epsilon = 1e-12);
+        assert_eq!(derivative(|x| x.floor(), 1.5).1, 0.0);
+    }
+
+    #[test]
+    fn jacobians() {
+        // polar to Cartesian
+        let f = |x: &Mat<DualN<f64, 2>, 2, 1>| -> Mat<DualN<f64, 2>, 2, 1> {
+            let (r, t) = (x[0][0], x[1][0]);
+            Mat::new(&[[r * t.cos()], [r * t.sin()]])
+        };
+        let (r, t) = (2.0f64, 0.6f64);
+        let j = jacobian(f, &mat![r; t]);
+        assert_abs_diff_eq!(
+            j,
+            mat![t.cos(), -r * t.sin(); t.sin(), r * t.cos()],
+            epsilon = 1e-15
+        );
+
+        // through Vec3 and a matrix product
+        let g = |x: &Mat<DualN<f64, 3>, 3, 1>| -> DualN<f64, 3> {
+            let v = Vec3::new(x[0][0], x[1][0], x[2][0]);
+            let a: Mat<DualN<f64, 3>, 3, 3> = Mat::diagonal(DualN::constant(2.0));
+            let w = a * *x;
+            v.cross(Vec3::new(w[0][0], w[2][0], w[1][0])).mag() + v.dot(v)
+        };
+        let x: Mat<f64, 3, 1> = mat![0.5; -1; 2];
+        let grad = gradient(g, &x);
+        let h = 1e-6;
+        for j in 0..3 {
+            let (mut ahead, mut behind) = (x, x);
+            ahead[j][0] += h;
+            behind[j][0] -= h;
+            let value = |x: &Mat<f64, 3, 1>| {
+                let c = Mat::from_slice(
+                    &x.as_slice()
+                        .iter()
+                        .map(|&v| DualN::constant(v))
+                        .collect::<Vec<_>>(),
+                );
+                g(&c).re
+            };
+            let numeric = (value(&ahead) - value(&behind)) / (2.0 * h);
+            assert_abs_diff_eq!(grad[j][0], numeric, epsilon = 1e-6);
+        }
+    }
+
+    // checks the second derivative of f against central differences of its
+    // first derivative
+    fn check_second(f: impl Fn(HyperDual<f64>) -> HyperDual<f64>, x: f64) {
+        let (y, d1, d2) = second_derivative(&f, x);
+        let first = |x: f64| f(HyperDual::new(x, 1.0, 0.0, 0.0)).e1;
+        let h = 1e-5;
+        let numeric = (first(x + h) - first(x - h)) / (2.0 * h);
+        assert_eq!(y, f(HyperDual::constant(x)).re);
+        assert_eq!(d1, first(x));
+        assert!(
+            (d2 - numeric).abs() < 1e-6 * numeric.abs().max(1.0),
+            "{} vs {}",
+            d2,
+            numeric
+        );
+    }
+
+    #[test]
+    fn hyper_dual() {
+        let (y, d1, d2) = second_derivative(|x| x * x * x - x / (x + HyperDual::one()), 2.0);
+        assert_eq!(y, 8.0 - 2.0 / 3.0);
+        assert_abs_diff_eq!(d1, 12.0 - 1.0 / 9.0, epsilon = 1e-14);
+        assert_abs_diff_eq!(d2, 12.0 + 2.0 / 27.0, epsilon = 1e-14);
+
+        let unary: [fn(HyperDual<f64>) -> HyperDual<f64>; 22] = [
+            Float::sqrt,
+            Float::cbrt,
+            Float::exp,
+            Float::exp2,
+            Float::exp_m1,
+            Float::ln,
+            Float::ln_1p,
+            Float::log2,
+            Float::log10,
+            Float::recip,
+            Float::sin,
+            Float::cos,
+            Float::tan,
+            Float::asin,
+            Float::acos,
+            Float::atan,
+            Float::sinh,
+            Float::cosh,
+            Float::tanh,
+            Float::asinh,
+            Float::atanh,
+            Float::abs,
+        ];
+        for f in unary {
+            check_second(f, 0.3);
+        }
+        check_second(Float::acosh, 1.7);
+        check_second(|x| x.powi(5), -1.3);
+        check_second(|x| x.powf(HyperDual::constant(2.5)), 1.3);
+        check_second(|x| HyperDual::constant(1.5).powf(x), 0.7);
+        check_second(|x| x.powf(x), 0.7);
+        check_second(|x| x % HyperDual::constant(0.25), 0.7);
+        check_second(|x| x.hypot(HyperDual::constant(2.0) * x * x), 1.5);
+        check_second(|x| x.atan2(x * x - HyperDual::one()), 0.4);
+        check_second(|x| (-x).atan2(-HyperDual::one() - x), 0.4);
+
+        // a constant exponent keeps a negative base finite
+        let (_, _, d2) = second_derivative(|x| x.powf(HyperDual::constant(3.0)), -2.0);
+        assert_abs_diff_eq!(d2, -12.0, epsilon = 1e-12);
+        assert_eq!(second_derivative(|x| x.powi(1), 0.0), (0.0, 1.0, 0.0));
+
+        // comparisons only look at the value
+        assert_eq!(HyperDual::variable(1.0), HyperDual::constant(1.0));
+        assert!(!relative_eq!(
+            HyperDual::variable(1.0),
+            HyperDual::constant(1.0)
+        ));
+    }
+
+    #[test]
+    fn hessians() {
+        // f(x, y) = x^2 y + sin(x y)
+        let f = |v: &Mat<HyperDual<f64>, 2, 1>| {
+            let (x, y) = (v[0][0], v[1][0]);
+            x * x * y + (x * y).sin()
+        };
+        let (x, y) = (0.7f64, -1.2f64);
+        let s = (x * y).sin();
+        let c = (x * y).cos();
+        let expected = mat![
+            2.0 * y - y * y * s, 2.0 * x + c - x * y * s;
+            2.0 * x + c - x * y * s, -x * x * s
+        ];
+        assert_abs_diff_eq!(hessian(f, &mat![x; y]), expected, epsilon = 1e-14);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Dual::new(3.0, [2.0]).to_string(), "3 + 2ε");
+        assert_eq!(DualN::new(1.0, [0.5, -1.0]).to_string(), "1 + 0.5ε0 + -1ε1");
+        assert_eq!(
+            HyperDual::new(1.0, 2.0, 3.0, 4.0).to_string(),
+            "1 + 2ε1 + 3ε2 + 4ε1ε2"
+        );
+    }
+}
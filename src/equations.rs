@@ -0,0 +1,806 @@
+use super::backend::{cholesky_solve, lu_solve, Backend, Native};
+#[cfg(feature = "alloc")]
+use super::backend::{cholesky_solve_dynamic, lu_solve_dynamic};
+#[cfg(feature = "alloc")]
+use super::dmatrix::DMat;
+use super::error::LinalgError;
+use super::matrix::Mat;
+use super::schur::{self, Schur};
+#[cfg(feature = "alloc")]
+use alloc::vec;
+use num::Float;
+
+// Solvers for the matrix equations that come up in control and estimation:
+//
+//   Sylvester   A X + X B = C
+//   Lyapunov    A X + X A^T + Q = 0
+//   CARE        A^T X + X A - X B R^-1 B^T X + Q = 0
+//   DARE        A^T X A - X - A^T X B (R + B^T X B)^-1 B^T X A + Q = 0
+//
+// Sylvester and Lyapunov equations are solved directly with the
+// Bartels-Stewart algorithm on the real Schur forms of the coefficients. The
+// Riccati equations are solved iteratively for their stabilizing solution:
+// CARE with Newton-Kleinman, which solves one Lyapunov equation per step, and
+// DARE with the structure-preserving doubling algorithm. Both converge
+// quadratically.
+//
+// Every solver takes `Mat`s, with shapes checked at compile time, and works
+// on the stack, so it is available without `alloc`. The `_dynamic` forms take
+// `DMat`s and return a `DSolution`; those check the shapes at run time and
+// fail with `DimensionMismatch`. The linear solves inside the Riccati
+// iterations use `Native`, or the backend passed to the `_with` variants.
+
+#[derive(Debug, Clone, Copy)]
+pub struct Solution<T: Float, const R: usize, const C: usize> {
+    pub x: Mat<T, R, C>,
+    // The Frobenius norm of the equation's residual at `x`, e.g.
+    // ||A X + X B - C|| for the Sylvester equation.
+    pub residual: T,
+    // Newton or doubling steps taken; 0 for the direct solvers.
+    pub iterations: usize,
+}
+
+// `Solution` for the `_dynamic` solvers.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct DSolution<T: Float> {
+    pub x: DMat<T>,
+    pub residual: T,
+    pub iterations: usize,
+}
+
+const MAX_ITERATIONS: usize = 50;
+
+// Panics on the `Singular`, `NonFinite` or `NoConvergence` from
+// `try_sylvester`.
+pub fn sylvester<T: Float, const N: usize, const M: usize>(
+    a: &Mat<T, N, N>,
+    b: &Mat<T, M, M>,
+    c: &Mat<T, N, M>,
+) -> Solution<T, N, M> {
+    try_sylvester(a, b, c).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Fails with `Singular` when A and -B share an eigenvalue, in which case the
+// solution is not unique.
+pub fn try_sylvester<T: Float, const N: usize, const M: usize>(
+    a: &Mat<T, N, N>,
+    b: &Mat<T, M, M>,
+    c: &Mat<T, N, M>,
+) -> Result<Solution<T, N, M>, LinalgError> {
+    let (sa, sb) = (Schur::try_new(a)?, Schur::try_new(b)?);
+    let (u, v) = (*sa.q(), *sb.q());
+    let mut y = u.transpose() * *c * v;
+    quasi_triangular_sylvester(N, M, sa.t().as_slice(), sb.t().as_slice(), y.as_mut_slice())?;
+    let x = u * y * v.transpose();
+    let residual = norm((*a * x + x * *b - *c).as_slice());
+    finish(x, residual, 0)
+}
+
+// Panics on any error from `try_sylvester_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn sylvester_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>, c: &DMat<T>) -> DSolution<T> {
+    try_sylvester_dynamic(a, b, c).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_sylvester` for an n x n A, an m x m B and an n x m C.
+#[cfg(feature = "alloc")]
+pub fn try_sylvester_dynamic<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    c: &DMat<T>,
+) -> Result<DSolution<T>, LinalgError> {
+    let (n, m) = (a.square()?, b.square()?);
+    c.check_shape((n, m))?;
+    let ((u, ta), (v, tb)) = (schur::try_dynamic(a)?, schur::try_dynamic(b)?);
+    let mut y = u.transpose() * c * &v;
+    quasi_triangular_sylvester(n, m, ta.as_slice(), tb.as_slice(), y.as_mut_slice())?;
+    let x = &u * &y * v.transpose();
+    let residual = norm((a * &x + &x * b - c).as_slice());
+    finish_dynamic(x, residual, 0)
+}
+
+// Panics on the `Singular`, `NonFinite` or `NoConvergence` from
+// `try_lyapunov`.
+pub fn lyapunov<T: Float, const N: usize>(a: &Mat<T, N, N>, q: &Mat<T, N, N>) -> Solution<T, N, N> {
+    try_lyapunov(a, q).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Q should be symmetric; X is then symmetric too. Fails with `Singular` when
+// two eigenvalues of A sum to zero, which cannot happen for a stable A.
+pub fn try_lyapunov<T: Float, const N: usize>(
+    a: &Mat<T, N, N>,
+    q: &Mat<T, N, N>,
+) -> Result<Solution<T, N, N>, LinalgError> {
+    let mut x = try_sylvester(a, &a.transpose(), &-*q)?.x;
+    symmetrize(N, x.as_mut_slice());
+    let residual = norm((*a * x + x * a.transpose() + *q).as_slice());
+    finish(x, residual, 0)
+}
+
+// Panics on any error from `try_lyapunov_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn lyapunov_dynamic<T: Float>(a: &DMat<T>, q: &DMat<T>) -> DSolution<T> {
+    try_lyapunov_dynamic(a, q).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_lyapunov` for n x n A and Q.
+#[cfg(feature = "alloc")]
+pub fn try_lyapunov_dynamic<T: Float>(
+    a: &DMat<T>,
+    q: &DMat<T>,
+) -> Result<DSolution<T>, LinalgError> {
+    let n = a.square()?;
+    q.check_shape((n, n))?;
+    let at = a.transpose();
+    let mut x = try_sylvester_dynamic(a, &at, &-q)?.x;
+    symmetrize(n, x.as_mut_slice());
+    let residual = norm((a * &x + &x * &at + q).as_slice());
+    finish_dynamic(x, residual, 0)
+}
+
+// Panics on any error from `try_care`.
+pub fn care<T: Float, const N: usize, const M: usize>(
+    a: &Mat<T, N, N>,
+    b: &Mat<T, N, M>,
+    q: &Mat<T, N, N>,
+    r: &Mat<T, M, M>,
+) -> Solution<T, N, N> {
+    try_care(a, b, q, r).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// The stabilizing solution, for which A - B R^-1 B^T X is stable. Q should be
+// symmetric positive semidefinite and R symmetric positive definite, or this
+// fails with `NotPositiveDefinite`. When A itself is not stable, (A, B) has to
+// be controllable for the starting gain to exist; otherwise the iteration
+// fails with `Singular` or `NoConvergence`.
+pub fn try_care<T: Float, const N: usize, const M: usize>(
+    a: &Mat<T, N, N>,
+    b: &Mat<T, N, M>,
+    q: &Mat<T, N, N>,
+    r: &Mat<T, M, M>,
+) -> Result<Solution<T, N, N>, LinalgError> {
+    try_care_with(&Native, a, b, q, r)
+}
+
+// `try_care` with the linear solves from `backend`.
+pub fn try_care_with<T: Float, const N: usize, const M: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, N, N>,
+    b: &Mat<T, N, M>,
+    q: &Mat<T, N, N>,
+    r: &Mat<T, M, M>,
+) -> Result<Solution<T, N, N>, LinalgError> {
+    let rinv_bt = cholesky_solve(backend, r, &b.transpose())?;
+    let mut k = stabilizing_gain(backend, a, b)?;
+    let mut x = Mat::zero();
+    let mut prev = T::infinity();
+    for iteration in 1..=MAX_ITERATIONS {
+        // Newton-Kleinman: X solves the Lyapunov equation of the closed loop
+        // under the current gain, and gives the next gain
+        let closed = *a - *b * k;
+        let next = try_lyapunov(&closed.transpose(), &(*q + k.transpose() * *r * k))?.x;
+        let diff = norm((next - x).as_slice());
+        x = next;
+        k = rinv_bt * x;
+        if converged(diff, prev, norm(x.as_slice())) {
+            let residual = norm((a.transpose() * x + x * *a - x * *b * k + *q).as_slice());
+            return finish(x, residual, iteration);
+        }
+        prev = diff;
+    }
+    Err(LinalgError::NoConvergence {
+        iterations: MAX_ITERATIONS,
+    })
+}
+
+// Panics on any error from `try_care_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn care_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>, q: &DMat<T>, r: &DMat<T>) -> DSolution<T> {
+    try_care_dynamic(a, b, q, r).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_care` for an n x n A, an n x m B, an n x n Q and an m x m R.
+#[cfg(feature = "alloc")]
+pub fn try_care_dynamic<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    q: &DMat<T>,
+    r: &DMat<T>,
+) -> Result<DSolution<T>, LinalgError> {
+    try_care_dynamic_with(&Native, a, b, q, r)
+}
+
+// `try_care_dynamic` with the linear solves from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_care_dynamic_with<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+    q: &DMat<T>,
+    r: &DMat<T>,
+) -> Result<DSolution<T>, LinalgError> {
+    let n = riccati_shapes(a, b, q, r)?;
+    let rinv_bt = cholesky_solve_dynamic(backend, r, &b.transpose())?;
+    let mut k = stabilizing_gain_dynamic(backend, a, b)?;
+    let mut x = DMat::zero(n, n);
+    let mut prev = T::infinity();
+    for iteration in 1..=MAX_ITERATIONS {
+        let closed = a - b * &k;
+        let next = try_lyapunov_dynamic(&closed.transpose(), &(q + k.transpose() * r * &k))?.x;
+        let diff = norm((&next - &x).as_slice());
+        x = next;
+        k = &rinv_bt * &x;
+        if converged(diff, prev, norm(x.as_slice())) {
+            let residual = norm((a.transpose() * &x + &x * a - &x * b * &k + q).as_slice());
+            return finish_dynamic(x, residual, iteration);
+        }
+        prev = diff;
+    }
+    Err(LinalgError::NoConvergence {
+        iterations: MAX_ITERATIONS,
+    })
+}
+
+// Panics on any error from `try_dare`.
+pub fn dare<T: Float, const N: usize, const M: usize>(
+    a: &Mat<T, N, N>,
+    b: &Mat<T, N, M>,
+    q: &Mat<T, N, N>,
+    r: &Mat<T, M, M>,
+) -> Solution<T, N, N> {
+    try_dare(a, b, q, r).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// The stabilizing solution, for which A - B (R + B^T X B)^-1 B^T X A is
+// stable. Q should be symmetric positive semidefinite and R symmetric positive
+// definite, or this fails with `NotPositiveDefinite`. (A, B) has to be
+// stabilizable, or the iteration fails with `Singular` or `NoConvergence`.
+pub fn try_dare<T: Float, const N: usize, const M: usize>(
+    a: &Mat<T, N, N>,
+    b: &Mat<T, N, M>,
+    q: &Mat<T, N, N>,
+    r: &Mat<T, M, M>,
+) -> Result<Solution<T, N, N>, LinalgError> {
+    try_dare_with(&Native, a, b, q, r)
+}
+
+// `try_dare` with the linear solves from `backend`.
+pub fn try_dare_with<T: Float, const N: usize, const M: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, N, N>,
+    b: &Mat<T, N, M>,
+    q: &Mat<T, N, N>,
+    r: &Mat<T, M, M>,
+) -> Result<Solution<T, N, N>, LinalgError> {
+    let rinv_bt = cholesky_solve(backend, r, &b.transpose())?;
+
+    // structure-preserving doubling: with W = I + G H,
+    //   A' = A W^-1 A,  G' = G + A W^-1 G A^T,  H' = H + A^T H W^-1 A
+    // and H converges to X
+    let (mut ak, mut g, mut h) = (*a, *b * rinv_bt, *q);
+    symmetrize(N, g.as_mut_slice());
+    let mut prev = T::infinity();
+    for iteration in 1..=MAX_ITERATIONS {
+        let w = Mat::identity() + g * h;
+        let wa = lu_solve(backend, &w, &ak)?;
+        let wg = lu_solve(backend, &w, &g)?;
+        let mut next = h + ak.transpose() * h * wa;
+        symmetrize(N, next.as_mut_slice());
+        (ak, g) = (ak * wa, g + ak * wg * ak.transpose());
+        symmetrize(N, g.as_mut_slice());
+        let diff = norm((next - h).as_slice());
+        h = next;
+        if converged(diff, prev, norm(h.as_slice())) {
+            let x = h;
+            let bt_x = b.transpose() * x;
+            let gain = lu_solve(backend, &(*r + bt_x * *b), &(bt_x * *a))?;
+            let at_x = a.transpose() * x;
+            let residual = norm((at_x * *a - x - at_x * *b * gain + *q).as_slice());
+            return finish(x, residual, iteration);
+        }
+        prev = diff;
+    }
+    Err(LinalgError::NoConvergence {
+        iterations: MAX_ITERATIONS,
+    })
+}
+
+// Panics on any error from `try_dare_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn dare_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>, q: &DMat<T>, r: &DMat<T>) -> DSolution<T> {
+    try_dare_dynamic(a, b, q, r).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_dare` for an n x n A, an n x m B, an n x n Q and an m x m R.
+#[cfg(feature = "alloc")]
+pub fn try_dare_dynamic<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    q: &DMat<T>,
+    r: &DMat<T>,
+) -> Result<DSolution<T>, LinalgError> {
+    try_dare_dynamic_with(&Native, a, b, q, r)
+}
+
+// `try_dare_dynamic` with the linear solves from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_dare_dynamic_with<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+    q: &DMat<T>,
+    r: &DMat<T>,
+) -> Result<DSolution<T>, LinalgError> {
+    let n = riccati_shapes(a, b, q, r)?;
+    let rinv_bt = cholesky_solve_dynamic(backend, r, &b.transpose())?;
+
+    let (mut ak, mut g, mut h) = (a.clone(), b * &rinv_bt, q.clone());
+    symmetrize(n, g.as_mut_slice());
+    let mut prev = T::infinity();
+    for iteration in 1..=MAX_ITERATIONS {
+        let w = DMat::identity(n) + &g * &h;
+        let wa = lu_solve_dynamic(backend, &w, &ak)?;
+        let wg = lu_solve_dynamic(backend, &w, &g)?;
+        let mut next = &h + ak.transpose() * &h * &wa;
+        symmetrize(n, next.as_mut_slice());
+        (ak, g) = (&ak * &wa, &g + &ak * &wg * ak.transpose());
+        symmetrize(n, g.as_mut_slice());
+        let diff = norm((&next - &h).as_slice());
+        h = next;
+        if converged(diff, prev, norm(h.as_slice())) {
+            let x = h;
+            let bt_x = b.transpose() * &x;
+            let gain = lu_solve_dynamic(backend, &(r + &bt_x * b), &(&bt_x * a))?;
+            let at_x = a.transpose() * &x;
+            let residual = norm((&at_x * a - &x - at_x * b * &gain + q).as_slice());
+            return finish_dynamic(x, residual, iteration);
+        }
+        prev = diff;
+    }
+    Err(LinalgError::NoConvergence {
+        iterations: MAX_ITERATIONS,
+    })
+}
+
+// Checks the shapes of a Riccati equation's coefficients, returning n.
+#[cfg(feature = "alloc")]
+fn riccati_shapes<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    q: &DMat<T>,
+    r: &DMat<T>,
+) -> Result<usize, LinalgError> {
+    let n = a.square()?;
+    let m = b.ncols();
+    b.check_shape((n, m))?;
+    q.check_shape((n, n))?;
+    r.check_shape((m, m))?;
+    Ok(n)
+}
+
+// Solves Ta Y + Y Tb = F for the n x n quasi-triangular Ta and the m x m
+// quasi-triangular Tb, overwriting F in `y` with Y. Y is found a column block
+// at a time from the left and, within it, a row block at a time from the
+// bottom.
+fn quasi_triangular_sylvester<T: Float>(
+    n: usize,
+    m: usize,
+    ta: &[T],
+    tb: &[T],
+    y: &mut [T],
+) -> Result<(), LinalgError> {
+    let mut k = 0;
+    while k < m {
+        let q = schur::block_size(m, tb, k);
+        for i in 0..n {
+            for c in k..k + q {
+                y[i * m + c] = y[i * m + c]
+                    - (0..k).fold(T::zero(), |acc, j| acc + y[i * m + j] * tb[j * m + c]);
+            }
+        }
+
+        let mut end = n;
+        while end > 0 {
+            let p = if end >= 2 && ta[(end - 1) * n + end - 2] != T::zero() {
+                2
+            } else {
+                1
+            };
+            let i = end - p;
+            let mut rhs = [[T::zero(); 2]; 2];
+            for r in 0..p {
+                for c in 0..q {
+                    rhs[r][c] = y[(i + r) * m + k + c]
+                        - (end..n).fold(T::zero(), |acc, l| {
+                            acc + ta[(i + r) * n + l] * y[l * m + k + c]
+                        });
+                }
+            }
+            let z = small_sylvester((n, ta, i, p), (m, tb, k, q), &rhs)?;
+            for r in 0..p {
+                for c in 0..q {
+                    y[(i + r) * m + k + c] = z[r][c];
+                }
+            }
+            end = i;
+        }
+        k += q;
+    }
+    Ok(())
+}
+
+// Solves the p x q (p, q <= 2) equation Ta[i..i+p, i..i+p] Z + Z Tb[k..k+q,
+// k..k+q] = rhs as a linear system in the entries of Z, with Gaussian
+// elimination and partial pivoting. Ta is n x n and Tb is m x m.
+#[allow(clippy::needless_range_loop)]
+fn small_sylvester<T: Float>(
+    (n, ta, i, p): (usize, &[T], usize, usize),
+    (m, tb, k, q): (usize, &[T], usize, usize),
+    rhs: &[[T; 2]; 2],
+) -> Result<[[T; 2]; 2], LinalgError> {
+    let size = p * q;
+    let mut e = [[T::zero(); 4]; 4];
+    let mut z = [T::zero(); 4];
+    for r in 0..p {
+        for c in 0..q {
+            let row = r * q + c;
+            z[row] = rhs[r][c];
+            for s in 0..p {
+                e[row][s * q + c] = e[row][s * q + c] + ta[(i + r) * n + i + s];
+            }
+            for s in 0..q {
+                e[row][r * q + s] = e[row][r * q + s] + tb[(k + s) * m + k + c];
+            }
+        }
+    }
+
+    let largest = e
+        .iter()
+        .flatten()
+        .fold(T::zero(), |acc, x| acc.max(x.abs()));
+    let tol = T::epsilon() * largest;
+    for col in 0..size {
+        let pivot = (col..size)
+            .max_by(|&a, &b| e[a][col].abs().partial_cmp(&e[b][col].abs()).unwrap())
+            .unwrap();
+        if e[pivot][col].abs() <= tol {
+            return Err(LinalgError::Singular);
+        }
+        e.swap(col, pivot);
+        z.swap(col, pivot);
+        for row in col + 1..size {
+            let factor = e[row][col] / e[col][col];
+            for j in col..size {
+                e[row][j] = e[row][j] - factor * e[col][j];
+            }
+            z[row] = z[row] - factor * z[col];
+        }
+    }
+    for row in (0..size).rev() {
+        let sum = (row + 1..size).fold(z[row], |acc, j| acc - e[row][j] * z[j]);
+        z[row] = sum / e[row][row];
+    }
+
+    let mut out = [[T::zero(); 2]; 2];
+    for r in 0..p {
+        for c in 0..q {
+            out[r][c] = z[r * q + c];
+        }
+    }
+    Ok(out)
+}
+
+// A gain K for which A - B K is stable, to start Newton-Kleinman from. Zero
+// works when A is already stable. Otherwise this is Bass's method: with beta
+// above the spectral radius of A, -(A + beta I) is stable, so
+//
+//   (A + beta I) Z + Z (A + beta I)^T = 2 B B^T
+//
+// has a positive definite solution when (A, B) is controllable, and
+// K = B^T Z^-1 makes (A - B K) Z + Z (A - B K)^T = -2 beta Z.
+fn stabilizing_gain<T: Float, const N: usize, const M: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, N, N>,
+    b: &Mat<T, N, M>,
+) -> Result<Mat<T, M, N>, LinalgError> {
+    let eigenvalues = Schur::try_new(a)?.eigenvalues();
+    if eigenvalues.iter().all(|&(re, _)| re < T::zero()) {
+        return Ok(Mat::zero());
+    }
+    let beta = norm(a.as_slice()) + T::one();
+    let mut shifted = *a;
+    for i in 0..N {
+        shifted[i][i] = shifted[i][i] + beta;
+    }
+    let two = T::one() + T::one();
+    let mut bbt = *b * b.transpose();
+    for x in bbt.iter_mut() {
+        *x = -two * *x;
+    }
+    let z = try_lyapunov(&shifted, &bbt)?.x;
+    // Z is symmetric, so B^T Z^-1 = (Z^-1 B)^T
+    Ok(lu_solve(backend, &z, b)?.transpose())
+}
+
+// `stabilizing_gain` for the dynamic solvers.
+#[cfg(feature = "alloc")]
+fn stabilizing_gain_dynamic<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+) -> Result<DMat<T>, LinalgError> {
+    let n = a.nrows();
+    let (_, t) = schur::try_dynamic(a)?;
+    let mut eigenvalues = vec![(T::zero(), T::zero()); n];
+    schur::eigenvalues(n, t.as_slice(), &mut eigenvalues);
+    if eigenvalues.iter().all(|&(re, _)| re < T::zero()) {
+        return Ok(DMat::zero(b.ncols(), n));
+    }
+    let beta = norm(a.as_slice()) + T::one();
+    let mut shifted = a.clone();
+    for i in 0..n {
+        shifted[(i, i)] = shifted[(i, i)] + beta;
+    }
+    let two = T::one() + T::one();
+    let mut bbt = b * &b.transpose();
+    for x in bbt.as_mut_slice() {
+        *x = -two * *x;
+    }
+    let z = try_lyapunov_dynamic(&shifted, &bbt)?.x;
+    Ok(lu_solve_dynamic(backend, &z, b)?.transpose())
+}
+
+// Stops at rounding level, or when the steps stop shrinking once they are
+// already small.
+fn converged<T: Float>(diff: T, prev: T, size: T) -> bool {
+    let tol = T::from(100.0).unwrap() * T::epsilon();
+    diff <= tol * size || (diff >= prev && diff <= T::epsilon().sqrt() * size)
+}
+
+fn finish<T: Float, const R: usize, const C: usize>(
+    x: Mat<T, R, C>,
+    residual: T,
+    iterations: usize,
+) -> Result<Solution<T, R, C>, LinalgError> {
+    if !residual.is_finite() {
+        return Err(LinalgError::NonFinite);
+    }
+    Ok(Solution {
+        x,
+        residual,
+        iterations,
+    })
+}
+
+#[cfg(feature = "alloc")]
+fn finish_dynamic<T: Float>(
+    x: DMat<T>,
+    residual: T,
+    iterations: usize,
+) -> Result<DSolution<T>, LinalgError> {
+    if !residual.is_finite() {
+        return Err(LinalgError::NonFinite);
+    }
+    Ok(DSolution {
+        x,
+        residual,
+        iterations,
+    })
+}
+
+// Replaces the n x n matrix in `m` with its symmetric part.
+fn symmetrize<T: Float>(n: usize, m: &mut [T]) {
+    let half = T::from(0.5).unwrap();
+    for i in 0..n {
+        for j in i..n {
+            let x = half * (m[i * n + j] + m[j * n + i]);
+            m[i * n + j] = x;
+            m[j * n + i] = x;
+        }
+    }
+}
+
+// The Frobenius norm.
+fn norm<T: Float>(m: &[T]) -> T {
+    m.iter().fold(T::zero(), |acc, &x| acc + x * x).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_util::Counting;
+    use crate::schur::Schur;
+    use crate::{assert_abs_diff_eq, mat};
+
+    fn values<const R: usize, const C: usize>(seed: u64) -> Mat<f64, R, C> {
+        let mut state = seed;
+        let mut m = Mat::zero();
+        for x in m.iter_mut() {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            *x = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+        }
+        m
+    }
+
+    fn stable(a: &Mat<f64, 3, 3>) -> bool {
+        Schur::new(a).eigenvalues().iter().all(|&(re, _)| re < 0.0)
+    }
+
+    #[test]
+    fn sylvester_equation() {
+        let a = values::<5, 5>(1);
+        let b = values::<3, 3>(2) + Mat::diagonal(2.0);
+        let c = values::<5, 3>(3);
+        let s = sylvester(&a, &b, &c);
+        assert!(s.residual < 1e-13, "{}", s.residual);
+        assert_abs_diff_eq!(a * s.x + s.x * b, c, epsilon = 1e-13);
+        assert_eq!(s.iterations, 0);
+
+        // complex eigenvalues on both sides
+        let a: Mat<f64, 4, 4> = mat![0, -2, 1, 0; 2, 0, 0, 1; 0, 0, 1, -3; 0, 0, 3, 1];
+        let b: Mat<f64, 2, 2> = mat![1, 1; -1, 1];
+        let c = values::<4, 2>(4);
+        let s = sylvester(&a, &b, &c);
+        assert_abs_diff_eq!(a * s.x + s.x * b, c, epsilon = 1e-12);
+
+        // A and -B share the eigenvalue 1
+        let a: Mat<f64, 2, 2> = mat![1, 0; 0, 2];
+        let b: Mat<f64, 1, 1> = mat![-1];
+        assert_eq!(
+            try_sylvester(&a, &b, &mat![1; 1]).unwrap_err(),
+            LinalgError::Singular
+        );
+    }
+
+    #[test]
+    fn lyapunov_equation() {
+        let a: Mat<f64, 2, 2> = mat![-1, 0; 0, -1];
+        let s = lyapunov(&a, &Mat::diagonal(2.0));
+        assert_abs_diff_eq!(s.x, Mat::identity(), epsilon = 1e-15);
+
+        let a = values::<4, 4>(5) - Mat::diagonal(2.0);
+        let q = values::<4, 4>(6);
+        let q = q * q.transpose();
+        let s = lyapunov(&a, &q);
+        assert!(s.residual < 1e-13, "{}", s.residual);
+        assert_eq!(s.x, s.x.transpose());
+        assert_abs_diff_eq!(a * s.x + s.x * a.transpose(), -q, epsilon = 1e-13);
+    }
+
+    #[test]
+    fn continuous_riccati() {
+        // double integrator, with X = [sqrt(3) 1; 1 sqrt(3)]
+        let a: Mat<f64, 2, 2> = mat![0, 1; 0, 0];
+        let b: Mat<f64, 2, 1> = mat![0; 1];
+        let s = care(&a, &b, &Mat::identity(), &mat![1]);
+        let r3 = 3f64.sqrt();
+        assert_abs_diff_eq!(s.x, mat![r3, 1; 1, r3], epsilon = 1e-12);
+        assert!(s.iterations > 0);
+
+        // unstable scalar system: 2x - x^2 + 1 = 0
+        let s = care::<f64, 1, 1>(&mat![1], &mat![1], &mat![1], &mat![1]);
+        assert_abs_diff_eq!(s.x[0][0], 1.0 + 2f64.sqrt(), epsilon = 1e-12);
+
+        let a = values::<3, 3>(7) + Mat::diagonal(0.5);
+        assert!(!stable(&a));
+        let b = values::<3, 2>(8);
+        let r: Mat<f64, 2, 2> = mat![2, 0.5; 0.5, 1];
+        let s = care(&a, &b, &Mat::identity(), &r);
+        assert!(s.residual < 1e-11, "{}", s.residual);
+        let k = cholesky_solve(&Native, &r, &b.transpose()).unwrap() * s.x;
+        assert!(stable(&(a - b * k)));
+
+        assert_eq!(
+            try_care(&a, &b, &Mat::identity(), &mat![1, 2; 2, 1]).unwrap_err(),
+            LinalgError::NotPositiveDefinite
+        );
+    }
+
+    #[test]
+    fn discrete_riccati() {
+        // x = 4x - 4x^2 / (1 + x) + 1, so x^2 - 4x - 1 = 0
+        let s = dare::<f64, 1, 1>(&mat![2], &mat![1], &mat![1], &mat![1]);
+        assert_abs_diff_eq!(s.x[0][0], 2.0 + 5f64.sqrt(), epsilon = 1e-12);
+
+        let a = values::<3, 3>(9) * Mat::diagonal(3.0);
+        let b = values::<3, 2>(10);
+        let s = dare(&a, &b, &Mat::identity(), &Mat::identity());
+        assert!(s.residual < 1e-10 * norm(s.x.as_slice()), "{}", s.residual);
+
+        // the closed loop is stable in the discrete sense
+        let bt_x = b.transpose() * s.x;
+        let k = lu_solve(&Native, &(Mat::identity() + bt_x * b), &(bt_x * a)).unwrap();
+        let eigenvalues = Schur::new(&(a - b * k)).eigenvalues();
+        assert!(eigenvalues.iter().all(|&(re, im)| re.hypot(im) < 1.0));
+    }
+
+    #[test]
+    fn riccati_backend() {
+        let a = values::<3, 3>(9) * Mat::diagonal(3.0);
+        let b = values::<3, 2>(10);
+        let (q, r) = (Mat::identity(), Mat::identity());
+        let backend = Counting::default();
+        let s = try_dare_with(&backend, &a, &b, &q, &r).unwrap();
+        assert!(backend.calls() > 0);
+        assert_eq!(s.x, dare(&a, &b, &q, &r).x);
+
+        let calls = backend.calls();
+        let s = try_care_with(&backend, &a, &b, &q, &r).unwrap();
+        assert!(backend.calls() > calls);
+        assert_eq!(s.x, care(&a, &b, &q, &r).x);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn dynamic() {
+        let (a, b, c) = (
+            values::<5, 5>(1),
+            values::<3, 3>(2) + Mat::diagonal(2.0),
+            values::<5, 3>(3),
+        );
+        let (da, db, dc) = (DMat::from_mat(&a), DMat::from_mat(&b), DMat::from_mat(&c));
+        let s = sylvester_dynamic(&da, &db, &dc);
+        assert!(s.residual < 1e-13, "{}", s.residual);
+        assert_eq!(s.x.to_mat::<5, 3>(), sylvester(&a, &b, &c).x);
+        assert_eq!(
+            try_sylvester_dynamic(&da, &db, &dc.transpose()).unwrap_err(),
+            LinalgError::DimensionMismatch {
+                expected: (5, 3),
+                found: (3, 5)
+            }
+        );
+        assert_eq!(
+            try_sylvester_dynamic(&dc, &db, &dc).unwrap_err(),
+            LinalgError::DimensionMismatch {
+                expected: (5, 5),
+                found: (5, 3)
+            }
+        );
+
+        let a = values::<4, 4>(5) - Mat::diagonal(2.0);
+        let q = values::<4, 4>(6);
+        let q = q * q.transpose();
+        let s = lyapunov_dynamic(&DMat::from_mat(&a), &DMat::from_mat(&q));
+        assert!(s.residual < 1e-13, "{}", s.residual);
+        assert_eq!(s.x.to_mat::<4, 4>(), lyapunov(&a, &q).x);
+
+        // a chain of n integrators
+        let n = 6;
+        let a = DMat::from_fn(n, n, |i, j| if j == i + 1 { 1.0 } else { 0.0 });
+        let b = DMat::from_fn(n, 1, |i, _| if i + 1 == n { 1.0 } else { 0.0 });
+        let (q, r) = (DMat::identity(n), DMat::identity(1));
+        let s = care_dynamic(&a, &b, &q, &r);
+        assert_eq!(s.x.shape(), (n, n));
+        assert!(s.residual < 1e-10, "{}", s.residual);
+        assert!(s.iterations > 0);
+        let s = dare_dynamic(&a, &b, &q, &r);
+        assert!(s.residual < 1e-10 * norm(s.x.as_slice()), "{}", s.residual);
+
+        let a = values::<3, 3>(9) * Mat::diagonal(3.0);
+        let b = values::<3, 2>(10);
+        let (da, db) = (DMat::from_mat(&a), DMat::from_mat(&b));
+        let (dq, dr) = (DMat::identity(3), DMat::identity(2));
+        let backend = Counting::default();
+        let s = try_dare_dynamic_with(&backend, &da, &db, &dq, &dr).unwrap();
+        assert!(backend.calls() > 0);
+        assert_eq!(
+            s.x.to_mat::<3, 3>(),
+            dare(&a, &b, &Mat::identity(), &Mat::identity()).x
+        );
+        let s = try_care_dynamic(&da, &db, &dq, &dr).unwrap();
+        assert_eq!(
+            s.x.to_mat::<3, 3>(),
+            care(&a, &b, &Mat::identity(), &Mat::identity()).x
+        );
+        assert_eq!(
+            try_care_dynamic(&da, &db, &dq, &dq).unwrap_err(),
+            LinalgError::DimensionMismatch {
+                expected: (2, 2),
+                found: (3, 3)
+            }
+        );
+    }
+}
@@ -0,0 +1,109 @@
+#[cfg(feature = "alloc")]
+use super::parse::ParseError;
+use core::fmt;
+
+// The error type shared by the fallible operations in the crate. Operations
+// that can fail come in two forms: a `try_` method returning this error, and a
+// panicking convenience method that documents which variant it unwraps.
+//
+// Shapes are (rows, columns); slices and vectors are reported as n x 1.
+//
+// `Parse` only exists with `alloc`, so the enum is non-exhaustive: a match
+// that compiles with one set of features has to compile with any other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LinalgError {
+    Singular,
+    NotPositiveDefinite,
+    DimensionMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    // `iterations` is 0 when the count is not known, e.g. when the failure
+    // was reported by LAPACK.
+    NoConvergence {
+        iterations: usize,
+    },
+    // The result would contain an infinite or NaN entry, e.g. because a
+    // determinant overflowed.
+    NonFinite,
+    // An argument outside the domain of the operation, e.g. a negative
+    // regularization parameter, with what was wrong with it.
+    InvalidArgument(&'static str),
+    #[cfg(feature = "alloc")]
+    Parse(ParseError),
+}
+
+impl fmt::Display for LinalgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinalgError::Singular => write!(f, "matrix is singular"),
+            LinalgError::NotPositiveDefinite => write!(f, "matrix is not positive definite"),
+            LinalgError::DimensionMismatch { expected, found } => write!(
+                f,
+                "expected a {}x{} matrix, found {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            LinalgError::NoConvergence { iterations } => {
+                write!(f, "no convergence after {} iterations", iterations)
+            }
+            LinalgError::NonFinite => write!(f, "result is not finite"),
+            LinalgError::InvalidArgument(what) => write!(f, "{}", what),
+            #[cfg(feature = "alloc")]
+            LinalgError::Parse(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LinalgError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LinalgError::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<ParseError> for LinalgError {
+    fn from(err: ParseError) -> Self {
+        LinalgError::Parse(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn display() {
+        let err = LinalgError::DimensionMismatch {
+            expected: (2, 3),
+            found: (3, 2),
+        };
+        assert_eq!(err.to_string(), "expected a 2x3 matrix, found 3x2");
+        assert_eq!(
+            LinalgError::NoConvergence { iterations: 64 }.to_string(),
+            "no convergence after 64 iterations"
+        );
+    }
+
+    // parsing needs `alloc`, and `Error::source` needs `std`
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn from_parse() {
+        let parse = "[1 2; 3]"
+            .parse::<crate::matrix::Mat<f64, 2, 2>>()
+            .unwrap_err();
+        let err = LinalgError::from(parse);
+        assert_eq!(err.to_string(), parse.to_string());
+        #[cfg(feature = "std")]
+        {
+            use std::error::Error;
+            assert!(err.source().is_some());
+            assert!(LinalgError::Singular.source().is_none());
+        }
+    }
+}
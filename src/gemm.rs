@@ -0,0 +1,528 @@
+use super::error::LinalgError;
+use super::matrix::Mat;
+use alloc::vec;
+use core::mem::size_of;
+use num::Float;
+
+pub use super::backend::Transpose;
+pub(crate) use super::backend::View;
+
+// General matrix multiply, C = alpha op(A) op(B) + beta C, structured like
+// BLIS/GotoBLAS: the operands are split into blocks that fit in cache, each
+// block is packed into contiguous panels, and a small register-tiled
+// micro-kernel accumulates an MR x NR tile of C at a time. The micro-kernel
+// is plain Rust that the compiler vectorizes; the tile shape is chosen per
+// element size so that the accumulators fit in registers.
+
+// Rows of A packed per block, depth packed per block, and columns of B packed
+// per block.
+pub(crate) const MC: usize = 128;
+pub(crate) const KC: usize = 256;
+pub(crate) const NC: usize = 2048;
+
+// Products of at least this many multiply-adds use the blocked kernel in
+// `Mul`; below it the naive loop is faster.
+pub(crate) const BLOCKED_THRESHOLD: usize = 48 * 48 * 48;
+
+// Whether `Mul` and `MulAssign` compute an m x k by k x n product with the
+// blocked kernel, for `Mat` and `DMat` alike.
+pub(crate) fn use_blocked(m: usize, n: usize, k: usize) -> bool {
+    m * n * k >= BLOCKED_THRESHOLD
+}
+
+// C = A B for row-major A (m x k), B (k x n) and C (m x n), through the
+// naive loop or the blocked kernel as `use_blocked` decides.
+pub(crate) fn mul_into<T: Float>(m: usize, n: usize, k: usize, a: &[T], b: &[T], c: &mut [T]) {
+    if use_blocked(m, n, k) {
+        let a = View {
+            data: a,
+            stride: k,
+            trans: Transpose::No,
+        };
+        let b = View {
+            data: b,
+            stride: n,
+            trans: Transpose::No,
+        };
+        gemm_strided(m, n, k, T::one(), a, b, T::zero(), c, n);
+        return;
+    }
+    for i in 0..m {
+        for j in 0..n {
+            let mut sum = T::zero();
+            for p in 0..k {
+                sum = sum + a[i * k + p] * b[p * n + j];
+            }
+            c[i * n + j] = sum;
+        }
+    }
+}
+
+// C is m x n with row stride `ldc`, op(A) is m x k and op(B) is k x n. As in
+// BLAS, C is not read when beta is zero, so it may hold NaN.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gemm_strided<T: Float>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: T,
+    a: View<T>,
+    b: View<T>,
+    beta: T,
+    c: &mut [T],
+    ldc: usize,
+) {
+    scale(beta, m, n, c, ldc);
+    if alpha == T::zero() || k == 0 {
+        return;
+    }
+
+    if size_of::<T>() <= 4 {
+        blocked::<T, 4, 8>(m, n, k, alpha, a, b, c, ldc);
+    } else {
+        blocked::<T, 4, 4>(m, n, k, alpha, a, b, c, ldc);
+    }
+}
+
+pub(crate) fn scale<T: Float>(beta: T, m: usize, n: usize, c: &mut [T], ldc: usize) {
+    for i in 0..m {
+        for x in &mut c[i * ldc..i * ldc + n] {
+            *x = if beta == T::zero() {
+                T::zero()
+            } else {
+                beta * *x
+            };
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn blocked<T: Float, const MR: usize, const NR: usize>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: T,
+    a: View<T>,
+    b: View<T>,
+    c: &mut [T],
+    ldc: usize,
+) {
+    let mut a_pack = vec![T::zero(); a_pack_len::<MR>(m, k)];
+    let mut b_pack = vec![T::zero(); KC.min(k) * NC.min(n).next_multiple_of(NR)];
+
+    for jc in (0..n).step_by(NC) {
+        let nc = NC.min(n - jc);
+        for pc in (0..k).step_by(KC) {
+            let kc = KC.min(k - pc);
+            pack::<T, NR>(&mut b_pack, kc, nc, |kk, j| b.at(pc + kk, jc + j));
+            for (ic, c) in (0..m).step_by(MC).zip(c.chunks_mut(MC * ldc)) {
+                let block = Block {
+                    rows: (ic, MC.min(m - ic)),
+                    depth: (pc, kc),
+                    cols: (jc, nc),
+                };
+                row_block::<T, MR, NR>(&mut a_pack, &b_pack, a, block, alpha, c, ldc);
+            }
+        }
+    }
+}
+
+pub(crate) fn a_pack_len<const MR: usize>(m: usize, k: usize) -> usize {
+    MC.min(m).next_multiple_of(MR) * KC.min(k)
+}
+
+// Offsets and lengths of the rows of C, the depth, and the columns of C
+// covered by one call to `row_block`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Block {
+    pub rows: (usize, usize),
+    pub depth: (usize, usize),
+    pub cols: (usize, usize),
+}
+
+// Packs a block of rows of op(A) and multiplies it by the packed block of B,
+// adding into `c`, which starts at the first row of the block. Each entry of
+// C is accumulated in the same order whichever way the row blocks are
+// scheduled.
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn row_block<T: Float, const MR: usize, const NR: usize>(
+    a_pack: &mut [T],
+    b_pack: &[T],
+    a: View<T>,
+    block: Block,
+    alpha: T,
+    c: &mut [T],
+    ldc: usize,
+) {
+    let ((ic, mc), (pc, kc), (jc, nc)) = (block.rows, block.depth, block.cols);
+    pack::<T, MR>(a_pack, kc, mc, |kk, i| a.at(ic + i, pc + kk));
+
+    for jr in (0..nc).step_by(NR) {
+        for ir in (0..mc).step_by(MR) {
+            let acc = kernel::<T, MR, NR>(
+                &a_pack[ir * kc..(ir + MR) * kc],
+                &b_pack[jr * kc..(jr + NR) * kc],
+            );
+            for i in 0..MR.min(mc - ir) {
+                let row = (ir + i) * ldc + jc + jr;
+                for j in 0..NR.min(nc - jr) {
+                    c[row + j] = c[row + j] + alpha * acc[i][j];
+                }
+            }
+        }
+    }
+}
+
+// Packs `len` rows of A or columns of B into panels of W, each stored depth
+// first so the micro-kernel reads both operands sequentially. `at(kk, i)` is
+// the entry at depth `kk` of row or column `i`. The last panel is padded with
+// zeros.
+pub(crate) fn pack<T: Float, const W: usize>(
+    buf: &mut [T],
+    depth: usize,
+    len: usize,
+    at: impl Fn(usize, usize) -> T,
+) {
+    for p in 0..len.div_ceil(W) {
+        let panel = &mut buf[p * W * depth..(p + 1) * W * depth];
+        for kk in 0..depth {
+            for w in 0..W {
+                let i = p * W + w;
+                panel[kk * W + w] = if i < len { at(kk, i) } else { T::zero() };
+            }
+        }
+    }
+}
+
+fn kernel<T: Float, const MR: usize, const NR: usize>(a: &[T], b: &[T]) -> [[T; NR]; MR] {
+    let mut acc = [[T::zero(); NR]; MR];
+    for (a, b) in a.chunks_exact(MR).zip(b.chunks_exact(NR)) {
+        for i in 0..MR {
+            for j in 0..NR {
+                acc[i][j] = acc[i][j] + a[i] * b[j];
+            }
+        }
+    }
+    acc
+}
+
+// C = alpha op(A) op(B) + beta C. The shapes are checked when called, since
+// which dimensions must agree depends on the transpose flags.
+//
+// Panics on the `DimensionMismatch` from `try_gemm`.
+#[allow(clippy::too_many_arguments)]
+pub fn gemm<
+    T: Float,
+    const M: usize,
+    const N: usize,
+    const AR: usize,
+    const AC: usize,
+    const BR: usize,
+    const BC: usize,
+>(
+    alpha: T,
+    a: &Mat<T, AR, AC>,
+    ta: Transpose,
+    b: &Mat<T, BR, BC>,
+    tb: Transpose,
+    beta: T,
+    c: &mut Mat<T, M, N>,
+) {
+    let (a, b, k) = views::<T, M, N, AR, AC, BR, BC>(a, ta, b, tb);
+    gemm_strided(M, N, k, alpha, a, b, beta, c.as_mut_slice(), N);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_gemm<
+    T: Float,
+    const M: usize,
+    const N: usize,
+    const AR: usize,
+    const AC: usize,
+    const BR: usize,
+    const BC: usize,
+>(
+    alpha: T,
+    a: &Mat<T, AR, AC>,
+    ta: Transpose,
+    b: &Mat<T, BR, BC>,
+    tb: Transpose,
+    beta: T,
+    c: &mut Mat<T, M, N>,
+) -> Result<(), LinalgError> {
+    let (a, b, k) = try_views::<T, M, N, AR, AC, BR, BC>(a, ta, b, tb)?;
+    gemm_strided(M, N, k, alpha, a, b, beta, c.as_mut_slice(), N);
+    Ok(())
+}
+
+fn dims(t: Transpose, rows: usize, cols: usize) -> (usize, usize) {
+    match t {
+        Transpose::No => (rows, cols),
+        Transpose::Yes => (cols, rows),
+    }
+}
+
+// `try_views`, panicking with the shapes of all three operands.
+#[allow(clippy::type_complexity)]
+pub(crate) fn views<
+    'a,
+    T: Float,
+    const M: usize,
+    const N: usize,
+    const AR: usize,
+    const AC: usize,
+    const BR: usize,
+    const BC: usize,
+>(
+    a: &'a Mat<T, AR, AC>,
+    ta: Transpose,
+    b: &'a Mat<T, BR, BC>,
+    tb: Transpose,
+) -> (View<'a, T>, View<'a, T>, usize) {
+    try_views::<T, M, N, AR, AC, BR, BC>(a, ta, b, tb).unwrap_or_else(|_| {
+        let (am, ak) = dims(ta, AR, AC);
+        let (bk, bn) = dims(tb, BR, BC);
+        panic!(
+            "cannot multiply {}x{} by {}x{} into {}x{}",
+            am, ak, bk, bn, M, N
+        )
+    })
+}
+
+// Checks the shapes for `gemm` and returns the operands as views along with
+// the inner dimension. A mismatch is reported against op(A) if its row count
+// is wrong, and against op(B) otherwise.
+#[allow(clippy::type_complexity)]
+pub(crate) fn try_views<
+    'a,
+    T: Float,
+    const M: usize,
+    const N: usize,
+    const AR: usize,
+    const AC: usize,
+    const BR: usize,
+    const BC: usize,
+>(
+    a: &'a Mat<T, AR, AC>,
+    ta: Transpose,
+    b: &'a Mat<T, BR, BC>,
+    tb: Transpose,
+) -> Result<(View<'a, T>, View<'a, T>, usize), LinalgError> {
+    let (am, ak) = dims(ta, AR, AC);
+    let (bk, bn) = dims(tb, BR, BC);
+    if am != M {
+        return Err(LinalgError::DimensionMismatch {
+            expected: (M, ak),
+            found: (am, ak),
+        });
+    }
+    if (bk, bn) != (ak, N) {
+        return Err(LinalgError::DimensionMismatch {
+            expected: (ak, N),
+            found: (bk, bn),
+        });
+    }
+
+    let a = View {
+        data: a.as_slice(),
+        stride: AC,
+        trans: ta,
+    };
+    let b = View {
+        data: b.as_slice(),
+        stride: BC,
+        trans: tb,
+    };
+    Ok((a, b, ak))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_relative_eq;
+    use alloc::vec::Vec;
+
+    fn values<T: Float>(seed: u64, n: usize) -> Vec<T> {
+        let mut state = seed;
+        (0..n)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                T::from((state >> 11) as f64 / (1u64 << 53) as f64 - 0.5).unwrap()
+            })
+            .collect()
+    }
+
+    fn naive<T: Float>(
+        (m, n, k): (usize, usize, usize),
+        alpha: T,
+        a: View<T>,
+        b: View<T>,
+        beta: T,
+        c: &[T],
+    ) -> Vec<T> {
+        let mut out = c.to_vec();
+        for i in 0..m {
+            for j in 0..n {
+                let mut sum = T::zero();
+                for kk in 0..k {
+                    sum = sum + a.at(i, kk) * b.at(kk, j);
+                }
+                out[i * n + j] = alpha * sum + beta * c[i * n + j];
+            }
+        }
+        out
+    }
+
+    fn check<T: Float + core::fmt::Debug>(m: usize, n: usize, k: usize, tol: T) {
+        let (alpha, beta) = (T::from(1.5).unwrap(), T::from(-0.5).unwrap());
+        let a_data = values::<T>(1, m * k);
+        let b_data = values::<T>(2, k * n);
+        let c_data = values::<T>(3, m * n);
+        for ta in [Transpose::No, Transpose::Yes] {
+            for tb in [Transpose::No, Transpose::Yes] {
+                let a = View {
+                    data: &a_data,
+                    stride: if ta == Transpose::No { k } else { m },
+                    trans: ta,
+                };
+                let b = View {
+                    data: &b_data,
+                    stride: if tb == Transpose::No { n } else { k },
+                    trans: tb,
+                };
+                let expected = naive((m, n, k), alpha, a, b, beta, &c_data);
+                let mut c = c_data.clone();
+                gemm_strided(m, n, k, alpha, a, b, beta, &mut c, n);
+                for (x, y) in c.iter().zip(&expected) {
+                    assert!((*x - *y).abs() <= tol, "{:?} != {:?}", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blocked_matches_naive() {
+        // sizes that are not multiples of the tiles or the blocks
+        for (m, n, k) in [(1, 1, 1), (5, 3, 7), (37, 53, 61), (130, 9, 300)] {
+            check::<f64>(m, n, k, 1e-12);
+            check::<f32>(m, n, k, 1e-4);
+        }
+    }
+
+    #[test]
+    fn beta_zero_ignores_c() {
+        let a = Mat::new(&[[1.0, 2.0], [3.0, 4.0]]);
+        let mut c = Mat::fill(f64::NAN);
+        gemm(1.0, &a, Transpose::No, &a, Transpose::Yes, 0.0, &mut c);
+        assert_eq!(c, Mat::new(&[[5.0, 11.0], [11.0, 25.0]]));
+    }
+
+    #[test]
+    fn gemm_mat() {
+        let a = Mat::new(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let b = Mat::new(&[[1.0, 0.0], [0.0, 1.0], [1.0, 1.0]]);
+        let mut c = Mat::<f64, 3, 3>::identity();
+        gemm(2.0, &a, Transpose::Yes, &b, Transpose::Yes, 1.0, &mut c);
+        assert_eq!(
+            c,
+            (a.transpose() * b.transpose()).const_scale(2.0) + Mat::identity()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot multiply 2x3 by 2x3 into 2x3")]
+    fn gemm_shape_mismatch() {
+        let a = Mat::<f64, 2, 3>::zero();
+        let mut c = Mat::<f64, 2, 3>::zero();
+        gemm(1.0, &a, Transpose::No, &a, Transpose::No, 0.0, &mut c);
+    }
+
+    #[test]
+    fn try_gemm_shape_mismatch() {
+        let a = Mat::<f64, 2, 3>::zero();
+        let mut c = Mat::<f64, 2, 3>::zero();
+        assert_eq!(
+            try_gemm(1.0, &a, Transpose::No, &a, Transpose::No, 0.0, &mut c),
+            Err(LinalgError::DimensionMismatch {
+                expected: (3, 3),
+                found: (2, 3)
+            })
+        );
+        let mut c = Mat::<f64, 3, 3>::zero();
+        assert_eq!(
+            try_gemm(1.0, &a, Transpose::No, &a, Transpose::No, 0.0, &mut c),
+            Err(LinalgError::DimensionMismatch {
+                expected: (3, 3),
+                found: (2, 3)
+            })
+        );
+        assert_eq!(
+            try_gemm(1.0, &a, Transpose::Yes, &a, Transpose::No, 0.0, &mut c),
+            Ok(())
+        );
+    }
+
+    // The kernel itself, for both tile shapes and without going through the
+    // scaling in `gemm_strided`.
+    #[test]
+    fn blocked_kernel() {
+        let (m, n, k) = (37, 530, 300);
+        let a_data = values::<f64>(4, m * k);
+        let b_data = values::<f64>(5, k * n);
+        let a = View {
+            data: &a_data,
+            stride: k,
+            trans: Transpose::No,
+        };
+        let b = View {
+            data: &b_data,
+            stride: n,
+            trans: Transpose::No,
+        };
+        let expected = naive((m, n, k), 2.0, a, b, 0.0, &vec![0.0; m * n]);
+        let mut square = vec![0.0; m * n];
+        blocked::<f64, 4, 4>(m, n, k, 2.0, a, b, &mut square, n);
+        let mut wide = vec![0.0; m * n];
+        blocked::<f64, 4, 8>(m, n, k, 2.0, a, b, &mut wide, n);
+        for ((x, y), z) in square.iter().zip(&wide).zip(&expected) {
+            assert!((x - z).abs() <= 1e-12 && (y - z).abs() <= 1e-12);
+        }
+    }
+
+    #[test]
+    fn dispatch() {
+        assert!(!use_blocked(47, 48, 48));
+        assert!(use_blocked(48, 48, 48));
+        assert!(use_blocked(50, 52, 56));
+        // only the product of the dimensions matters
+        assert!(use_blocked(1, 1, BLOCKED_THRESHOLD));
+    }
+
+    #[test]
+    fn mul_large() {
+        let a: Mat<f64, 50, 56> = Mat::from_slice(&values(4, 50 * 56));
+        let b: Mat<f64, 56, 52> = Mat::from_slice(&values(5, 56 * 52));
+        let view = |data, stride| View {
+            data,
+            stride,
+            trans: Transpose::No,
+        };
+        let expected = naive(
+            (50, 52, 56),
+            1.0,
+            view(a.as_slice(), 56),
+            view(b.as_slice(), 52),
+            0.0,
+            &[0.0; 50 * 52],
+        );
+        assert_relative_eq!(a * b, Mat::from_slice(&expected), max_relative = 1e-12);
+
+        // `*=` takes the same path as `*`
+        let c: Mat<f64, 50, 50> = Mat::from_slice(&values(6, 50 * 50));
+        let mut d = c;
+        d *= c;
+        assert_eq!(d, c * c);
+    }
+}
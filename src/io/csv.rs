@@ -0,0 +1,536 @@
+use crate::dmatrix::DMat;
+use crate::matrix::Mat;
+use num::Float;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+// Reading and writing of matrices as CSV. Quoted cells follow RFC 4180: a
+// quote inside a quoted cell is doubled, and quoted cells may span lines.
+// Unquoted cells are trimmed of surrounding whitespace.
+
+// What to do with an empty cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Missing {
+    #[default]
+    Error,
+    Nan,
+    Zero,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    delimiter: char,
+    quote: Option<char>,
+    header: bool,
+    missing: Missing,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote: Some('"'),
+            header: false,
+            missing: Missing::Error,
+        }
+    }
+}
+
+impl Options {
+    pub fn delimiter(self, delimiter: char) -> Self {
+        Self { delimiter, ..self }
+    }
+
+    // `None` disables quoting, so quote characters are read as part of a cell.
+    pub fn quote(self, quote: Option<char>) -> Self {
+        Self { quote, ..self }
+    }
+
+    pub fn header(self, header: bool) -> Self {
+        Self { header, ..self }
+    }
+
+    pub fn missing(self, missing: Missing) -> Self {
+        Self { missing, ..self }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvErrorKind {
+    BadNumber,
+    Missing,
+    UnterminatedQuote,
+    // Text between a closing quote and the end of its cell.
+    AfterQuote,
+    Rows { expected: usize, found: usize },
+    Columns { expected: usize, found: usize },
+}
+
+// Rows and columns are 1-based; rows count records, including the header.
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    Cell {
+        row: usize,
+        column: usize,
+        kind: CsvErrorKind,
+    },
+}
+
+impl CsvError {
+    fn cell(row: usize, column: usize, kind: CsvErrorKind) -> Self {
+        CsvError::Cell { row, column, kind }
+    }
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (row, column, kind) = match self {
+            CsvError::Io(err) => return write!(f, "{}", err),
+            CsvError::Cell { row, column, kind } => (row, column, kind),
+        };
+        write!(f, "row {}, column {}: ", row, column)?;
+        match kind {
+            CsvErrorKind::BadNumber => write!(f, "invalid number"),
+            CsvErrorKind::Missing => write!(f, "empty cell"),
+            CsvErrorKind::UnterminatedQuote => write!(f, "unterminated quote"),
+            CsvErrorKind::AfterQuote => write!(f, "text after closing quote"),
+            CsvErrorKind::Rows { expected, found } => {
+                write!(f, "expected {} rows, found {}", expected, found)
+            }
+            CsvErrorKind::Columns { expected, found } => {
+                write!(f, "expected {} columns, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl Error for CsvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CsvError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(err: io::Error) -> Self {
+        CsvError::Io(err)
+    }
+}
+
+// Splits the input into records of cells. Each cell remembers whether it was
+// quoted, since quoted cells are not trimmed. Blank lines are not records, so
+// they do not count towards the row numbers in errors either.
+fn records(text: &str, options: &Options) -> Result<Vec<Vec<(String, bool)>>, CsvError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    let finish_cell = |cell: &mut String, quoted: &mut bool, record: &mut Vec<(String, bool)>| {
+        let text = if *quoted {
+            std::mem::take(cell)
+        } else {
+            let text = cell.trim().to_string();
+            cell.clear();
+            text
+        };
+        record.push((text, *quoted));
+        *quoted = false;
+    };
+
+    while let Some(c) = chars.next() {
+        if Some(c) == options.quote && cell.trim().is_empty() && !quoted {
+            cell.clear();
+            quoted = true;
+            loop {
+                match chars.next() {
+                    Some(c) if Some(c) == options.quote => {
+                        if chars.peek() == Some(&c) {
+                            chars.next();
+                            cell.push(c);
+                        } else {
+                            break;
+                        }
+                    }
+                    Some(c) => cell.push(c),
+                    None => {
+                        return Err(CsvError::cell(
+                            records.len() + 1,
+                            record.len() + 1,
+                            CsvErrorKind::UnterminatedQuote,
+                        ))
+                    }
+                }
+            }
+        } else if c == options.delimiter {
+            finish_cell(&mut cell, &mut quoted, &mut record);
+        } else if c == '\n' || c == '\r' {
+            if c == '\r' && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+            finish_cell(&mut cell, &mut quoted, &mut record);
+            push_record(&mut records, std::mem::take(&mut record));
+        } else if !quoted {
+            cell.push(c);
+        } else if !c.is_whitespace() {
+            return Err(CsvError::cell(
+                records.len() + 1,
+                record.len() + 1,
+                CsvErrorKind::AfterQuote,
+            ));
+        }
+    }
+    if !cell.is_empty() || quoted || !record.is_empty() {
+        finish_cell(&mut cell, &mut quoted, &mut record);
+        push_record(&mut records, record);
+    }
+    Ok(records)
+}
+
+fn push_record(records: &mut Vec<Vec<(String, bool)>>, record: Vec<(String, bool)>) {
+    let blank = record.len() == 1 && record[0].0.is_empty() && !record[0].1;
+    if !blank {
+        records.push(record);
+    }
+}
+
+fn parse_cell<T: Float>(
+    cell: &str,
+    row: usize,
+    column: usize,
+    missing: Missing,
+) -> Result<T, CsvError> {
+    if cell.trim().is_empty() {
+        return match missing {
+            Missing::Error => Err(CsvError::cell(row, column, CsvErrorKind::Missing)),
+            Missing::Nan => Ok(T::nan()),
+            Missing::Zero => Ok(T::zero()),
+        };
+    }
+    cell.trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(T::from)
+        .ok_or(CsvError::cell(row, column, CsvErrorKind::BadNumber))
+}
+
+// Fails with `Columns` at the first missing or extra cell of the record.
+fn check_columns(row: usize, expected: usize, found: usize) -> Result<(), CsvError> {
+    if found == expected {
+        Ok(())
+    } else {
+        Err(CsvError::cell(
+            row,
+            found.min(expected) + 1,
+            CsvErrorKind::Columns { expected, found },
+        ))
+    }
+}
+
+// Returns the column names when `options` has a header row.
+pub fn read<T: Float, Rd: Read, const R: usize, const C: usize>(
+    reader: &mut Rd,
+    options: &Options,
+) -> Result<(Option<Vec<String>>, Mat<T, R, C>), CsvError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut records = records(&text, options)?.into_iter().enumerate();
+
+    let header = match options.header {
+        true => match records.next() {
+            Some((_, header)) => {
+                check_columns(1, C, header.len())?;
+                Some(header.into_iter().map(|(name, _)| name).collect())
+            }
+            None => None,
+        },
+        false => None,
+    };
+    let offset = header.is_some() as usize;
+
+    let mut mat = Mat::zero();
+    let mut found = 0;
+    for (index, record) in records {
+        let row = index + 1;
+        found += 1;
+        if found > R {
+            continue;
+        }
+        check_columns(row, C, record.len())?;
+        for (j, (cell, _)) in record.iter().enumerate() {
+            mat[found - 1][j] = parse_cell(cell, row, j + 1, options.missing)?;
+        }
+    }
+    if found != R {
+        return Err(CsvError::cell(
+            offset + found.min(R) + 1,
+            1,
+            CsvErrorKind::Rows { expected: R, found },
+        ));
+    }
+    Ok((header, mat))
+}
+
+// `read` for a matrix of any shape. The header row, or the first record
+// without one, sets the number of columns, and every record must match it.
+pub fn read_dynamic<T: Float, Rd: Read>(
+    reader: &mut Rd,
+    options: &Options,
+) -> Result<(Option<Vec<String>>, DMat<T>), CsvError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut records = records(&text, options)?.into_iter().enumerate().peekable();
+
+    let header: Option<Vec<String>> = match options.header {
+        true => records
+            .next()
+            .map(|(_, header)| header.into_iter().map(|(name, _)| name).collect()),
+        false => None,
+    };
+    let cols = match (&header, records.peek()) {
+        (Some(header), _) => header.len(),
+        (None, Some((_, record))) => record.len(),
+        (None, None) => 0,
+    };
+
+    let (mut rows, mut data) = (0, Vec::new());
+    for (index, record) in records {
+        let row = index + 1;
+        check_columns(row, cols, record.len())?;
+        for (j, (cell, _)) in record.iter().enumerate() {
+            data.push(parse_cell(cell, row, j + 1, options.missing)?);
+        }
+        rows += 1;
+    }
+    Ok((header, DMat::from_vec(rows, cols, data)))
+}
+
+fn write_cell<W: Write>(writer: &mut W, cell: &str, options: &Options) -> io::Result<()> {
+    let quote = match options.quote {
+        Some(quote) if cell.contains([options.delimiter, quote, '\n', '\r']) => quote,
+        _ => return write!(writer, "{}", cell),
+    };
+    let escaped = cell.replace(quote, &format!("{}{}", quote, quote));
+    write!(writer, "{}{}{}", quote, escaped, quote)
+}
+
+// Writes a header row first when `header` is given; `options.header` is
+// ignored. NaN is written as an empty cell when `options.missing` is `Nan`.
+pub fn write<T: Float + fmt::Display, W: Write, const R: usize, const C: usize>(
+    writer: &mut W,
+    mat: &Mat<T, R, C>,
+    header: Option<&[&str]>,
+    options: &Options,
+) -> Result<(), CsvError> {
+    write_rows(writer, (R, C), |i, j| mat[i][j], header, options)
+}
+
+pub fn write_dynamic<T: Float + fmt::Display, W: Write>(
+    writer: &mut W,
+    mat: &DMat<T>,
+    header: Option<&[&str]>,
+    options: &Options,
+) -> Result<(), CsvError> {
+    write_rows(writer, mat.shape(), |i, j| mat[(i, j)], header, options)
+}
+
+fn write_rows<T: Float + fmt::Display, W: Write>(
+    writer: &mut W,
+    (rows, cols): (usize, usize),
+    entry: impl Fn(usize, usize) -> T,
+    header: Option<&[&str]>,
+    options: &Options,
+) -> Result<(), CsvError> {
+    if let Some(header) = header {
+        assert_eq!(
+            header.len(),
+            cols,
+            "header must have exactly {} names",
+            cols
+        );
+        for (j, name) in header.iter().enumerate() {
+            if j > 0 {
+                write!(writer, "{}", options.delimiter)?;
+            }
+            write_cell(writer, name, options)?;
+        }
+        writeln!(writer)?;
+    }
+    for i in 0..rows {
+        for j in 0..cols {
+            if j > 0 {
+                write!(writer, "{}", options.delimiter)?;
+            }
+            let x = entry(i, j);
+            if !(x.is_nan() && options.missing == Missing::Nan) {
+                write!(writer, "{}", x)?;
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_str<const R: usize, const C: usize>(
+        text: &str,
+        options: &Options,
+    ) -> Result<(Option<Vec<String>>, Mat<f64, R, C>), CsvError> {
+        read(&mut text.as_bytes(), options)
+    }
+
+    #[test]
+    fn basic() {
+        let (header, mat) = read_str::<2, 3>("1, 2, 3\n4,5,6.5\n", &Options::default()).unwrap();
+        assert_eq!(header, None);
+        assert_eq!(mat, Mat::new(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.5]]));
+    }
+
+    #[test]
+    fn header_and_quotes() {
+        let text = "\"price, usd\";\"say \"\"hi\"\"\"\r\n\"1.5\";2\r\n\r\n3;4";
+        let options = Options::default().delimiter(';').header(true);
+        let (header, mat) = read_str::<2, 2>(text, &options).unwrap();
+        assert_eq!(
+            header.unwrap(),
+            vec!["price, usd".to_string(), "say \"hi\"".to_string()]
+        );
+        assert_eq!(mat, Mat::new(&[[1.5, 2.0], [3.0, 4.0]]));
+
+        let options = Options::default().quote(None);
+        let err = read_str::<1, 1>("\"1\"", &options).unwrap_err();
+        assert_eq!(err.to_string(), "row 1, column 1: invalid number");
+    }
+
+    #[test]
+    fn missing() {
+        let text = "1,,NaN\n";
+        let err = read_str::<1, 3>(text, &Options::default()).unwrap_err();
+        assert_eq!(err.to_string(), "row 1, column 2: empty cell");
+
+        let (_, mat) = read_str::<1, 3>(text, &Options::default().missing(Missing::Zero)).unwrap();
+        assert_eq!(mat[0][1], 0.0);
+        assert!(mat[0][2].is_nan());
+
+        let (_, mat) = read_str::<1, 3>(text, &Options::default().missing(Missing::Nan)).unwrap();
+        assert!(mat[0][1].is_nan());
+    }
+
+    #[test]
+    fn errors() {
+        let options = Options::default().header(true);
+        let err = read_str::<2, 2>("a,b\n1,2\n3,x\n", &options).unwrap_err();
+        assert_eq!(err.to_string(), "row 3, column 2: invalid number");
+
+        let err = read_str::<2, 2>("a,b\n1,2\n3\n", &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 3, column 2: expected 2 columns, found 1"
+        );
+
+        let err = read_str::<2, 2>("a,b\n1,2\n", &options).unwrap_err();
+        assert_eq!(err.to_string(), "row 3, column 1: expected 2 rows, found 1");
+
+        let err = read_str::<1, 2>("1,\"2\n", &Options::default()).unwrap_err();
+        assert_eq!(err.to_string(), "row 1, column 2: unterminated quote");
+
+        // blank lines do not count towards the row, whatever the error
+        let err = read_str::<2, 2>("\n1,2\n\n3,\"4\n", &Options::default()).unwrap_err();
+        assert_eq!(err.to_string(), "row 2, column 2: unterminated quote");
+        let err = read_str::<2, 2>("\n1,2\n\n3,x\n", &Options::default()).unwrap_err();
+        assert_eq!(err.to_string(), "row 2, column 2: invalid number");
+
+        let err = read_str::<1, 2>("\"1\"2,3\n", &Options::default()).unwrap_err();
+        assert_eq!(err.to_string(), "row 1, column 1: text after closing quote");
+        let (_, mat) = read_str::<1, 2>("\"1\" ,3\n", &Options::default()).unwrap();
+        assert_eq!(mat, Mat::new(&[[1.0, 3.0]]));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mat = Mat::new(&[[1.0, -2.5, f64::NAN], [1e-3, 0.0, 7.0]]);
+        let options = Options::default().header(true).missing(Missing::Nan);
+        let mut bytes = Vec::new();
+        write(&mut bytes, &mat, Some(&["x", "y,z", "w"]), &options).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+        assert_eq!(text, "x,\"y,z\",w\n1,-2.5,\n0.001,0,7\n");
+
+        let (header, back) = read_str::<2, 3>(&text, &options).unwrap();
+        assert_eq!(header.unwrap(), vec!["x", "y,z", "w"]);
+        assert!(back[0][2].is_nan());
+        assert_eq!(back[1], mat[1]);
+    }
+
+    #[test]
+    fn dynamic() {
+        let mat = DMat::from_vec(3, 2, vec![1.0, -2.5, f64::NAN, 1e-3, 0.0, 7.0]);
+        let options = Options::default().header(true).missing(Missing::Nan);
+        let mut bytes = Vec::new();
+        write_dynamic(&mut bytes, &mat, Some(&["x", "y"]), &options).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+        assert_eq!(text, "x,y\n1,-2.5\n,0.001\n0,7\n");
+
+        let (header, back) = read_dynamic::<f64, _>(&mut text.as_bytes(), &options).unwrap();
+        assert_eq!(header.unwrap(), vec!["x", "y"]);
+        assert_eq!(back.shape(), (3, 2));
+        assert!(back[(1, 0)].is_nan());
+        assert_eq!(back.row(2), mat.row(2));
+
+        // without a header the first record sets the width
+        let (header, back) = read_dynamic::<f64, _>(
+            &mut "1;2;3\n4;5;6".as_bytes(),
+            &Options::default().delimiter(';'),
+        )
+        .unwrap();
+        assert_eq!(header, None);
+        assert_eq!(
+            back,
+            DMat::from_vec(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0])
+        );
+        let (_, back) = read_dynamic::<f64, _>(&mut "".as_bytes(), &Options::default()).unwrap();
+        assert_eq!(back.shape(), (0, 0));
+    }
+
+    #[test]
+    fn dynamic_errors() {
+        let options = Options::default().header(true);
+        let read_str = |text: &str| read_dynamic::<f64, _>(&mut text.as_bytes(), &options);
+
+        // a ragged row, one cell too long and one too short
+        let err = read_str("a,b\n1,2\n3,4,5\n").unwrap_err();
+        assert!(matches!(
+            err,
+            CsvError::Cell {
+                row: 3,
+                column: 3,
+                kind: CsvErrorKind::Columns {
+                    expected: 2,
+                    found: 3
+                }
+            }
+        ));
+        let err = read_str("a,b\n1,2\n3,4\n5\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 4, column 2: expected 2 columns, found 1"
+        );
+
+        let err = read_str("a,b\n1,2\n3,x\n").unwrap_err();
+        assert!(matches!(
+            err,
+            CsvError::Cell {
+                row: 3,
+                column: 2,
+                kind: CsvErrorKind::BadNumber
+            }
+        ));
+    }
+}
@@ -0,0 +1,732 @@
+use crate::dmatrix::DMat;
+use crate::matrix::Mat;
+use crate::sparse::CscMatrix;
+use num::Float;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+// Reading and writing of Matrix Market `.mtx` files. Both the `coordinate`
+// and `array` formats are read, with `real`, `integer` or `pattern` fields
+// (pattern entries become one) and `general`, `symmetric` or
+// `skew-symmetric` storage. The crate has no complex matrices, so `complex`
+// fields, and the `hermitian` symmetry that only applies to them, are
+// `Unsupported`.
+//
+// Any file can be read into a `CscMatrix` (`read_sparse`), a `DMat`
+// (`read_dynamic`) or a fixed-size `Mat` (`read`); the last two fill in the
+// missing entries of coordinate files with zeros. Every reader sums
+// repeated coordinates, as the Matrix Market convention has it for
+// assembled matrices. Coordinate files are read
+// as a list of entries, so reading one into sparse storage never allocates
+// the dense matrix. Dense storage is only allocated once every entry has
+// been read and counted, and a declared shape that cannot be allocated is
+// `TooLarge` rather than an abort.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Coordinate,
+    Array,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    General,
+    Symmetric,
+    SkewSymmetric,
+}
+
+#[derive(Debug)]
+pub enum MtxError {
+    Io(io::Error),
+    BadHeader(String),
+    Unsupported(String),
+    // line numbers are 1-based
+    BadLine(usize),
+    IndexOutOfRange(usize),
+    ShapeMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    EntryCount {
+        expected: usize,
+        found: usize,
+    },
+    NotSymmetric,
+    TooLarge {
+        rows: usize,
+        cols: usize,
+    },
+}
+
+impl fmt::Display for MtxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MtxError::Io(err) => write!(f, "{}", err),
+            MtxError::BadHeader(header) => write!(f, "malformed header: {}", header),
+            MtxError::Unsupported(what) => write!(f, "unsupported {}", what),
+            MtxError::BadLine(line) => write!(f, "line {}: malformed entry", line),
+            MtxError::IndexOutOfRange(line) => write!(f, "line {}: index out of range", line),
+            MtxError::ShapeMismatch { expected, found } => write!(
+                f,
+                "expected a {}x{} matrix, found {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            MtxError::EntryCount { expected, found } => {
+                write!(f, "expected {} entries, found {}", expected, found)
+            }
+            MtxError::NotSymmetric => write!(f, "matrix does not have the requested symmetry"),
+            MtxError::TooLarge { rows, cols } => {
+                write!(f, "a dense {}x{} matrix cannot be allocated", rows, cols)
+            }
+        }
+    }
+}
+
+impl Error for MtxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MtxError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MtxError {
+    fn from(err: io::Error) -> Self {
+        MtxError::Io(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Real,
+    Integer,
+    Pattern,
+}
+
+struct Header {
+    format: Format,
+    field: Field,
+    symmetry: Symmetry,
+}
+
+fn parse_header(line: &str) -> Result<Header, MtxError> {
+    let bad = || MtxError::BadHeader(line.trim_end().to_string());
+    let words: Vec<String> = line.split_whitespace().map(str::to_lowercase).collect();
+    let [banner, object, format, field, symmetry] = &words[..] else {
+        return Err(bad());
+    };
+    if banner != "%%matrixmarket" {
+        return Err(bad());
+    }
+    if object != "matrix" {
+        return Err(MtxError::Unsupported(format!("object '{}'", object)));
+    }
+    let format = match format.as_str() {
+        "coordinate" => Format::Coordinate,
+        "array" => Format::Array,
+        _ => return Err(MtxError::Unsupported(format!("format '{}'", format))),
+    };
+    let field = match field.as_str() {
+        "real" | "double" => Field::Real,
+        "integer" => Field::Integer,
+        "pattern" if format == Format::Coordinate => Field::Pattern,
+        "complex" => {
+            return Err(MtxError::Unsupported(
+                "field 'complex': complex matrices are not supported".to_string(),
+            ))
+        }
+        _ => return Err(MtxError::Unsupported(format!("field '{}'", field))),
+    };
+    let symmetry = match symmetry.as_str() {
+        "general" => Symmetry::General,
+        "symmetric" => Symmetry::Symmetric,
+        "skew-symmetric" => Symmetry::SkewSymmetric,
+        // Hermitian storage of real data is ill-formed, and is not silently
+        // read as symmetric
+        "hermitian" => {
+            return Err(MtxError::Unsupported(
+                "symmetry 'hermitian': only valid for complex matrices".to_string(),
+            ))
+        }
+        _ => return Err(MtxError::Unsupported(format!("symmetry '{}'", symmetry))),
+    };
+    Ok(Header {
+        format,
+        field,
+        symmetry,
+    })
+}
+
+fn parse_value<T: Float>(word: Option<&str>, field: Field, line: usize) -> Result<T, MtxError> {
+    let word = word.ok_or(MtxError::BadLine(line))?;
+    let value = match field {
+        Field::Real => word.parse::<f64>().ok().and_then(T::from),
+        Field::Integer => word.parse::<i64>().ok().and_then(T::from),
+        Field::Pattern => unreachable!(),
+    };
+    value.ok_or(MtxError::BadLine(line))
+}
+
+fn parse_index(word: Option<&str>, len: usize, line: usize) -> Result<usize, MtxError> {
+    let index: usize = word
+        .and_then(|w| w.parse().ok())
+        .ok_or(MtxError::BadLine(line))?;
+    if index == 0 || index > len {
+        return Err(MtxError::IndexOutOfRange(line));
+    }
+    Ok(index - 1)
+}
+
+// The stored entries of a file, with those implied by its symmetry added.
+struct Entries<T> {
+    rows: usize,
+    cols: usize,
+    triplets: Vec<(usize, usize, T)>,
+}
+
+impl<T> Entries<T> {
+    // An empty vector with room for `len` elements, where `len` is worked out
+    // from the declared shape: `TooLarge` if it overflowed or cannot be
+    // allocated.
+    fn allocate<U>(&self, len: Option<usize>) -> Result<Vec<U>, MtxError> {
+        let too_large = || MtxError::TooLarge {
+            rows: self.rows,
+            cols: self.cols,
+        };
+        let mut vec = Vec::new();
+        vec.try_reserve_exact(len.ok_or_else(too_large)?)
+            .map_err(|_| too_large())?;
+        Ok(vec)
+    }
+}
+
+// Reads a whole file. With `shape`, a file of any other shape fails with
+// `ShapeMismatch` before its entries are read. Zeros in `array` files are
+// dropped, since they are not stored entries of the matrix.
+fn read_entries<T: Float, Rd: BufRead>(
+    reader: Rd,
+    shape: Option<(usize, usize)>,
+) -> Result<Entries<T>, MtxError> {
+    let mut lines = reader
+        .lines()
+        .enumerate()
+        .map(|(n, line)| line.map(|line| (n + 1, line)));
+
+    let (_, first) = lines
+        .next()
+        .transpose()?
+        .ok_or_else(|| MtxError::BadHeader(String::new()))?;
+    let header = parse_header(&first)?;
+
+    // everything after the banner is data, apart from comments and blank lines
+    let mut data = lines.filter(|line| match line {
+        Ok((_, line)) => !line.starts_with('%') && !line.trim().is_empty(),
+        Err(_) => true,
+    });
+
+    let (size_line, size) = data.next().transpose()?.ok_or(MtxError::BadLine(0))?;
+    let size: Vec<usize> = size
+        .split_whitespace()
+        .map(|w| w.parse().map_err(|_| MtxError::BadLine(size_line)))
+        .collect::<Result<_, _>>()?;
+    let (rows, cols, count) = match (header.format, &size[..]) {
+        (Format::Coordinate, &[rows, cols, nnz]) => (rows, cols, nnz),
+        (Format::Array, &[rows, cols]) => {
+            // the sizes come from the file, so the products may overflow
+            let count = match header.symmetry {
+                Symmetry::General => rows.checked_mul(cols),
+                Symmetry::Symmetric => rows
+                    .checked_add(1)
+                    .and_then(|n| rows.checked_mul(n))
+                    .map(|n| n / 2),
+                Symmetry::SkewSymmetric => rows.checked_mul(rows.saturating_sub(1)).map(|n| n / 2),
+            };
+            (rows, cols, count.ok_or(MtxError::BadLine(size_line))?)
+        }
+        _ => return Err(MtxError::BadLine(size_line)),
+    };
+    if let Some(expected) = shape {
+        if (rows, cols) != expected {
+            return Err(MtxError::ShapeMismatch {
+                expected,
+                found: (rows, cols),
+            });
+        }
+    }
+    if header.symmetry != Symmetry::General && rows != cols {
+        return Err(MtxError::BadLine(size_line));
+    }
+
+    // array entries are stored column by column, covering only the lower
+    // triangle when the matrix is symmetric
+    let mut array_positions = (0..cols).flat_map(|j| {
+        let start = match header.symmetry {
+            Symmetry::General => 0,
+            Symmetry::Symmetric => j,
+            Symmetry::SkewSymmetric => j + 1,
+        };
+        (start..rows).map(move |i| (i, j))
+    });
+
+    // the count comes from the file, so it only bounds the reservation
+    let mut triplets = Vec::with_capacity(count.min(1 << 20));
+    let mut found = 0;
+    for entry in data {
+        let (line, entry) = entry?;
+        found += 1;
+        if found > count {
+            continue;
+        }
+        let mut words = entry.split_whitespace();
+        let (i, j, value) = match header.format {
+            Format::Coordinate => {
+                let i = parse_index(words.next(), rows, line)?;
+                let j = parse_index(words.next(), cols, line)?;
+                let value = match header.field {
+                    Field::Pattern => T::one(),
+                    field => parse_value(words.next(), field, line)?,
+                };
+                (i, j, value)
+            }
+            Format::Array => {
+                let (i, j) = array_positions.next().unwrap();
+                (i, j, parse_value(words.next(), header.field, line)?)
+            }
+        };
+        if words.next().is_some() {
+            return Err(MtxError::BadLine(line));
+        }
+        if header.format == Format::Array && value == T::zero() {
+            continue;
+        }
+        triplets.push((i, j, value));
+        if i != j {
+            match header.symmetry {
+                Symmetry::General => {}
+                Symmetry::Symmetric => triplets.push((j, i, value)),
+                Symmetry::SkewSymmetric => triplets.push((j, i, -value)),
+            }
+        }
+    }
+    if found != count {
+        return Err(MtxError::EntryCount {
+            expected: count,
+            found,
+        });
+    }
+    Ok(Entries {
+        rows,
+        cols,
+        triplets,
+    })
+}
+
+// Reads into a matrix of the shape given in the file.
+pub fn read_sparse<T: Float, Rd: BufRead>(reader: Rd) -> Result<CscMatrix<T>, MtxError> {
+    let entries = read_entries(reader, None)?;
+    // `from_triplets` allocates the column pointers infallibly
+    entries.allocate::<usize>(entries.cols.checked_add(1))?;
+    Ok(CscMatrix::from_triplets(
+        entries.rows,
+        entries.cols,
+        &entries.triplets,
+    ))
+}
+
+// Reads into a dense matrix of the shape given in the file.
+pub fn read_dynamic<T: Float, Rd: BufRead>(reader: Rd) -> Result<DMat<T>, MtxError> {
+    let entries = read_entries(reader, None)?;
+    let mut data = entries.allocate(entries.rows.checked_mul(entries.cols))?;
+    // the product did not overflow, or the allocation would have failed
+    data.resize(entries.rows * entries.cols, T::zero());
+    let mut mat = DMat::from_vec(entries.rows, entries.cols, data);
+    for (i, j, value) in entries.triplets {
+        mat[(i, j)] = mat[(i, j)] + value;
+    }
+    Ok(mat)
+}
+
+// `read_dynamic` for a file that has to be R x C.
+pub fn read<T: Float, Rd: BufRead, const R: usize, const C: usize>(
+    reader: Rd,
+) -> Result<Mat<T, R, C>, MtxError> {
+    let entries = read_entries(reader, Some((R, C)))?;
+    let mut mat = Mat::zero();
+    for (i, j, value) in entries.triplets {
+        mat[i][j] = mat[i][j] + value;
+    }
+    Ok(mat)
+}
+
+fn check_symmetry<T: Float, const R: usize, const C: usize>(
+    mat: &Mat<T, R, C>,
+    symmetry: Symmetry,
+) -> Result<(), MtxError> {
+    let sign = match symmetry {
+        Symmetry::General => return Ok(()),
+        Symmetry::Symmetric => T::one(),
+        Symmetry::SkewSymmetric => -T::one(),
+    };
+    if R != C {
+        return Err(MtxError::NotSymmetric);
+    }
+    for i in 0..R {
+        for j in 0..=i {
+            if mat[i][j] != sign * mat[j][i] {
+                return Err(MtxError::NotSymmetric);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn symmetry_name(symmetry: Symmetry) -> &'static str {
+    match symmetry {
+        Symmetry::General => "general",
+        Symmetry::Symmetric => "symmetric",
+        Symmetry::SkewSymmetric => "skew-symmetric",
+    }
+}
+
+// Entries that are written, in file order: everything for a general matrix,
+// otherwise the lower triangle (strictly lower when skew-symmetric).
+fn stored(symmetry: Symmetry, i: usize, j: usize) -> bool {
+    match symmetry {
+        Symmetry::General => true,
+        Symmetry::Symmetric => i >= j,
+        Symmetry::SkewSymmetric => i > j,
+    }
+}
+
+pub fn write<T: Float + fmt::Display, W: Write, const R: usize, const C: usize>(
+    writer: &mut W,
+    mat: &Mat<T, R, C>,
+    format: Format,
+    symmetry: Symmetry,
+) -> Result<(), MtxError> {
+    check_symmetry(mat, symmetry)?;
+    let symmetry_name = symmetry_name(symmetry);
+
+    match format {
+        Format::Array => {
+            writeln!(writer, "%%MatrixMarket matrix array real {}", symmetry_name)?;
+            writeln!(writer, "{} {}", R, C)?;
+            for j in 0..C {
+                for i in (0..R).filter(|&i| stored(symmetry, i, j)) {
+                    writeln!(writer, "{}", mat[i][j])?;
+                }
+            }
+        }
+        Format::Coordinate => {
+            let nonzeros: Vec<(usize, usize)> = (0..C)
+                .flat_map(|j| (0..R).map(move |i| (i, j)))
+                .filter(|&(i, j)| stored(symmetry, i, j) && mat[i][j] != T::zero())
+                .collect();
+            writeln!(
+                writer,
+                "%%MatrixMarket matrix coordinate real {}",
+                symmetry_name
+            )?;
+            writeln!(writer, "{} {} {}", R, C, nonzeros.len())?;
+            for (i, j) in nonzeros {
+                writeln!(writer, "{} {} {}", i + 1, j + 1, mat[i][j])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Writes the stored entries of `mat` in coordinate format, explicit zeros
+// included, so that the pattern survives a round trip through `read_sparse`.
+// Only the lower triangle is written for a symmetric `symmetry`, which every
+// stored entry has to satisfy.
+pub fn write_sparse<T: Float + fmt::Display, W: Write>(
+    writer: &mut W,
+    mat: &CscMatrix<T>,
+    symmetry: Symmetry,
+) -> Result<(), MtxError> {
+    let sign = match symmetry {
+        Symmetry::General | Symmetry::Symmetric => T::one(),
+        Symmetry::SkewSymmetric => -T::one(),
+    };
+    let entries = || {
+        (0..mat.ncols()).flat_map(move |j| {
+            let (rows, values) = mat.col(j);
+            rows.iter().zip(values).map(move |(&i, &v)| (i, j, v))
+        })
+    };
+    if symmetry != Symmetry::General {
+        if mat.nrows() != mat.ncols() {
+            return Err(MtxError::NotSymmetric);
+        }
+        if entries().any(|(i, j, v)| mat.get(j, i) != sign * v) {
+            return Err(MtxError::NotSymmetric);
+        }
+    }
+
+    let count = entries()
+        .filter(|&(i, j, _)| stored(symmetry, i, j))
+        .count();
+    writeln!(
+        writer,
+        "%%MatrixMarket matrix coordinate real {}",
+        symmetry_name(symmetry)
+    )?;
+    writeln!(writer, "{} {} {}", mat.nrows(), mat.ncols(), count)?;
+    for (i, j, v) in entries().filter(|&(i, j, _)| stored(symmetry, i, j)) {
+        writeln!(writer, "{} {} {}", i + 1, j + 1, v)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sym() -> Mat<f64, 3, 3> {
+        Mat::new(&[[4.0, 1.0, 0.0], [1.0, 3.0, -0.5], [0.0, -0.5, 2.0]])
+    }
+
+    #[test]
+    fn coordinate() {
+        let file = "\
+%%MatrixMarket matrix coordinate real general
+% a comment
+
+2 3 3
+1 1 1.5
+2 3 -2e1
+1 2 4
+";
+        assert_eq!(
+            read::<f64, _, 2, 3>(file.as_bytes()).unwrap(),
+            Mat::new(&[[1.5, 4.0, 0.0], [0.0, 0.0, -20.0]])
+        );
+    }
+
+    #[test]
+    fn array() {
+        let file = "%%MatrixMarket matrix array integer general\n2 2\n1\n3\n2\n4\n";
+        assert_eq!(
+            read::<f32, _, 2, 2>(file.as_bytes()).unwrap(),
+            Mat::new(&[[1.0, 2.0], [3.0, 4.0]])
+        );
+    }
+
+    #[test]
+    fn symmetry() {
+        let file = "%%MatrixMarket matrix coordinate pattern symmetric\n3 3 2\n1 1\n3 1\n";
+        assert_eq!(
+            read::<f64, _, 3, 3>(file.as_bytes()).unwrap(),
+            Mat::new(&[[1.0, 0.0, 1.0], [0.0, 0.0, 0.0], [1.0, 0.0, 0.0]])
+        );
+
+        let file = "%%MatrixMarket matrix array real skew-symmetric\n3 3\n1\n2\n3\n";
+        assert_eq!(
+            read::<f64, _, 3, 3>(file.as_bytes()).unwrap(),
+            Mat::new(&[[0.0, -1.0, -2.0], [1.0, 0.0, -3.0], [2.0, 3.0, 0.0]])
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in [Format::Coordinate, Format::Array] {
+            for symmetry in [Symmetry::General, Symmetry::Symmetric] {
+                let mut bytes = Vec::new();
+                write(&mut bytes, &sym(), format, symmetry).unwrap();
+                assert_eq!(read::<f64, _, 3, 3>(&bytes[..]).unwrap(), sym());
+            }
+        }
+
+        let mut bytes = Vec::new();
+        write(&mut bytes, &sym(), Format::Coordinate, Symmetry::Symmetric).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "\
+%%MatrixMarket matrix coordinate real symmetric
+3 3 5
+1 1 4
+2 1 1
+2 2 3
+3 2 -0.5
+3 3 2
+"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = |file: &str| read::<f64, _, 2, 2>(file.as_bytes()).unwrap_err();
+
+        let complex = err("%%MatrixMarket matrix coordinate complex general\n2 2 0\n");
+        assert!(matches!(complex, MtxError::Unsupported(_)));
+        assert_eq!(
+            complex.to_string(),
+            "unsupported field 'complex': complex matrices are not supported"
+        );
+        let hermitian = err("%%MatrixMarket matrix coordinate real hermitian\n2 2 0\n");
+        assert_eq!(
+            hermitian.to_string(),
+            "unsupported symmetry 'hermitian': only valid for complex matrices"
+        );
+        assert!(matches!(
+            err("%%MatrixMarket matrix array real general\n3 2\n"),
+            MtxError::ShapeMismatch {
+                expected: (2, 2),
+                found: (3, 2)
+            }
+        ));
+        assert!(matches!(
+            err("%%MatrixMarket matrix coordinate real general\n2 2 1\n3 1 1.0\n"),
+            MtxError::IndexOutOfRange(3)
+        ));
+        assert!(matches!(
+            err("%%MatrixMarket matrix coordinate real general\n2 2 1\n1 1 x\n"),
+            MtxError::BadLine(3)
+        ));
+        assert!(matches!(
+            err("%%MatrixMarket matrix array real general\n2 2\n1\n2\n3\n"),
+            MtxError::EntryCount {
+                expected: 4,
+                found: 3
+            }
+        ));
+        assert!(matches!(err("2 2 0\n"), MtxError::BadHeader(_)));
+
+        let mat = Mat::new(&[[1.0, 2.0], [3.0, 4.0]]);
+        assert!(matches!(
+            write(&mut Vec::new(), &mat, Format::Array, Symmetry::Symmetric),
+            Err(MtxError::NotSymmetric)
+        ));
+    }
+
+    #[test]
+    fn sparse() {
+        let file = "\
+%%MatrixMarket matrix coordinate real symmetric
+4 4 4
+1 1 2
+3 1 -1
+4 2 0
+4 4 5
+";
+        let a = read_sparse::<f64, _>(file.as_bytes()).unwrap();
+        assert_eq!((a.nrows(), a.ncols(), a.nnz()), (4, 4, 6));
+        // the diagonal is not mirrored onto itself
+        assert_eq!(a.diagonal(), vec![2.0, 0.0, 0.0, 5.0]);
+        assert_eq!((a.get(0, 2), a.get(2, 0)), (-1.0, -1.0));
+        // the explicit zero is part of the pattern
+        assert_eq!(a.col(1).0, &[3]);
+
+        let mut bytes = Vec::new();
+        write_sparse(&mut bytes, &a, Symmetry::Symmetric).unwrap();
+        assert_eq!(String::from_utf8(bytes.clone()).unwrap(), file);
+        assert_eq!(read_sparse::<f64, _>(&bytes[..]).unwrap(), a);
+
+        let file = "%%MatrixMarket matrix array real general\n2 3\n1\n0\n0\n2\n3\n0\n";
+        let a = read_sparse::<f64, _>(file.as_bytes()).unwrap();
+        assert_eq!(a.nnz(), 3);
+        assert_eq!(
+            a.to_dense::<2, 3>(),
+            Mat::new(&[[1.0, 0.0, 3.0], [0.0, 2.0, 0.0]])
+        );
+        assert!(matches!(
+            write_sparse(&mut Vec::new(), &a, Symmetry::Symmetric),
+            Err(MtxError::NotSymmetric)
+        ));
+    }
+
+    #[test]
+    fn dynamic() {
+        let file = "%%MatrixMarket matrix array real skew-symmetric\n3 3\n1\n2\n3\n";
+        let a = read_dynamic::<f64, _>(file.as_bytes()).unwrap();
+        assert_eq!(a.shape(), (3, 3));
+        assert_eq!(
+            a.to_mat::<3, 3>(),
+            read::<f64, _, 3, 3>(file.as_bytes()).unwrap()
+        );
+        assert_eq!(a.row(2), &[2.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn duplicates() {
+        // every reader sums repeated coordinates, including those that a
+        // symmetric file mirrors onto each other
+        for (file, (i, j)) in [
+            (
+                "coordinate real general\n2 2 3\n1 1 1\n2 1 5\n1 1 2\n",
+                (0, 0),
+            ),
+            (
+                "coordinate integer symmetric\n2 2 3\n2 1 4\n1 1 1\n2 1 -1\n",
+                (0, 1),
+            ),
+            ("coordinate pattern general\n2 2 3\n1 2\n1 2\n1 2\n", (0, 1)),
+        ] {
+            let file = format!("%%MatrixMarket matrix {}", file);
+            let sparse = read_sparse::<f64, _>(file.as_bytes()).unwrap();
+            let dynamic = read_dynamic::<f64, _>(file.as_bytes()).unwrap();
+            let fixed = read::<f64, _, 2, 2>(file.as_bytes()).unwrap();
+            assert_eq!(fixed[i][j], 3.0);
+            assert_eq!(sparse.to_dense::<2, 2>(), fixed);
+            assert_eq!(dynamic.to_mat::<2, 2>(), fixed);
+        }
+    }
+
+    // The sizes come from the file and are not trusted for arithmetic or
+    // allocation.
+    #[test]
+    fn hostile_sizes() {
+        for file in [
+            "%%MatrixMarket matrix array real general\n18446744073709551615 2\n",
+            "%%MatrixMarket matrix array real symmetric\n18446744073709551615 18446744073709551615\n",
+            "%%MatrixMarket matrix array real skew-symmetric\n4294967297 4294967297\n",
+        ] {
+            assert!(matches!(
+                read_dynamic::<f64, _>(file.as_bytes()),
+                Err(MtxError::BadLine(2))
+            ));
+        }
+
+        // an array file has to hold every entry it declares
+        let file = "%%MatrixMarket matrix array real general\n100000 100000\n1\n";
+        assert!(matches!(
+            read_dynamic::<f64, _>(file.as_bytes()),
+            Err(MtxError::EntryCount {
+                expected: 10_000_000_000,
+                found: 1
+            })
+        ));
+
+        let file = "%%MatrixMarket matrix coordinate real general\n2147483648 2147483648 0\n";
+        assert!(matches!(
+            read_dynamic::<f64, _>(file.as_bytes()),
+            Err(MtxError::TooLarge {
+                rows: 2147483648,
+                cols: 2147483648
+            })
+        ));
+        let file = "%%MatrixMarket matrix coordinate real general\n4294967296 4294967296 0\n";
+        assert!(matches!(
+            read_dynamic::<f64, _>(file.as_bytes()),
+            Err(MtxError::TooLarge { .. })
+        ));
+
+        // sparse storage still needs a pointer per column
+        for cols in ["18446744073709551615", "2305843009213693952"] {
+            let file = format!(
+                "%%MatrixMarket matrix coordinate real general\n1 {} 0\n",
+                cols
+            );
+            assert!(matches!(
+                read_sparse::<f64, _>(file.as_bytes()),
+                Err(MtxError::TooLarge { rows: 1, .. })
+            ));
+        }
+        let file = "%%MatrixMarket matrix coordinate real general\n18446744073709551615 1 0\n";
+        let a = read_sparse::<f64, _>(file.as_bytes()).unwrap();
+        assert_eq!((a.nrows(), a.ncols(), a.nnz()), (usize::MAX, 1, 0));
+    }
+}
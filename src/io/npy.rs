@@ -0,0 +1,628 @@
+use crate::dmatrix::DMat;
+use crate::matrix::Mat;
+use num::Float;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+// Reading and writing of NumPy `.npy` files (format versions 1.0, 2.0 and
+// 3.0). Any little- or big-endian float or integer dtype can be read and is
+// converted to `T`; complex and structured dtypes are rejected. Files are
+// written as version 1.0 with the native dtype of `T`, which must be `f32` or
+// `f64`. `read_dynamic` and `write_dynamic` do the same for `DMat`.
+//
+// Sizes in the file are not trusted for allocation: the header length is
+// capped, and the data is read as it arrives rather than into a buffer sized
+// from the header's shape.
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+// Longer headers are rejected, as NumPy itself does, so that a corrupt or
+// hostile length field cannot make the reader allocate gigabytes.
+const MAX_HEADER_LEN: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub enum NpyError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u8, u8),
+    BadHeader(String),
+    HeaderTooLong(usize),
+    UnsupportedDtype(String),
+    // `read_dynamic` only takes 1-d and 2-d arrays
+    UnsupportedShape(Vec<usize>),
+    ShapeMismatch {
+        expected: (usize, usize),
+        found: Vec<usize>,
+    },
+}
+
+impl fmt::Display for NpyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NpyError::Io(err) => write!(f, "{}", err),
+            NpyError::BadMagic => write!(f, "not a .npy file"),
+            NpyError::UnsupportedVersion(major, minor) => {
+                write!(f, "unsupported .npy version {}.{}", major, minor)
+            }
+            NpyError::BadHeader(header) => write!(f, "malformed .npy header: {}", header),
+            NpyError::HeaderTooLong(len) => write!(
+                f,
+                ".npy header of {} bytes is longer than the {} allowed",
+                len, MAX_HEADER_LEN
+            ),
+            NpyError::UnsupportedDtype(descr) => write!(f, "unsupported dtype '{}'", descr),
+            NpyError::UnsupportedShape(shape) => {
+                write!(f, "unsupported shape {:?}, expected 1-d or 2-d", shape)
+            }
+            NpyError::ShapeMismatch { expected, found } => write!(
+                f,
+                "expected an array of shape ({}, {}), found {:?}",
+                expected.0, expected.1, found
+            ),
+        }
+    }
+}
+
+impl Error for NpyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NpyError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NpyError {
+    fn from(err: io::Error) -> Self {
+        NpyError::Io(err)
+    }
+}
+
+// Element types that can be written to a `.npy` file.
+pub trait NpyElement: Float {
+    const DESCR: &'static str;
+
+    fn write_le<W: Write>(self, writer: &mut W) -> io::Result<()>;
+}
+
+impl NpyElement for f32 {
+    const DESCR: &'static str = "<f4";
+
+    fn write_le<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+impl NpyElement for f64 {
+    const DESCR: &'static str = "<f8";
+
+    fn write_le<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Float,
+    Int,
+    UInt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Dtype {
+    kind: Kind,
+    size: usize,
+    big_endian: bool,
+}
+
+impl Dtype {
+    fn parse(descr: &str) -> Result<Self, NpyError> {
+        let unsupported = || NpyError::UnsupportedDtype(descr.to_string());
+        let mut chars = descr.chars();
+        let big_endian = match chars.next() {
+            Some('<') => false,
+            Some('>') => true,
+            Some('|') => false,
+            Some('=') => cfg!(target_endian = "big"),
+            _ => return Err(unsupported()),
+        };
+        let kind = match chars.next() {
+            Some('f') => Kind::Float,
+            Some('i') => Kind::Int,
+            Some('u') => Kind::UInt,
+            _ => return Err(unsupported()),
+        };
+        let size = chars.as_str().parse().map_err(|_| unsupported())?;
+        let supported = match kind {
+            Kind::Float => matches!(size, 4 | 8),
+            Kind::Int | Kind::UInt => matches!(size, 1 | 2 | 4 | 8),
+        };
+        if !supported {
+            return Err(unsupported());
+        }
+        Ok(Self {
+            kind,
+            size,
+            big_endian,
+        })
+    }
+
+    fn convert<T: Float>(&self, bytes: &[u8]) -> Option<T> {
+        let mut buf = [0u8; 8];
+        buf[..self.size].copy_from_slice(bytes);
+        if self.big_endian {
+            buf[..self.size].reverse();
+        }
+        match (self.kind, self.size) {
+            (Kind::Float, 4) => T::from(f32::from_le_bytes(buf[..4].try_into().unwrap())),
+            (Kind::Float, 8) => T::from(f64::from_le_bytes(buf)),
+            (Kind::Int, 1) => T::from(buf[0] as i8),
+            (Kind::Int, 2) => T::from(i16::from_le_bytes(buf[..2].try_into().unwrap())),
+            (Kind::Int, 4) => T::from(i32::from_le_bytes(buf[..4].try_into().unwrap())),
+            (Kind::Int, 8) => T::from(i64::from_le_bytes(buf)),
+            (Kind::UInt, 1) => T::from(buf[0]),
+            (Kind::UInt, 2) => T::from(u16::from_le_bytes(buf[..2].try_into().unwrap())),
+            (Kind::UInt, 4) => T::from(u32::from_le_bytes(buf[..4].try_into().unwrap())),
+            (Kind::UInt, 8) => T::from(u64::from_le_bytes(buf)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Header {
+    descr: String,
+    fortran_order: bool,
+    shape: Vec<usize>,
+}
+
+// Parses the Python dict literal that makes up the header, e.g.
+// {'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }
+// A valid header is pure ASCII, which lets the scanning below slice by byte
+// offset.
+fn parse_header(s: &str) -> Result<Header, NpyError> {
+    let bad = || NpyError::BadHeader(s.trim_end().to_string());
+    if !s.is_ascii() {
+        return Err(bad());
+    }
+    let body = s
+        .trim()
+        .strip_prefix('{')
+        .and_then(|s| s.strip_suffix('}'))
+        .ok_or_else(bad)?;
+
+    let mut descr = None;
+    let mut fortran_order = None;
+    let mut shape = None;
+    let mut rest = body.trim_start();
+    while !rest.is_empty() {
+        let quote = rest.chars().next().filter(|&c| c == '\'' || c == '"');
+        let quote = quote.ok_or_else(bad)?;
+        let end = rest[1..].find(quote).ok_or_else(bad)? + 1;
+        let key = &rest[1..end];
+        rest = rest[end + 1..]
+            .trim_start()
+            .strip_prefix(':')
+            .ok_or_else(bad)?;
+        rest = rest.trim_start();
+
+        let value_end = if rest.starts_with('(') {
+            rest.find(')').ok_or_else(bad)? + 1
+        } else if rest.starts_with(['\'', '"']) {
+            rest[1..].find(&rest[..1]).ok_or_else(bad)? + 2
+        } else {
+            rest.find(',').unwrap_or(rest.len())
+        };
+        let value = rest[..value_end].trim();
+        rest = rest[value_end..].trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+
+        match key {
+            "descr" => {
+                let quoted = |q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q));
+                let inner = quoted('\'').or_else(|| quoted('"')).ok_or_else(bad)?;
+                descr = Some(inner.to_string());
+            }
+            "fortran_order" => {
+                fortran_order = Some(match value {
+                    "True" => true,
+                    "False" => false,
+                    _ => return Err(bad()),
+                })
+            }
+            "shape" => {
+                let dims = value
+                    .strip_prefix('(')
+                    .and_then(|v| v.strip_suffix(')'))
+                    .ok_or_else(bad)?;
+                shape = Some(
+                    dims.split(',')
+                        .map(str::trim)
+                        .filter(|d| !d.is_empty())
+                        .map(|d| d.parse().map_err(|_| bad()))
+                        .collect::<Result<Vec<usize>, _>>()?,
+                );
+            }
+            _ => return Err(bad()),
+        }
+    }
+
+    Ok(Header {
+        descr: descr.ok_or_else(bad)?,
+        fortran_order: fortran_order.ok_or_else(bad)?,
+        shape: shape.ok_or_else(bad)?,
+    })
+}
+
+fn read_header<Rd: Read>(reader: &mut Rd) -> Result<Header, NpyError> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic[..6] != MAGIC {
+        return Err(NpyError::BadMagic);
+    }
+
+    let len = match (magic[6], magic[7]) {
+        (1, 0) => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        (2, 0) | (3, 0) => {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        (major, minor) => return Err(NpyError::UnsupportedVersion(major, minor)),
+    };
+    if len > MAX_HEADER_LEN {
+        return Err(NpyError::HeaderTooLong(len));
+    }
+
+    let mut header = vec![0u8; len];
+    reader.read_exact(&mut header)?;
+    // versions 1 and 2 are latin-1, which only matters for non-ASCII bytes
+    // that cannot appear in a valid header anyway
+    let header = String::from_utf8(header)
+        .map_err(|err| NpyError::BadHeader(String::from_utf8_lossy(err.as_bytes()).into()))?;
+    parse_header(&header)
+}
+
+// Reads the `len` entries that follow the header, in file order.
+fn read_data<T: Float, Rd: Read>(
+    reader: &mut Rd,
+    header: &Header,
+    len: usize,
+) -> Result<Vec<T>, NpyError> {
+    let dtype = Dtype::parse(&header.descr)?;
+    let bytes = len
+        .checked_mul(dtype.size)
+        .ok_or_else(|| NpyError::BadHeader(format!("shape {:?}", header.shape)))?;
+    let mut data = Vec::new();
+    reader.take(bytes as u64).read_to_end(&mut data)?;
+    if data.len() != bytes {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    data.chunks_exact(dtype.size)
+        .map(|bytes| {
+            dtype
+                .convert(bytes)
+                .ok_or_else(|| NpyError::UnsupportedDtype(header.descr.clone()))
+        })
+        .collect()
+}
+
+pub fn read<T: Float, Rd: Read, const R: usize, const C: usize>(
+    reader: &mut Rd,
+) -> Result<Mat<T, R, C>, NpyError> {
+    let header = read_header(reader)?;
+
+    // a 1-d array is accepted for a row or column vector
+    let shape_ok = match header.shape[..] {
+        [r, c] => r == R && c == C,
+        [n] => n == R * C && (R == 1 || C == 1),
+        _ => false,
+    };
+    if !shape_ok {
+        return Err(NpyError::ShapeMismatch {
+            expected: (R, C),
+            found: header.shape,
+        });
+    }
+
+    let data = read_data(reader, &header, R * C)?;
+    let mut arr = [[T::zero(); C]; R];
+    for (k, x) in data.into_iter().enumerate() {
+        let (i, j) = if header.fortran_order {
+            (k % R, k / R)
+        } else {
+            (k / C, k % C)
+        };
+        arr[i][j] = x;
+    }
+    Ok(Mat::new(&arr))
+}
+
+// Reads a 2-d array of any shape, or a 1-d array as a column vector.
+pub fn read_dynamic<T: Float, Rd: Read>(reader: &mut Rd) -> Result<DMat<T>, NpyError> {
+    let header = read_header(reader)?;
+    let (rows, cols) = match header.shape[..] {
+        [r, c] => (r, c),
+        [n] => (n, 1),
+        _ => return Err(NpyError::UnsupportedShape(header.shape)),
+    };
+    let len = rows
+        .checked_mul(cols)
+        .ok_or_else(|| NpyError::BadHeader(format!("shape {:?}", header.shape)))?;
+    let data = read_data(reader, &header, len)?;
+    let mat = DMat::from_vec(rows, cols, data);
+    if header.fortran_order {
+        Ok(DMat::from_vec(cols, rows, mat.into_vec()).transpose())
+    } else {
+        Ok(mat)
+    }
+}
+
+// Writes a rows x cols matrix whose entries are given in row-major order.
+fn write_order<T: NpyElement, W: Write>(
+    writer: &mut W,
+    (rows, cols): (usize, usize),
+    data: &[T],
+    fortran_order: bool,
+) -> Result<(), NpyError> {
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': {}, 'shape': ({}, {}), }}",
+        T::DESCR,
+        if fortran_order { "True" } else { "False" },
+        rows,
+        cols
+    );
+    // pad so that the data starts on a 64-byte boundary, ending in a newline
+    let unpadded = MAGIC.len() + 4 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+    header.push('\n');
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+
+    if fortran_order {
+        for j in 0..cols {
+            for i in 0..rows {
+                data[i * cols + j].write_le(writer)?;
+            }
+        }
+    } else {
+        for x in data {
+            x.write_le(writer)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write<T: NpyElement, W: Write, const R: usize, const C: usize>(
+    writer: &mut W,
+    mat: &Mat<T, R, C>,
+) -> Result<(), NpyError> {
+    write_order(writer, (R, C), mat.as_slice(), false)
+}
+
+pub fn write_fortran<T: NpyElement, W: Write, const R: usize, const C: usize>(
+    writer: &mut W,
+    mat: &Mat<T, R, C>,
+) -> Result<(), NpyError> {
+    write_order(writer, (R, C), mat.as_slice(), true)
+}
+
+pub fn write_dynamic<T: NpyElement, W: Write>(
+    writer: &mut W,
+    mat: &DMat<T>,
+) -> Result<(), NpyError> {
+    write_order(writer, mat.shape(), mat.as_slice(), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn npy(version: u8, header: &str, data: &[u8]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([version, 0]);
+        if version == 1 {
+            bytes.extend((header.len() as u16).to_le_bytes());
+        } else {
+            bytes.extend((header.len() as u32).to_le_bytes());
+        }
+        bytes.extend(header.as_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    fn mat_2x3() -> Mat<f64, 2, 3> {
+        Mat::new(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]])
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut bytes = Vec::new();
+        write(&mut bytes, &mat_2x3()).unwrap();
+        assert_eq!(bytes.len(), 128 + 6 * 8);
+        assert_eq!(bytes[127], b'\n');
+        assert_eq!(read::<f64, _, 2, 3>(&mut &bytes[..]).unwrap(), mat_2x3());
+    }
+
+    #[test]
+    fn round_trip_fortran() {
+        let mut bytes = Vec::new();
+        let mat: Mat<f32, 2, 3> = Mat::new(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        write_fortran(&mut bytes, &mat).unwrap();
+        assert_eq!(&bytes[128..132], &1.0f32.to_le_bytes());
+        assert_eq!(&bytes[132..136], &4.0f32.to_le_bytes());
+        assert_eq!(read::<f32, _, 2, 3>(&mut &bytes[..]).unwrap(), mat);
+    }
+
+    #[test]
+    fn versions() {
+        let header = "{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }\n";
+        let data: Vec<u8> = mat_2x3()
+            .as_slice()
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        for version in 1..=3 {
+            let bytes = npy(version, header, &data);
+            assert_eq!(read::<f64, _, 2, 3>(&mut &bytes[..]).unwrap(), mat_2x3());
+        }
+        let bytes = npy(4, header, &data);
+        assert!(matches!(
+            read::<f64, _, 2, 3>(&mut &bytes[..]),
+            Err(NpyError::UnsupportedVersion(4, 0))
+        ));
+    }
+
+    #[test]
+    fn big_endian_int() {
+        let header = "{'shape': (2, 2), 'fortran_order': True, 'descr': '>i2'}";
+        let data: Vec<u8> = [1i16, -3, 2, 4]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        let bytes = npy(1, header, &data);
+        assert_eq!(
+            read::<f32, _, 2, 2>(&mut &bytes[..]).unwrap(),
+            Mat::new(&[[1.0, 2.0], [-3.0, 4.0]])
+        );
+    }
+
+    #[test]
+    fn one_dimensional() {
+        let wide = DMat::from_fn(3, 70, |i, j| (i * 70 + j) as f32 / 7.0);
+        let mut bytes = Vec::new();
+        write_dynamic(&mut bytes, &wide).unwrap();
+        assert_eq!(bytes.len() % 64, (3 * 70 * 4) % 64);
+        assert_eq!(read_dynamic::<f32, _>(&mut &bytes[..]).unwrap(), wide);
+
+        let header = "{'descr': '|u1', 'fortran_order': False, 'shape': (3,), }";
+        let bytes = npy(1, header, &[7, 8, 9]);
+        assert_eq!(
+            read::<f64, _, 3, 1>(&mut &bytes[..]).unwrap(),
+            Mat::new(&[[7.0], [8.0], [9.0]])
+        );
+        assert_eq!(
+            read::<f64, _, 1, 3>(&mut &bytes[..]).unwrap(),
+            Mat::new(&[[7.0, 8.0, 9.0]])
+        );
+    }
+
+    #[test]
+    fn shape_mismatch() {
+        let mut bytes = Vec::new();
+        write(&mut bytes, &mat_2x3()).unwrap();
+        match read::<f64, _, 3, 2>(&mut &bytes[..]) {
+            Err(NpyError::ShapeMismatch { expected, found }) => {
+                assert_eq!(expected, (3, 2));
+                assert_eq!(found, vec![2, 3]);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn unsupported_dtype() {
+        let header = "{'descr': '<c16', 'fortran_order': False, 'shape': (1, 1), }";
+        let bytes = npy(1, header, &[0; 16]);
+        let err = read::<f64, _, 1, 1>(&mut &bytes[..]).unwrap_err();
+        assert_eq!(err.to_string(), "unsupported dtype '<c16'");
+    }
+
+    #[test]
+    fn dynamic() {
+        let mut bytes = Vec::new();
+        write(&mut bytes, &mat_2x3()).unwrap();
+        let mat = read_dynamic::<f64, _>(&mut &bytes[..]).unwrap();
+        assert_eq!(mat.to_mat::<2, 3>(), mat_2x3());
+
+        let mut bytes = Vec::new();
+        write_fortran(&mut bytes, &mat_2x3()).unwrap();
+        let mat = read_dynamic::<f64, _>(&mut &bytes[..]).unwrap();
+        assert_eq!(mat.to_mat::<2, 3>(), mat_2x3());
+
+        let wide = DMat::from_fn(3, 70, |i, j| (i * 70 + j) as f32 / 7.0);
+        let mut bytes = Vec::new();
+        write_dynamic(&mut bytes, &wide).unwrap();
+        assert_eq!(bytes.len() % 64, (3 * 70 * 4) % 64);
+        assert_eq!(read_dynamic::<f32, _>(&mut &bytes[..]).unwrap(), wide);
+
+        let header = "{'descr': '|u1', 'fortran_order': False, 'shape': (3,), }";
+        let bytes = npy(1, header, &[7, 8, 9]);
+        let mat = read_dynamic::<f64, _>(&mut &bytes[..]).unwrap();
+        assert_eq!(mat.shape(), (3, 1));
+
+        let header = "{'descr': '<f8', 'fortran_order': False, 'shape': (2, 2, 2), }";
+        let bytes = npy(1, header, &[]);
+        assert!(matches!(
+            read_dynamic::<f64, _>(&mut &bytes[..]),
+            Err(NpyError::UnsupportedShape(shape)) if shape == [2, 2, 2]
+        ));
+    }
+
+    // The shape and the header length come from the file and are not trusted
+    // for allocation.
+    #[test]
+    fn hostile_sizes() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([2, 0]);
+        bytes.extend(u32::MAX.to_le_bytes());
+        assert!(matches!(
+            read::<f64, _, 1, 1>(&mut &bytes[..]),
+            Err(NpyError::HeaderTooLong(len)) if len == u32::MAX as usize
+        ));
+
+        let header = "{'descr': '<f8', 'fortran_order': False, 'shape': (100000, 100000), }";
+        let bytes = npy(1, header, &[0; 16]);
+        assert!(matches!(
+            read_dynamic::<f64, _>(&mut &bytes[..]),
+            Err(NpyError::Io(_))
+        ));
+        let header =
+            "{'descr': '<f8', 'fortran_order': False, 'shape': (1, 18446744073709551615), }";
+        let bytes = npy(1, header, &[]);
+        assert!(matches!(
+            read_dynamic::<f64, _>(&mut &bytes[..]),
+            Err(NpyError::BadHeader(_))
+        ));
+    }
+
+    #[test]
+    fn bad_input() {
+        assert!(matches!(
+            read::<f64, _, 1, 1>(&mut &b"PK\x03\x04rest"[..]),
+            Err(NpyError::BadMagic)
+        ));
+        let bytes = npy(1, "{'descr': '<f8'}", &[]);
+        assert!(matches!(
+            read::<f64, _, 1, 1>(&mut &bytes[..]),
+            Err(NpyError::BadHeader(_))
+        ));
+        for header in [
+            "{'descr': é, 'fortran_order': False, 'shape': (1, 1), }",
+            "{'descr': '<f8', 'fortran_order': False, 'shäpe': (1, 1), }",
+            "{'descr': '<f8, 'fortran_order': False, 'shape': (1, 1), }",
+            "{'descr': <f8, 'fortran_order': False, 'shape': (1, 1), }",
+        ] {
+            let bytes = npy(1, header, &[0; 8]);
+            assert!(matches!(
+                read::<f64, _, 1, 1>(&mut &bytes[..]),
+                Err(NpyError::BadHeader(_))
+            ));
+        }
+        let mut bytes = Vec::new();
+        write(&mut bytes, &mat_2x3()).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        assert!(matches!(
+            read::<f64, _, 2, 3>(&mut &bytes[..]),
+            Err(NpyError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,438 @@
+use super::error::LinalgError;
+use super::precond::Preconditioner;
+use super::sparse::CscMatrix;
+use alloc::vec;
+use alloc::vec::Vec;
+use num::Float;
+
+// Krylov solvers for sparse systems A x = b: conjugate gradients for
+// symmetric positive definite A, and restarted GMRES for general A. Both take
+// a preconditioner from `precond`, or `precond::Identity` for none, and stop
+// once the residual satisfies ||b - A x|| <= tolerance ||b||.
+//
+// GMRES is preconditioned on the right, A M^-1 u = b with x = M^-1 u, so the
+// residual it monitors is the true residual rather than M^-1 (b - A x).
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options<T: Float> {
+    max_iterations: usize,
+    tolerance: T,
+    restart: usize,
+}
+
+impl<T: Float> Default for Options<T> {
+    fn default() -> Self {
+        Self {
+            max_iterations: 1000,
+            tolerance: T::from(1e-10).unwrap(),
+            restart: 30,
+        }
+    }
+}
+
+impl<T: Float> Options<T> {
+    // Matrix-vector products before giving up.
+    pub fn max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    // The target residual relative to ||b||.
+    pub fn tolerance(self, tolerance: T) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    // The Krylov subspace size after which GMRES restarts.
+    pub fn restart(self, restart: usize) -> Self {
+        assert!(restart > 0, "restart length must be nonzero");
+        Self { restart, ..self }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution<T: Float> {
+    pub x: Vec<T>,
+    // ||b - A x|| / ||b||, or ||b - A x|| when b is zero
+    pub residual: T,
+    pub iterations: usize,
+}
+
+// Panics on the `DimensionMismatch`, `NotPositiveDefinite` or `NoConvergence`
+// from `try_cg`.
+pub fn cg<T: Float>(
+    a: &CscMatrix<T>,
+    b: &[T],
+    precond: &dyn Preconditioner<T>,
+    options: &Options<T>,
+) -> Solution<T> {
+    try_cg(a, b, precond, options).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Preconditioned conjugate gradients from x = 0. Both A and the preconditioner
+// must be symmetric positive definite; fails with `NotPositiveDefinite` when
+// a search direction shows that A is not.
+pub fn try_cg<T: Float>(
+    a: &CscMatrix<T>,
+    b: &[T],
+    precond: &dyn Preconditioner<T>,
+    options: &Options<T>,
+) -> Result<Solution<T>, LinalgError> {
+    let n = check(a, b)?;
+    let scale = scale(b);
+    let mut x = vec![T::zero(); n];
+    let mut r = b.to_vec();
+    let mut z = vec![T::zero(); n];
+    if norm(&r) <= options.tolerance * scale {
+        return Ok(Solution {
+            x,
+            residual: norm(&r) / scale,
+            iterations: 0,
+        });
+    }
+    precond.apply(&r, &mut z);
+    let mut p = z.clone();
+    let mut rz = dot(&r, &z);
+
+    for iteration in 1..=options.max_iterations {
+        let q = a.mul_vec(&p);
+        let pq = dot(&p, &q);
+        if pq <= T::zero() || pq.is_nan() {
+            return Err(LinalgError::NotPositiveDefinite);
+        }
+        let alpha = rz / pq;
+        axpy(alpha, &p, &mut x);
+        axpy(-alpha, &q, &mut r);
+        let residual = norm(&r) / scale;
+        if residual <= options.tolerance {
+            return Ok(Solution {
+                x,
+                residual,
+                iterations: iteration,
+            });
+        }
+
+        precond.apply(&r, &mut z);
+        let next = dot(&r, &z);
+        let beta = next / rz;
+        rz = next;
+        for (p, &z) in p.iter_mut().zip(&z) {
+            *p = z + beta * *p;
+        }
+    }
+    Err(LinalgError::NoConvergence {
+        iterations: options.max_iterations,
+    })
+}
+
+// Panics on the `DimensionMismatch` or `NoConvergence` from `try_gmres`.
+pub fn gmres<T: Float>(
+    a: &CscMatrix<T>,
+    b: &[T],
+    precond: &dyn Preconditioner<T>,
+    options: &Options<T>,
+) -> Solution<T> {
+    try_gmres(a, b, precond, options).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Right-preconditioned GMRES(m) from x = 0, restarting after `restart`
+// iterations. The Hessenberg least squares problem is kept triangular with
+// Givens rotations, so its residual is known at every step without forming
+// x.
+pub fn try_gmres<T: Float>(
+    a: &CscMatrix<T>,
+    b: &[T],
+    precond: &dyn Preconditioner<T>,
+    options: &Options<T>,
+) -> Result<Solution<T>, LinalgError> {
+    let n = check(a, b)?;
+    let m = options.restart;
+    let scale = scale(b);
+    let mut x = vec![T::zero(); n];
+    let mut z = vec![T::zero(); n];
+    let mut iterations = 0;
+
+    loop {
+        let mut r = a.mul_vec(&x);
+        for (r, &b) in r.iter_mut().zip(b) {
+            *r = b - *r;
+        }
+        let beta = norm(&r);
+        if beta <= options.tolerance * scale {
+            return Ok(Solution {
+                x,
+                residual: beta / scale,
+                iterations,
+            });
+        }
+        if iterations >= options.max_iterations {
+            return Err(LinalgError::NoConvergence { iterations });
+        }
+
+        // Arnoldi basis, the columns of the rotated Hessenberg matrix, the
+        // rotations (c, s) and the rotated right-hand side
+        let mut v: Vec<Vec<T>> = vec![r.iter().map(|&r| r / beta).collect()];
+        let mut h: Vec<Vec<T>> = Vec::with_capacity(m);
+        let mut rotations: Vec<(T, T)> = Vec::with_capacity(m);
+        let mut g = vec![beta];
+
+        while h.len() < m && iterations < options.max_iterations {
+            let j = h.len();
+            precond.apply(&v[j], &mut z);
+            let mut w = a.mul_vec(&z);
+            iterations += 1;
+
+            // modified Gram-Schmidt
+            let mut column = Vec::with_capacity(j + 2);
+            for vi in &v {
+                let hij = dot(&w, vi);
+                axpy(-hij, vi, &mut w);
+                column.push(hij);
+            }
+            let next = norm(&w);
+            column.push(next);
+
+            for (i, &(c, s)) in rotations.iter().enumerate() {
+                let (hi, hk) = (column[i], column[i + 1]);
+                column[i] = c * hi + s * hk;
+                column[i + 1] = c * hk - s * hi;
+            }
+            let (c, s) = givens(column[j], column[j + 1]);
+            column[j] = c * column[j] + s * column[j + 1];
+            column.pop();
+            rotations.push((c, s));
+            g.push(-s * g[j]);
+            g[j] = c * g[j];
+            h.push(column);
+
+            if g[j + 1].abs() <= options.tolerance * scale || next == T::zero() {
+                break;
+            }
+            v.push(w.iter().map(|&w| w / next).collect());
+        }
+
+        // back substitution for y in H y = g, then x += M^-1 V y
+        let k = h.len();
+        let mut y = g[..k].to_vec();
+        for i in (0..k).rev() {
+            for j in i + 1..k {
+                y[i] = y[i] - h[j][i] * y[j];
+            }
+            y[i] = y[i] / h[i][i];
+        }
+        let mut u = vec![T::zero(); n];
+        for (vi, &yi) in v.iter().zip(&y) {
+            axpy(yi, vi, &mut u);
+        }
+        precond.apply(&u, &mut z);
+        axpy(T::one(), &z, &mut x);
+    }
+}
+
+// The rotation (c, s) with c a + s b = r and c b - s a = 0.
+fn givens<T: Float>(a: T, b: T) -> (T, T) {
+    if b == T::zero() {
+        return (T::one(), T::zero());
+    }
+    let r = a.hypot(b);
+    (a / r, b / r)
+}
+
+fn check<T: Float>(a: &CscMatrix<T>, b: &[T]) -> Result<usize, LinalgError> {
+    assert_eq!(a.nrows(), a.ncols(), "matrix must be square");
+    if b.len() != a.nrows() {
+        return Err(LinalgError::DimensionMismatch {
+            expected: (a.nrows(), 1),
+            found: (b.len(), 1),
+        });
+    }
+    Ok(a.nrows())
+}
+
+// ||b||, or one for b = 0 so that the tolerance becomes absolute
+fn scale<T: Float>(b: &[T]) -> T {
+    let norm = norm(b);
+    if norm == T::zero() {
+        T::one()
+    } else {
+        norm
+    }
+}
+
+fn dot<T: Float>(x: &[T], y: &[T]) -> T {
+    x.iter().zip(y).fold(T::zero(), |sum, (&x, &y)| sum + x * y)
+}
+
+fn norm<T: Float>(x: &[T]) -> T {
+    dot(x, x).sqrt()
+}
+
+// y += alpha x
+fn axpy<T: Float>(alpha: T, x: &[T], y: &mut [T]) {
+    for (y, &x) in y.iter_mut().zip(x) {
+        *y = *y + alpha * x;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::precond::{BlockJacobi, Ic0, Identity, Ilu, Jacobi, Ssor};
+
+    // The 5-point Laplacian on an n x n grid, plus a first-order term of
+    // strength `wind` in one direction, which makes it unsymmetric.
+    fn convection_diffusion(n: usize, wind: f64) -> CscMatrix<f64> {
+        let mut triplets = Vec::new();
+        for x in 0..n {
+            for y in 0..n {
+                let k = x * n + y;
+                triplets.push((k, k, 4.0));
+                if x > 0 {
+                    triplets.push((k, k - n, -1.0 - wind));
+                    triplets.push((k - n, k, -1.0 + wind));
+                }
+                if y > 0 {
+                    triplets.push((k, k - 1, -1.0));
+                    triplets.push((k - 1, k, -1.0));
+                }
+            }
+        }
+        CscMatrix::from_triplets(n * n, n * n, &triplets)
+    }
+
+    // Symmetric, with diagonal entries spread over several orders of
+    // magnitude.
+    fn badly_scaled(n: usize) -> CscMatrix<f64> {
+        let a = convection_diffusion(n, 0.0);
+        let scale: Vec<f64> = (0..n * n).map(|i| 10f64.powi((i % 4) as i32)).collect();
+        let mut triplets = Vec::new();
+        for j in 0..a.ncols() {
+            let (rows, values) = a.col(j);
+            for (&i, &v) in rows.iter().zip(values) {
+                triplets.push((i, j, v * scale[i] * scale[j]));
+            }
+        }
+        CscMatrix::from_triplets(n * n, n * n, &triplets)
+    }
+
+    fn rhs(n: usize) -> Vec<f64> {
+        (0..n).map(|i| ((i * 7) % 11) as f64 - 5.0).collect()
+    }
+
+    fn residual(a: &CscMatrix<f64>, x: &[f64], b: &[f64]) -> f64 {
+        let ax = a.mul_vec(x);
+        let r: Vec<f64> = ax.iter().zip(b).map(|(ax, b)| b - ax).collect();
+        norm(&r) / norm(b)
+    }
+
+    #[test]
+    fn cg_converges() {
+        let a = convection_diffusion(10, 0.0);
+        let b = rhs(100);
+        let solution = cg(&a, &b, &Identity, &Options::default());
+        assert!(solution.residual <= 1e-10);
+        assert!(residual(&a, &solution.x, &b) <= 1e-9);
+    }
+
+    #[test]
+    fn ic0_reduces_cg_iterations() {
+        let a = convection_diffusion(20, 0.0);
+        let b = rhs(400);
+        let options = Options::default();
+        let plain = cg(&a, &b, &Identity, &options);
+        let ic0 = cg(&a, &b, &Ic0::new(&a), &options);
+        assert!(residual(&a, &ic0.x, &b) <= 1e-9);
+        assert!(ic0.iterations * 3 < plain.iterations * 2);
+    }
+
+    #[test]
+    fn jacobi_reduces_cg_iterations() {
+        let a = badly_scaled(10);
+        let b = rhs(100);
+        let options = Options::default().max_iterations(10_000);
+        let plain = cg(&a, &b, &Identity, &options);
+        let jacobi = cg(&a, &b, &Jacobi::new(&a), &options);
+        assert!(residual(&a, &jacobi.x, &b) <= 1e-9);
+        assert!(jacobi.iterations < plain.iterations);
+    }
+
+    #[test]
+    fn cg_with_every_symmetric_preconditioner() {
+        let a = convection_diffusion(8, 0.0);
+        let b = rhs(64);
+        let options = Options::default();
+        let preconditioners: [&dyn Preconditioner<f64>; 4] = [
+            &Jacobi::new(&a),
+            &BlockJacobi::new(&a, 8),
+            &Ssor::new(&a, 1.2),
+            &Ic0::new(&a),
+        ];
+        for p in preconditioners {
+            let solution = cg(&a, &b, p, &options);
+            assert!(residual(&a, &solution.x, &b) <= 1e-9);
+        }
+    }
+
+    #[test]
+    fn cg_not_positive_definite() {
+        let a = CscMatrix::from_triplets(2, 2, &[(0, 0, 1.0), (1, 1, -1.0)]);
+        assert_eq!(
+            try_cg(&a, &[0.0, 1.0], &Identity, &Options::default()).unwrap_err(),
+            LinalgError::NotPositiveDefinite
+        );
+    }
+
+    #[test]
+    fn ilu_reduces_gmres_iterations() {
+        let a = convection_diffusion(20, 0.5);
+        let b = rhs(400);
+        let options = Options::default();
+        let plain = gmres(&a, &b, &Identity, &options);
+        let ilu0 = gmres(&a, &b, &Ilu::zero_fill(&a), &options);
+        let ilut = gmres(&a, &b, &Ilu::threshold(&a, 1e-3), &options);
+        for solution in [&plain, &ilu0, &ilut] {
+            assert!(residual(&a, &solution.x, &b) <= 1e-9);
+        }
+        assert!(ilu0.iterations * 2 < plain.iterations);
+        assert!(ilut.iterations < ilu0.iterations);
+    }
+
+    #[test]
+    fn gmres_restarts() {
+        let a = convection_diffusion(10, 0.3);
+        let b = rhs(100);
+        let solution = gmres(&a, &b, &Jacobi::new(&a), &Options::default().restart(5));
+        assert!(solution.iterations > 5);
+        assert!(residual(&a, &solution.x, &b) <= 1e-9);
+    }
+
+    #[test]
+    fn zero_rhs() {
+        let a = convection_diffusion(3, 0.0);
+        let solution = gmres(&a, &[0.0; 9], &Identity, &Options::default());
+        assert_eq!(solution.iterations, 0);
+        assert_eq!(solution.x, [0.0; 9]);
+    }
+
+    #[test]
+    fn no_convergence() {
+        let a = convection_diffusion(10, 0.0);
+        let options = Options::default().max_iterations(3);
+        let err = LinalgError::NoConvergence { iterations: 3 };
+        assert_eq!(try_cg(&a, &rhs(100), &Identity, &options).unwrap_err(), err);
+        assert_eq!(
+            try_gmres(&a, &rhs(100), &Identity, &options).unwrap_err(),
+            err
+        );
+    }
+
+    #[test]
+    fn dimension_mismatch() {
+        let a = convection_diffusion(3, 0.0);
+        assert!(matches!(
+            try_gmres(&a, &[1.0; 4], &Identity, &Options::default()),
+            Err(LinalgError::DimensionMismatch { .. })
+        ));
+    }
+}
@@ -1,6 +1,49 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+// Without `std`, `num::Float` is only available through `libm`.
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("either the `std` or the `libm` feature must be enabled");
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod approx;
+pub mod backend;
+pub mod display;
+#[cfg(feature = "alloc")]
+pub mod dmatrix;
+pub mod dual;
+pub mod equations;
+pub mod error;
+#[cfg(feature = "alloc")]
+pub mod gemm;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "alloc")]
+pub mod iterative;
+pub mod lstsq;
+pub mod macros;
+pub mod mat3a;
 pub mod matrix;
+#[cfg(feature = "alloc")]
+pub mod nonlinear;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "alloc")]
+pub mod parse;
+#[cfg(feature = "bytemuck")]
+pub mod pod;
+#[cfg(feature = "alloc")]
+pub mod precond;
+pub mod products;
+pub mod schur;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod simd;
+#[cfg(feature = "alloc")]
+pub mod sparse;
 pub mod vector;
 
 pub mod prelude {
-    pub use crate::vector::{vec2::Vec2, vec3::Vec3, vec4::Vec4};
+    pub use crate::vector::{vec2::Vec2, vec3::Vec3, vec3a::Vec3A, vec4::Vec4};
 }
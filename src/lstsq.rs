@@ -0,0 +1,1948 @@
+use super::backend::{lu_solve_in_place, Backend, Diag, Native, Side, Transpose, Uplo};
+#[cfg(feature = "alloc")]
+use super::dmatrix::DMat;
+use super::error::LinalgError;
+use super::matrix::Mat;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use num::Float;
+
+// Linear least squares: minimizing ||A x - b|| for an R x C matrix A, plus
+// the weighted, regularized, constrained and errors-in-variables variants.
+// Nothing forms the normal equations A^T A; the solvers work on Householder
+// QR or the SVD of A, which keeps the accuracy of the fit tied to cond(A)
+// rather than cond(A)^2. The factorizations come from `Native`, or from the
+// backend passed to the `_with` variants.
+//
+// Every solver returns a `Fit` with the residuals, the rank that was used and
+// the covariance of the estimate, scaled by the residual variance as in most
+// fitting tools:
+//
+//   covariance = s^2 (A^T A)^-1,   s^2 = ||b - A x||^2 / (R - rank)
+//
+// Every solver also has a `_dynamic` form that takes `DMat`s, for fits whose
+// number of observations is only known at run time, and returns a `DFit`.
+// The shape requirements that the fixed-size solvers check at compile time
+// are `DimensionMismatch` errors there. Both forms run the same code on
+// slices; the fixed-size solvers keep their scratch space on the stack, so
+// they work without `alloc`, except for `ridge_sweep`, whose result holds the
+// λs it tried.
+
+#[derive(Debug, Clone, Copy)]
+pub struct Fit<T: Float, const R: usize, const C: usize> {
+    pub x: Mat<T, C, 1>,
+    // b - A x
+    pub residuals: Mat<T, R, 1>,
+    // The numerical rank of A, or the number of parameters that were free in
+    // the fit for the constrained solvers.
+    pub rank: usize,
+    // NaN when there are no degrees of freedom left to estimate the residual
+    // variance from.
+    pub covariance: Mat<T, C, C>,
+}
+
+impl<T: Float, const R: usize, const C: usize> Fit<T, R, C> {
+    pub fn residual_norm(&self) -> T {
+        norm(self.residuals.as_slice())
+    }
+
+    fn zero() -> Self {
+        Fit {
+            x: Mat::zero(),
+            residuals: Mat::zero(),
+            rank: 0,
+            covariance: Mat::zero(),
+        }
+    }
+
+    fn parts(&mut self) -> Parts<'_, T> {
+        Parts {
+            x: self.x.as_mut_slice(),
+            residuals: self.residuals.as_mut_slice(),
+            covariance: self.covariance.as_mut_slice(),
+        }
+    }
+}
+
+// `Fit` for the `_dynamic` solvers, with x and the residuals as columns.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct DFit<T: Float> {
+    pub x: DMat<T>,
+    pub residuals: DMat<T>,
+    pub rank: usize,
+    pub covariance: DMat<T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Float> DFit<T> {
+    pub fn residual_norm(&self) -> T {
+        norm(self.residuals.as_slice())
+    }
+
+    fn zero(m: usize, n: usize) -> Self {
+        DFit {
+            x: DMat::zero(n, 1),
+            residuals: DMat::zero(m, 1),
+            rank: 0,
+            covariance: DMat::zero(n, n),
+        }
+    }
+
+    fn parts(&mut self) -> Parts<'_, T> {
+        Parts {
+            x: self.x.as_mut_slice(),
+            residuals: self.residuals.as_mut_slice(),
+            covariance: self.covariance.as_mut_slice(),
+        }
+    }
+
+    fn to_fixed<const R: usize, const C: usize>(&self) -> Fit<T, R, C> {
+        Fit {
+            x: self.x.to_mat(),
+            residuals: self.residuals.to_mat(),
+            rank: self.rank,
+            covariance: self.covariance.to_mat(),
+        }
+    }
+}
+
+// The fit chosen by `ridge_sweep`, with the generalized cross-validation score
+// of every λ that was tried.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct RidgeSweep<T: Float, const R: usize, const C: usize> {
+    pub fit: Fit<T, R, C>,
+    pub lambda: T,
+    pub lambdas: Vec<T>,
+    pub gcv: Vec<T>,
+}
+
+// `RidgeSweep` for `ridge_sweep_dynamic`.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct DRidgeSweep<T: Float> {
+    pub fit: DFit<T>,
+    pub lambda: T,
+    pub lambdas: Vec<T>,
+    pub gcv: Vec<T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Float> DRidgeSweep<T> {
+    fn into_fixed<const R: usize, const C: usize>(self) -> RidgeSweep<T, R, C> {
+        RidgeSweep {
+            fit: self.fit.to_fixed(),
+            lambda: self.lambda,
+            lambdas: self.lambdas,
+            gcv: self.gcv,
+        }
+    }
+}
+
+// The parts of a `Fit` or `DFit` that the solvers fill in.
+struct Parts<'a, T> {
+    x: &'a mut [T],
+    residuals: &'a mut [T],
+    covariance: &'a mut [T],
+}
+
+// Scratch space for an m x n problem, which a solver takes pieces of as it
+// goes: m x n blocks from `rows`, n x n blocks from `squares` and vectors of
+// length n from `vectors`. Each solver says how many of each it takes.
+struct Work<'a, T> {
+    rows: Pool<'a, T>,
+    squares: Pool<'a, T>,
+    vectors: Pool<'a, T>,
+}
+
+struct Pool<'a, T>(&'a mut [T]);
+
+impl<'a, T> Pool<'a, T> {
+    fn take(&mut self, len: usize) -> &'a mut [T] {
+        let (head, tail) = core::mem::take(&mut self.0).split_at_mut(len);
+        self.0 = tail;
+        head
+    }
+}
+
+// `Work` for the fixed-size solvers: `A` R x C blocks, `B` C x C blocks and
+// `V` vectors of length C, on the stack.
+struct Stack<T, const R: usize, const C: usize, const A: usize, const B: usize, const V: usize> {
+    rows: [[[T; C]; R]; A],
+    squares: [[[T; C]; C]; B],
+    vectors: [[T; C]; V],
+}
+
+impl<T: Float, const R: usize, const C: usize, const A: usize, const B: usize, const V: usize>
+    Stack<T, R, C, A, B, V>
+{
+    fn new() -> Self {
+        Stack {
+            rows: [[[T::zero(); C]; R]; A],
+            squares: [[[T::zero(); C]; C]; B],
+            vectors: [[T::zero(); C]; V],
+        }
+    }
+
+    fn work(&mut self) -> Work<'_, T> {
+        Work {
+            rows: Pool(self.rows.as_flattened_mut().as_flattened_mut()),
+            squares: Pool(self.squares.as_flattened_mut().as_flattened_mut()),
+            vectors: Pool(self.vectors.as_flattened_mut()),
+        }
+    }
+}
+
+// `Work` for the dynamic solvers, with as many blocks and vectors of each
+// kind as `counts` says.
+#[cfg(feature = "alloc")]
+struct Heap<T> {
+    rows: Vec<T>,
+    squares: Vec<T>,
+    vectors: Vec<T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Float> Heap<T> {
+    fn new(m: usize, n: usize, [rows, squares, vectors]: [usize; 3]) -> Self {
+        Heap {
+            rows: vec![T::zero(); rows * m * n],
+            squares: vec![T::zero(); squares * n * n],
+            vectors: vec![T::zero(); vectors * n],
+        }
+    }
+
+    fn work(&mut self) -> Work<'_, T> {
+        Work {
+            rows: Pool(&mut self.rows),
+            squares: Pool(&mut self.squares),
+            vectors: Pool(&mut self.vectors),
+        }
+    }
+}
+
+// Lawson-Hanson adds one variable to the passive set per outer iteration, and
+// rarely needs more than a few passes over all of them.
+const NNLS_SWEEPS: usize = 3;
+
+// Panics on the `Singular` from `try_qr`.
+pub fn qr<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+) -> Fit<T, R, C> {
+    try_qr(a, b).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Ordinary least squares through the QR factorization of A. Fails with
+// `Singular` when A does not have full column rank; use `try_svd` for
+// rank-deficient problems.
+pub fn try_qr<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    try_qr_with(&Native, a, b)
+}
+
+// `try_qr` with the QR factorization from `backend`.
+pub fn try_qr_with<T: Float, const R: usize, const C: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    const {
+        assert!(
+            R >= C,
+            "QR least squares needs at least as many rows as columns"
+        )
+    };
+    let mut fit = Fit::zero();
+    let mut stack = Stack::<T, R, C, 1, 1, 1>::new();
+    let (a, b) = (a.as_slice(), b.as_slice());
+    fit.rank = qr_into(backend, (R, C), a, b, &mut stack.work(), fit.parts())?;
+    Ok(fit)
+}
+
+// Panics on any error from `try_qr_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn qr_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>) -> DFit<T> {
+    try_qr_dynamic(a, b).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_qr` for an m x n A with m >= n and an m x 1 b.
+#[cfg(feature = "alloc")]
+pub fn try_qr_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>) -> Result<DFit<T>, LinalgError> {
+    try_qr_dynamic_with(&Native, a, b)
+}
+
+// `try_qr_dynamic` with the QR factorization from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_qr_dynamic_with<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+) -> Result<DFit<T>, LinalgError> {
+    let (m, n) = a.shape();
+    b.check_shape((m, 1))?;
+    check_rows(a, n)?;
+    let mut fit = DFit::zero(m, n);
+    let mut heap = Heap::new(m, n, [1, 1, 1]);
+    let (a, b) = (a.as_slice(), b.as_slice());
+    fit.rank = qr_into(backend, (m, n), a, b, &mut heap.work(), fit.parts())?;
+    Ok(fit)
+}
+
+// Takes an m x n block, an n x n block and a vector from `work`.
+fn qr_into<T: Float>(
+    backend: &impl Backend<T>,
+    (m, n): (usize, usize),
+    a: &[T],
+    b: &[T],
+    work: &mut Work<T>,
+    out: Parts<T>,
+) -> Result<usize, LinalgError> {
+    let (qr, rinv, tau) = (
+        work.rows.take(m * n),
+        work.squares.take(n * n),
+        work.vectors.take(n),
+    );
+    qr.copy_from_slice(a);
+    out.residuals.copy_from_slice(b);
+    qr_solve(backend, m, n, qr, out.residuals, tau)?;
+    out.x.copy_from_slice(&out.residuals[..n]);
+    unscaled_covariance(backend, n, qr, rinv, out.covariance);
+    finish((m, n), a, b, T::from(m - n).unwrap(), out);
+    Ok(n)
+}
+
+// Panics on the `NoConvergence` from `try_svd`.
+pub fn svd<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    rcond: Option<T>,
+) -> Fit<T, R, C> {
+    try_svd(a, b, rcond).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// The minimum-norm least squares solution through the SVD of A. Singular
+// values below `rcond` times the largest one are treated as zero; the default
+// is machine epsilon times max(R, C). Works for any shape and rank.
+pub fn try_svd<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    rcond: Option<T>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    try_svd_with(&Native, a, b, rcond)
+}
+
+// `try_svd` with the SVD from `backend`.
+pub fn try_svd_with<T: Float, const R: usize, const C: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    rcond: Option<T>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    let mut fit = Fit::zero();
+    let mut stack = Stack::<T, R, C, 2, 1, 2>::new();
+    let (a, b) = (a.as_slice(), b.as_slice());
+    fit.rank = svd_into(backend, (R, C), a, b, rcond, &mut stack.work(), fit.parts())?;
+    Ok(fit)
+}
+
+// Panics on any error from `try_svd_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn svd_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>, rcond: Option<T>) -> DFit<T> {
+    try_svd_dynamic(a, b, rcond).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_svd` for an m x n A and an m x 1 b.
+#[cfg(feature = "alloc")]
+pub fn try_svd_dynamic<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    rcond: Option<T>,
+) -> Result<DFit<T>, LinalgError> {
+    try_svd_dynamic_with(&Native, a, b, rcond)
+}
+
+// `try_svd_dynamic` with the SVD from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_svd_dynamic_with<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+    rcond: Option<T>,
+) -> Result<DFit<T>, LinalgError> {
+    let (m, n) = a.shape();
+    b.check_shape((m, 1))?;
+    let mut fit = DFit::zero(m, n);
+    let mut heap = Heap::new(m, n, [2, 1, 2]);
+    let (a, b) = (a.as_slice(), b.as_slice());
+    fit.rank = svd_into(backend, (m, n), a, b, rcond, &mut heap.work(), fit.parts())?;
+    Ok(fit)
+}
+
+// Takes two m x n blocks, an n x n block and two vectors from `work`.
+fn svd_into<T: Float>(
+    backend: &impl Backend<T>,
+    (m, n): (usize, usize),
+    a: &[T],
+    b: &[T],
+    rcond: Option<T>,
+    work: &mut Work<T>,
+    out: Parts<T>,
+) -> Result<usize, LinalgError> {
+    let copy = work.rows.take(m * n);
+    copy.copy_from_slice(a);
+    let svd = Svd::new(backend, m, n, copy, work)?;
+    let rcond = rcond.unwrap_or_else(|| T::epsilon() * T::from(m.max(n)).unwrap());
+    let cutoff = rcond * svd.s.first().copied().unwrap_or_else(T::zero);
+    let rank = svd.s.iter().filter(|&&s| s > cutoff).count();
+    let beta = work.vectors.take(svd.k);
+    svd.filtered(backend, b, beta, out.x, out.covariance, |s| {
+        if s > cutoff {
+            (T::one() / s, T::one() / (s * s))
+        } else {
+            (T::zero(), T::zero())
+        }
+    });
+    finish((m, n), a, b, T::from(m - rank).unwrap(), out);
+    Ok(rank)
+}
+
+// Panics on the `InvalidArgument` or `Singular` from `try_weighted`.
+pub fn weighted<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    w: &Mat<T, R, 1>,
+) -> Fit<T, R, C> {
+    try_weighted(a, b, w).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Minimizes sum_i w_i (A x - b)_i^2, e.g. with w_i the inverse variance of
+// b_i. Fails with `InvalidArgument` when a weight is negative or NaN. The
+// residuals are unweighted, but the residual variance behind the covariance
+// is the weighted one.
+pub fn try_weighted<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    w: &Mat<T, R, 1>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    try_weighted_with(&Native, a, b, w)
+}
+
+// `try_weighted` with the QR factorization from `backend`.
+pub fn try_weighted_with<T: Float, const R: usize, const C: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    w: &Mat<T, R, 1>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    const {
+        assert!(
+            R >= C,
+            "QR least squares needs at least as many rows as columns"
+        )
+    };
+    let mut fit = Fit::zero();
+    let mut stack = Stack::<T, R, C, 1, 1, 1>::new();
+    let (a, b, w) = (a.as_slice(), b.as_slice(), w.as_slice());
+    fit.rank = weighted_into(backend, (R, C), a, b, w, &mut stack.work(), fit.parts())?;
+    Ok(fit)
+}
+
+// Panics on any error from `try_weighted_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn weighted_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>, w: &DMat<T>) -> DFit<T> {
+    try_weighted_dynamic(a, b, w).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_weighted` for an m x n A with m >= n and m x 1 b and w.
+#[cfg(feature = "alloc")]
+pub fn try_weighted_dynamic<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    w: &DMat<T>,
+) -> Result<DFit<T>, LinalgError> {
+    try_weighted_dynamic_with(&Native, a, b, w)
+}
+
+// `try_weighted_dynamic` with the QR factorization from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_weighted_dynamic_with<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+    w: &DMat<T>,
+) -> Result<DFit<T>, LinalgError> {
+    let (m, n) = a.shape();
+    b.check_shape((m, 1))?;
+    w.check_shape((m, 1))?;
+    check_rows(a, n)?;
+    let mut fit = DFit::zero(m, n);
+    let mut heap = Heap::new(m, n, [1, 1, 1]);
+    let (a, b, w) = (a.as_slice(), b.as_slice(), w.as_slice());
+    fit.rank = weighted_into(backend, (m, n), a, b, w, &mut heap.work(), fit.parts())?;
+    Ok(fit)
+}
+
+// QR least squares on the rows of A and b scaled by sqrt(w). Takes an m x n
+// block, an n x n block and a vector from `work`.
+fn weighted_into<T: Float>(
+    backend: &impl Backend<T>,
+    (m, n): (usize, usize),
+    a: &[T],
+    b: &[T],
+    w: &[T],
+    work: &mut Work<T>,
+    out: Parts<T>,
+) -> Result<usize, LinalgError> {
+    if !w.iter().all(|&w| w >= T::zero()) {
+        return Err(LinalgError::InvalidArgument(
+            "least squares weights must be non-negative",
+        ));
+    }
+    let (qr, rinv, tau) = (
+        work.rows.take(m * n),
+        work.squares.take(n * n),
+        work.vectors.take(n),
+    );
+    for i in 0..m {
+        let root = w[i].sqrt();
+        out.residuals[i] = root * b[i];
+        for j in 0..n {
+            qr[i * n + j] = root * a[i * n + j];
+        }
+    }
+    qr_solve(backend, m, n, qr, out.residuals, tau)?;
+    out.x.copy_from_slice(&out.residuals[..n]);
+    unscaled_covariance(backend, n, qr, rinv, out.covariance);
+
+    residuals((m, n), a, b, out.x, out.residuals);
+    let rss = out
+        .residuals
+        .iter()
+        .zip(w)
+        .fold(T::zero(), |acc, (&r, &w)| acc.hypot(w.sqrt() * r));
+    scale_covariance(out.covariance, rss, T::from(m - n).unwrap());
+    Ok(n)
+}
+
+// Panics on the `InvalidArgument` or `NoConvergence` from `try_ridge`.
+pub fn ridge<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    lambda: T,
+) -> Fit<T, R, C> {
+    try_ridge(a, b, lambda).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Tikhonov regularization: minimizes ||A x - b||^2 + λ ||x||^2 for λ >= 0,
+// failing with `InvalidArgument` for a negative or NaN λ.
+// `rank` is the numerical rank of A, while the residual variance uses the
+// effective number of parameters sum_i s_i^2 / (s_i^2 + λ).
+pub fn try_ridge<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    lambda: T,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    try_ridge_with(&Native, a, b, lambda)
+}
+
+// `try_ridge` with the SVD from `backend`.
+pub fn try_ridge_with<T: Float, const R: usize, const C: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    lambda: T,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    let mut fit = Fit::zero();
+    let mut stack = Stack::<T, R, C, 2, 1, 2>::new();
+    let (a, b) = (a.as_slice(), b.as_slice());
+    fit.rank = ridge_into(
+        backend,
+        (R, C),
+        a,
+        b,
+        lambda,
+        &mut stack.work(),
+        fit.parts(),
+    )?;
+    Ok(fit)
+}
+
+// Panics on any error from `try_ridge_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn ridge_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>, lambda: T) -> DFit<T> {
+    try_ridge_dynamic(a, b, lambda).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_ridge` for an m x n A and an m x 1 b.
+#[cfg(feature = "alloc")]
+pub fn try_ridge_dynamic<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    lambda: T,
+) -> Result<DFit<T>, LinalgError> {
+    try_ridge_dynamic_with(&Native, a, b, lambda)
+}
+
+// `try_ridge_dynamic` with the SVD from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_ridge_dynamic_with<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+    lambda: T,
+) -> Result<DFit<T>, LinalgError> {
+    let (m, n) = a.shape();
+    b.check_shape((m, 1))?;
+    let mut fit = DFit::zero(m, n);
+    let mut heap = Heap::new(m, n, [2, 1, 2]);
+    let (a, b) = (a.as_slice(), b.as_slice());
+    fit.rank = ridge_into(backend, (m, n), a, b, lambda, &mut heap.work(), fit.parts())?;
+    Ok(fit)
+}
+
+// Takes two m x n blocks, an n x n block and two vectors from `work`.
+fn ridge_into<T: Float>(
+    backend: &impl Backend<T>,
+    (m, n): (usize, usize),
+    a: &[T],
+    b: &[T],
+    lambda: T,
+    work: &mut Work<T>,
+    out: Parts<T>,
+) -> Result<usize, LinalgError> {
+    check_lambda(lambda)?;
+    let copy = work.rows.take(m * n);
+    copy.copy_from_slice(a);
+    let svd = Svd::new(backend, m, n, copy, work)?;
+    let beta = work.vectors.take(svd.k);
+    Ok(svd.ridge(backend, a, b, lambda, beta, out))
+}
+
+// Panics on the `InvalidArgument` or `NoConvergence` from
+// `try_ridge_sweep`.
+#[cfg(feature = "alloc")]
+pub fn ridge_sweep<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    lambdas: Option<&[T]>,
+) -> RidgeSweep<T, R, C> {
+    try_ridge_sweep(a, b, lambdas).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Ridge regression with λ chosen by generalized cross-validation,
+//
+//   GCV(λ) = R ||A x_λ - b||^2 / (R - sum_i s_i^2 / (s_i^2 + λ))^2,
+//
+// which needs a single SVD for the whole sweep. Without `lambdas`, 50 values
+// are spaced logarithmically between eps s_max^2 and s_max^2. Fails with
+// `InvalidArgument` when `lambdas` is empty or has a negative or NaN entry.
+#[cfg(feature = "alloc")]
+pub fn try_ridge_sweep<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    lambdas: Option<&[T]>,
+) -> Result<RidgeSweep<T, R, C>, LinalgError> {
+    try_ridge_sweep_with(&Native, a, b, lambdas)
+}
+
+// `try_ridge_sweep` with the SVD from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_ridge_sweep_with<T: Float, const R: usize, const C: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    lambdas: Option<&[T]>,
+) -> Result<RidgeSweep<T, R, C>, LinalgError> {
+    let (a, b) = (DMat::from_mat(a), DMat::from_mat(b));
+    Ok(try_ridge_sweep_dynamic_with(backend, &a, &b, lambdas)?.into_fixed())
+}
+
+// Panics on any error from `try_ridge_sweep_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn ridge_sweep_dynamic<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    lambdas: Option<&[T]>,
+) -> DRidgeSweep<T> {
+    try_ridge_sweep_dynamic(a, b, lambdas).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_ridge_sweep` for an m x n A and an m x 1 b.
+#[cfg(feature = "alloc")]
+pub fn try_ridge_sweep_dynamic<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    lambdas: Option<&[T]>,
+) -> Result<DRidgeSweep<T>, LinalgError> {
+    try_ridge_sweep_dynamic_with(&Native, a, b, lambdas)
+}
+
+// `try_ridge_sweep_dynamic` with the SVD from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_ridge_sweep_dynamic_with<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+    lambdas: Option<&[T]>,
+) -> Result<DRidgeSweep<T>, LinalgError> {
+    let (m, n) = a.shape();
+    b.check_shape((m, 1))?;
+    let mut heap = Heap::new(m, n, [2, 1, 2]);
+    let mut work = heap.work();
+    let copy = work.rows.take(m * n);
+    copy.copy_from_slice(a.as_slice());
+    let svd = Svd::new(backend, m, n, copy, &mut work)?;
+    let lambdas = match lambdas {
+        Some(lambdas) => lambdas.to_vec(),
+        None => {
+            let top = svd
+                .s
+                .first()
+                .map_or_else(T::one, |&s| s * s)
+                .max(T::min_positive_value());
+            let (lo, hi) = ((T::epsilon() * top).ln(), top.ln());
+            let steps = T::from(49).unwrap();
+            (0..50)
+                .map(|i| (lo + (hi - lo) * T::from(i).unwrap() / steps).exp())
+                .collect()
+        }
+    };
+    if lambdas.is_empty() {
+        return Err(LinalgError::InvalidArgument(
+            "ridge sweep needs at least one λ",
+        ));
+    }
+    for &lambda in &lambdas {
+        check_lambda(lambda)?;
+    }
+
+    // with beta = U^T b, the residual is the part of b outside the range of U
+    // plus the shrunk components λ / (s^2 + λ) beta_i
+    let beta = work.vectors.take(svd.k);
+    svd.ut_b(backend, b.as_slice(), beta);
+    let outside = (norm(b.as_slice()).powi(2) - beta.iter().fold(T::zero(), |acc, &x| acc + x * x))
+        .max(T::zero());
+    let rows = T::from(m).unwrap();
+    let gcv: Vec<T> = lambdas
+        .iter()
+        .map(|&lambda| {
+            let (mut rss, mut dof) = (outside, T::zero());
+            for (&s, &beta) in svd.s.iter().zip(beta.iter()) {
+                let s2 = s * s;
+                let shrink = if s2 + lambda == T::zero() {
+                    T::zero()
+                } else {
+                    lambda / (s2 + lambda)
+                };
+                rss = rss + (shrink * beta).powi(2);
+                dof = dof + T::one() - shrink;
+            }
+            if dof >= rows {
+                T::infinity()
+            } else {
+                rows * rss / (rows - dof).powi(2)
+            }
+        })
+        .collect();
+
+    let best = (0..lambdas.len())
+        .min_by(|&i, &j| {
+            gcv[i]
+                .partial_cmp(&gcv[j])
+                .unwrap_or(core::cmp::Ordering::Equal)
+        })
+        .unwrap();
+    let lambda = lambdas[best];
+    let mut fit = DFit::zero(m, n);
+    let (a, b) = (a.as_slice(), b.as_slice());
+    fit.rank = svd.ridge(backend, a, b, lambda, beta, fit.parts());
+    Ok(DRidgeSweep {
+        fit,
+        lambda,
+        lambdas,
+        gcv,
+    })
+}
+
+// Panics on the `Singular` from `try_constrained`.
+pub fn constrained<T: Float, const R: usize, const C: usize, const P: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    c: &Mat<T, P, C>,
+    d: &Mat<T, P, 1>,
+) -> Fit<T, R, C> {
+    try_constrained(a, b, c, d).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Minimizes ||A x - b|| subject to the P equality constraints C x = d, by
+// the null-space method: with C^T = Q [L^T; 0], x = Q1 y1 + Q2 y2 where y1 is
+// fixed by the constraints and y2 solves an unconstrained problem in the
+// C - P remaining directions. Fails with `Singular` when the constraints are
+// linearly dependent or A restricted to the null space of C is rank
+// deficient. `rank` is the number of free directions, C - P.
+pub fn try_constrained<T: Float, const R: usize, const C: usize, const P: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    c: &Mat<T, P, C>,
+    d: &Mat<T, P, 1>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    try_constrained_with(&Native, a, b, c, d)
+}
+
+// `try_constrained` with the factorizations from `backend`.
+pub fn try_constrained_with<T: Float, const R: usize, const C: usize, const P: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+    c: &Mat<T, P, C>,
+    d: &Mat<T, P, 1>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    const {
+        assert!(
+            P <= C && R + P >= C,
+            "constrained least squares needs P <= C <= R + P"
+        )
+    };
+    let mut fit = Fit::zero();
+    let mut stack = Stack::<T, R, C, 1, 3, 2>::new();
+    let (a, b) = (a.as_slice(), b.as_slice());
+    let constraints = (c.as_slice(), d.as_slice());
+    fit.rank = constrained_into(
+        backend,
+        (R, C, P),
+        a,
+        b,
+        constraints,
+        &mut stack.work(),
+        fit.parts(),
+    )?;
+    Ok(fit)
+}
+
+// Panics on any error from `try_constrained_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn constrained_dynamic<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    c: &DMat<T>,
+    d: &DMat<T>,
+) -> DFit<T> {
+    try_constrained_dynamic(a, b, c, d).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_constrained` for an m x n A, an m x 1 b, a p x n C and a p x 1 d,
+// with p <= n <= m + p.
+#[cfg(feature = "alloc")]
+pub fn try_constrained_dynamic<T: Float>(
+    a: &DMat<T>,
+    b: &DMat<T>,
+    c: &DMat<T>,
+    d: &DMat<T>,
+) -> Result<DFit<T>, LinalgError> {
+    try_constrained_dynamic_with(&Native, a, b, c, d)
+}
+
+// `try_constrained_dynamic` with the factorizations from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_constrained_dynamic_with<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+    c: &DMat<T>,
+    d: &DMat<T>,
+) -> Result<DFit<T>, LinalgError> {
+    let (m, n) = a.shape();
+    let p = c.nrows();
+    b.check_shape((m, 1))?;
+    c.check_shape((p, n))?;
+    d.check_shape((p, 1))?;
+    if p > n {
+        return Err(LinalgError::DimensionMismatch {
+            expected: (n, n),
+            found: c.shape(),
+        });
+    }
+    check_rows(a, n - p)?;
+    let mut fit = DFit::zero(m, n);
+    let mut heap = Heap::new(m, n, [1, 3, 2]);
+    let (a, b) = (a.as_slice(), b.as_slice());
+    let constraints = (c.as_slice(), d.as_slice());
+    fit.rank = constrained_into(
+        backend,
+        (m, n, p),
+        a,
+        b,
+        constraints,
+        &mut heap.work(),
+        fit.parts(),
+    )?;
+    Ok(fit)
+}
+
+// Takes an m x n block, three n x n blocks and two vectors from `work`.
+fn constrained_into<T: Float>(
+    backend: &impl Backend<T>,
+    (m, n, p): (usize, usize, usize),
+    a: &[T],
+    b: &[T],
+    (c, d): (&[T], &[T]),
+    work: &mut Work<T>,
+    out: Parts<T>,
+) -> Result<usize, LinalgError> {
+    let (zero, one) = (T::zero(), T::one());
+    let free = n - p;
+
+    // C^T, n x p
+    let ct = work.squares.take(n * p);
+    for i in 0..p {
+        for j in 0..n {
+            ct[j * p + i] = c[i * n + j];
+        }
+    }
+    let tau = work.vectors.take(n);
+    backend.geqrf(n, p, ct, p, &mut tau[..p]);
+    check_triangle(n, p, ct)?;
+
+    // L y1 = d, where L = R1^T
+    let y = work.vectors.take(n);
+    y[..p].copy_from_slice(d);
+    backend.trsm(
+        Side::Left,
+        Uplo::Upper,
+        Transpose::Yes,
+        Diag::NonUnit,
+        p,
+        1,
+        one,
+        ct,
+        p,
+        &mut y[..p],
+        1,
+    );
+
+    // Q = H(0) ... H(p - 1), formed explicitly
+    let q = work.squares.take(n * n);
+    for i in 0..n {
+        q[i * n + i] = one;
+    }
+    for i in (0..p).rev() {
+        apply_reflector(n, p, ct, tau, i, q, n);
+    }
+
+    // A Q, whose last `free` columns are then packed in place into A Q2,
+    // with b - A Q1 y1 on the right-hand side
+    let aq = work.rows.take(m * n);
+    for r in 0..m {
+        for j in 0..n {
+            let mut sum = zero;
+            for k in 0..n {
+                sum = sum + a[r * n + k] * q[k * n + j];
+            }
+            aq[r * n + j] = sum;
+        }
+        out.residuals[r] = b[r];
+        for j in 0..p {
+            out.residuals[r] = out.residuals[r] - aq[r * n + j] * y[j];
+        }
+        aq.copy_within(r * n + p..(r + 1) * n, r * free);
+    }
+    let aq2 = &mut aq[..m * free];
+    qr_solve(backend, m, free, aq2, out.residuals, &mut tau[..free])?;
+    y[p..].copy_from_slice(&out.residuals[..free]);
+    let cov2 = &mut out.covariance[..free * free];
+    unscaled_covariance(backend, free, aq2, work.squares.take(n * n), cov2);
+
+    backend.gemv(Transpose::No, n, n, one, q, n, y, zero, out.x);
+    // the covariance lives in the null space: Q2 cov2 Q2^T, with Q2 read in
+    // place as the last columns of Q and A Q2 no longer needed
+    let q2 = &q[p..];
+    let tmp = &mut aq[..n * free];
+    backend.gemm(
+        Transpose::No,
+        Transpose::No,
+        n,
+        free,
+        free,
+        one,
+        q2,
+        n,
+        &out.covariance[..free * free],
+        free,
+        zero,
+        tmp,
+        free,
+    );
+    backend.gemm(
+        Transpose::No,
+        Transpose::Yes,
+        n,
+        n,
+        free,
+        one,
+        tmp,
+        free,
+        q2,
+        n,
+        zero,
+        out.covariance,
+        n,
+    );
+    finish((m, n), a, b, T::from(m - free).unwrap(), out);
+    Ok(free)
+}
+
+// Panics on the `Singular` or `NoConvergence` from `try_nnls`.
+pub fn nnls<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+) -> Fit<T, R, C> {
+    try_nnls(a, b).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Minimizes ||A x - b|| subject to x >= 0 with the Lawson-Hanson active set
+// method. `rank` is the number of strictly positive entries of x, and the
+// covariance is that of the fit restricted to them, with zero rows and
+// columns for the variables held at the bound.
+pub fn try_nnls<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    try_nnls_with(&Native, a, b)
+}
+
+// `try_nnls` with the QR factorizations from `backend`.
+pub fn try_nnls_with<T: Float, const R: usize, const C: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    const { assert!(R >= C, "NNLS needs at least as many rows as columns") };
+    let mut fit = Fit::zero();
+    let mut stack = Stack::<T, R, C, 1, 2, 3>::new();
+    let (a, b) = (a.as_slice(), b.as_slice());
+    let passive = &mut [false; C];
+    fit.rank = nnls_into(
+        backend,
+        (R, C),
+        a,
+        b,
+        passive,
+        &mut stack.work(),
+        fit.parts(),
+    )?;
+    Ok(fit)
+}
+
+// Panics on any error from `try_nnls_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn nnls_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>) -> DFit<T> {
+    try_nnls_dynamic(a, b).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_nnls` for an m x n A with m >= n and an m x 1 b.
+#[cfg(feature = "alloc")]
+pub fn try_nnls_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>) -> Result<DFit<T>, LinalgError> {
+    try_nnls_dynamic_with(&Native, a, b)
+}
+
+// `try_nnls_dynamic` with the QR factorizations from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_nnls_dynamic_with<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+) -> Result<DFit<T>, LinalgError> {
+    let (m, n) = a.shape();
+    b.check_shape((m, 1))?;
+    check_rows(a, n)?;
+    let mut fit = DFit::zero(m, n);
+    let mut heap = Heap::new(m, n, [1, 2, 3]);
+    let (a, b) = (a.as_slice(), b.as_slice());
+    let passive = &mut vec![false; n];
+    fit.rank = nnls_into(
+        backend,
+        (m, n),
+        a,
+        b,
+        passive,
+        &mut heap.work(),
+        fit.parts(),
+    )?;
+    Ok(fit)
+}
+
+// Takes an m x n block, two n x n blocks and three vectors from `work`.
+#[allow(clippy::needless_range_loop)]
+fn nnls_into<T: Float>(
+    backend: &impl Backend<T>,
+    (m, n): (usize, usize),
+    a: &[T],
+    b: &[T],
+    passive: &mut [bool],
+    work: &mut Work<T>,
+    out: Parts<T>,
+) -> Result<usize, LinalgError> {
+    let zero = T::zero();
+    let norm1 = (0..n)
+        .map(|j| (0..m).fold(zero, |acc, i| acc + a[i * n + j].abs()))
+        .fold(zero, T::max);
+    let largest = b.iter().fold(zero, |acc, x| acc.max(x.abs()));
+    let tol = T::from(10 * m).unwrap() * T::epsilon() * norm1 * largest.max(T::one());
+
+    let sub = work.rows.take(m * n);
+    let (tau, s, w) = (
+        work.vectors.take(n),
+        work.vectors.take(n),
+        work.vectors.take(n),
+    );
+    let x = out.x;
+    let max_iterations = NNLS_SWEEPS * n.max(1);
+    let mut iterations = 0;
+    loop {
+        // the gradient of -||A x - b||^2 / 2
+        residuals((m, n), a, b, x, out.residuals);
+        for j in 0..n {
+            w[j] = (0..m).fold(zero, |acc, i| acc + a[i * n + j] * out.residuals[i]);
+        }
+        let candidate = (0..n)
+            .filter(|&j| !passive[j] && w[j] > tol)
+            .max_by(|&i, &j| w[i].partial_cmp(&w[j]).unwrap());
+        let Some(j) = candidate else {
+            break;
+        };
+        passive[j] = true;
+
+        loop {
+            iterations += 1;
+            if iterations > max_iterations {
+                return Err(LinalgError::NoConvergence {
+                    iterations: max_iterations,
+                });
+            }
+            passive_solve(backend, (m, n), a, b, passive, (sub, tau), out.residuals, s)?;
+            if (0..n).all(|j| !passive[j] || s[j] > zero) {
+                x.copy_from_slice(s);
+                break;
+            }
+            // step from x towards s until the first passive variable hits zero
+            let alpha = (0..n)
+                .filter(|&j| passive[j] && s[j] <= zero)
+                .map(|j| x[j] / (x[j] - s[j]))
+                .fold(T::infinity(), T::min);
+            for j in 0..n {
+                x[j] = x[j] + alpha * (s[j] - x[j]);
+                if passive[j] && x[j] <= tol {
+                    passive[j] = false;
+                    x[j] = zero;
+                }
+            }
+        }
+    }
+
+    // the covariance of the fit in the passive columns, embedded in n x n
+    let rank = passive.iter().filter(|&&p| p).count();
+    passive_solve(backend, (m, n), a, b, passive, (sub, tau), out.residuals, s)?;
+    let (rinv, cov) = (
+        work.squares.take(rank * rank),
+        work.squares.take(rank * rank),
+    );
+    unscaled_covariance(backend, rank, sub, rinv, cov);
+    let columns = || (0..n).filter(|&j| passive[j]).enumerate();
+    for (c, j) in columns() {
+        for (d, l) in columns() {
+            out.covariance[j * n + l] = cov[c * rank + d];
+        }
+    }
+    let out = Parts { x, ..out };
+    finish((m, n), a, b, T::from(m - rank).unwrap(), out);
+    Ok(rank)
+}
+
+// Panics on the `Singular` or `NoConvergence` from `try_total`.
+pub fn total<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+) -> Fit<T, R, C> {
+    try_total(a, b).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Total least squares, for errors in A as well as b: the smallest Frobenius
+// norm correction [dA db] with (A + dA) x = b + db, from the right singular
+// vector v of [A b] for its smallest singular value s, x = -v[..C] / v[C].
+// Fails with `Singular` when v[C] is zero and there is no such x. The
+// covariance is the first-order approximation
+//
+//   s^2 / (R - C) (1 + ||x||^2) (A^T A - s^2 I)^-1.
+pub fn try_total<T: Float, const R: usize, const C: usize>(
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    try_total_with(&Native, a, b)
+}
+
+// `try_total` with the SVD and LU factorization from `backend`.
+pub fn try_total_with<T: Float, const R: usize, const C: usize>(
+    backend: &impl Backend<T>,
+    a: &Mat<T, R, C>,
+    b: &Mat<T, R, 1>,
+) -> Result<Fit<T, R, C>, LinalgError> {
+    const { assert!(R > C, "total least squares needs more rows than columns") };
+    // the scratch space for [A b] is counted in R x C blocks
+    const { assert!(C > 0, "total least squares needs at least one column") };
+    let mut fit = Fit::zero();
+    let mut stack = Stack::<T, R, C, 4, 5, 2>::new();
+    let (a, b) = (a.as_slice(), b.as_slice());
+    let ipiv = &mut [0; C];
+    fit.rank = total_into(backend, (R, C), a, b, ipiv, &mut stack.work(), fit.parts())?;
+    Ok(fit)
+}
+
+// Panics on any error from `try_total_dynamic`.
+#[cfg(feature = "alloc")]
+pub fn total_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>) -> DFit<T> {
+    try_total_dynamic(a, b).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `try_total` for an m x n A with m > n and an m x 1 b.
+#[cfg(feature = "alloc")]
+pub fn try_total_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>) -> Result<DFit<T>, LinalgError> {
+    try_total_dynamic_with(&Native, a, b)
+}
+
+// `try_total_dynamic` with the SVD and LU factorization from `backend`.
+#[cfg(feature = "alloc")]
+pub fn try_total_dynamic_with<T: Float>(
+    backend: &impl Backend<T>,
+    a: &DMat<T>,
+    b: &DMat<T>,
+) -> Result<DFit<T>, LinalgError> {
+    let (m, n) = a.shape();
+    b.check_shape((m, 1))?;
+    check_rows(a, n + 1)?;
+    let mut fit = DFit::zero(m, n);
+    // [A b] is m x (n + 1), so its blocks are counted in m x (n + 1) too
+    let mut heap = Heap::new(m, n + 1, [2, 2, 1]);
+    let (a, b) = (a.as_slice(), b.as_slice());
+    let ipiv = &mut vec![0; n];
+    fit.rank = total_into(backend, (m, n), a, b, ipiv, &mut heap.work(), fit.parts())?;
+    Ok(fit)
+}
+
+// Takes two m x (n + 1) blocks, an (n + 1) x (n + 1) block, an n x n block
+// and a vector of length n + 1 from `work`.
+fn total_into<T: Float>(
+    backend: &impl Backend<T>,
+    (m, n): (usize, usize),
+    a: &[T],
+    b: &[T],
+    ipiv: &mut [usize],
+    work: &mut Work<T>,
+    out: Parts<T>,
+) -> Result<usize, LinalgError> {
+    let k = n + 1;
+    let ab = work.rows.take(m * k);
+    for i in 0..m {
+        ab[i * k..i * k + n].copy_from_slice(&a[i * n..(i + 1) * n]);
+        ab[i * k + n] = b[i];
+    }
+    let svd = Svd::new(backend, m, k, ab, work)?;
+    let v = &svd.vt[n * k..];
+    let smallest = svd.s[n];
+    if v[n].abs() <= T::epsilon() {
+        return Err(LinalgError::Singular);
+    }
+    for (x, &vi) in out.x.iter_mut().zip(v) {
+        *x = -vi / v[n];
+    }
+
+    let cutoff = T::epsilon() * T::from(m).unwrap() * svd.s[0];
+    let rank = svd.s[..n].iter().filter(|&&s| s > cutoff).count();
+    // (A^T A - s^2 I)^-1, through the LU factorization
+    let shifted = work.squares.take(n * n);
+    for i in 0..n {
+        for j in 0..n {
+            let ata = (0..m).fold(T::zero(), |acc, r| acc + a[r * n + i] * a[r * n + j]);
+            shifted[i * n + j] = ata;
+        }
+        shifted[i * n + i] = shifted[i * n + i] - smallest * smallest;
+        out.covariance[i * n + i] = T::one();
+    }
+    lu_solve_in_place(backend, n, n, shifted, ipiv, out.covariance)?;
+    residuals((m, n), a, b, out.x, out.residuals);
+    let scale = smallest * smallest / T::from(m - n).unwrap() * (T::one() + norm(out.x).powi(2));
+    for c in out.covariance.iter_mut() {
+        *c = scale * *c;
+    }
+    Ok(rank)
+}
+
+// Fails with `InvalidArgument` unless λ >= 0.
+fn check_lambda<T: Float>(lambda: T) -> Result<(), LinalgError> {
+    if lambda >= T::zero() {
+        Ok(())
+    } else {
+        Err(LinalgError::InvalidArgument(
+            "ridge parameter must be non-negative",
+        ))
+    }
+}
+
+// Fails with `DimensionMismatch` unless A has at least `needed` rows.
+#[cfg(feature = "alloc")]
+fn check_rows<T: Float>(a: &DMat<T>, needed: usize) -> Result<(), LinalgError> {
+    if a.nrows() < needed {
+        return Err(LinalgError::DimensionMismatch {
+            expected: (needed, a.ncols()),
+            found: a.shape(),
+        });
+    }
+    Ok(())
+}
+
+struct Svd<'a, T> {
+    k: usize,
+    n: usize,
+    // m x k
+    u: &'a [T],
+    s: &'a [T],
+    // k x n
+    vt: &'a [T],
+}
+
+impl<'a, T: Float> Svd<'a, T> {
+    // The SVD of the m x n matrix in `a`, which is overwritten, with U, s and
+    // V^T taken from `work`.
+    fn new(
+        backend: &impl Backend<T>,
+        m: usize,
+        n: usize,
+        a: &mut [T],
+        work: &mut Work<'a, T>,
+    ) -> Result<Self, LinalgError> {
+        let k = m.min(n);
+        let (u, s, vt) = (
+            work.rows.take(m * k),
+            work.vectors.take(k),
+            work.squares.take(k * n),
+        );
+        backend.gesdd(m, n, a, n, s, u, k, vt, n)?;
+        Ok(Self { k, n, u, s, vt })
+    }
+
+    fn ut_b(&self, backend: &impl Backend<T>, b: &[T], beta: &mut [T]) {
+        backend.gemv(
+            Transpose::Yes,
+            b.len(),
+            self.k,
+            T::one(),
+            self.u,
+            self.k,
+            b,
+            T::zero(),
+            beta,
+        );
+    }
+
+    // x = sum_i f(s_i) (u_i^T b) v_i and cov = sum_i g(s_i) v_i v_i^T, for
+    // (f, g) = filter(s_i), with U^T b in `beta`.
+    #[allow(clippy::needless_range_loop)]
+    fn filtered(
+        &self,
+        backend: &impl Backend<T>,
+        b: &[T],
+        beta: &mut [T],
+        x: &mut [T],
+        cov: &mut [T],
+        filter: impl Fn(T) -> (T, T),
+    ) {
+        let n = self.n;
+        self.ut_b(backend, b, beta);
+        x.fill(T::zero());
+        cov.fill(T::zero());
+        for i in 0..self.k {
+            let (f, g) = filter(self.s[i]);
+            let v = &self.vt[i * n..(i + 1) * n];
+            for r in 0..n {
+                x[r] = x[r] + f * beta[i] * v[r];
+                for c in 0..n {
+                    cov[r * n + c] = cov[r * n + c] + g * v[r] * v[c];
+                }
+            }
+        }
+    }
+
+    // The ridge fit for the m x n A and b that this is the SVD of, returning
+    // the numerical rank of A.
+    fn ridge(
+        &self,
+        backend: &impl Backend<T>,
+        a: &[T],
+        b: &[T],
+        lambda: T,
+        beta: &mut [T],
+        out: Parts<T>,
+    ) -> usize {
+        let m = b.len();
+        let cutoff = T::epsilon()
+            * T::from(m.max(self.n)).unwrap()
+            * self.s.first().copied().unwrap_or_else(T::zero);
+        let rank = self.s.iter().filter(|&&s| s > cutoff).count();
+        let dof = self
+            .s
+            .iter()
+            .filter(|&&s| s > cutoff)
+            .fold(T::zero(), |acc, &s| acc + s * s / (s * s + lambda));
+        self.filtered(backend, b, beta, out.x, out.covariance, |s| {
+            if s > cutoff {
+                let d = s * s + lambda;
+                (s / d, s * s / (d * d))
+            } else {
+                (T::zero(), T::zero())
+            }
+        });
+        finish((m, self.n), a, b, T::from(m).unwrap() - dof, out);
+        rank
+    }
+}
+
+// Minimizes ||A x - b|| for the m x n (m >= n) row-major A through Householder
+// QR, leaving x in the first n entries of `b`, and R and the reflectors in `a`
+// and `tau`. Fails with `Singular` when A is rank deficient.
+pub(crate) fn qr_solve<T: Float>(
+    backend: &impl Backend<T>,
+    m: usize,
+    n: usize,
+    a: &mut [T],
+    b: &mut [T],
+    tau: &mut [T],
+) -> Result<(), LinalgError> {
+    backend.geqrf(m, n, a, n, tau);
+    check_triangle(m, n, a)?;
+    for i in 0..n {
+        apply_reflector(m, n, a, tau, i, b, 1);
+    }
+    backend.trsm(
+        Side::Left,
+        Uplo::Upper,
+        Transpose::No,
+        Diag::NonUnit,
+        n,
+        1,
+        T::one(),
+        a,
+        n,
+        &mut b[..n],
+        1,
+    );
+    Ok(())
+}
+
+// (A^T A)^-1 = R^-1 R^-T into `out`, from the R factor in the first n rows of
+// the `qr_solve` factorization `qr` with n columns. R^-1 goes in `rinv`.
+fn unscaled_covariance<T: Float>(
+    backend: &impl Backend<T>,
+    n: usize,
+    qr: &[T],
+    rinv: &mut [T],
+    out: &mut [T],
+) {
+    let (zero, one) = (T::zero(), T::one());
+    let rinv = &mut rinv[..n * n];
+    rinv.fill(zero);
+    for i in 0..n {
+        rinv[i * n + i] = one;
+    }
+    backend.trsm(
+        Side::Left,
+        Uplo::Upper,
+        Transpose::No,
+        Diag::NonUnit,
+        n,
+        n,
+        one,
+        qr,
+        n,
+        rinv,
+        n,
+    );
+    backend.gemm(
+        Transpose::No,
+        Transpose::Yes,
+        n,
+        n,
+        n,
+        one,
+        rinv,
+        n,
+        rinv,
+        n,
+        zero,
+        out,
+        n,
+    );
+}
+
+// Fails with `Singular` when the R factor of an m x n QR factorization has a
+// negligible diagonal entry.
+fn check_triangle<T: Float>(m: usize, n: usize, qr: &[T]) -> Result<(), LinalgError> {
+    let diagonal = (0..m.min(n)).map(|i| qr[i * n + i].abs());
+    let largest = diagonal.clone().fold(T::zero(), T::max);
+    let tol = T::epsilon() * T::from(m.max(n)).unwrap() * largest;
+    if diagonal.clone().any(|d| d <= tol) {
+        return Err(LinalgError::Singular);
+    }
+    Ok(())
+}
+
+// Applies the reflector H(i) from an m x n `geqrf` factorization to the m
+// rows of the row-major B with `cols` columns.
+fn apply_reflector<T: Float>(
+    m: usize,
+    n: usize,
+    qr: &[T],
+    tau: &[T],
+    i: usize,
+    b: &mut [T],
+    cols: usize,
+) {
+    for c in 0..cols {
+        let dot = (i + 1..m).fold(b[i * cols + c], |acc, r| {
+            acc + qr[r * n + i] * b[r * cols + c]
+        });
+        let f = tau[i] * dot;
+        b[i * cols + c] = b[i * cols + c] - f;
+        for r in i + 1..m {
+            b[r * cols + c] = b[r * cols + c] - f * qr[r * n + i];
+        }
+    }
+}
+
+// Solves the unconstrained problem in the passive columns into the full
+// length `x`, zero elsewhere. The passive columns of A are factored in `sub`
+// and `tau`, and `rhs` takes the m entries of b.
+#[allow(clippy::too_many_arguments)]
+fn passive_solve<T: Float>(
+    backend: &impl Backend<T>,
+    (m, n): (usize, usize),
+    a: &[T],
+    b: &[T],
+    passive: &[bool],
+    (sub, tau): (&mut [T], &mut [T]),
+    rhs: &mut [T],
+    x: &mut [T],
+) -> Result<(), LinalgError> {
+    let k = passive.iter().filter(|&&p| p).count();
+    let columns = || (0..n).filter(|&j| passive[j]).enumerate();
+    for i in 0..m {
+        for (c, j) in columns() {
+            sub[i * k + c] = a[i * n + j];
+        }
+    }
+    rhs.copy_from_slice(b);
+    qr_solve(backend, m, k, &mut sub[..m * k], rhs, &mut tau[..k])?;
+    x.fill(T::zero());
+    for (c, j) in columns() {
+        x[j] = rhs[c];
+    }
+    Ok(())
+}
+
+// r = b - A x for the m x n A.
+fn residuals<T: Float>((m, n): (usize, usize), a: &[T], b: &[T], x: &[T], r: &mut [T]) {
+    for i in 0..m {
+        let mut sum = T::zero();
+        for j in 0..n {
+            sum = sum + a[i * n + j] * x[j];
+        }
+        r[i] = b[i] - sum;
+    }
+}
+
+// Fills in the residuals of a solution in `out.x` and scales the unscaled
+// covariance in `out.covariance` by the residual variance over `dof` degrees
+// of freedom.
+fn finish<T: Float>((m, n): (usize, usize), a: &[T], b: &[T], dof: T, out: Parts<T>) {
+    residuals((m, n), a, b, out.x, out.residuals);
+    scale_covariance(out.covariance, norm(out.residuals), dof);
+}
+
+fn scale_covariance<T: Float>(covariance: &mut [T], rss: T, dof: T) {
+    let variance = if dof > T::zero() {
+        rss.powi(2) / dof
+    } else {
+        T::nan()
+    };
+    for c in covariance.iter_mut() {
+        *c = variance * *c;
+    }
+}
+
+fn norm<T: Float>(v: &[T]) -> T {
+    v.iter().fold(T::zero(), |acc, &x| acc.hypot(x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::lu_solve;
+    use crate::backend::test_util::Counting;
+    use crate::{assert_abs_diff_eq, mat};
+
+    fn values<const R: usize, const C: usize>(seed: u64) -> Mat<f64, R, C> {
+        let mut state = seed;
+        let mut m = Mat::zero();
+        for x in m.iter_mut() {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            *x = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+        }
+        m
+    }
+
+    // a quadratic in t sampled at 8 points
+    fn singular_values<const R: usize, const C: usize>(a: &Mat<f64, R, C>) -> std::vec::Vec<f64> {
+        let k = R.min(C);
+        let (mut s, mut u, mut vt) = (
+            std::vec![0.0; k],
+            std::vec![0.0; R * k],
+            std::vec![0.0; k * C],
+        );
+        let mut a = *a;
+        Native
+            .gesdd(R, C, a.as_mut_slice(), C, &mut s, &mut u, k, &mut vt, C)
+            .unwrap();
+        s
+    }
+
+    fn vandermonde() -> Mat<f64, 8, 3> {
+        let mut a = Mat::zero();
+        for i in 0..8 {
+            let t = i as f64 / 2.0;
+            a[i] = [1.0, t, t * t];
+        }
+        a
+    }
+
+    fn normal_equations<const R: usize, const C: usize>(
+        a: &Mat<f64, R, C>,
+        b: &Mat<f64, R, 1>,
+        lambda: f64,
+    ) -> Mat<f64, C, 1> {
+        let ata = a.transpose() * *a + Mat::diagonal(lambda);
+        lu_solve(&Native, &ata, &(a.transpose() * *b)).unwrap()
+    }
+
+    #[test]
+    fn ordinary() {
+        let a = vandermonde();
+        let truth: Mat<f64, 3, 1> = mat![1; 2; -0.5];
+        let exact = qr(&a, &(a * truth));
+        assert_abs_diff_eq!(exact.x, truth, epsilon = 1e-12);
+        assert!(exact.residual_norm() < 1e-12);
+        assert_eq!(exact.rank, 3);
+
+        let b = a * truth + values::<8, 1>(1);
+        let fit = qr(&a, &b);
+        assert_abs_diff_eq!(fit.x, normal_equations(&a, &b, 0.0), epsilon = 1e-12);
+        assert_abs_diff_eq!(fit.residuals, b - a * fit.x, epsilon = 1e-15);
+
+        // s^2 (A^T A)^-1 with s^2 = rss / (8 - 3)
+        let variance = fit.residual_norm().powi(2) / 5.0;
+        let inverse = lu_solve(&Native, &(a.transpose() * a), &Mat::identity()).unwrap();
+        let mut expected = inverse;
+        for x in expected.iter_mut() {
+            *x *= variance;
+        }
+        assert_abs_diff_eq!(fit.covariance, expected, epsilon = 1e-12);
+
+        let by_svd = svd(&a, &b, None);
+        assert_abs_diff_eq!(by_svd.x, fit.x, epsilon = 1e-12);
+        assert_abs_diff_eq!(by_svd.covariance, fit.covariance, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn rank_deficient() {
+        // the second column repeats the first
+        let a: Mat<f64, 3, 3> = mat![1, 1, 0; 2, 2, 1; 0, 0, 1];
+        let b: Mat<f64, 3, 1> = mat![2; 5; 1];
+        assert_eq!(try_qr(&a, &b).unwrap_err(), LinalgError::Singular);
+
+        // the minimum norm solution splits the weight evenly
+        let fit = svd(&a, &b, None);
+        assert_eq!(fit.rank, 2);
+        assert_abs_diff_eq!(fit.x, mat![1; 1; 1], epsilon = 1e-12);
+
+        // underdetermined
+        let a: Mat<f64, 1, 2> = mat![3, 4];
+        let fit = svd(&a, &mat![25], None);
+        assert_abs_diff_eq!(fit.x, mat![3; 4], epsilon = 1e-12);
+        assert!(fit.covariance[0][0].is_nan());
+    }
+
+    #[test]
+    fn weights() {
+        let a = vandermonde();
+        let truth: Mat<f64, 3, 1> = mat![1; 2; -0.5];
+        let mut b = a * truth;
+        b[5][0] += 100.0;
+        let mut w: Mat<f64, 8, 1> = Mat::from_slice(&[1.0; 8]);
+        w[5][0] = 0.0;
+        let fit = weighted(&a, &b, &w);
+        assert_abs_diff_eq!(fit.x, truth, epsilon = 1e-10);
+        assert_abs_diff_eq!(fit.residuals[5][0], 100.0, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let (a, b) = (vandermonde(), values::<8, 1>(3));
+        let negative = LinalgError::InvalidArgument("ridge parameter must be non-negative");
+        assert_eq!(try_ridge(&a, &b, -1.0).unwrap_err(), negative);
+        assert_eq!(try_ridge(&a, &b, f64::NAN).unwrap_err(), negative);
+        let w: Mat<f64, 8, 1> = Mat::from_slice(&[f64::NAN; 8]);
+        assert!(matches!(
+            try_weighted(&a, &b, &w),
+            Err(LinalgError::InvalidArgument(_))
+        ));
+        #[cfg(feature = "alloc")]
+        {
+            assert_eq!(
+                try_ridge_sweep(&a, &b, Some(&[0.1, -1.0])).unwrap_err(),
+                negative
+            );
+            assert!(matches!(
+                try_ridge_sweep(&a, &b, Some(&[])),
+                Err(LinalgError::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "least squares weights must be non-negative")]
+    fn negative_weight() {
+        let a = vandermonde();
+        let mut w: Mat<f64, 8, 1> = Mat::from_slice(&[1.0; 8]);
+        w[0][0] = -1.0;
+        weighted(&a, &Mat::zero(), &w);
+    }
+
+    #[test]
+    fn regularized() {
+        let a = values::<6, 4>(2);
+        let b = values::<6, 1>(3);
+        assert_abs_diff_eq!(ridge(&a, &b, 0.0).x, qr(&a, &b).x, epsilon = 1e-12);
+        let fit = ridge(&a, &b, 0.3);
+        assert_abs_diff_eq!(fit.x, normal_equations(&a, &b, 0.3), epsilon = 1e-12);
+        assert_eq!(fit.rank, 4);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn regularization_sweep() {
+        let a = values::<6, 4>(2);
+        let b = values::<6, 1>(3);
+        let lambdas = [0.0, 0.01, 0.1, 1.0, 10.0];
+        let sweep = ridge_sweep(&a, &b, Some(&lambdas));
+        assert_eq!(sweep.gcv.len(), 5);
+        let best = sweep.gcv.iter().cloned().fold(f64::INFINITY, f64::min);
+        let i = sweep.gcv.iter().position(|&g| g == best).unwrap();
+        assert_eq!(sweep.lambda, lambdas[i]);
+        assert_abs_diff_eq!(sweep.fit.x, ridge(&a, &b, lambdas[i]).x, epsilon = 1e-12);
+
+        // GCV agrees with its definition
+        let fit = ridge(&a, &b, 1.0);
+        let dof: f64 = singular_values(&a)
+            .iter()
+            .map(|s| s * s / (s * s + 1.0))
+            .sum();
+        let gcv = 6.0 * fit.residual_norm().powi(2) / (6.0 - dof).powi(2);
+        assert_abs_diff_eq!(sweep.gcv[3], gcv, epsilon = 1e-12);
+
+        let sweep = ridge_sweep(&a, &b, None);
+        assert_eq!(sweep.lambdas.len(), 50);
+        assert!(sweep.lambdas.contains(&sweep.lambda));
+    }
+
+    #[test]
+    fn equality_constrained() {
+        let a = values::<6, 3>(4);
+        let b = values::<6, 1>(5);
+        let c: Mat<f64, 1, 3> = mat![1, 1, 1];
+        let d: Mat<f64, 1, 1> = mat![1];
+        let fit = constrained(&a, &b, &c, &d);
+        assert_abs_diff_eq!(c * fit.x, d, epsilon = 1e-14);
+        assert_eq!(fit.rank, 2);
+
+        // the KKT system [A^T A  C^T; C  0] [x; mu] = [A^T b; d]
+        let mut kkt = Mat::<f64, 4, 4>::zero();
+        kkt.set_block(0, 0, &(a.transpose() * a));
+        kkt.set_block(0, 3, &c.transpose());
+        kkt.set_block(3, 0, &c);
+        let rhs: Mat<f64, 4, 1> = (a.transpose() * b).vstack(&d);
+        let solution = lu_solve(&Native, &kkt, &rhs).unwrap();
+        assert_abs_diff_eq!(fit.x, solution.fixed_block::<3, 1>(0, 0), epsilon = 1e-12);
+
+        // the estimate cannot move along C, so neither can its covariance
+        assert_abs_diff_eq!(c * fit.covariance, Mat::zero(), epsilon = 1e-14);
+
+        let dependent: Mat<f64, 2, 3> = mat![1, 1, 1; 2, 2, 2];
+        assert_eq!(
+            try_constrained(&a, &b, &dependent, &mat![1; 2]).unwrap_err(),
+            LinalgError::Singular
+        );
+    }
+
+    #[test]
+    fn non_negative() {
+        let a: Mat<f64, 4, 3> = mat![1, 0, 1; 0, 1, 1; 1, 1, 0; 1, 0, 0];
+        let b: Mat<f64, 4, 1> = mat![2; -1; 1; 2];
+        let fit = nnls(&a, &b);
+        assert!(fit.x.iter().all(|&x| x >= 0.0));
+
+        // KKT: the gradient vanishes on the positive entries and points into
+        // the bound on the others
+        let w = a.transpose() * fit.residuals;
+        for j in 0..3 {
+            if fit.x[j][0] > 0.0 {
+                assert_abs_diff_eq!(w[j][0], 0.0, epsilon = 1e-12);
+            } else {
+                assert!(w[j][0] <= 1e-12);
+            }
+        }
+        assert_eq!(fit.rank, fit.x.iter().filter(|&&x| x > 0.0).count());
+        assert!(fit.rank < 3);
+
+        // an unconstrained solution that is already non-negative
+        let a = vandermonde();
+        let truth: Mat<f64, 3, 1> = mat![1; 2; 0.5];
+        assert_abs_diff_eq!(nnls(&a, &(a * truth)).x, truth, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn errors_in_variables() {
+        let a = vandermonde();
+        let truth: Mat<f64, 3, 1> = mat![1; 2; -0.5];
+        let fit = total(&a, &(a * truth));
+        assert_abs_diff_eq!(fit.x, truth, epsilon = 1e-10);
+
+        // x solves (A^T A - s^2 I) x = A^T b, with s the smallest singular
+        // value of [A b]
+        let a = a + values::<8, 3>(6);
+        let b = a * truth + values::<8, 1>(7);
+        let fit = total(&a, &b);
+        let ab: Mat<f64, 8, 4> = a.hstack(&b);
+        let s = *singular_values(&ab).last().unwrap();
+        let shifted = a.transpose() * a - Mat::diagonal(s * s);
+        assert_abs_diff_eq!(shifted * fit.x, a.transpose() * b, epsilon = 1e-10);
+        assert_eq!(fit.rank, 3);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn dynamic() {
+        // both forms run the same code, so they agree exactly on the same
+        // data; also check the shapes the fixed-size solvers rule out
+        // statically
+        let (a, b) = (vandermonde() + values::<8, 3>(10), values::<8, 1>(11));
+        let (da, db) = (DMat::from_mat(&a), DMat::from_mat(&b));
+        let same = |d: &DFit<f64>, f: &Fit<f64, 8, 3>| {
+            assert_eq!(d.x.to_mat::<3, 1>(), f.x);
+            assert_eq!(d.residuals.to_mat::<8, 1>(), f.residuals);
+            assert_eq!(d.covariance.to_mat::<3, 3>(), f.covariance);
+            assert_eq!(d.rank, f.rank);
+        };
+        same(&qr_dynamic(&da, &db), &qr(&a, &b));
+        same(&svd_dynamic(&da, &db, None), &svd(&a, &b, None));
+        let mut w = values::<8, 1>(12);
+        for x in w.iter_mut() {
+            *x = x.abs();
+        }
+        same(
+            &weighted_dynamic(&da, &db, &DMat::from_mat(&w)),
+            &weighted(&a, &b, &w),
+        );
+        same(&ridge_dynamic(&da, &db, 0.1), &ridge(&a, &b, 0.1));
+        let sweep = ridge_sweep_dynamic(&da, &db, None);
+        same(&sweep.fit, &ridge_sweep(&a, &b, None).fit);
+        let (c, d): (Mat<f64, 1, 3>, Mat<f64, 1, 1>) = (mat![1, 1, 1], mat![1]);
+        same(
+            &constrained_dynamic(&da, &db, &DMat::from_mat(&c), &DMat::from_mat(&d)),
+            &constrained(&a, &b, &c, &d),
+        );
+        same(&nnls_dynamic(&da, &db), &nnls(&a, &b));
+        same(&total_dynamic(&da, &db), &total(&a, &b));
+
+        // a row count only known at run time
+        let samples: Vec<f64> = (0..13).map(|i| i as f64 / 4.0).collect();
+        let a = DMat::from_fn(samples.len(), 3, |i, j| samples[i].powi(j as i32));
+        let truth = DMat::from_vec(3, 1, vec![1.0, 2.0, -0.5]);
+        let fit = qr_dynamic(&a, &(&a * &truth));
+        assert_abs_diff_eq!(fit.x.to_mat::<3, 1>(), truth.to_mat(), epsilon = 1e-12);
+        assert_eq!(fit.residuals.shape(), (13, 1));
+        assert!(fit.residual_norm() < 1e-12);
+
+        let mismatch = |expected, found| LinalgError::DimensionMismatch { expected, found };
+        let short = DMat::<f64>::zero(12, 1);
+        assert_eq!(
+            try_qr_dynamic(&a, &short).unwrap_err(),
+            mismatch((13, 1), (12, 1))
+        );
+        let wide = DMat::<f64>::zero(2, 3);
+        let zeros = DMat::zero(2, 1);
+        assert_eq!(
+            try_qr_dynamic(&wide, &zeros).unwrap_err(),
+            mismatch((3, 3), (2, 3))
+        );
+        assert_eq!(
+            try_nnls_dynamic(&wide, &zeros).unwrap_err(),
+            mismatch((3, 3), (2, 3))
+        );
+        let square = DMat::<f64>::identity(3);
+        assert_eq!(
+            try_total_dynamic(&square, &DMat::zero(3, 1)).unwrap_err(),
+            mismatch((4, 3), (3, 3))
+        );
+        assert_eq!(
+            try_weighted_dynamic(&a, &(&a * &truth), &short).unwrap_err(),
+            mismatch((13, 1), (12, 1))
+        );
+        let c = DMat::zero(4, 3);
+        assert_eq!(
+            try_constrained_dynamic(&a, &(&a * &truth), &c, &DMat::zero(4, 1)).unwrap_err(),
+            mismatch((3, 3), (4, 3))
+        );
+        let c = DMat::from_vec(1, 3, vec![1.0, 1.0, 1.0]);
+        assert_eq!(
+            try_constrained_dynamic(
+                &wide.block(0, 0, 1, 3),
+                &DMat::zero(1, 1),
+                &c,
+                &DMat::zero(1, 1)
+            )
+            .unwrap_err(),
+            mismatch((2, 3), (1, 3))
+        );
+    }
+
+    #[test]
+    fn backend() {
+        let a = vandermonde() + values::<8, 3>(8);
+        let b = values::<8, 1>(9);
+        let backend = Counting::default();
+        let fit = try_qr_with(&backend, &a, &b).unwrap();
+        // geqrf, two trsm and a gemm
+        assert_eq!(backend.calls(), 4);
+        assert_eq!(fit.x, qr(&a, &b).x);
+
+        let fit = try_ridge_with(&backend, &a, &b, 0.1).unwrap();
+        // gesdd and a gemv
+        assert_eq!(backend.calls(), 6);
+        assert_eq!(fit.x, ridge(&a, &b, 0.1).x);
+    }
+}
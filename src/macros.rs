@@ -0,0 +1,250 @@
+use super::matrix::Mat;
+use num::{Float, NumCast, ToPrimitive};
+
+// Literal macros. The element type comes from the surrounding code, and
+// integer literals can be used for it. When every entry is a literal token it
+// is cast with `as`, which keeps the macros usable in constants. Otherwise the
+// entries must be primitive numbers and are converted with `NumCast`, so an
+// expression is never narrowed by a silent `as`, and a value the element type
+// cannot represent panics. Shapes are taken from the literal, and ragged rows
+// fail to compile.
+//
+//   let m: Mat<f64, 2, 3> = mat![1, 2, 3; 4, 5, 6];
+//   let v: Vec3<f32> = vec3!(1, 0, 0);
+//
+// `mat!` entries can also be matrices, which are placed as blocks, with `,`
+// between the blocks of a block row and `;` between block rows:
+//
+//   let m: Mat<f64, 4, 4> = mat![a, b; c, d];
+//
+// Numbers count as 1 x 1 blocks. The output shape cannot be computed from the
+// blocks on stable Rust, so it comes from the surrounding code. Blocks in a
+// block row must have the same number of rows, block rows must have the same
+// number of columns, and the sizes must add up to the output shape; all of
+// this is checked at compile time. Without blocks, a literal can have any
+// size; with them, or with entries that are not literal tokens, it can have up
+// to 16 entries per row and 16 rows.
+
+#[macro_export]
+macro_rules! mat {
+    ($($($e:literal),+);+ $(;)?) => {
+        $crate::matrix::Mat::new(&[$([$($e as _),+]),+])
+    };
+    ($($($e:expr),+);+ $(;)?) => {
+        $crate::macros::VCat::vcat((
+            $($crate::macros::HCat::hcat(($($crate::macros::Block::block($e),)+)),)+
+        ))
+    };
+}
+
+#[macro_export]
+macro_rules! vec2 {
+    ($x:literal, $y:literal $(,)?) => {
+        $crate::vector::vec2::Vec2::new($x as _, $y as _)
+    };
+    ($x:expr, $y:expr $(,)?) => {
+        $crate::vector::vec2::Vec2::new($crate::macros::entry($x), $crate::macros::entry($y))
+    };
+}
+
+#[macro_export]
+macro_rules! vec3 {
+    ($x:literal, $y:literal, $z:literal $(,)?) => {
+        $crate::vector::vec3::Vec3::new($x as _, $y as _, $z as _)
+    };
+    ($x:expr, $y:expr, $z:expr $(,)?) => {
+        $crate::vector::vec3::Vec3::new(
+            $crate::macros::entry($x),
+            $crate::macros::entry($y),
+            $crate::macros::entry($z),
+        )
+    };
+}
+
+#[macro_export]
+macro_rules! vec4 {
+    ($x:literal, $y:literal, $z:literal, $w:literal $(,)?) => {
+        $crate::vector::vec4::Vec4::new($x as _, $y as _, $z as _, $w as _)
+    };
+    ($x:expr, $y:expr, $z:expr, $w:expr $(,)?) => {
+        $crate::vector::vec4::Vec4::new(
+            $crate::macros::entry($x),
+            $crate::macros::entry($y),
+            $crate::macros::entry($z),
+            $crate::macros::entry($w),
+        )
+    };
+}
+
+#[doc(hidden)]
+pub trait Primitive: ToPrimitive {}
+
+macro_rules! impl_primitive {
+    ($($t:ty),+) => {
+        $(impl Primitive for $t {})+
+    };
+}
+
+impl_primitive!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+#[doc(hidden)]
+pub fn entry<T: Float, S: Primitive>(value: S) -> T {
+    <T as NumCast>::from(value).expect("literal entry does not fit the element type")
+}
+
+// A `mat!` entry as a block: a number is a 1 x 1 matrix.
+#[doc(hidden)]
+pub trait Block<T> {
+    type Mat;
+    fn block(self) -> Self::Mat;
+}
+
+impl<T: Float, S: Primitive> Block<T> for S {
+    type Mat = Mat<T, 1, 1>;
+    fn block(self) -> Mat<T, 1, 1> {
+        Mat::new(&[[entry(self)]])
+    }
+}
+
+impl<T: Float, const R: usize, const C: usize> Block<T> for Mat<T, R, C> {
+    type Mat = Self;
+    fn block(self) -> Self {
+        self
+    }
+}
+
+#[doc(hidden)]
+pub trait HCat<Out> {
+    fn hcat(self) -> Out;
+}
+
+#[doc(hidden)]
+pub trait VCat<Out> {
+    fn vcat(self) -> Out;
+}
+
+macro_rules! impl_cat {
+    ($($blk:ident $n:ident),+) => {
+        impl<T: Float, const R: usize, const C: usize, $(const $n: usize),+> HCat<Mat<T, R, C>>
+            for ($(Mat<T, R, $n>,)+)
+        {
+            fn hcat(self) -> Mat<T, R, C> {
+                const { assert!(0 $(+ $n)+ == C, "block columns do not add up") };
+                let ($($blk,)+) = self;
+                let mut out = Mat::zero();
+                let mut offset = 0;
+                $(
+                    for i in 0..R {
+                        out[i][offset..offset + $n].copy_from_slice(&$blk[i]);
+                    }
+                    offset += $n;
+                )+
+                let _ = offset;
+                out
+            }
+        }
+
+        impl<T: Float, const R: usize, const C: usize, $(const $n: usize),+> VCat<Mat<T, R, C>>
+            for ($(Mat<T, $n, C>,)+)
+        {
+            fn vcat(self) -> Mat<T, R, C> {
+                const { assert!(0 $(+ $n)+ == R, "block rows do not add up") };
+                let ($($blk,)+) = self;
+                let mut out = Mat::zero();
+                let mut offset = 0;
+                $(
+                    for i in 0..$n {
+                        out[offset + i] = $blk[i];
+                    }
+                    offset += $n;
+                )+
+                let _ = offset;
+                out
+            }
+        }
+    };
+}
+
+impl_cat!(a A);
+impl_cat!(a A, b B);
+impl_cat!(a A, b B, c C1);
+impl_cat!(a A, b B, c C1, d D);
+impl_cat!(a A, b B, c C1, d D, e E);
+impl_cat!(a A, b B, c C1, d D, e E, f F);
+impl_cat!(a A, b B, c C1, d D, e E, f F, g G);
+impl_cat!(a A, b B, c C1, d D, e E, f F, g G, h H);
+impl_cat!(a A, b B, c C1, d D, e E, f F, g G, h H, i I);
+impl_cat!(a A, b B, c C1, d D, e E, f F, g G, h H, i I, j J);
+impl_cat!(a A, b B, c C1, d D, e E, f F, g G, h H, i I, j J, k K);
+impl_cat!(a A, b B, c C1, d D, e E, f F, g G, h H, i I, j J, k K, l L);
+impl_cat!(a A, b B, c C1, d D, e E, f F, g G, h H, i I, j J, k K, l L, m M);
+impl_cat!(a A, b B, c C1, d D, e E, f F, g G, h H, i I, j J, k K, l L, m M, n N);
+impl_cat!(a A, b B, c C1, d D, e E, f F, g G, h H, i I, j J, k K, l L, m M, n N, o O);
+impl_cat!(a A, b B, c C1, d D, e E, f F, g G, h H, i I, j J, k K, l L, m M, n N, o O, p P);
+
+#[cfg(test)]
+mod tests {
+    use crate::dual::Dual;
+    use crate::matrix::Mat;
+    use crate::vector::{vec2::Vec2, vec3::Vec3, vec4::Vec4};
+
+    #[test]
+    fn mat() {
+        let m: Mat<f64, 2, 3> = mat![1, 2.5, 3; 4, 5, -6];
+        assert_eq!(m, Mat::new(&[[1.0, 2.5, 3.0], [4.0, 5.0, -6.0]]));
+
+        let row: Mat<f32, 1, 3> = mat![1, 2, 3];
+        let col: Mat<f32, 3, 1> = mat![1; 2; 3;];
+        assert_eq!(row, Mat::new(&[[1.0, 2.0, 3.0]]));
+        assert_eq!(col, Mat::new(&[[1.0], [2.0], [3.0]]));
+
+        let x = 2.0;
+        assert_eq!(mat![x * x, 0; 0, 1], Mat::new(&[[4.0, 0.0], [0.0, 1.0]]));
+    }
+
+    #[test]
+    fn vec() {
+        assert_eq!(vec2!(1, 2), Vec2::new(1.0, 2.0));
+        assert_eq!(vec3!(1, 2, 3.5), Vec3::new(1.0f32, 2.0, 3.5));
+        assert_eq!(vec4!(1, 2, 3, 4,), Vec4::new(1.0, 2.0, 3.0, 4.0));
+
+        let x = 2;
+        assert_eq!(vec3!(x, x * 3, -1.5), Vec3::new(2.0f32, 6.0, -1.5));
+    }
+
+    #[test]
+    fn entries_are_checked() {
+        let big = u64::MAX;
+        let v: Vec2<f32> = vec2!(big, -1i8);
+        assert_eq!(v, Vec2::new(u64::MAX as f32, -1.0));
+
+        let x = 3.0;
+        let m: Mat<Dual<f64>, 1, 3> = mat![x, x * x, 1];
+        assert_eq!(m[0][1], Dual::constant(9.0));
+        assert_eq!(m[0][2], Dual::constant(1.0));
+    }
+
+    #[test]
+    fn block() {
+        let a: Mat<f64, 2, 2> = mat![1, 2; 3, 4];
+        let b: Mat<f64, 2, 1> = mat![5; 6];
+        let c: Mat<f64, 1, 3> = mat![7, 8, 9];
+        let m: Mat<f64, 3, 3> = mat![a, b; c];
+        assert_eq!(m, mat![1, 2, 5; 3, 4, 6; 7, 8, 9]);
+
+        let i = Mat::<f64, 2, 2>::identity();
+        let z = Mat::<f64, 2, 2>::zero();
+        let m: Mat<f64, 4, 4> = mat![i, z; z, a];
+        assert_eq!(m[3], [0.0, 0.0, 3.0, 4.0]);
+
+        let wide: Mat<f64, 2, 5> = mat![a, b, a];
+        assert_eq!(wide[1], [3.0, 4.0, 6.0, 3.0, 4.0]);
+
+        // numbers are 1 x 1 blocks
+        let x = 0.5;
+        let bordered: Mat<f64, 3, 3> = mat![a, b; 0, x, 1];
+        assert_eq!(bordered[2], [0.0, 0.5, 1.0]);
+        let long: Mat<f64, 1, 16> = mat![x, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+        assert_eq!(long[0][15], 15.0);
+    }
+}
@@ -0,0 +1,58 @@
+use super::matrix::Mat;
+use super::vector::vec3a::Vec3A;
+use num::Float;
+
+// A 3x3 matrix stored as three columns padded to 16 bytes each, matching the
+// layout of a `mat3` in std140/std430 buffers. `Mat` itself is row-major, so
+// converting between the two transposes the storage but not the value.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(C)]
+pub struct Mat3A<T: Float> {
+    pub cols: [Vec3A<T>; 3],
+}
+
+impl<T: Float> Mat3A<T> {
+    pub fn from_cols(x: Vec3A<T>, y: Vec3A<T>, z: Vec3A<T>) -> Self {
+        Self { cols: [x, y, z] }
+    }
+}
+
+impl<T: Float> From<Mat<T, 3, 3>> for Mat3A<T> {
+    fn from(mat: Mat<T, 3, 3>) -> Self {
+        let col = |j: usize| Vec3A::new(mat[0][j], mat[1][j], mat[2][j]);
+        Self::from_cols(col(0), col(1), col(2))
+    }
+}
+
+impl<T: Float> From<Mat3A<T>> for Mat<T, 3, 3> {
+    #[allow(clippy::needless_range_loop)]
+    fn from(mat: Mat3A<T>) -> Self {
+        let mut arr = [[T::zero(); 3]; 3];
+        for j in 0..3 {
+            for i in 0..3 {
+                arr[i][j] = mat.cols[j].as_array()[i];
+            }
+        }
+        Mat::new(&arr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::size_of;
+
+    #[test]
+    fn layout() {
+        assert_eq!(size_of::<Mat3A<f32>>(), 48);
+    }
+
+    #[test]
+    fn convert() {
+        let mat = Mat::new(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        let padded = Mat3A::from(mat);
+        assert_eq!(padded.cols[0], Vec3A::new(1.0, 4.0, 7.0));
+        assert_eq!(padded.cols[2], Vec3A::new(3.0, 6.0, 9.0));
+        assert_eq!(Mat::from(padded), mat);
+    }
+}
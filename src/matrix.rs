@@ -1,29 +1,250 @@
-use super::vector::{vec2::Vec2, vec3::Vec3, vec4::Vec4};
+use super::error::LinalgError;
+#[cfg(feature = "alloc")]
+use super::gemm;
+use super::macros::{HCat, VCat};
+use super::vector::{vec2::Vec2, vec3::Vec3, vec3a::Vec3A, vec4::Vec4};
+use core::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
+use core::{array, iter, slice};
 use num::Float;
-use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
 
 trait DimEqual<const A: usize, const B: usize> {}
 
 impl<const A: usize> DimEqual<A, A> for () {}
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(C)]
 pub struct Mat<T: Float, const R: usize, const C: usize>(pub [[T; C]; R]);
 
 impl<T: Float, const R: usize, const C: usize> Mat<T, R, C> {
-    pub fn new(arr: &[[T; C]; R]) -> Self {
+    pub const fn new(arr: &[[T; C]; R]) -> Self {
         Self(*arr)
     }
 
-    pub fn fill(n: T) -> Self {
+    pub const fn fill(n: T) -> Self {
         Self([[n; C]; R])
     }
 
     pub fn zero() -> Self {
         Self::fill(T::zero())
     }
+
+    pub const fn as_array(&self) -> &[[T; C]; R] {
+        &self.0
+    }
+
+    pub fn as_mut_array(&mut self) -> &mut [[T; C]; R] {
+        &mut self.0
+    }
+
+    // Entries in row-major order.
+    pub fn as_slice(&self) -> &[T] {
+        self.0.as_flattened()
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.0.as_flattened_mut()
+    }
+
+    // Panics on the `DimensionMismatch` from `try_from_slice`.
+    pub fn from_slice(slice: &[T]) -> Self {
+        Self::try_from_slice(slice).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Takes the entries in row-major order.
+    pub fn try_from_slice(slice: &[T]) -> Result<Self, LinalgError> {
+        if slice.len() != R * C {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (R * C, 1),
+                found: (slice.len(), 1),
+            });
+        }
+        let mut arr = [[T::zero(); C]; R];
+        arr.as_flattened_mut().copy_from_slice(slice);
+        Ok(Self(arr))
+    }
+
+    pub fn transpose(self) -> Mat<T, C, R> {
+        let mut arr = [[T::zero(); R]; C];
+        for i in 0..R {
+            for j in 0..C {
+                arr[j][i] = self[i][j];
+            }
+        }
+        Mat(arr)
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Option<&T> {
+        self.0.get(i)?.get(j)
+    }
+
+    pub fn get_mut(&mut self, i: usize, j: usize) -> Option<&mut T> {
+        self.0.get_mut(i)?.get_mut(j)
+    }
+
+    /// # Safety
+    ///
+    /// `i < R` and `j < C` must hold.
+    pub unsafe fn get_unchecked(&self, i: usize, j: usize) -> &T {
+        self.0.get_unchecked(i).get_unchecked(j)
+    }
+
+    /// # Safety
+    ///
+    /// `i < R` and `j < C` must hold.
+    pub unsafe fn get_unchecked_mut(&mut self, i: usize, j: usize) -> &mut T {
+        self.0.get_unchecked_mut(i).get_unchecked_mut(j)
+    }
+
+    pub fn swap_rows(&mut self, a: usize, b: usize) {
+        self.0.swap(a, b);
+    }
+
+    pub fn swap_columns(&mut self, a: usize, b: usize) {
+        for row in &mut self.0 {
+            row.swap(a, b);
+        }
+    }
+
+    pub fn set_row(&mut self, i: usize, row: [T; C]) {
+        self.0[i] = row;
+    }
+
+    pub fn set_column(&mut self, j: usize, column: [T; R]) {
+        for (row, x) in self.0.iter_mut().zip(column) {
+            row[j] = x;
+        }
+    }
+
+    // Entries in row-major order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+
+    pub fn rows(&self) -> slice::Iter<'_, [T; C]> {
+        self.0.iter()
+    }
+
+    // Columns are not contiguous, so they are returned by value.
+    pub fn columns(&self) -> impl ExactSizeIterator<Item = [T; R]> + '_ {
+        (0..C).map(|j| self.0.map(|row| row[j]))
+    }
+
+    // The shape methods below cannot compute their output shape on stable
+    // Rust, so it comes from the surrounding code and is checked at compile
+    // time, as in `mat!` with blocks:
+    //
+    //   let ab: Mat<f64, 2, 5> = a.hstack(&b);
+    //   let flat: Mat<f64, 1, 6> = m.reshape();
+
+    pub fn hstack<const C2: usize, const C3: usize>(&self, rhs: &Mat<T, R, C2>) -> Mat<T, R, C3> {
+        HCat::hcat((*self, *rhs))
+    }
+
+    pub fn vstack<const R2: usize, const R3: usize>(&self, rhs: &Mat<T, R2, C>) -> Mat<T, R3, C> {
+        VCat::vcat((*self, *rhs))
+    }
+
+    // The R2 x C2 block whose top-left entry is at (i, j). Panics if the
+    // block does not fit.
+    pub fn fixed_block<const R2: usize, const C2: usize>(
+        &self,
+        i: usize,
+        j: usize,
+    ) -> Mat<T, R2, C2> {
+        const { assert!(R2 <= R && C2 <= C, "block is larger than the matrix") };
+        assert!(
+            i + R2 <= R && j + C2 <= C,
+            "block at ({}, {}) is out of bounds",
+            i,
+            j
+        );
+        let mut out = Mat::zero();
+        for r in 0..R2 {
+            out[r].copy_from_slice(&self[i + r][j..j + C2]);
+        }
+        out
+    }
+
+    // Overwrites the block whose top-left entry is at (i, j). Panics if the
+    // block does not fit.
+    pub fn set_block<const R2: usize, const C2: usize>(
+        &mut self,
+        i: usize,
+        j: usize,
+        block: &Mat<T, R2, C2>,
+    ) {
+        const { assert!(R2 <= R && C2 <= C, "block is larger than the matrix") };
+        assert!(
+            i + R2 <= R && j + C2 <= C,
+            "block at ({}, {}) is out of bounds",
+            i,
+            j
+        );
+        for r in 0..R2 {
+            self[i + r][j..j + C2].copy_from_slice(&block[r]);
+        }
+    }
+
+    // Reinterprets the entries, in row-major order, as an R2 x C2 matrix.
+    pub fn reshape<const R2: usize, const C2: usize>(&self) -> Mat<T, R2, C2> {
+        const { assert!(R * C == R2 * C2, "reshape must keep the number of entries") };
+        let mut out = Mat::zero();
+        out.as_mut_slice().copy_from_slice(self.as_slice());
+        out
+    }
+
+    // Inserts `row` before row i, so that it becomes row i. `i == R` appends.
+    pub fn insert_row<const R2: usize>(&self, i: usize, row: [T; C]) -> Mat<T, R2, C> {
+        const { assert!(R2 == R + 1, "inserting a row adds one row") };
+        assert!(i <= R, "row {} is out of bounds", i);
+        let mut out = Mat::zero();
+        out.0[..i].copy_from_slice(&self.0[..i]);
+        out.0[i] = row;
+        out.0[i + 1..].copy_from_slice(&self.0[i..]);
+        out
+    }
+
+    pub fn remove_row<const R2: usize>(&self, i: usize) -> Mat<T, R2, C> {
+        const { assert!(R2 + 1 == R, "removing a row drops one row") };
+        assert!(i < R, "row {} is out of bounds", i);
+        let mut out = Mat::zero();
+        out.0[..i].copy_from_slice(&self.0[..i]);
+        out.0[i..].copy_from_slice(&self.0[i + 1..]);
+        out
+    }
+
+    // Inserts `column` before column j, so that it becomes column j. `j == C`
+    // appends.
+    pub fn insert_column<const C2: usize>(&self, j: usize, column: [T; R]) -> Mat<T, R, C2> {
+        const { assert!(C2 == C + 1, "inserting a column adds one column") };
+        assert!(j <= C, "column {} is out of bounds", j);
+        let mut out = Mat::zero();
+        for i in 0..R {
+            out[i][..j].copy_from_slice(&self[i][..j]);
+            out[i][j] = column[i];
+            out[i][j + 1..].copy_from_slice(&self[i][j..]);
+        }
+        out
+    }
+
+    pub fn remove_column<const C2: usize>(&self, j: usize) -> Mat<T, R, C2> {
+        const { assert!(C2 + 1 == C, "removing a column drops one column") };
+        assert!(j < C, "column {} is out of bounds", j);
+        let mut out = Mat::zero();
+        for i in 0..R {
+            out[i][..j].copy_from_slice(&self[i][..j]);
+            out[i][j..].copy_from_slice(&self[i][j + 1..]);
+        }
+        out
+    }
 }
 
 impl<T: Float, const N: usize> Mat<T, N, N> {
+    #[allow(clippy::needless_range_loop)]
     pub fn diagonal(n: T) -> Self {
         let mut arr = [[T::zero(); N]; N];
         for i in 0..N {
@@ -41,6 +262,96 @@ impl<T: Float, const N: usize> Mat<T, N, N> {
     }
 }
 
+// Trait methods cannot be called in `const` contexts, so `f32` and `f64`
+// matrices get constants and `const fn` versions of the basic arithmetic for
+// building derived constants at compile time.
+macro_rules! impl_const {
+    ($t:ty) => {
+        impl<const R: usize, const C: usize> Mat<$t, R, C> {
+            pub const ZERO: Self = Self([[0.0; C]; R]);
+
+            pub const fn const_add(self, rhs: Self) -> Self {
+                let mut arr = self.0;
+                let mut i = 0;
+                while i < R {
+                    let mut j = 0;
+                    while j < C {
+                        arr[i][j] += rhs.0[i][j];
+                        j += 1;
+                    }
+                    i += 1;
+                }
+                Self(arr)
+            }
+
+            pub const fn const_sub(self, rhs: Self) -> Self {
+                self.const_add(rhs.const_scale(-1.0))
+            }
+
+            pub const fn const_scale(self, k: $t) -> Self {
+                let mut arr = self.0;
+                let mut i = 0;
+                while i < R {
+                    let mut j = 0;
+                    while j < C {
+                        arr[i][j] *= k;
+                        j += 1;
+                    }
+                    i += 1;
+                }
+                Self(arr)
+            }
+
+            pub const fn const_mul<const K: usize>(self, rhs: Mat<$t, C, K>) -> Mat<$t, R, K> {
+                let mut arr = [[0.0; K]; R];
+                let mut i = 0;
+                while i < R {
+                    let mut j = 0;
+                    while j < K {
+                        let mut k = 0;
+                        while k < C {
+                            arr[i][j] += self.0[i][k] * rhs.0[k][j];
+                            k += 1;
+                        }
+                        j += 1;
+                    }
+                    i += 1;
+                }
+                Mat(arr)
+            }
+
+            pub const fn const_transpose(self) -> Mat<$t, C, R> {
+                let mut arr = [[0.0; R]; C];
+                let mut i = 0;
+                while i < R {
+                    let mut j = 0;
+                    while j < C {
+                        arr[j][i] = self.0[i][j];
+                        j += 1;
+                    }
+                    i += 1;
+                }
+                Mat(arr)
+            }
+        }
+
+        impl<const N: usize> Mat<$t, N, N> {
+            pub const IDENTITY: Self = {
+                let mut arr = [[0.0; N]; N];
+                let mut i = 0;
+                while i < N {
+                    arr[i][i] = 1.0;
+                    i += 1;
+                }
+                Self(arr)
+            };
+        }
+    };
+}
+
+impl_const!(f32);
+impl_const!(f64);
+
 impl<T: Float, const R: usize, const C: usize> Index<usize> for Mat<T, R, C> {
     type Output = [T; C];
 
@@ -55,6 +366,48 @@ impl<T: Float, const R: usize, const C: usize> IndexMut<usize> for Mat<T, R, C>
     }
 }
 
+impl<T: Float, const R: usize, const C: usize> Index<(usize, usize)> for Mat<T, R, C> {
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        &self.0[i][j]
+    }
+}
+
+impl<T: Float, const R: usize, const C: usize> IndexMut<(usize, usize)> for Mat<T, R, C> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Self::Output {
+        &mut self.0[i][j]
+    }
+}
+
+// Iterating over a matrix visits its entries in row-major order.
+impl<T: Float, const R: usize, const C: usize> IntoIterator for Mat<T, R, C> {
+    type Item = T;
+    type IntoIter = iter::Flatten<array::IntoIter<[T; C], R>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter().flatten()
+    }
+}
+
+impl<'a, T: Float, const R: usize, const C: usize> IntoIterator for &'a Mat<T, R, C> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Float, const R: usize, const C: usize> IntoIterator for &'a mut Mat<T, R, C> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 impl<T: Float, const R: usize, const C: usize> Neg for Mat<T, R, C> {
     type Output = Self;
 
@@ -72,6 +425,7 @@ impl<T: Float, const R: usize, const C: usize> Neg for Mat<T, R, C> {
 impl<T: Float, const R: usize, const C: usize> Add<Mat<T, R, C>> for Mat<T, R, C> {
     type Output = Self;
 
+    #[allow(clippy::needless_range_loop)]
     fn add(self, rhs: Self) -> Self::Output {
         let mut arr = [[T::zero(); C]; R];
         for i in 0..R {
@@ -96,6 +450,7 @@ impl<T: Float, const R: usize, const C: usize> AddAssign<Mat<T, R, C>> for Mat<T
 impl<T: Float, const R: usize, const C: usize> Sub<Mat<T, R, C>> for Mat<T, R, C> {
     type Output = Self;
 
+    #[allow(clippy::needless_range_loop)]
     fn sub(self, rhs: Self) -> Self::Output {
         let mut arr = [[T::zero(); C]; R];
         for i in 0..R {
@@ -125,6 +480,21 @@ where
     type Output = Mat<T, LR, RC>;
 
     fn mul(self, rhs: Mat<T, RR, RC>) -> Self::Output {
+        #[cfg(feature = "alloc")]
+        if gemm::use_blocked(LR, RC, LC) {
+            let mut out = Mat::zero();
+            gemm::gemm(
+                T::one(),
+                &self,
+                gemm::Transpose::No,
+                &rhs,
+                gemm::Transpose::No,
+                T::zero(),
+                &mut out,
+            );
+            return out;
+        }
+
         let mut arr = [[T::zero(); RC]; LR];
         for lrow in 0..LR {
             for rcol in 0..RC {
@@ -140,17 +510,10 @@ where
     }
 }
 
+// Goes through `Mul`, which reads all of `self` before writing the product.
 impl<T: Float, const N: usize> MulAssign for Mat<T, N, N> {
     fn mul_assign(&mut self, rhs: Self) {
-        for lrow in 0..N {
-            for rcol in 0..N {
-                let mut sum = T::zero();
-                for i in 0..N {
-                    sum = sum + self[lrow][i] * rhs[i][rcol];
-                }
-                self[lrow][rcol] = sum;
-            }
-        }
+        *self = *self * rhs;
     }
 }
 
@@ -172,6 +535,12 @@ impl<T: Float> From<Vec4<T>> for Mat<T, 4, 1> {
     }
 }
 
+impl<T: Float> From<Vec3A<T>> for Mat<T, 3, 1> {
+    fn from(vec: Vec3A<T>) -> Self {
+        Self([[vec.x], [vec.y], [vec.z]])
+    }
+}
+
 impl<T: Float, const R: usize> Mul<Vec2<T>> for Mat<T, R, 2> {
     type Output = Mat<T, R, 1>;
 
@@ -199,6 +568,7 @@ impl<T: Float, const R: usize> Mul<Vec4<T>> for Mat<T, R, 4> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mat;
 
     #[test]
     fn new_1() {
@@ -256,6 +626,140 @@ mod tests {
         );
     }
 
+    #[test]
+    fn as_slice() {
+        let mut mat = Mat::new(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        assert_eq!(mat.as_slice(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        mat.as_mut_slice()[4] = 0.0;
+        assert_eq!(mat.as_array(), &[[1.0, 2.0, 3.0], [4.0, 0.0, 6.0]]);
+    }
+
+    #[test]
+    fn from_slice() {
+        assert_eq!(
+            Mat::<f32, 3, 2>::from_slice(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
+            Mat::new(&[[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a 4x1 matrix, found 3x1")]
+    fn from_slice_wrong_length() {
+        Mat::<f32, 2, 2>::from_slice(&[1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn try_from_slice() {
+        assert_eq!(
+            Mat::<f32, 2, 2>::try_from_slice(&[1.0, 2.0, 3.0]),
+            Err(LinalgError::DimensionMismatch {
+                expected: (4, 1),
+                found: (3, 1)
+            })
+        );
+        assert_eq!(
+            Mat::<f32, 1, 2>::try_from_slice(&[1.0, 2.0]),
+            Ok(Mat::new(&[[1.0, 2.0]]))
+        );
+    }
+
+    #[test]
+    fn element_access() {
+        let mut m: Mat<f64, 2, 3> = mat![1, 2, 3; 4, 5, 6];
+        assert_eq!(m[(1, 2)], 6.0);
+        m[(0, 1)] = -2.0;
+        assert_eq!(m.get(0, 1), Some(&-2.0));
+        assert_eq!(m.get(2, 0), None);
+        assert_eq!(m.get(0, 3), None);
+        *m.get_mut(1, 0).unwrap() = 7.0;
+        assert_eq!(unsafe { *m.get_unchecked(1, 0) }, 7.0);
+        unsafe { *m.get_unchecked_mut(1, 1) = 8.0 };
+        assert_eq!(m, mat![1, -2, 3; 7, 8, 6]);
+    }
+
+    #[test]
+    fn swap_and_set() {
+        let mut m: Mat<f64, 3, 2> = mat![1, 2; 3, 4; 5, 6];
+        m.swap_rows(0, 2);
+        assert_eq!(m, mat![5, 6; 3, 4; 1, 2]);
+        m.swap_columns(0, 1);
+        assert_eq!(m, mat![6, 5; 4, 3; 2, 1]);
+        m.set_row(1, [0.0, 0.0]);
+        m.set_column(0, [9.0, 9.0, 9.0]);
+        assert_eq!(m, mat![9, 5; 9, 0; 9, 1]);
+    }
+
+    #[test]
+    fn iterators() {
+        let mut m: Mat<f64, 2, 3> = mat![1, 2, 3; 4, 5, 6];
+        assert_eq!(m.iter().sum::<f64>(), 21.0);
+        assert_eq!(m.rows().nth(1), Some(&[4.0, 5.0, 6.0]));
+        let columns: Vec<[f64; 2]> = m.columns().collect();
+        assert_eq!(columns, [[1.0, 4.0], [2.0, 5.0], [3.0, 6.0]]);
+        assert_eq!(m.columns().len(), 3);
+
+        for x in m.iter_mut() {
+            *x *= 2.0;
+        }
+        for x in &mut m {
+            *x += 1.0;
+        }
+        assert_eq!((&m).into_iter().copied().last(), Some(13.0));
+        assert_eq!(
+            m.into_iter().collect::<Vec<_>>(),
+            [3.0, 5.0, 7.0, 9.0, 11.0, 13.0]
+        );
+    }
+
+    #[test]
+    fn stack_and_split() {
+        // a KKT system [H A^T; A 0]
+        let h: Mat<f64, 2, 2> = mat![4, 1; 1, 3];
+        let a: Mat<f64, 1, 2> = mat![1, 1];
+        let top: Mat<f64, 2, 3> = h.hstack(&a.transpose());
+        let bottom: Mat<f64, 1, 3> = a.hstack(&Mat::<f64, 1, 1>::zero());
+        let kkt: Mat<f64, 3, 3> = top.vstack(&bottom);
+        assert_eq!(kkt, mat![4, 1, 1; 1, 3, 1; 1, 1, 0]);
+
+        assert_eq!(kkt.fixed_block::<2, 2>(0, 0), h);
+        assert_eq!(kkt.fixed_block::<1, 2>(2, 0), a);
+        assert_eq!(kkt.fixed_block::<2, 1>(1, 2), mat![1; 0]);
+
+        let mut m = Mat::<f64, 3, 3>::zero();
+        m.set_block(0, 0, &h);
+        m.set_block(2, 0, &a);
+        m.set_block(0, 2, &a.transpose());
+        assert_eq!(m, kkt);
+    }
+
+    #[test]
+    #[should_panic(expected = "block at (2, 1) is out of bounds")]
+    fn fixed_block_out_of_bounds() {
+        Mat::<f64, 3, 3>::zero().fixed_block::<2, 2>(2, 1);
+    }
+
+    #[test]
+    fn reshape() {
+        let m: Mat<f64, 2, 3> = mat![1, 2, 3; 4, 5, 6];
+        let r: Mat<f64, 3, 2> = m.reshape();
+        assert_eq!(r, mat![1, 2; 3, 4; 5, 6]);
+        assert_eq!(m.reshape::<1, 6>(), mat![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let m: Mat<f64, 2, 2> = mat![1, 2; 3, 4];
+        let rows: Mat<f64, 3, 2> = m.insert_row(1, [9.0, 9.0]);
+        assert_eq!(rows, mat![1, 2; 9, 9; 3, 4]);
+        assert_eq!(m.insert_row::<3>(2, [9.0, 9.0]), mat![1, 2; 3, 4; 9, 9]);
+        assert_eq!(rows.remove_row::<2>(1), m);
+
+        let cols: Mat<f64, 2, 3> = m.insert_column(0, [7.0, 8.0]);
+        assert_eq!(cols, mat![7, 1, 2; 8, 3, 4]);
+        assert_eq!(cols.remove_column::<2>(0), m);
+        assert_eq!(m.remove_column::<1>(1), mat![1; 3]);
+    }
+
     #[test]
     fn neg() {
         assert_eq!(
@@ -296,6 +800,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mul_assign() {
+        let mut a = Mat::new(&[[1.0, 2.0], [3.0, 4.0]]);
+        a *= Mat::new(&[[5.0, 6.0], [7.0, 8.0]]);
+        assert_eq!(a, Mat::new(&[[19.0, 22.0], [43.0, 50.0]]));
+    }
+
     #[test]
     fn mul_square_3x3() {
         assert_eq!(
@@ -360,4 +871,42 @@ mod tests {
             Mat::new(&[[20.0], [60.0], [100.0], [140.0]])
         );
     }
+
+    #[test]
+    fn transpose() {
+        let mat = Mat::new(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        assert_eq!(
+            mat.transpose(),
+            Mat::new(&[[1.0, 4.0], [2.0, 5.0], [3.0, 6.0]])
+        );
+    }
+
+    // Everything evaluated in these `const` items is usable at compile time.
+    #[test]
+    fn const_eval() {
+        const RGB_TO_YUV: Mat<f64, 3, 3> = Mat::new(&[
+            [0.299, 0.587, 0.114],
+            [-0.14713, -0.28886, 0.436],
+            [0.615, -0.51499, -0.10001],
+        ]);
+        const ROUND_TRIP: Mat<f64, 3, 3> = RGB_TO_YUV.const_mul(RGB_TO_YUV.const_transpose());
+        const SHIFTED: Mat<f32, 2, 2> = Mat::<f32, 2, 2>::IDENTITY
+            .const_scale(2.0)
+            .const_add(Mat::fill(1.0))
+            .const_sub(Mat::<f32, 2, 2>::ZERO);
+        const LITERAL: Mat<f64, 2, 2> = mat![1, 2; 3, 4];
+        const UP: Vec3<f32> = Vec3::new(0.0, 1.0, 0.0);
+        const ORIGIN: Vec2<f64> = Vec2::fill(0.0);
+        const W: f32 = Vec4::new(0.0, 0.0, 0.0, 1.0).as_array()[3];
+        const FIRST: f64 = LITERAL.as_array()[0][1];
+
+        assert_eq!(ROUND_TRIP, RGB_TO_YUV * RGB_TO_YUV.transpose());
+        assert_eq!(SHIFTED, Mat::new(&[[3.0, 1.0], [1.0, 3.0]]));
+        assert_eq!(Mat::<f64, 3, 3>::IDENTITY, Mat::identity());
+        assert_eq!(Mat::<f64, 2, 3>::ZERO, Mat::zero());
+        assert_eq!(UP.y, 1.0);
+        assert_eq!(ORIGIN, Vec2::zero());
+        assert_eq!(W, 1.0);
+        assert_eq!(FIRST, 2.0);
+    }
 }
@@ -0,0 +1,566 @@
+use super::backend::{cholesky_solve, Backend, Native};
+use super::error::LinalgError;
+use super::lstsq::qr_solve;
+use super::matrix::Mat;
+use alloc::vec;
+use alloc::vec::Vec;
+use num::Float;
+
+// Nonlinear least squares: minimizing the cost ||r(x)||^2 / 2 for M residuals
+// of N parameters, with Levenberg-Marquardt or Gauss-Newton.
+//
+// Each iteration linearizes r around x and solves for the step h in
+//
+//   min ||J h + r||^2 + mu ||D h||^2
+//
+// either as a stacked least squares problem through QR, or through the
+// Cholesky factorization of J^T J + mu D^2, which is cheaper but squares the
+// condition number. D^2 is the largest diagonal of J^T J seen so far
+// (Marquardt's scaling, which makes the method invariant to rescaling the
+// parameters), and the damping mu acts as a trust region: it shrinks after
+// steps whose cost reduction matches the linear model and grows after steps
+// that are rejected. Gauss-Newton takes mu = 0 and halves rejected steps
+// instead.
+//
+// Bounds are handled by projecting every trial point onto the box, after
+// holding parameters that sit on a bound the gradient pushes against out of
+// the step. Convergence on the gradient is measured with the projected
+// gradient, so that a minimum on a bound is recognized.
+
+// Anything that can produce residuals, and optionally their Jacobian. Plain
+// closures work too, and get central-difference Jacobians.
+pub trait Problem<T: Float, const M: usize, const N: usize> {
+    fn residuals(&self, x: &Mat<T, N, 1>) -> Mat<T, M, 1>;
+
+    // The M x N matrix of derivatives of residual i with respect to
+    // parameter j.
+    fn jacobian(&self, x: &Mat<T, N, 1>) -> Mat<T, M, N> {
+        finite_difference(|x| self.residuals(x), x)
+    }
+}
+
+impl<T: Float, const M: usize, const N: usize, F> Problem<T, M, N> for F
+where
+    F: Fn(&Mat<T, N, 1>) -> Mat<T, M, 1>,
+{
+    fn residuals(&self, x: &Mat<T, N, 1>) -> Mat<T, M, 1> {
+        self(x)
+    }
+}
+
+// The Jacobian of f at x by central differences, with steps of cbrt(eps)
+// times max(|x_j|, 1). f is evaluated on both sides of x, so it has to be
+// defined slightly outside any bounds.
+pub fn finite_difference<T: Float, const M: usize, const N: usize>(
+    f: impl Fn(&Mat<T, N, 1>) -> Mat<T, M, 1>,
+    x: &Mat<T, N, 1>,
+) -> Mat<T, M, N> {
+    let base = T::epsilon().cbrt();
+    let mut jacobian = Mat::zero();
+    for j in 0..N {
+        let step = base * x[j][0].abs().max(T::one());
+        let (mut ahead, mut behind) = (*x, *x);
+        ahead[j][0] = ahead[j][0] + step;
+        behind[j][0] = behind[j][0] - step;
+        // the step actually taken, after rounding
+        let width = ahead[j][0] - behind[j][0];
+        let diff = f(&ahead) - f(&behind);
+        for i in 0..M {
+            jacobian[i][j] = diff[i][0] / width;
+        }
+    }
+    jacobian
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Method {
+    #[default]
+    LevenbergMarquardt,
+    GaussNewton,
+}
+
+// How the linearized step is solved for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Solver {
+    #[default]
+    Qr,
+    Cholesky,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options<T: Float, const N: usize> {
+    method: Method,
+    solver: Solver,
+    max_iterations: usize,
+    cost_tolerance: T,
+    step_tolerance: T,
+    gradient_tolerance: T,
+    initial_damping: T,
+    lower: Mat<T, N, 1>,
+    upper: Mat<T, N, 1>,
+}
+
+impl<T: Float, const N: usize> Default for Options<T, N> {
+    fn default() -> Self {
+        let tol = T::from(1e-10).unwrap();
+        Self {
+            method: Method::LevenbergMarquardt,
+            solver: Solver::Qr,
+            max_iterations: 100,
+            cost_tolerance: tol,
+            step_tolerance: tol,
+            gradient_tolerance: tol,
+            initial_damping: T::from(1e-3).unwrap(),
+            lower: Mat::from_slice(&[T::neg_infinity(); N]),
+            upper: Mat::from_slice(&[T::infinity(); N]),
+        }
+    }
+}
+
+impl<T: Float, const N: usize> Options<T, N> {
+    pub fn method(self, method: Method) -> Self {
+        Self { method, ..self }
+    }
+
+    pub fn solver(self, solver: Solver) -> Self {
+        Self { solver, ..self }
+    }
+
+    // Iterations, accepted or not, before giving up.
+    pub fn max_iterations(self, max_iterations: usize) -> Self {
+        Self {
+            max_iterations,
+            ..self
+        }
+    }
+
+    // Stops when an accepted step reduces the cost by less than this
+    // fraction.
+    pub fn cost_tolerance(self, cost_tolerance: T) -> Self {
+        Self {
+            cost_tolerance,
+            ..self
+        }
+    }
+
+    // Stops when a step is shorter than this relative to ||x||.
+    pub fn step_tolerance(self, step_tolerance: T) -> Self {
+        Self {
+            step_tolerance,
+            ..self
+        }
+    }
+
+    // Stops when the largest entry of the projected gradient J^T r is below
+    // this.
+    pub fn gradient_tolerance(self, gradient_tolerance: T) -> Self {
+        Self {
+            gradient_tolerance,
+            ..self
+        }
+    }
+
+    // The starting damping, relative to the largest diagonal of J^T J.
+    pub fn initial_damping(self, initial_damping: T) -> Self {
+        Self {
+            initial_damping,
+            ..self
+        }
+    }
+
+    // Box constraints lower <= x <= upper; use infinities for unbounded
+    // parameters.
+    pub fn bounds(self, lower: Mat<T, N, 1>, upper: Mat<T, N, 1>) -> Self {
+        assert!(
+            lower.iter().zip(&upper).all(|(l, u)| l <= u),
+            "lower bounds must not exceed upper bounds"
+        );
+        Self {
+            lower,
+            upper,
+            ..self
+        }
+    }
+
+    fn project(&self, x: &Mat<T, N, 1>) -> Mat<T, N, 1> {
+        let mut out = *x;
+        for j in 0..N {
+            out[j][0] = x[j][0].max(self.lower[j][0]).min(self.upper[j][0]);
+        }
+        out
+    }
+}
+
+// Why the iteration stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    GradientTolerance,
+    CostTolerance,
+    StepTolerance,
+    MaxIterations,
+}
+
+// One line of the iteration report. `cost` is the cost after the iteration,
+// and `gradient` the largest entry of the projected gradient before it.
+// `damping` is zero for Gauss-Newton.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Iteration<T> {
+    pub cost: T,
+    pub gradient: T,
+    pub step: T,
+    pub damping: T,
+    pub accepted: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Report<T: Float, const M: usize, const N: usize> {
+    pub x: Mat<T, N, 1>,
+    pub residuals: Mat<T, M, 1>,
+    pub cost: T,
+    pub termination: Termination,
+    pub iterations: Vec<Iteration<T>>,
+}
+
+// Panics on the `NonFinite` or `Singular` from `try_solve`.
+pub fn solve<T: Float, const M: usize, const N: usize>(
+    problem: &impl Problem<T, M, N>,
+    x0: &Mat<T, N, 1>,
+    options: &Options<T, N>,
+) -> Report<T, M, N> {
+    try_solve(problem, x0, options).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// Minimizes ||r(x)||^2 / 2 starting from x0 (projected onto the bounds).
+// Reaching the iteration limit is not an error; the report says so and holds
+// the best point found. Fails with `NonFinite` when the residuals or Jacobian
+// at an accepted point are not finite, and with `Singular` when Gauss-Newton
+// meets a rank-deficient Jacobian.
+pub fn try_solve<T: Float, const M: usize, const N: usize>(
+    problem: &impl Problem<T, M, N>,
+    x0: &Mat<T, N, 1>,
+    options: &Options<T, N>,
+) -> Result<Report<T, M, N>, LinalgError> {
+    try_solve_with(&Native, problem, x0, options)
+}
+
+// `try_solve` with the factorizations behind each step from `backend`.
+pub fn try_solve_with<T: Float, const M: usize, const N: usize>(
+    backend: &impl Backend<T>,
+    problem: &impl Problem<T, M, N>,
+    x0: &Mat<T, N, 1>,
+    options: &Options<T, N>,
+) -> Result<Report<T, M, N>, LinalgError> {
+    let (zero, one) = (T::zero(), T::one());
+    let two = one + one;
+    let damped = options.method == Method::LevenbergMarquardt;
+
+    let mut x = options.project(x0);
+    let mut r = problem.residuals(&x);
+    let mut j = problem.jacobian(&x);
+    if !finite(r.as_slice()) || !finite(j.as_slice()) {
+        return Err(LinalgError::NonFinite);
+    }
+    let mut cost = half_norm2(&r);
+
+    let mut scale = [zero; N];
+    update_scale(&mut scale, &j);
+    let mut mu = if damped {
+        options.initial_damping * scale.iter().fold(zero, |acc, &d| acc.max(d))
+    } else {
+        zero
+    };
+    let mut nu = two;
+    // the fraction of the Gauss-Newton step to take
+    let mut fraction = one;
+
+    let mut iterations = Vec::new();
+    let termination = loop {
+        let g = j.transpose() * r;
+        let gradient = (options.project(&(x - g)) - x)
+            .iter()
+            .fold(zero, |acc, v| acc.max(v.abs()));
+        if gradient <= options.gradient_tolerance {
+            break Termination::GradientTolerance;
+        }
+        if iterations.len() == options.max_iterations {
+            break Termination::MaxIterations;
+        }
+
+        // parameters on a bound that the gradient pushes against stay put
+        let mut fixed = [false; N];
+        for k in 0..N {
+            fixed[k] = (x[k][0] <= options.lower[k][0] && g[k][0] > zero)
+                || (x[k][0] >= options.upper[k][0] && g[k][0] < zero);
+        }
+        let mut h = step(backend, &j, &r, &fixed, &scale, mu, options.solver)?;
+        if !damped {
+            for v in h.iter_mut() {
+                *v = fraction * *v;
+            }
+        }
+        let trial = options.project(&(x + h));
+        let h = trial - x;
+        let length = norm(h.as_slice());
+
+        let trial_r = problem.residuals(&trial);
+        let trial_cost = half_norm2(&trial_r);
+        // the reduction predicted by the linear model, cost - ||J h + r||^2 / 2
+        let predicted = cost - half_norm2(&(j * h + r));
+        let actual = cost - trial_cost;
+        let accepted = finite(trial_r.as_slice()) && actual > zero;
+
+        if accepted {
+            let rho = actual / predicted;
+            x = trial;
+            r = trial_r;
+            cost = trial_cost;
+            j = problem.jacobian(&x);
+            if !finite(j.as_slice()) {
+                return Err(LinalgError::NonFinite);
+            }
+            update_scale(&mut scale, &j);
+            if damped {
+                // Nielsen's update
+                let shrink = one - (two * rho - one).powi(3);
+                mu = mu * shrink.max(one / (two + one));
+                nu = two;
+            } else {
+                fraction = one;
+            }
+        } else if damped {
+            mu = mu * nu;
+            nu = nu * two;
+        } else {
+            fraction = fraction / two;
+        }
+
+        iterations.push(Iteration {
+            cost,
+            gradient,
+            step: length,
+            damping: mu,
+            accepted,
+        });
+
+        let size = norm(x.as_slice());
+        if length <= options.step_tolerance * (size + options.step_tolerance) {
+            break Termination::StepTolerance;
+        }
+        if accepted && actual <= options.cost_tolerance * (cost + actual) {
+            break Termination::CostTolerance;
+        }
+    };
+
+    Ok(Report {
+        x,
+        residuals: r,
+        cost,
+        termination,
+        iterations,
+    })
+}
+
+// Solves min ||J h + r||^2 + mu ||D h||^2 for h, with h = 0 in the `fixed`
+// parameters.
+fn step<T: Float, const M: usize, const N: usize>(
+    backend: &impl Backend<T>,
+    j: &Mat<T, M, N>,
+    r: &Mat<T, M, 1>,
+    fixed: &[bool; N],
+    scale: &[T; N],
+    mu: T,
+    solver: Solver,
+) -> Result<Mat<T, N, 1>, LinalgError> {
+    let (zero, one) = (T::zero(), T::one());
+    let mut j = *j;
+    for i in 0..M {
+        for k in 0..N {
+            if fixed[k] {
+                j[i][k] = zero;
+            }
+        }
+    }
+    // the rows of D that enter the system, with a unit row pinning each fixed
+    // parameter
+    let diagonal = |k: usize| {
+        if fixed[k] {
+            one
+        } else {
+            (mu * scale[k]).sqrt()
+        }
+    };
+
+    match solver {
+        Solver::Qr => {
+            // [J; sqrt(mu) D] h = [-r; 0]
+            let stacked = mu > zero || fixed.contains(&true);
+            let rows = if stacked { M + N } else { M };
+            if rows < N {
+                return Err(LinalgError::Singular);
+            }
+            let mut a = vec![zero; rows * N];
+            let mut b = vec![zero; rows];
+            a[..M * N].copy_from_slice(j.as_slice());
+            for i in 0..M {
+                b[i] = -r[i][0];
+            }
+            if stacked {
+                for k in 0..N {
+                    a[(M + k) * N + k] = diagonal(k);
+                }
+            }
+            let mut tau = vec![zero; N];
+            qr_solve(backend, rows, N, &mut a, &mut b, &mut tau)?;
+            Ok(Mat::from_slice(&b[..N]))
+        }
+        Solver::Cholesky => {
+            let mut normal = j.transpose() * j;
+            for k in 0..N {
+                normal[k][k] = normal[k][k] + diagonal(k).powi(2);
+            }
+            cholesky_solve(backend, &normal, &-(j.transpose() * *r)).map_err(|err| match err {
+                LinalgError::NotPositiveDefinite => LinalgError::Singular,
+                err => err,
+            })
+        }
+    }
+}
+
+// D^2 keeps the largest diagonal of J^T J seen for each parameter, with 1 for
+// parameters that have not affected the residuals yet.
+fn update_scale<T: Float, const M: usize, const N: usize>(scale: &mut [T; N], j: &Mat<T, M, N>) {
+    for k in 0..N {
+        let d = (0..M).fold(T::zero(), |acc, i| acc + j[i][k] * j[i][k]);
+        scale[k] = scale[k].max(d);
+        if scale[k] == T::zero() {
+            scale[k] = T::one();
+        }
+    }
+}
+
+fn half_norm2<T: Float, const M: usize>(r: &Mat<T, M, 1>) -> T {
+    let n = norm(r.as_slice());
+    n * n / (T::one() + T::one())
+}
+
+fn norm<T: Float>(v: &[T]) -> T {
+    v.iter().fold(T::zero(), |acc, &x| acc.hypot(x))
+}
+
+fn finite<T: Float>(v: &[T]) -> bool {
+    v.iter().all(|x| x.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_abs_diff_eq, mat};
+
+    struct Rosenbrock;
+
+    impl Problem<f64, 2, 2> for Rosenbrock {
+        fn residuals(&self, x: &Mat<f64, 2, 1>) -> Mat<f64, 2, 1> {
+            mat![10.0 * (x[1][0] - x[0][0] * x[0][0]); 1.0 - x[0][0]]
+        }
+
+        fn jacobian(&self, x: &Mat<f64, 2, 1>) -> Mat<f64, 2, 2> {
+            mat![-20.0 * x[0][0], 10; -1, 0]
+        }
+    }
+
+    // y = 2 exp(-0.5 t) sampled at t = 0, 0.5, ..., 4.5
+    fn decay(x: &Mat<f64, 2, 1>) -> Mat<f64, 10, 1> {
+        let mut r = Mat::zero();
+        for i in 0..10 {
+            let t = i as f64 / 2.0;
+            r[i][0] = x[0][0] * (x[1][0] * t).exp() - 2.0 * (-0.5 * t).exp();
+        }
+        r
+    }
+
+    #[test]
+    fn rosenbrock() {
+        let x0: Mat<f64, 2, 1> = mat![-1.2; 1];
+        for method in [Method::LevenbergMarquardt, Method::GaussNewton] {
+            for solver in [Solver::Qr, Solver::Cholesky] {
+                let options = Options::default().method(method).solver(solver);
+                let report = solve(&Rosenbrock, &x0, &options);
+                assert_abs_diff_eq!(report.x, mat![1; 1], epsilon = 1e-8);
+                assert!(report.cost < 1e-20, "{:?}", report);
+                assert_ne!(report.termination, Termination::MaxIterations);
+            }
+        }
+    }
+
+    #[test]
+    fn curve_fit() {
+        let x0: Mat<f64, 2, 1> = mat![1; 0];
+        for solver in [Solver::Qr, Solver::Cholesky] {
+            let report = solve(&decay, &x0, &Options::default().solver(solver));
+            assert_abs_diff_eq!(report.x, mat![2; -0.5], epsilon = 1e-8);
+        }
+    }
+
+    #[test]
+    fn reports() {
+        let x0: Mat<f64, 2, 1> = mat![-1.2; 1];
+        let report = solve(&Rosenbrock, &x0, &Options::default());
+        let accepted: Vec<f64> = report
+            .iterations
+            .iter()
+            .filter(|it| it.accepted)
+            .map(|it| it.cost)
+            .collect();
+        assert!(accepted.windows(2).all(|w| w[1] < w[0]));
+        assert_eq!(report.iterations.last().unwrap().cost, report.cost);
+        assert_eq!(report.residuals, Rosenbrock.residuals(&report.x));
+
+        let report = solve(&Rosenbrock, &x0, &Options::default().max_iterations(2));
+        assert_eq!(report.termination, Termination::MaxIterations);
+        assert_eq!(report.iterations.len(), 2);
+    }
+
+    #[test]
+    fn bounded() {
+        // the unconstrained minimum (1, 1) is cut off, leaving (0.5, 0.25)
+        let options = Options::default().bounds(mat![-2; -2], mat![0.5; 2]);
+        let report = solve(&Rosenbrock, &mat![-1.2; 1], &options);
+        assert_abs_diff_eq!(report.x, mat![0.5; 0.25], epsilon = 1e-8);
+        assert_ne!(report.termination, Termination::MaxIterations);
+
+        // a start outside the box is projected onto it
+        let report = solve(&Rosenbrock, &mat![3; 1], &options);
+        assert!(report.x[0][0] <= 0.5);
+    }
+
+    #[test]
+    fn derivatives() {
+        let x: Mat<f64, 2, 1> = mat![0.7; -1.3];
+        let numeric = finite_difference(|x| Rosenbrock.residuals(x), &x);
+        assert_abs_diff_eq!(numeric, Rosenbrock.jacobian(&x), epsilon = 1e-9);
+
+        let f = |x: &Mat<f64, 2, 1>| -> Mat<f64, 1, 1> { mat![x[0][0].sin() * x[1][0]] };
+        let numeric = f.jacobian(&x);
+        let exact: Mat<f64, 1, 2> = mat![x[0][0].cos() * x[1][0], x[0][0].sin()];
+        assert_abs_diff_eq!(numeric, exact, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn failures() {
+        let f = |_: &Mat<f64, 1, 1>| -> Mat<f64, 1, 1> { mat![f64::NAN] };
+        assert_eq!(
+            try_solve(&f, &mat![0], &Options::default()).unwrap_err(),
+            LinalgError::NonFinite
+        );
+
+        // one residual cannot pin down two parameters without damping
+        let f = |x: &Mat<f64, 2, 1>| -> Mat<f64, 1, 1> { mat![x[0][0] + x[1][0] - 1.0] };
+        let options = Options::default().method(Method::GaussNewton);
+        assert_eq!(
+            try_solve(&f, &mat![0; 0], &options).unwrap_err(),
+            LinalgError::Singular
+        );
+        let report = solve(&f, &mat![0; 0], &Options::default());
+        assert_abs_diff_eq!(report.x[0][0] + report.x[1][0], 1.0, epsilon = 1e-8);
+    }
+}
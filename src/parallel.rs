@@ -0,0 +1,642 @@
+use super::backend::{Backend, Diag, Native, Side, Uplo};
+use super::dmatrix::{Cholesky, DMat, Lu};
+use super::error::LinalgError;
+use super::gemm::{self, Block, Transpose, View, KC, MC, NC};
+use super::matrix::Mat;
+use super::vector::{vec3::Vec3, vec4::Vec4};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem::size_of;
+use num::Float;
+use rayon::prelude::*;
+
+// Parallel versions of the dense operations. Work is split so that each
+// output entry is computed by a single task, in the same order as the
+// sequential code, so results are identical to the sequential ones and do not
+// depend on the number of threads.
+//
+// `DMat` works on its storage in place. Its blocked LU and Cholesky
+// factorizations factor each panel of NB columns sequentially and update the
+// rest of the matrix in parallel, which is where almost all of the work is.
+
+// Columns per panel of the blocked factorizations.
+const NB: usize = 64;
+
+// `gemm::gemm`, with the row blocks of C computed in parallel.
+//
+// Panics on the `DimensionMismatch` from `try_par_gemm`.
+#[allow(clippy::too_many_arguments)]
+pub fn par_gemm<
+    T: Float + Send + Sync,
+    const M: usize,
+    const N: usize,
+    const AR: usize,
+    const AC: usize,
+    const BR: usize,
+    const BC: usize,
+>(
+    alpha: T,
+    a: &Mat<T, AR, AC>,
+    ta: Transpose,
+    b: &Mat<T, BR, BC>,
+    tb: Transpose,
+    beta: T,
+    c: &mut Mat<T, M, N>,
+) {
+    let (a, b, k) = gemm::views::<T, M, N, AR, AC, BR, BC>(a, ta, b, tb);
+    par_gemm_strided(M, N, k, alpha, a, b, beta, c.as_mut_slice(), N);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_par_gemm<
+    T: Float + Send + Sync,
+    const M: usize,
+    const N: usize,
+    const AR: usize,
+    const AC: usize,
+    const BR: usize,
+    const BC: usize,
+>(
+    alpha: T,
+    a: &Mat<T, AR, AC>,
+    ta: Transpose,
+    b: &Mat<T, BR, BC>,
+    tb: Transpose,
+    beta: T,
+    c: &mut Mat<T, M, N>,
+) -> Result<(), LinalgError> {
+    let (a, b, k) = gemm::try_views::<T, M, N, AR, AC, BR, BC>(a, ta, b, tb)?;
+    par_gemm_strided(M, N, k, alpha, a, b, beta, c.as_mut_slice(), N);
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn par_gemm_strided<T: Float + Send + Sync>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: T,
+    a: View<T>,
+    b: View<T>,
+    beta: T,
+    c: &mut [T],
+    ldc: usize,
+) {
+    gemm::scale(beta, m, n, c, ldc);
+    if alpha == T::zero() || k == 0 || m == 0 {
+        return;
+    }
+
+    // C may end right after its last row, as for a trailing submatrix
+    let c = &mut c[..(m - 1) * ldc + n];
+    if size_of::<T>() <= 4 {
+        par_blocked::<T, 4, 8>(m, n, k, alpha, a, b, c, ldc);
+    } else {
+        par_blocked::<T, 4, 4>(m, n, k, alpha, a, b, c, ldc);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn par_blocked<T: Float + Send + Sync, const MR: usize, const NR: usize>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: T,
+    a: View<T>,
+    b: View<T>,
+    c: &mut [T],
+    ldc: usize,
+) {
+    let mut b_pack = vec![T::zero(); KC.min(k) * NC.min(n).next_multiple_of(NR)];
+
+    for jc in (0..n).step_by(NC) {
+        let nc = NC.min(n - jc);
+        for pc in (0..k).step_by(KC) {
+            let kc = KC.min(k - pc);
+            gemm::pack::<T, NR>(&mut b_pack, kc, nc, |kk, j| b.at(pc + kk, jc + j));
+            let b_pack = &b_pack[..];
+            c.par_chunks_mut(MC * ldc).enumerate().for_each_init(
+                || vec![T::zero(); gemm::a_pack_len::<MR>(m, k)],
+                |a_pack, (i, c)| {
+                    let ic = i * MC;
+                    let block = Block {
+                        rows: (ic, MC.min(m - ic)),
+                        depth: (pc, kc),
+                        cols: (jc, nc),
+                    };
+                    gemm::row_block::<T, MR, NR>(a_pack, b_pack, a, block, alpha, c, ldc);
+                },
+            );
+        }
+    }
+}
+
+impl<T: Float + Send + Sync, const R: usize, const C: usize> Mat<T, R, C> {
+    pub fn par_mul<const K: usize>(&self, rhs: &Mat<T, C, K>) -> Mat<T, R, K> {
+        let mut out = Mat::zero();
+        par_gemm(
+            T::one(),
+            self,
+            Transpose::No,
+            rhs,
+            Transpose::No,
+            T::zero(),
+            &mut out,
+        );
+        out
+    }
+
+    pub fn par_map(&self, f: impl Fn(T) -> T + Sync) -> Self {
+        let mut out = *self;
+        out.as_mut_slice().par_iter_mut().for_each(|x| *x = f(*x));
+        out
+    }
+
+    // Reduces each row to a single value, e.g. `|row| row.iter().sum()`.
+    pub fn par_reduce_rows(&self, f: impl Fn(&[T; C]) -> T + Sync) -> Mat<T, R, 1> {
+        let mut out = Mat::zero();
+        out.as_mut_slice()
+            .par_iter_mut()
+            .zip(self.as_array().par_iter())
+            .for_each(|(x, row)| *x = f(row));
+        out
+    }
+}
+
+// Multiplies `lhs` by each matrix in `rhs`, writing the products to `out`.
+//
+// Panics on the `DimensionMismatch` from `try_par_batch_mul`.
+pub fn par_batch_mul<T: Float + Send + Sync, const R: usize, const C: usize, const K: usize>(
+    lhs: &Mat<T, R, C>,
+    rhs: &[Mat<T, C, K>],
+    out: &mut [Mat<T, R, K>],
+) {
+    try_par_batch_mul(lhs, rhs, out).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// `out` must have the same length as `rhs`; the lengths are reported as n x 1.
+pub fn try_par_batch_mul<T: Float + Send + Sync, const R: usize, const C: usize, const K: usize>(
+    lhs: &Mat<T, R, C>,
+    rhs: &[Mat<T, C, K>],
+    out: &mut [Mat<T, R, K>],
+) -> Result<(), LinalgError> {
+    if rhs.len() != out.len() {
+        return Err(LinalgError::DimensionMismatch {
+            expected: (rhs.len(), 1),
+            found: (out.len(), 1),
+        });
+    }
+    out.par_iter_mut()
+        .zip(rhs.par_iter())
+        .for_each(|(out, rhs)| {
+            gemm::mul_into(R, K, C, lhs.as_slice(), rhs.as_slice(), out.as_mut_slice())
+        });
+    Ok(())
+}
+
+impl<T: Float + Send + Sync> DMat<T> {
+    // Panics on the `DimensionMismatch` from `try_par_mul`.
+    pub fn par_mul(&self, rhs: &Self) -> Self {
+        self.try_par_mul(rhs)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // The product through the blocked kernel, with the row blocks of the
+    // result computed in parallel.
+    pub fn try_par_mul(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        if self.ncols() != rhs.nrows() {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (self.ncols(), rhs.ncols()),
+                found: rhs.shape(),
+            });
+        }
+        let (m, n, k) = (self.nrows(), rhs.ncols(), self.ncols());
+        let a = View {
+            data: self.as_slice(),
+            stride: k,
+            trans: Transpose::No,
+        };
+        let b = View {
+            data: rhs.as_slice(),
+            stride: n,
+            trans: Transpose::No,
+        };
+        let mut out = Self::zero(m, n);
+        par_gemm_strided(m, n, k, T::one(), a, b, T::zero(), out.as_mut_slice(), n);
+        Ok(out)
+    }
+
+    pub fn par_map(&self, f: impl Fn(T) -> T + Sync) -> Self {
+        let mut out = self.clone();
+        out.as_mut_slice().par_iter_mut().for_each(|x| *x = f(*x));
+        out
+    }
+
+    // Panics on the `Singular` or `DimensionMismatch` from `try_par_lu`.
+    pub fn par_lu(&self) -> Lu<T> {
+        self.try_par_lu().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // `try_lu`, blocked: each panel is factored with partial pivoting, its
+    // row swaps are applied across the matrix, and the trailing submatrix is
+    // updated with `par_gemm_strided`. The pivots are chosen within the panel
+    // as in LAPACK's `getrf`.
+    pub fn try_par_lu(&self) -> Result<Lu<T>, LinalgError> {
+        let n = square(self)?;
+        let mut lu = self.clone();
+        let mut ipiv = vec![0; n];
+        let mut singular = false;
+        let a = lu.as_mut_slice();
+        for k0 in (0..n).step_by(NB) {
+            let kb = NB.min(n - k0);
+            let end = k0 + kb;
+            singular |= Native
+                .getrf(n - k0, kb, &mut a[k0 * n + k0..], n, &mut ipiv[k0..end])
+                .is_err();
+            for (i, p) in (k0..end).zip(&mut ipiv[k0..end]) {
+                *p += k0;
+                let p = *p;
+                if p != i {
+                    for j in (0..k0).chain(end..n) {
+                        a.swap(i * n + j, p * n + j);
+                    }
+                }
+            }
+            if end == n {
+                break;
+            }
+
+            // U12 = L11^-1 A12
+            let l11: Vec<T> = (k0..end)
+                .flat_map(|i| a[i * n + k0..i * n + end].to_vec())
+                .collect();
+            Native.trsm(
+                Side::Left,
+                Uplo::Lower,
+                Transpose::No,
+                Diag::Unit,
+                kb,
+                n - end,
+                T::one(),
+                &l11,
+                kb,
+                &mut a[k0 * n + end..],
+                n,
+            );
+
+            // A22 -= L21 U12
+            let (top, bottom) = a.split_at_mut(end * n);
+            let l21 = panel(bottom, n, k0, kb);
+            let l21 = View {
+                data: &l21,
+                stride: kb,
+                trans: Transpose::No,
+            };
+            let u12 = View {
+                data: &top[k0 * n + end..],
+                stride: n,
+                trans: Transpose::No,
+            };
+            let rest = n - end;
+            par_gemm_strided(
+                rest,
+                rest,
+                kb,
+                -T::one(),
+                l21,
+                u12,
+                T::one(),
+                &mut bottom[end..],
+                n,
+            );
+        }
+        if singular {
+            return Err(LinalgError::Singular);
+        }
+        Ok(Lu {
+            lu,
+            ipiv,
+            backend: Native,
+        })
+    }
+
+    // Panics on the `NotPositiveDefinite` or `DimensionMismatch` from
+    // `try_par_cholesky`.
+    pub fn par_cholesky(&self) -> Cholesky<T> {
+        self.try_par_cholesky()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // `try_cholesky`, blocked: each diagonal block is factored, the panel
+    // below it is solved a block of rows per task, and the trailing
+    // submatrix is updated with `par_gemm_strided`. Only the lower triangle
+    // is read.
+    pub fn try_par_cholesky(&self) -> Result<Cholesky<T>, LinalgError> {
+        let n = square(self)?;
+        let mut l = self.clone();
+        let a = l.as_mut_slice();
+        for k0 in (0..n).step_by(NB) {
+            let kb = NB.min(n - k0);
+            let end = k0 + kb;
+            Native.potrf(Uplo::Lower, kb, &mut a[k0 * n + k0..], n)?;
+            if end == n {
+                break;
+            }
+
+            // L21 = A21 L11^-T
+            let (top, bottom) = a.split_at_mut(end * n);
+            let l11 = &top[k0 * n + k0..];
+            bottom.par_chunks_mut(MC * n).for_each(|rows| {
+                Native.trsm(
+                    Side::Right,
+                    Uplo::Lower,
+                    Transpose::Yes,
+                    Diag::NonUnit,
+                    rows.len() / n,
+                    kb,
+                    T::one(),
+                    l11,
+                    n,
+                    &mut rows[k0..],
+                    n,
+                );
+            });
+
+            // A22 -= L21 L21^T, of which only the lower triangle is used
+            let l21 = panel(bottom, n, k0, kb);
+            let view = |trans| View {
+                data: &l21,
+                stride: kb,
+                trans,
+            };
+            let rest = n - end;
+            par_gemm_strided(
+                rest,
+                rest,
+                kb,
+                -T::one(),
+                view(Transpose::No),
+                view(Transpose::Yes),
+                T::one(),
+                &mut bottom[end..],
+                n,
+            );
+        }
+        for i in 0..n {
+            for x in &mut l.row_mut(i)[i + 1..] {
+                *x = T::zero();
+            }
+        }
+        Ok(Cholesky { l, backend: Native })
+    }
+}
+
+fn square<T: Float>(a: &DMat<T>) -> Result<usize, LinalgError> {
+    let (rows, cols) = a.shape();
+    if rows != cols {
+        return Err(LinalgError::DimensionMismatch {
+            expected: (rows, rows),
+            found: (rows, cols),
+        });
+    }
+    Ok(rows)
+}
+
+// Copies columns `k0..k0 + kb` of the rows of the row-major `rows`, so that
+// the trailing update can read them while writing the same rows.
+fn panel<T: Float>(rows: &[T], n: usize, k0: usize, kb: usize) -> Vec<T> {
+    rows.chunks(n)
+        .flat_map(|row| row[k0..k0 + kb].iter().copied())
+        .collect()
+}
+
+pub fn par_transform<T: Float + Send + Sync>(m: &Mat<T, 4, 4>, vecs: &mut [Vec4<T>]) {
+    vecs.par_iter_mut().for_each(|v| {
+        let [[x], [y], [z], [w]] = *(*m * *v).as_array();
+        *v = Vec4::new(x, y, z, w);
+    });
+}
+
+// Transforms points (w = 1) by an affine transform; the last row of `m` is
+// ignored.
+pub fn par_transform_points<T: Float + Send + Sync>(m: &Mat<T, 4, 4>, points: &mut [Vec3<T>]) {
+    par_transform3(m, points, T::one());
+}
+
+// Transforms direction vectors (w = 0), so translation is ignored.
+pub fn par_transform_vectors<T: Float + Send + Sync>(m: &Mat<T, 4, 4>, vecs: &mut [Vec3<T>]) {
+    par_transform3(m, vecs, T::zero());
+}
+
+fn par_transform3<T: Float + Send + Sync>(m: &Mat<T, 4, 4>, vecs: &mut [Vec3<T>], w: T) {
+    vecs.par_iter_mut().for_each(|v| {
+        let [[x], [y], [z], _] = *(*m * Vec4::new(v.x, v.y, v.z, w)).as_array();
+        *v = Vec3::new(x, y, z);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use rayon::ThreadPoolBuilder;
+
+    // Runs `f` on pools of different sizes and checks that every run gives
+    // the same result.
+    fn deterministic<R: PartialEq + core::fmt::Debug + Send>(f: impl Fn() -> R + Sync) -> R {
+        let runs: Vec<R> = [1, 3, 8]
+            .iter()
+            .map(|&n| {
+                let pool = ThreadPoolBuilder::new().num_threads(n).build().unwrap();
+                pool.install(&f)
+            })
+            .collect();
+        for run in &runs[1..] {
+            assert_eq!(run, &runs[0]);
+        }
+        runs.into_iter().next().unwrap()
+    }
+
+    fn values(seed: u64, n: usize) -> Vec<f64> {
+        let mut state = seed;
+        (0..n)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gemm_matches_sequential() {
+        // several row blocks, and a depth spanning more than one block
+        let (m, n, k) = (300, 20, 270);
+        let a_data = values(1, m * k);
+        let b_data = values(2, n * k);
+        let c_data = values(3, m * n);
+        let a = View {
+            data: &a_data,
+            stride: k,
+            trans: Transpose::No,
+        };
+        let b = View {
+            data: &b_data,
+            stride: k,
+            trans: Transpose::Yes,
+        };
+
+        let par = deterministic(|| {
+            let mut c = c_data.clone();
+            par_gemm_strided(m, n, k, 2.0, a, b, 0.5, &mut c, n);
+            c
+        });
+        let mut seq = c_data.clone();
+        gemm::gemm_strided(m, n, k, 2.0, a, b, 0.5, &mut seq, n);
+        assert_eq!(par, seq);
+
+        let a: Mat<f32, 5, 3> =
+            Mat::from_slice(&values(4, 15).iter().map(|&x| x as f32).collect::<Vec<_>>());
+        let b: Mat<f32, 3, 4> =
+            Mat::from_slice(&values(5, 12).iter().map(|&x| x as f32).collect::<Vec<_>>());
+        assert_eq!(deterministic(|| a.par_mul(&b)), a * b);
+    }
+
+    #[test]
+    fn map_and_reduce() {
+        let m: Mat<f64, 64, 32> = Mat::from_slice(&values(6, 64 * 32));
+        let mapped = deterministic(|| m.par_map(|x| x * x + 1.0));
+        assert_eq!(mapped[10][7], m[10][7] * m[10][7] + 1.0);
+
+        let sums = deterministic(|| m.par_reduce_rows(|row| row.iter().copied().sum()));
+        for i in 0..64 {
+            assert_eq!(sums[i][0], m[i].iter().copied().sum::<f64>());
+        }
+    }
+
+    #[test]
+    fn batched() {
+        let m = Mat::new(&[
+            [0.0, -1.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0, 2.0],
+            [0.0, 0.0, 1.0, 3.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let points: Vec<Vec3<f32>> = (0..1000)
+            .map(|i| Vec3::new(i as f32, 1.0, -(i as f32)))
+            .collect();
+
+        let moved = deterministic(|| {
+            let mut p = points.clone();
+            par_transform_points(&m, &mut p);
+            p
+        });
+        assert_eq!(moved[5], Vec3::new(0.0, 7.0, -2.0));
+
+        let mut dirs = points.clone();
+        par_transform_vectors(&m, &mut dirs);
+        assert_eq!(dirs[5], Vec3::new(-1.0, 5.0, -5.0));
+
+        let mut vecs = [Vec4::new(1.0, 0.0, 0.0, 1.0); 3];
+        par_transform(&m, &mut vecs);
+        assert_eq!(vecs[2], Vec4::new(1.0, 3.0, 3.0, 1.0));
+
+        let rhs: Vec<Mat<f32, 4, 1>> = points
+            .iter()
+            .map(|&p| Mat::from(Vec4::new(p.x, p.y, p.z, 1.0)))
+            .collect();
+        let mut out = vec![Mat::zero(); rhs.len()];
+        par_batch_mul(&m, &rhs, &mut out);
+        assert_eq!(out[5], Mat::from(Vec4::new(0.0, 7.0, -2.0, 1.0)));
+        assert_eq!(
+            try_par_batch_mul(&m, &rhs, &mut out[1..]),
+            Err(LinalgError::DimensionMismatch {
+                expected: (1000, 1),
+                found: (999, 1)
+            })
+        );
+    }
+
+    fn random(seed: u64, rows: usize, cols: usize) -> DMat<f64> {
+        DMat::from_vec(rows, cols, values(seed, rows * cols))
+    }
+
+    fn assert_close(a: &DMat<f64>, b: &DMat<f64>, tol: f64) {
+        assert_eq!(a.shape(), b.shape());
+        for (x, y) in a.as_slice().iter().zip(b.as_slice()) {
+            assert!((x - y).abs() <= tol, "{} != {}", x, y);
+        }
+    }
+
+    #[test]
+    fn dmat_mul() {
+        let (a, b) = (random(7, 150, 140), random(8, 140, 130));
+        let par = deterministic(|| a.par_mul(&b));
+        // the sequential product takes the blocked kernel at this size
+        assert_eq!(par, &a * &b);
+        assert_eq!(
+            a.try_par_mul(&a).unwrap_err(),
+            LinalgError::DimensionMismatch {
+                expected: (140, 140),
+                found: (150, 140)
+            }
+        );
+        assert_eq!(
+            deterministic(|| a.par_map(|x| 2.0 * x))[(3, 4)],
+            2.0 * a[(3, 4)]
+        );
+    }
+
+    #[test]
+    fn blocked_lu() {
+        // two full panels and a partial one
+        let n = 150;
+        let a = random(9, n, n);
+        let (l, u, pivots) = deterministic(|| {
+            let lu = a.par_lu();
+            (lu.l(), lu.u(), lu.pivots().to_vec())
+        });
+        let mut pa = a.clone();
+        for (i, &p) in pivots.iter().enumerate() {
+            pa.swap_rows(i, p);
+        }
+        assert_close(&(&l * &u), &pa, 1e-12);
+        assert!(l.as_slice().iter().all(|x| x.abs() <= 1.0));
+
+        let b = random(10, n, 2);
+        assert_close(&a.par_lu().solve(&b), &a.solve(&b), 1e-9);
+
+        let mut singular = a.clone();
+        for i in 0..n {
+            singular[(i, 100)] = 0.0;
+        }
+        assert_eq!(singular.try_par_lu().unwrap_err(), LinalgError::Singular);
+    }
+
+    #[test]
+    fn blocked_cholesky() {
+        let n = 150;
+        let r = random(11, n, n);
+        let mut a = &r.transpose() * &r;
+        for i in 0..n {
+            a[(i, i)] += n as f64;
+        }
+        let l = deterministic(|| a.par_cholesky().l().clone());
+        assert_close(&(&l * &l.transpose()), &a, 1e-10);
+        assert_close(&l, a.cholesky().l(), 1e-12);
+
+        a[(120, 120)] = -1.0;
+        assert_eq!(
+            a.try_par_cholesky().unwrap_err(),
+            LinalgError::NotPositiveDefinite
+        );
+        assert_eq!(
+            DMat::<f64>::zero(2, 3).try_par_lu().unwrap_err(),
+            LinalgError::DimensionMismatch {
+                expected: (2, 2),
+                found: (2, 3)
+            }
+        );
+    }
+}
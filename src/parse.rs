@@ -0,0 +1,590 @@
+use super::dmatrix::DMat;
+use super::matrix::Mat;
+use super::vector::{vec2::Vec2, vec3::Vec3, vec3a::Vec3A, vec4::Vec4};
+use alloc::vec::Vec;
+use core::{fmt, str::FromStr};
+use num::Float;
+
+// Accepted matrix syntaxes:
+//
+//   MATLAB              [1 2; 3 4] or [1, 2; 3, 4]
+//   NumPy / JSON        [[1, 2], [3, 4]], optionally wrapped in np.array(...)
+//   whitespace rows     one row per line, entries separated by whitespace or
+//                       commas
+//
+// The box drawn by `Display` is skipped, so its output parses back as
+// whitespace rows. `DMat` takes its shape from the literal, with the first row
+// setting the number of columns. Vectors accept (1, 2, 3), [1, 2, 3] or
+// 1 2 3.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    BadNumber,
+    Syntax,
+    Rows { expected: usize, found: usize },
+    Columns { expected: usize, found: usize },
+    Length { expected: usize, found: usize },
+}
+
+// Lines and columns are 1-based and count characters, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    pub fn is_shape_mismatch(&self) -> bool {
+        matches!(
+            self.kind,
+            ParseErrorKind::Rows { .. }
+                | ParseErrorKind::Columns { .. }
+                | ParseErrorKind::Length { .. }
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match self.kind {
+            ParseErrorKind::BadNumber => write!(f, "invalid number"),
+            ParseErrorKind::Syntax => write!(f, "unexpected token"),
+            ParseErrorKind::Rows { expected, found } => {
+                write!(f, "expected {} rows, found {}", expected, found)
+            }
+            ParseErrorKind::Columns { expected, found } => {
+                write!(f, "expected {} columns, found {}", expected, found)
+            }
+            ParseErrorKind::Length { expected, found } => {
+                write!(f, "expected {} components, found {}", expected, found)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pos {
+    line: usize,
+    column: usize,
+}
+
+impl Pos {
+    fn error(self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Open(char),
+    Close(char),
+    Comma,
+    Semicolon,
+    Newline,
+    End,
+}
+
+fn is_border(c: char) -> bool {
+    matches!(c, '┌' | '┐' | '└' | '┘' | '│')
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || is_border(c) || "[](),;".contains(c)
+}
+
+fn tokenize(s: &str) -> Vec<(Token<'_>, Pos)> {
+    let mut tokens = Vec::new();
+    let mut pos = Pos { line: 1, column: 1 };
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let here = pos;
+        pos.column += 1;
+        let token = match c {
+            '\n' => {
+                pos.line += 1;
+                pos.column = 1;
+                Token::Newline
+            }
+            '[' | '(' => Token::Open(c),
+            ']' | ')' => Token::Close(c),
+            ',' => Token::Comma,
+            ';' => Token::Semicolon,
+            _ if c.is_whitespace() || is_border(c) => continue,
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if is_delimiter(c) {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    pos.column += 1;
+                    chars.next();
+                }
+                Token::Word(&s[start..end])
+            }
+        };
+        tokens.push((token, here));
+    }
+    tokens.push((Token::End, pos));
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token<'a>, Pos)>,
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            tokens: tokenize(s),
+            index: 0,
+        }
+    }
+
+    fn peek(&self) -> (Token<'a>, Pos) {
+        self.tokens[self.index]
+    }
+
+    fn next(&mut self) -> (Token<'a>, Pos) {
+        let token = self.peek();
+        if token.0 != Token::End {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek().0 == Token::Newline {
+            self.index += 1;
+        }
+    }
+
+    // The first token after any newlines, without consuming anything.
+    fn peek_past_newlines(&self, from: usize) -> Token<'a> {
+        self.tokens[from..]
+            .iter()
+            .map(|t| t.0)
+            .find(|&t| t != Token::Newline)
+            .unwrap_or(Token::End)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<Pos, ParseError> {
+        self.skip_newlines();
+        let (token, pos) = self.next();
+        if token == expected {
+            Ok(pos)
+        } else {
+            Err(pos.error(ParseErrorKind::Syntax))
+        }
+    }
+
+    fn number<T: Float + FromStr>(&mut self) -> Result<T, ParseError> {
+        match self.next() {
+            (Token::Word(w), pos) => w.parse().map_err(|_| pos.error(ParseErrorKind::BadNumber)),
+            (_, pos) => Err(pos.error(ParseErrorKind::Syntax)),
+        }
+    }
+
+    // Entries separated by commas and/or whitespace, with rows ending at a
+    // semicolon or newline. Stops at a closing bracket or the end of input.
+    fn rows<T: Float + FromStr>(&mut self) -> Result<Vec<(Vec<T>, Pos)>, ParseError> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut start = self.peek().1;
+        loop {
+            let (token, pos) = self.peek();
+            match token {
+                Token::Word(_) => {
+                    if row.is_empty() {
+                        start = pos;
+                    }
+                    row.push(self.number()?);
+                }
+                Token::Comma => {
+                    self.next();
+                }
+                Token::Semicolon | Token::Newline | Token::Close(_) | Token::End => {
+                    if !row.is_empty() {
+                        rows.push((core::mem::take(&mut row), start));
+                    }
+                    if matches!(token, Token::Close(_) | Token::End) {
+                        return Ok(rows);
+                    }
+                    self.next();
+                }
+                Token::Open(_) => return Err(pos.error(ParseErrorKind::Syntax)),
+            }
+        }
+    }
+
+    // [[1, 2], [3, 4]]
+    fn nested<T: Float + FromStr>(&mut self) -> Result<Vec<(Vec<T>, Pos)>, ParseError> {
+        self.expect(Token::Open('['))?;
+        let mut rows = Vec::new();
+        loop {
+            self.skip_newlines();
+            if let (Token::Close(']'), _) = self.peek() {
+                self.next();
+                return Ok(rows);
+            }
+            let start = self.expect(Token::Open('['))?;
+            let mut row = Vec::new();
+            loop {
+                self.skip_newlines();
+                match self.peek().0 {
+                    Token::Close(']') => break,
+                    _ => row.push(self.number()?),
+                }
+                self.skip_newlines();
+                match self.peek() {
+                    (Token::Comma, _) => {
+                        self.next();
+                    }
+                    (Token::Close(']'), _) => break,
+                    (_, pos) => return Err(pos.error(ParseErrorKind::Syntax)),
+                }
+            }
+            self.next();
+            rows.push((row, start));
+
+            self.skip_newlines();
+            match self.peek() {
+                (Token::Comma, _) => {
+                    self.next();
+                }
+                (Token::Close(']'), _) => {}
+                (_, pos) => return Err(pos.error(ParseErrorKind::Syntax)),
+            }
+        }
+    }
+
+    fn matrix<T: Float + FromStr>(&mut self) -> Result<Vec<(Vec<T>, Pos)>, ParseError> {
+        self.skip_newlines();
+        let wrapped = matches!(self.peek().0, Token::Word("np.array" | "numpy.array"));
+        if wrapped {
+            self.next();
+            self.expect(Token::Open('('))?;
+            self.skip_newlines();
+        }
+
+        let rows = if self.peek().0 == Token::Open('[') {
+            if self.peek_past_newlines(self.index + 1) == Token::Open('[') {
+                self.nested()?
+            } else {
+                self.next();
+                let rows = self.rows()?;
+                self.expect(Token::Close(']'))?;
+                rows
+            }
+        } else {
+            self.rows()?
+        };
+
+        if wrapped {
+            self.expect(Token::Close(')'))?;
+        }
+        self.expect(Token::End)?;
+        Ok(rows)
+    }
+}
+
+impl<T: Float + FromStr, const R: usize, const C: usize> FromStr for Mat<T, R, C> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let rows = parser.matrix::<T>()?;
+        if rows.len() != R {
+            let pos = rows.get(R).map_or(parser.peek().1, |row| row.1);
+            return Err(pos.error(ParseErrorKind::Rows {
+                expected: R,
+                found: rows.len(),
+            }));
+        }
+
+        let mut mat = Mat::zero();
+        for (i, (row, pos)) in rows.iter().enumerate() {
+            if row.len() != C {
+                return Err(pos.error(ParseErrorKind::Columns {
+                    expected: C,
+                    found: row.len(),
+                }));
+            }
+            mat[i].copy_from_slice(row);
+        }
+        Ok(mat)
+    }
+}
+
+impl<T: Float + FromStr> FromStr for DMat<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = Parser::new(s).matrix::<T>()?;
+        let cols = rows.first().map_or(0, |row| row.0.len());
+        let mut data = Vec::with_capacity(rows.len() * cols);
+        for (row, pos) in &rows {
+            if row.len() != cols {
+                return Err(pos.error(ParseErrorKind::Columns {
+                    expected: cols,
+                    found: row.len(),
+                }));
+            }
+            data.extend_from_slice(row);
+        }
+        Ok(DMat::from_vec(rows.len(), cols, data))
+    }
+}
+
+fn parse_components<T: Float + FromStr, const N: usize>(s: &str) -> Result<[T; N], ParseError> {
+    let mut parser = Parser::new(s);
+    parser.skip_newlines();
+    let close = match parser.peek().0 {
+        Token::Open('(') => Some(Token::Close(')')),
+        Token::Open('[') => Some(Token::Close(']')),
+        _ => None,
+    };
+    let start = if close.is_some() {
+        parser.next();
+        parser.skip_newlines();
+        parser.peek().1
+    } else {
+        parser.peek().1
+    };
+
+    let mut components = Vec::new();
+    loop {
+        parser.skip_newlines();
+        match parser.peek().0 {
+            Token::Word(_) => components.push(parser.number()?),
+            Token::Comma => {
+                parser.next();
+            }
+            _ => break,
+        }
+    }
+    if let Some(close) = close {
+        parser.expect(close)?;
+    }
+    parser.expect(Token::End)?;
+
+    components.try_into().map_err(|c: Vec<T>| {
+        start.error(ParseErrorKind::Length {
+            expected: N,
+            found: c.len(),
+        })
+    })
+}
+
+impl<T: Float + FromStr> FromStr for Vec2<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [x, y] = parse_components(s)?;
+        Ok(Vec2::new(x, y))
+    }
+}
+
+impl<T: Float + FromStr> FromStr for Vec3<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [x, y, z] = parse_components(s)?;
+        Ok(Vec3::new(x, y, z))
+    }
+}
+
+impl<T: Float + FromStr> FromStr for Vec3A<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [x, y, z] = parse_components(s)?;
+        Ok(Vec3A::new(x, y, z))
+    }
+}
+
+impl<T: Float + FromStr> FromStr for Vec4<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [x, y, z, w] = parse_components(s)?;
+        Ok(Vec4::new(x, y, z, w))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mat3a::Mat3A;
+    use crate::sparse::CscMatrix;
+
+    fn mat_2x2() -> Mat<f64, 2, 2> {
+        Mat::new(&[[1.0, 2.0], [3.0, 4.0]])
+    }
+
+    #[test]
+    fn matlab() {
+        assert_eq!("[1 2; 3 4]".parse(), Ok(mat_2x2()));
+        assert_eq!("[1, 2; 3, 4]".parse(), Ok(mat_2x2()));
+        assert_eq!("[1 2\n 3 4]".parse(), Ok(mat_2x2()));
+    }
+
+    #[test]
+    fn nested() {
+        assert_eq!("[[1, 2], [3, 4]]".parse(), Ok(mat_2x2()));
+        assert_eq!("[\n  [1.0, 2.0],\n  [3.0, 4.0],\n]".parse(), Ok(mat_2x2()));
+        assert_eq!("np.array([[1, 2], [3, 4]])".parse(), Ok(mat_2x2()));
+    }
+
+    #[test]
+    fn whitespace_rows() {
+        assert_eq!("1 2\n3 4\n".parse(), Ok(mat_2x2()));
+        assert_eq!("\n  1,2\n\n  3,4".parse(), Ok(mat_2x2()));
+    }
+
+    #[test]
+    fn special_values() {
+        let mat: Mat<f64, 1, 3> = "[-inf 1e-3 +2.5E2]".parse().unwrap();
+        assert_eq!(mat, Mat::new(&[[f64::NEG_INFINITY, 0.001, 250.0]]));
+    }
+
+    #[test]
+    fn bad_number() {
+        let err = "[1 2; 3 x4]".parse::<Mat<f64, 2, 2>>().unwrap_err();
+        assert_eq!(err.kind(), ParseErrorKind::BadNumber);
+        assert_eq!((err.line(), err.column()), (1, 9));
+        assert!(!err.is_shape_mismatch());
+    }
+
+    #[test]
+    fn ragged() {
+        let err = "1 2\n3 4 5".parse::<Mat<f64, 2, 2>>().unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ParseErrorKind::Columns {
+                expected: 2,
+                found: 3
+            }
+        );
+        assert_eq!((err.line(), err.column()), (2, 1));
+        assert!(err.is_shape_mismatch());
+    }
+
+    #[test]
+    fn row_count() {
+        let err = "[[1, 2], [3, 4], [5, 6]]"
+            .parse::<Mat<f64, 2, 2>>()
+            .unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ParseErrorKind::Rows {
+                expected: 2,
+                found: 3
+            }
+        );
+        assert_eq!((err.line(), err.column()), (1, 18));
+    }
+
+    #[test]
+    fn syntax() {
+        let err = "[[1, 2], [3, 4]".parse::<Mat<f64, 2, 2>>().unwrap_err();
+        assert_eq!(err.kind(), ParseErrorKind::Syntax);
+        assert_eq!(err.to_string(), "line 1, column 16: unexpected token");
+    }
+
+    #[test]
+    fn dynamic() {
+        let expected = DMat::from_mat(&mat_2x2());
+        assert_eq!("[1 2; 3 4]".parse(), Ok(expected.clone()));
+        assert_eq!("np.array([[1, 2], [3, 4]])".parse(), Ok(expected.clone()));
+        assert_eq!("1 2\n3 4".parse(), Ok(expected));
+
+        let wide: DMat<f64> = "[1 2 3]".parse().unwrap();
+        assert_eq!(wide.shape(), (1, 3));
+
+        let err = "[[1, 2],\n [3, 4, 5]]".parse::<DMat<f64>>().unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ParseErrorKind::Columns {
+                expected: 2,
+                found: 3
+            }
+        );
+        assert_eq!((err.line(), err.column()), (2, 2));
+        assert_eq!(
+            "[1 2; 3 y]".parse::<DMat<f64>>().unwrap_err().kind(),
+            ParseErrorKind::BadNumber
+        );
+    }
+
+    #[test]
+    fn vectors() {
+        assert_eq!("(1, 2)".parse(), Ok(Vec2::new(1.0, 2.0)));
+        assert_eq!("[1, 2, 3]".parse(), Ok(Vec3::new(1.0, 2.0, 3.0)));
+        assert_eq!("1 2 3 4".parse(), Ok(Vec4::new(1.0, 2.0, 3.0, 4.0)));
+
+        let err = "(1, 2)".parse::<Vec3<f64>>().unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ParseErrorKind::Length {
+                expected: 3,
+                found: 2
+            }
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mat = Mat::new(&[
+            [0.1, -1.0 / 3.0, 1e300],
+            [-2.5e-200, f64::MAX, f64::MIN_POSITIVE],
+        ]);
+        assert_eq!(mat.to_string().parse(), Ok(mat));
+        assert_eq!(mat.numpy().to_string().parse(), Ok(mat));
+        assert_eq!(mat.matlab().to_string().parse(), Ok(mat));
+        assert_eq!(mat.to_string().parse(), Ok(DMat::from_mat(&mat)));
+
+        let vec = Vec3::new(0.1, 0.2, 0.30000000000000004);
+        assert_eq!(vec.to_string().parse(), Ok(vec));
+    }
+
+    #[test]
+    fn round_trip_dynamic() {
+        let dmat = DMat::from_fn(3, 4, |i, j| {
+            (i as f64 + 0.1) / (j as f64 + 3.0) * if (i + j) % 2 == 0 { 1e-7 } else { -1e12 }
+        });
+        assert_eq!(format!("{}", dmat).parse(), Ok(dmat.clone()));
+
+        let sparse = CscMatrix::from_triplets(3, 3, &[(0, 0, 0.1), (2, 1, -1.0 / 3.0)]);
+        let parsed: DMat<f64> = sparse.to_string().parse().unwrap();
+        assert_eq!(parsed.shape(), (3, 3));
+        assert_eq!(parsed[(2, 1)], -1.0 / 3.0);
+
+        let mat3 = Mat::new(&[[0.1, 0.2, 0.3], [1e-300, 2.0, 1.0 / 7.0], [7.0, 8.0, 9.0]]);
+        assert_eq!(Mat3A::from(mat3).to_string().parse(), Ok(mat3));
+    }
+}
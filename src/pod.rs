@@ -0,0 +1,68 @@
+use super::mat3a::Mat3A;
+use super::matrix::Mat;
+use super::vector::{vec2::Vec2, vec3::Vec3, vec3a::Vec3A, vec4::Vec4};
+use bytemuck::{Pod, Zeroable};
+use num::Float;
+
+// SAFETY: all of these are `#[repr(C)]` and consist only of fields of type
+// `T` (or arrays of them), so they have no padding bytes and are valid for
+// any bit pattern that is valid for `T`.
+
+unsafe impl<T: Float + Zeroable, const R: usize, const C: usize> Zeroable for Mat<T, R, C> {}
+unsafe impl<T: Float + Pod, const R: usize, const C: usize> Pod for Mat<T, R, C> {}
+
+unsafe impl<T: Float + Zeroable> Zeroable for Vec2<T> {}
+unsafe impl<T: Float + Pod> Pod for Vec2<T> {}
+
+unsafe impl<T: Float + Zeroable> Zeroable for Vec3<T> {}
+unsafe impl<T: Float + Pod> Pod for Vec3<T> {}
+
+unsafe impl<T: Float + Zeroable> Zeroable for Vec4<T> {}
+unsafe impl<T: Float + Pod> Pod for Vec4<T> {}
+
+// The padded types are only padding-free when four components fill a
+// multiple of their 16-byte alignment, so they are limited to `f32` and `f64`.
+
+unsafe impl Zeroable for Vec3A<f32> {}
+unsafe impl Pod for Vec3A<f32> {}
+unsafe impl Zeroable for Vec3A<f64> {}
+unsafe impl Pod for Vec3A<f64> {}
+
+unsafe impl Zeroable for Mat3A<f32> {}
+unsafe impl Pod for Mat3A<f32> {}
+unsafe impl Zeroable for Mat3A<f64> {}
+unsafe impl Pod for Mat3A<f64> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cast_vertices() {
+        let vertices = [Vec3::new(1.0f32, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0)];
+        let floats: &[f32] = bytemuck::cast_slice(&vertices);
+        assert_eq!(floats, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(bytemuck::cast_slice::<_, u8>(&vertices).len(), 24);
+    }
+
+    #[test]
+    fn cast_mat() {
+        let mat = Mat::<f32, 4, 4>::identity();
+        let bytes = bytemuck::bytes_of(&mat);
+        assert_eq!(bytes.len(), 64);
+        assert_eq!(bytemuck::pod_read_unaligned::<Mat<f32, 4, 4>>(bytes), mat);
+    }
+
+    #[test]
+    fn cast_padded() {
+        let vec = Vec3A::new(1.0f32, 2.0, 3.0);
+        assert_eq!(bytemuck::cast::<_, [f32; 4]>(vec), [1.0, 2.0, 3.0, 0.0]);
+
+        let mat = Mat3A::from(Mat::<f32, 3, 3>::identity());
+        let floats: [f32; 12] = bytemuck::cast(mat);
+        assert_eq!(
+            floats,
+            [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+        );
+    }
+}
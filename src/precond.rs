@@ -0,0 +1,696 @@
+use super::error::LinalgError;
+use super::sparse::CscMatrix;
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use num::Float;
+
+// Preconditioners for the Krylov solvers in `iterative`. Construction does all
+// of the factorization work up front; `apply` only does triangular solves or
+// diagonal scaling and can be reused for any number of right-hand sides. The
+// incomplete factorizations take their sparsity pattern from the stored
+// entries of the matrix.
+
+pub trait Preconditioner<T: Float> {
+    // z = M^-1 r. Panics if r or z do not match the size of the matrix the
+    // preconditioner was built from.
+    fn apply(&self, r: &[T], z: &mut [T]);
+}
+
+// No preconditioning, M = I.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl<T: Float> Preconditioner<T> for Identity {
+    fn apply(&self, r: &[T], z: &mut [T]) {
+        z.copy_from_slice(r);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Jacobi<T: Float> {
+    inv_diag: Vec<T>,
+}
+
+impl<T: Float> Jacobi<T> {
+    // Panics on the `Singular` from `try_new`.
+    pub fn new(a: &CscMatrix<T>) -> Self {
+        Self::try_new(a).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Fails with `Singular` when the diagonal has a zero.
+    pub fn try_new(a: &CscMatrix<T>) -> Result<Self, LinalgError> {
+        let inv_diag = nonzero_diagonal(a)?
+            .into_iter()
+            .map(|d| T::one() / d)
+            .collect();
+        Ok(Self { inv_diag })
+    }
+}
+
+impl<T: Float> Preconditioner<T> for Jacobi<T> {
+    fn apply(&self, r: &[T], z: &mut [T]) {
+        check_len(self.inv_diag.len(), r, z);
+        for ((z, &r), &d) in z.iter_mut().zip(r).zip(&self.inv_diag) {
+            *z = r * d;
+        }
+    }
+}
+
+// Diagonal blocks of `block_size` rows (the last one is smaller when the size
+// does not divide n) are inverted during setup and kept dense.
+#[derive(Debug, Clone)]
+pub struct BlockJacobi<T: Float> {
+    n: usize,
+    block_size: usize,
+    // the inverse blocks one after another, each in row-major order
+    inv: Vec<T>,
+}
+
+impl<T: Float> BlockJacobi<T> {
+    // Panics on the `Singular` from `try_new`.
+    pub fn new(a: &CscMatrix<T>, block_size: usize) -> Self {
+        Self::try_new(a, block_size).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Fails with `Singular` when a diagonal block is singular. Panics if the
+    // block size is zero or `a` is not square.
+    pub fn try_new(a: &CscMatrix<T>, block_size: usize) -> Result<Self, LinalgError> {
+        assert!(block_size > 0, "block size must be nonzero");
+        let n = square(a);
+        let mut inv = Vec::with_capacity(n * block_size);
+        let mut start = 0;
+        while start < n {
+            let end = usize::min(start + block_size, n);
+            let size = end - start;
+            let mut block = vec![T::zero(); size * size];
+            for j in start..end {
+                let (rows, values) = a.col(j);
+                for (&i, &v) in rows.iter().zip(values) {
+                    if (start..end).contains(&i) {
+                        block[(i - start) * size + j - start] = v;
+                    }
+                }
+            }
+            inv.extend(invert(size, block)?);
+            start = end;
+        }
+        Ok(Self { n, block_size, inv })
+    }
+}
+
+impl<T: Float> Preconditioner<T> for BlockJacobi<T> {
+    fn apply(&self, r: &[T], z: &mut [T]) {
+        check_len(self.n, r, z);
+        let mut blocks = self.inv.as_slice();
+        for (z, r) in z.chunks_mut(self.block_size).zip(r.chunks(self.block_size)) {
+            let (block, rest) = blocks.split_at(r.len() * r.len());
+            blocks = rest;
+            for (z, row) in z.iter_mut().zip(block.chunks(r.len())) {
+                *z = dot(row, r);
+            }
+        }
+    }
+}
+
+// Gauss-Jordan elimination with partial pivoting on a dense n x n block in
+// row-major order.
+fn invert<T: Float>(n: usize, mut work: Vec<T>) -> Result<Vec<T>, LinalgError> {
+    let mut out = vec![T::zero(); n * n];
+    for i in 0..n {
+        out[i * n + i] = T::one();
+    }
+
+    for k in 0..n {
+        let p = (k..n)
+            .max_by(|&i, &j| {
+                work[i * n + k]
+                    .abs()
+                    .partial_cmp(&work[j * n + k].abs())
+                    .unwrap_or(core::cmp::Ordering::Equal)
+            })
+            .unwrap();
+        if work[p * n + k] == T::zero() {
+            return Err(LinalgError::Singular);
+        }
+        for j in 0..n {
+            work.swap(k * n + j, p * n + j);
+            out.swap(k * n + j, p * n + j);
+        }
+
+        let pivot = work[k * n + k];
+        for j in 0..n {
+            work[k * n + j] = work[k * n + j] / pivot;
+            out[k * n + j] = out[k * n + j] / pivot;
+        }
+        for i in (0..n).filter(|&i| i != k) {
+            let f = work[i * n + k];
+            for j in 0..n {
+                work[i * n + j] = work[i * n + j] - f * work[k * n + j];
+                out[i * n + j] = out[i * n + j] - f * out[k * n + j];
+            }
+        }
+    }
+    Ok(out)
+}
+
+// Symmetric successive over-relaxation, M = (D + wL) D^-1 (D + wU) / (w(2 - w)).
+// With `omega == 1` this is symmetric Gauss-Seidel.
+#[derive(Debug, Clone)]
+pub struct Ssor<T: Float> {
+    a: CscMatrix<T>,
+    diag: Vec<T>,
+    omega: T,
+}
+
+impl<T: Float> Ssor<T> {
+    // Panics on the `Singular` from `try_new`.
+    pub fn new(a: &CscMatrix<T>, omega: T) -> Self {
+        Self::try_new(a, omega).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Fails with `Singular` when the diagonal has a zero. Panics unless
+    // 0 < omega < 2.
+    pub fn try_new(a: &CscMatrix<T>, omega: T) -> Result<Self, LinalgError> {
+        let two = T::one() + T::one();
+        assert!(
+            omega > T::zero() && omega < two,
+            "relaxation parameter must be between zero and two"
+        );
+        Ok(Self {
+            a: a.clone(),
+            diag: nonzero_diagonal(a)?,
+            omega,
+        })
+    }
+}
+
+impl<T: Float> Preconditioner<T> for Ssor<T> {
+    fn apply(&self, r: &[T], z: &mut [T]) {
+        let n = self.diag.len();
+        check_len(n, r, z);
+        let (a, d, w) = (&self.a, &self.diag, self.omega);
+
+        // (D + wL) y = r, column by column
+        z.copy_from_slice(r);
+        for j in 0..n {
+            z[j] = z[j] / d[j];
+            let (rows, values) = a.col(j);
+            for (&i, &v) in rows.iter().zip(values) {
+                if i > j {
+                    z[i] = z[i] - w * v * z[j];
+                }
+            }
+        }
+
+        // (D + wU) z = D y
+        for (z, &d) in z.iter_mut().zip(d) {
+            *z = *z * d;
+        }
+        for j in (0..n).rev() {
+            z[j] = z[j] / d[j];
+            let (rows, values) = a.col(j);
+            for (&i, &v) in rows.iter().zip(values) {
+                if i < j {
+                    z[i] = z[i] - w * v * z[j];
+                }
+            }
+        }
+
+        let scale = w * (T::one() + T::one() - w);
+        for z in z.iter_mut() {
+            *z = *z * scale;
+        }
+    }
+}
+
+// Zero fill-in incomplete Cholesky, A ~ L L^T with L restricted to the pattern
+// of the lower triangle of A. Only the lower triangle of `a` is read.
+#[derive(Debug, Clone)]
+pub struct Ic0<T: Float> {
+    // diagonal first in each column
+    l: CscMatrix<T>,
+}
+
+impl<T: Float> Ic0<T> {
+    // Panics on the `NotPositiveDefinite` from `try_new`.
+    pub fn new(a: &CscMatrix<T>) -> Self {
+        Self::try_new(a).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Fails with `NotPositiveDefinite` on a pivot that is not positive, which
+    // can happen for some positive definite matrices too, since entries are
+    // dropped.
+    pub fn try_new(a: &CscMatrix<T>) -> Result<Self, LinalgError> {
+        let n = square(a);
+        let (mut col_ptr, mut row_idx, mut values) = (vec![0], Vec::new(), Vec::new());
+        for j in 0..n {
+            // a diagonal outside the pattern would be a zero pivot
+            if a.get(j, j) == T::zero() {
+                return Err(LinalgError::NotPositiveDefinite);
+            }
+            let (rows, vals) = a.col(j);
+            for (&i, &v) in rows.iter().zip(vals) {
+                if i >= j {
+                    row_idx.push(i);
+                    values.push(v);
+                }
+            }
+            col_ptr.push(row_idx.len());
+        }
+
+        // left-looking, as in `sparse::cholesky`, but updates that fall
+        // outside the pattern are dropped
+        let mut position = vec![usize::MAX; n];
+        let mut head = vec![usize::MAX; n];
+        let mut link = vec![usize::MAX; n];
+        let mut next = vec![0; n];
+        for j in 0..n {
+            let column = col_ptr[j]..col_ptr[j + 1];
+            for p in column.clone() {
+                position[row_idx[p]] = p;
+            }
+
+            let mut k = head[j];
+            while k != usize::MAX {
+                let following = link[k];
+                let p = next[k];
+                let ljk = values[p];
+                for q in p..col_ptr[k + 1] {
+                    let slot = position[row_idx[q]];
+                    if column.contains(&slot) {
+                        values[slot] = values[slot] - values[q] * ljk;
+                    }
+                }
+                next[k] = p + 1;
+                if next[k] < col_ptr[k + 1] {
+                    let r = row_idx[next[k]];
+                    link[k] = head[r];
+                    head[r] = k;
+                }
+                k = following;
+            }
+
+            let d = values[column.start];
+            if d <= T::zero() || d.is_nan() {
+                return Err(LinalgError::NotPositiveDefinite);
+            }
+            let ljj = d.sqrt();
+            values[column.start] = ljj;
+            for v in &mut values[column.start + 1..column.end] {
+                *v = *v / ljj;
+            }
+            next[j] = column.start + 1;
+            if next[j] < column.end {
+                let r = row_idx[next[j]];
+                link[j] = head[r];
+                head[r] = j;
+            }
+        }
+        Ok(Self {
+            l: CscMatrix::new(n, n, col_ptr, row_idx, values),
+        })
+    }
+
+    pub fn l(&self) -> &CscMatrix<T> {
+        &self.l
+    }
+}
+
+impl<T: Float> Preconditioner<T> for Ic0<T> {
+    fn apply(&self, r: &[T], z: &mut [T]) {
+        let n = self.l.ncols();
+        check_len(n, r, z);
+        z.copy_from_slice(r);
+        for j in 0..n {
+            let (rows, values) = self.l.col(j);
+            z[j] = z[j] / values[0];
+            for (&i, &v) in rows.iter().zip(values).skip(1) {
+                z[i] = z[i] - v * z[j];
+            }
+        }
+        for j in (0..n).rev() {
+            let (rows, values) = self.l.col(j);
+            let mut sum = z[j];
+            for (&i, &v) in rows.iter().zip(values).skip(1) {
+                sum = sum - v * z[i];
+            }
+            z[j] = sum / values[0];
+        }
+    }
+}
+
+// Incomplete LU without pivoting. The unit lower factor L and the upper factor
+// U share one pattern, with the diagonal belonging to U. The factorization
+// works row by row, so the rows are stored as the columns of the transpose.
+#[derive(Debug, Clone)]
+pub struct Ilu<T: Float> {
+    rows: CscMatrix<T>,
+    // position of the diagonal within each row
+    diag: Vec<usize>,
+}
+
+impl<T: Float> Ilu<T> {
+    // Panics on the `Singular` from `try_zero_fill`.
+    pub fn zero_fill(a: &CscMatrix<T>) -> Self {
+        Self::try_zero_fill(a).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // ILU(0): fill-in is restricted to the nonzero pattern of `a`. Fails with
+    // `Singular` on a zero pivot.
+    pub fn try_zero_fill(a: &CscMatrix<T>) -> Result<Self, LinalgError> {
+        let n = square(a);
+        let mut rows = a.transpose();
+        let diag = (0..n)
+            .map(|i| rows.col(i).0.binary_search(&i))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| LinalgError::Singular)?;
+        let ptr = rows.col_ptr().to_vec();
+        let idx = rows.row_idx().to_vec();
+        let values = rows.values_mut();
+
+        let mut position = vec![usize::MAX; n];
+        for i in 0..n {
+            let row = ptr[i]..ptr[i + 1];
+            for p in row.clone() {
+                position[idx[p]] = p;
+            }
+            for p in ptr[i]..ptr[i] + diag[i] {
+                let k = idx[p];
+                let pivot = values[ptr[k] + diag[k]];
+                if pivot == T::zero() {
+                    return Err(LinalgError::Singular);
+                }
+                values[p] = values[p] / pivot;
+                for q in ptr[k] + diag[k] + 1..ptr[k + 1] {
+                    let slot = position[idx[q]];
+                    if row.contains(&slot) {
+                        values[slot] = values[slot] - values[p] * values[q];
+                    }
+                }
+            }
+            if values[ptr[i] + diag[i]] == T::zero() {
+                return Err(LinalgError::Singular);
+            }
+        }
+        Ok(Self { rows, diag })
+    }
+
+    // Panics on the `Singular` from `try_threshold`.
+    pub fn threshold(a: &CscMatrix<T>, tol: T) -> Self {
+        Self::try_threshold(a, tol).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // ILUT: fill-in is allowed anywhere, but entries smaller than
+    // `tol * |row i of a|` are dropped. A tolerance of zero gives the complete
+    // LU factorization. Fails with `Singular` on a zero pivot.
+    pub fn try_threshold(a: &CscMatrix<T>, tol: T) -> Result<Self, LinalgError> {
+        let n = square(a);
+        let rows_a = a.transpose();
+        let (mut ptr, mut idx, mut values, mut diag) = (
+            vec![0],
+            Vec::<usize>::new(),
+            Vec::new(),
+            Vec::<usize>::new(),
+        );
+
+        // the working row is dense, with its pattern tracked in `pattern`
+        let mut w = vec![T::zero(); n];
+        let mut present: Vec<bool> = vec![false; n];
+        for i in 0..n {
+            let (cols, vals) = rows_a.col(i);
+            let drop = tol * norm(vals);
+            let mut pattern: Vec<usize> = cols.to_vec();
+            let mut lower = BTreeSet::new();
+            for (&j, &v) in cols.iter().zip(vals) {
+                w[j] = v;
+                present[j] = true;
+                if j < i {
+                    lower.insert(j);
+                }
+            }
+
+            while let Some(k) = lower.pop_first() {
+                let pivot = values[ptr[k] + diag[k]];
+                if pivot == T::zero() {
+                    return Err(LinalgError::Singular);
+                }
+                w[k] = w[k] / pivot;
+                if w[k].abs() < drop {
+                    w[k] = T::zero();
+                    continue;
+                }
+                for q in ptr[k] + diag[k] + 1..ptr[k + 1] {
+                    let j = idx[q];
+                    if !present[j] {
+                        present[j] = true;
+                        pattern.push(j);
+                        if j < i {
+                            lower.insert(j);
+                        }
+                    }
+                    w[j] = w[j] - w[k] * values[q];
+                }
+            }
+
+            pattern.sort_unstable();
+            let mut d = usize::MAX;
+            for &j in &pattern {
+                if j == i || (w[j] != T::zero() && w[j].abs() >= drop) {
+                    if j == i {
+                        d = idx.len() - ptr[i];
+                    }
+                    idx.push(j);
+                    values.push(w[j]);
+                }
+                w[j] = T::zero();
+                present[j] = false;
+            }
+            if d == usize::MAX || values[ptr[i] + d] == T::zero() {
+                return Err(LinalgError::Singular);
+            }
+            diag.push(d);
+            ptr.push(idx.len());
+        }
+        Ok(Self {
+            rows: CscMatrix::new(n, n, ptr, idx, values),
+            diag,
+        })
+    }
+
+    // L (without its unit diagonal) and U together in one matrix.
+    pub fn lu(&self) -> CscMatrix<T> {
+        self.rows.transpose()
+    }
+}
+
+impl<T: Float> Preconditioner<T> for Ilu<T> {
+    fn apply(&self, r: &[T], z: &mut [T]) {
+        let n = self.diag.len();
+        check_len(n, r, z);
+        for i in 0..n {
+            let (cols, values) = self.rows.col(i);
+            let d = self.diag[i];
+            z[i] = r[i] - dot_at(&cols[..d], &values[..d], z);
+        }
+        for i in (0..n).rev() {
+            let (cols, values) = self.rows.col(i);
+            let d = self.diag[i];
+            z[i] = (z[i] - dot_at(&cols[d + 1..], &values[d + 1..], z)) / values[d];
+        }
+    }
+}
+
+fn square<T: Float>(a: &CscMatrix<T>) -> usize {
+    assert_eq!(a.nrows(), a.ncols(), "matrix must be square");
+    a.ncols()
+}
+
+fn nonzero_diagonal<T: Float>(a: &CscMatrix<T>) -> Result<Vec<T>, LinalgError> {
+    square(a);
+    let diag = a.diagonal();
+    if diag.iter().any(|d| d.is_zero()) {
+        return Err(LinalgError::Singular);
+    }
+    Ok(diag)
+}
+
+fn check_len<T>(n: usize, r: &[T], z: &[T]) {
+    assert!(
+        r.len() == n && z.len() == n,
+        "vector length does not match the preconditioner"
+    );
+}
+
+fn dot<T: Float>(x: &[T], y: &[T]) -> T {
+    x.iter().zip(y).fold(T::zero(), |sum, (&x, &y)| sum + x * y)
+}
+
+// sum_p values[p] * x[index[p]]
+fn dot_at<T: Float>(index: &[usize], values: &[T], x: &[T]) -> T {
+    index
+        .iter()
+        .zip(values)
+        .fold(T::zero(), |sum, (&i, &v)| sum + v * x[i])
+}
+
+fn norm<T: Float>(x: &[T]) -> T {
+    dot(x, x).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_abs_diff_eq;
+    use crate::matrix::Mat;
+
+    fn assert_inverts<const N: usize>(p: &impl Preconditioner<f64>, a: Mat<f64, N, N>) {
+        let mut x = Mat::<f64, N, 1>::zero();
+        for i in 0..N {
+            x[i][0] = (i + 1) as f64;
+        }
+        let r = a * x;
+        let mut z = [0.0; N];
+        p.apply(r.as_slice(), &mut z);
+        assert_abs_diff_eq!(Mat::from_slice(&z), x, epsilon = 1e-10);
+    }
+
+    fn sparse<const N: usize>(a: &Mat<f64, N, N>) -> CscMatrix<f64> {
+        CscMatrix::from_dense(a)
+    }
+
+    fn tridiagonal() -> Mat<f64, 4, 4> {
+        Mat::new(&[
+            [4.0, -1.0, 0.0, 0.0],
+            [-1.0, 4.0, -1.0, 0.0],
+            [0.0, -1.0, 4.0, -1.0],
+            [0.0, 0.0, -1.0, 4.0],
+        ])
+    }
+
+    #[test]
+    fn jacobi_diagonal() {
+        let a = Mat::new(&[[2.0, 0.0, 0.0], [0.0, 4.0, 0.0], [0.0, 0.0, 8.0]]);
+        assert_inverts(&Jacobi::new(&sparse(&a)), a);
+    }
+
+    #[test]
+    fn jacobi_zero_diagonal() {
+        let a = Mat::new(&[[0.0, 1.0], [1.0, 0.0]]);
+        assert_eq!(
+            Jacobi::try_new(&sparse(&a)).unwrap_err(),
+            LinalgError::Singular
+        );
+    }
+
+    #[test]
+    fn block_jacobi_block_diagonal() {
+        let a = Mat::new(&[
+            [0.0, 2.0, 0.0, 0.0, 0.0],
+            [3.0, 1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 4.0, 1.0, 0.0],
+            [0.0, 0.0, 2.0, 5.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0, 7.0],
+        ]);
+        assert_inverts(&BlockJacobi::new(&sparse(&a), 2), a);
+    }
+
+    #[test]
+    fn block_jacobi_singular_block() {
+        let a = Mat::new(&[[1.0, 2.0], [2.0, 4.0]]);
+        assert_eq!(
+            BlockJacobi::try_new(&sparse(&a), 2).unwrap_err(),
+            LinalgError::Singular
+        );
+    }
+
+    #[test]
+    fn ssor_diagonal() {
+        let a = Mat::new(&[[2.0, 0.0, 0.0], [0.0, 4.0, 0.0], [0.0, 0.0, 8.0]]);
+        assert_inverts(&Ssor::new(&sparse(&a), 1.0), a);
+    }
+
+    #[test]
+    fn ssor_matches_definition() {
+        // M = (D + wL) D^-1 (D + wU) / (w(2 - w)), applied to M x
+        let a = tridiagonal();
+        let w = 1.5;
+        let (mut lower, mut upper, mut d_inv): (Mat<f64, 4, 4>, Mat<f64, 4, 4>, Mat<f64, 4, 4>) =
+            (Mat::zero(), Mat::zero(), Mat::zero());
+        for i in 0..4 {
+            for j in 0..4 {
+                if i > j {
+                    lower[i][j] = w * a[i][j];
+                } else if i < j {
+                    upper[i][j] = w * a[i][j];
+                }
+            }
+            lower[i][i] = a[i][i];
+            upper[i][i] = a[i][i];
+            d_inv[i][i] = 1.0 / a[i][i];
+        }
+        let scale = Mat::diagonal(1.0 / (w * (2.0 - w)));
+        assert_inverts(&Ssor::new(&sparse(&a), w), lower * d_inv * upper * scale);
+    }
+
+    #[test]
+    #[should_panic(expected = "relaxation parameter")]
+    fn ssor_omega_range() {
+        Ssor::new(&sparse(&tridiagonal()), 2.0);
+    }
+
+    #[test]
+    fn ic0_tridiagonal() {
+        // a tridiagonal factorization has no fill-in, so IC(0) is exact
+        let a = tridiagonal();
+        assert_inverts(&Ic0::new(&sparse(&a)), a);
+    }
+
+    #[test]
+    fn ic0_not_positive_definite() {
+        let a = Mat::new(&[[1.0, 2.0], [2.0, 1.0]]);
+        assert_eq!(
+            Ic0::try_new(&sparse(&a)).unwrap_err(),
+            LinalgError::NotPositiveDefinite
+        );
+    }
+
+    #[test]
+    fn ilu0_tridiagonal() {
+        let a = Mat::new(&[
+            [4.0, -2.0, 0.0, 0.0],
+            [-1.0, 5.0, 1.0, 0.0],
+            [0.0, 3.0, 6.0, -1.0],
+            [0.0, 0.0, 2.0, 3.0],
+        ]);
+        assert_inverts(&Ilu::zero_fill(&sparse(&a)), a);
+    }
+
+    #[test]
+    fn ilu0_zero_pivot() {
+        let a = Mat::new(&[[0.0, 1.0], [1.0, 0.0]]);
+        assert_eq!(
+            Ilu::try_zero_fill(&sparse(&a)).unwrap_err(),
+            LinalgError::Singular
+        );
+    }
+
+    #[test]
+    fn ilut_zero_tolerance_is_exact() {
+        let a = Mat::new(&[[4.0, 1.0, 2.0], [1.0, 5.0, 3.0], [2.0, 3.0, 6.0]]);
+        assert_inverts(&Ilu::threshold(&sparse(&a), 0.0), a);
+    }
+
+    #[test]
+    fn ilut_drops_fill() {
+        let a = sparse(&Mat::new(&[
+            [4.0, 1.0, 0.0],
+            [1.0, 4.0, 1.0],
+            [1.0, 1.0, 4.0],
+        ]));
+        let full = Ilu::threshold(&a, 0.0).lu();
+        let dropped = Ilu::threshold(&a, 0.1).lu();
+        assert_eq!(full.get(2, 0), 0.25);
+        assert_eq!(dropped.get(2, 0), 0.0);
+    }
+}
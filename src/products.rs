@@ -0,0 +1,282 @@
+#[cfg(feature = "alloc")]
+use super::dmatrix::DMat;
+#[cfg(feature = "alloc")]
+use super::error::LinalgError;
+use super::matrix::Mat;
+use num::Float;
+
+// Products other than the matrix product, and the column-stacking `vec`
+// operator that links the Kronecker product to ordinary products:
+//
+//   vec(A X B) = (B^T ⊗ A) vec(X)
+//
+// As with `hstack` and `reshape`, output shapes come from the surrounding
+// code and are checked at compile time. The `DMat` versions check their
+// shapes at run time instead.
+
+// The Kronecker product, whose (i, j) block is a[i][j] * b.
+pub fn kronecker<
+    T: Float,
+    const RA: usize,
+    const CA: usize,
+    const RB: usize,
+    const CB: usize,
+    const R: usize,
+    const C: usize,
+>(
+    a: &Mat<T, RA, CA>,
+    b: &Mat<T, RB, CB>,
+) -> Mat<T, R, C> {
+    const {
+        assert!(
+            R == RA * RB && C == CA * CB,
+            "Kronecker product has the wrong shape"
+        )
+    };
+    let mut out = Mat::zero();
+    for i in 0..RA {
+        for j in 0..CA {
+            for k in 0..RB {
+                for l in 0..CB {
+                    out[i * RB + k][j * CB + l] = a[i][j] * b[k][l];
+                }
+            }
+        }
+    }
+    out
+}
+
+// u v^T. Any of the vector types, or a column matrix, can be used for either
+// side.
+pub fn outer<T: Float, const R: usize, const C: usize>(
+    u: impl Into<Mat<T, R, 1>>,
+    v: impl Into<Mat<T, C, 1>>,
+) -> Mat<T, R, C> {
+    let (u, v) = (u.into(), v.into());
+    let mut out = Mat::zero();
+    for i in 0..R {
+        for j in 0..C {
+            out[i][j] = u[i][0] * v[j][0];
+        }
+    }
+    out
+}
+
+impl<T: Float, const R: usize, const C: usize> Mat<T, R, C> {
+    // The elementwise product.
+    pub fn hadamard(&self, rhs: &Self) -> Self {
+        self.component_mul(rhs)
+    }
+
+    pub fn component_mul(&self, rhs: &Self) -> Self {
+        self.zip_map(rhs, |a, b| a * b)
+    }
+
+    pub fn component_div(&self, rhs: &Self) -> Self {
+        self.zip_map(rhs, |a, b| a / b)
+    }
+
+    fn zip_map(&self, rhs: &Self, f: impl Fn(T, T) -> T) -> Self {
+        let mut out = *self;
+        for (x, &y) in out.iter_mut().zip(rhs) {
+            *x = f(*x, y);
+        }
+        out
+    }
+
+    // Stacks the columns into a single column, so entry (i, j) lands at
+    // j * R + i.
+    pub fn vec<const N: usize>(&self) -> Mat<T, N, 1> {
+        const { assert!(N == R * C, "vec must keep the number of entries") };
+        let mut out = Mat::zero();
+        for (j, column) in self.columns().enumerate() {
+            for i in 0..R {
+                out[j * R + i][0] = column[i];
+            }
+        }
+        out
+    }
+
+    // The inverse of `vec`.
+    pub fn unvec<const N: usize>(v: &Mat<T, N, 1>) -> Self {
+        const { assert!(N == R * C, "unvec must keep the number of entries") };
+        let mut out = Mat::zero();
+        for j in 0..C {
+            for i in 0..R {
+                out[i][j] = v[j * R + i][0];
+            }
+        }
+        out
+    }
+}
+
+// `kronecker` for matrices whose shapes are only known at run time.
+#[cfg(feature = "alloc")]
+pub fn kronecker_dynamic<T: Float>(a: &DMat<T>, b: &DMat<T>) -> DMat<T> {
+    let (rb, cb) = b.shape();
+    DMat::from_fn(a.nrows() * rb, a.ncols() * cb, |i, j| {
+        a[(i / rb, j / cb)] * b[(i % rb, j % cb)]
+    })
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Float> DMat<T> {
+    // Panics on the `DimensionMismatch` from `try_hadamard`.
+    pub fn hadamard(&self, rhs: &Self) -> Self {
+        self.component_mul(rhs)
+    }
+
+    pub fn try_hadamard(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        self.try_component_mul(rhs)
+    }
+
+    pub fn component_mul(&self, rhs: &Self) -> Self {
+        self.try_component_mul(rhs)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_component_mul(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        self.try_zip_map(rhs, |a, b| a * b)
+    }
+
+    pub fn component_div(&self, rhs: &Self) -> Self {
+        self.try_component_div(rhs)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_component_div(&self, rhs: &Self) -> Result<Self, LinalgError> {
+        self.try_zip_map(rhs, |a, b| a / b)
+    }
+
+    fn try_zip_map(&self, rhs: &Self, f: impl Fn(T, T) -> T) -> Result<Self, LinalgError> {
+        if self.shape() != rhs.shape() {
+            return Err(LinalgError::DimensionMismatch {
+                expected: self.shape(),
+                found: rhs.shape(),
+            });
+        }
+        let data = self
+            .as_slice()
+            .iter()
+            .zip(rhs.as_slice())
+            .map(|(&a, &b)| f(a, b))
+            .collect();
+        Ok(DMat::from_vec(self.nrows(), self.ncols(), data))
+    }
+
+    // Stacks the columns into a single column, so entry (i, j) lands at
+    // j * nrows() + i.
+    pub fn vec(&self) -> Self {
+        let rows = self.nrows();
+        DMat::from_fn(self.as_slice().len(), 1, |k, _| self[(k % rows, k / rows)])
+    }
+
+    // Panics on the `DimensionMismatch` from `try_unvec`.
+    pub fn unvec(v: &Self, rows: usize, cols: usize) -> Self {
+        Self::try_unvec(v, rows, cols).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // The inverse of `vec`. `v` has to be a column of rows * cols entries.
+    pub fn try_unvec(v: &Self, rows: usize, cols: usize) -> Result<Self, LinalgError> {
+        if v.shape() != (rows * cols, 1) {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (rows * cols, 1),
+                found: v.shape(),
+            });
+        }
+        Ok(DMat::from_fn(rows, cols, |i, j| v[(j * rows + i, 0)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mat;
+    use crate::vector::{vec2::Vec2, vec3::Vec3, vec3a::Vec3A, vec4::Vec4};
+
+    #[test]
+    fn kronecker_product() {
+        let a: Mat<f64, 2, 2> = mat![1, 2; 3, 4];
+        let b: Mat<f64, 2, 3> = mat![0, 5, 1; 6, 7, 1];
+        let k: Mat<f64, 4, 6> = kronecker(&a, &b);
+        assert_eq!(
+            k,
+            mat![
+                0, 5, 1, 0, 10, 2;
+                6, 7, 1, 12, 14, 2;
+                0, 15, 3, 0, 20, 4;
+                18, 21, 3, 24, 28, 4
+            ]
+        );
+
+        let i: Mat<f64, 4, 4> =
+            kronecker(&Mat::<f64, 2, 2>::identity(), &Mat::<f64, 2, 2>::identity());
+        assert_eq!(i, Mat::identity());
+    }
+
+    #[test]
+    fn elementwise() {
+        let a: Mat<f64, 2, 2> = mat![1, 2; 3, 4];
+        let b: Mat<f64, 2, 2> = mat![2, 2; 0.5, -1];
+        assert_eq!(a.hadamard(&b), mat![2, 4; 1.5, -4]);
+        assert_eq!(a.component_mul(&b), a.hadamard(&b));
+        assert_eq!(a.component_div(&b), mat![0.5, 1; 6, -4]);
+
+        #[cfg(feature = "alloc")]
+        {
+            let (da, db) = (DMat::from_mat(&a), DMat::from_mat(&b));
+            assert_eq!(da.hadamard(&db).to_mat::<2, 2>(), a.hadamard(&b));
+            assert_eq!(da.component_div(&db).to_mat::<2, 2>(), a.component_div(&b));
+            assert_eq!(
+                da.try_component_mul(&da.vec()),
+                Err(LinalgError::DimensionMismatch {
+                    expected: (2, 2),
+                    found: (4, 1)
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn outer_products() {
+        let m: Mat<f64, 3, 2> = outer(Vec3::new(1.0, 2.0, 3.0), Vec2::new(1.0, -1.0));
+        assert_eq!(m, mat![1, -1; 2, -2; 3, -3]);
+
+        let m = outer(Vec4::new(1.0, 0.0, 0.0, 2.0), Vec3A::new(1.0, 2.0, 3.0));
+        assert_eq!(m[3], [2.0, 4.0, 6.0]);
+
+        let col: Mat<f32, 2, 1> = mat![1; 2];
+        assert_eq!(outer(col, col), mat![1, 2; 2, 4]);
+    }
+
+    #[test]
+    fn vec_identity() {
+        let a: Mat<f64, 2, 3> = mat![1, 2, 3; 4, 5, 6];
+        let x: Mat<f64, 3, 4> = mat![1, 0, 2, -1; 3, 1, 0, 2; -2, 4, 1, 1];
+        let b: Mat<f64, 4, 2> = mat![1, 2; 0, 1; -1, 3; 2, 0];
+
+        let lhs: Mat<f64, 4, 1> = (a * x * b).vec();
+        let k: Mat<f64, 4, 12> = kronecker(&b.transpose(), &a);
+        assert_eq!(lhs, k * x.vec::<12>());
+
+        assert_eq!(a.vec::<6>(), mat![1; 4; 2; 5; 3; 6]);
+        assert_eq!(Mat::<f64, 3, 4>::unvec(&x.vec::<12>()), x);
+
+        #[cfg(feature = "alloc")]
+        {
+            let (da, dx, db) = (DMat::from_mat(&a), DMat::from_mat(&x), DMat::from_mat(&b));
+            let lhs = (&(&da * &dx) * &db).vec();
+            let k = kronecker_dynamic(&db.transpose(), &da);
+            assert_eq!(k.to_mat::<4, 12>(), kronecker(&b.transpose(), &a));
+            assert_eq!(lhs, &k * &dx.vec());
+            assert_eq!(DMat::unvec(&dx.vec(), 3, 4), dx);
+            assert_eq!(
+                DMat::try_unvec(&dx.vec(), 4, 4),
+                Err(LinalgError::DimensionMismatch {
+                    expected: (16, 1),
+                    found: (12, 1)
+                })
+            );
+        }
+    }
+}
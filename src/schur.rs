@@ -0,0 +1,530 @@
+#[cfg(feature = "alloc")]
+use super::dmatrix::DMat;
+use super::error::LinalgError;
+use super::matrix::Mat;
+use core::ops::{Index, IndexMut};
+use num::Float;
+
+// Real Schur decomposition A = Q T Q^T, with Q orthogonal and T upper
+// quasi-triangular: 1x1 diagonal blocks for real eigenvalues and 2x2 blocks
+// for complex conjugate pairs. A is reduced to Hessenberg form with
+// Householder reflections, then to Schur form with the Francis double-shift
+// QR iteration, following EISPACK's `orthes` and `hqr2`.
+#[derive(Debug, Clone, Copy)]
+pub struct Schur<T: Float, const N: usize> {
+    q: Mat<T, N, N>,
+    t: Mat<T, N, N>,
+}
+
+// QR iterations allowed per eigenvalue before giving up. The exceptional
+// shifts at 10 and 30 iterations almost always make it converge well before
+// this.
+const MAX_ITERATIONS: usize = 100;
+
+impl<T: Float, const N: usize> Schur<T, N> {
+    // Panics on the `NonFinite` or `NoConvergence` from `try_new`.
+    pub fn new(a: &Mat<T, N, N>) -> Self {
+        Self::try_new(a).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Fails with `NonFinite` if `a` has an infinite or NaN entry, or with
+    // `NoConvergence` if the QR iteration stalls.
+    pub fn try_new(a: &Mat<T, N, N>) -> Result<Self, LinalgError> {
+        if a.iter().any(|x| !x.is_finite()) {
+            return Err(LinalgError::NonFinite);
+        }
+        let (mut t, mut q) = (*a, Mat::identity());
+        reduce(N, t.as_mut_slice(), q.as_mut_slice(), &mut [T::zero(); N])?;
+        Ok(Self { q, t })
+    }
+
+    pub fn q(&self) -> &Mat<T, N, N> {
+        &self.q
+    }
+
+    pub fn t(&self) -> &Mat<T, N, N> {
+        &self.t
+    }
+
+    // The size (1 or 2) of the diagonal block of T starting at row i.
+    pub fn block_size(&self, i: usize) -> usize {
+        block_size(N, self.t.as_slice(), i)
+    }
+
+    // Eigenvalues as (real, imaginary) pairs, in the order of the diagonal
+    // blocks of T. A complex pair comes out as two consecutive entries, the
+    // one with positive imaginary part first.
+    pub fn eigenvalues(&self) -> [(T, T); N] {
+        let mut out = [(T::zero(), T::zero()); N];
+        eigenvalues(N, self.t.as_slice(), &mut out);
+        out
+    }
+}
+
+// `Schur::try_new` for a matrix whose size is only known at run time, as
+// (Q, T).
+#[cfg(feature = "alloc")]
+pub(crate) fn try_dynamic<T: Float>(a: &DMat<T>) -> Result<(DMat<T>, DMat<T>), LinalgError> {
+    let size = a.nrows();
+    assert_eq!(a.ncols(), size, "the Schur form needs a square matrix");
+    if a.as_slice().iter().any(|x| !x.is_finite()) {
+        return Err(LinalgError::NonFinite);
+    }
+    let (mut t, mut q) = (a.clone(), DMat::identity(size));
+    let mut ort = alloc::vec![T::zero(); size];
+    reduce(size, t.as_mut_slice(), q.as_mut_slice(), &mut ort)?;
+    Ok((q, t))
+}
+
+// The size (1 or 2) of the diagonal block starting at row i of the size x size
+// quasi-triangular `t`, stored row-major.
+pub(crate) fn block_size<T: Float>(size: usize, t: &[T], i: usize) -> usize {
+    if i + 1 < size && t[(i + 1) * size + i] != T::zero() {
+        2
+    } else {
+        1
+    }
+}
+
+// The eigenvalues of the quasi-triangular `t`, as in `Schur::eigenvalues`.
+pub(crate) fn eigenvalues<T: Float>(size: usize, t: &[T], out: &mut [(T, T)]) {
+    let at = |i: usize, j: usize| t[i * size + j];
+    let two = T::one() + T::one();
+    let mut i = 0;
+    while i < size {
+        if block_size(size, t, i) == 1 {
+            out[i] = (at(i, i), T::zero());
+            i += 1;
+            continue;
+        }
+        let re = (at(i, i) + at(i + 1, i + 1)) / two;
+        let p = (at(i, i) - at(i + 1, i + 1)) / two;
+        let im = (-(p * p + at(i, i + 1) * at(i + 1, i)))
+            .max(T::zero())
+            .sqrt();
+        out[i] = (re, im);
+        out[i + 1] = (re, -im);
+        i += 2;
+    }
+}
+
+// A row-major size x size matrix borrowed from a slice, indexed like `Mat` so
+// that the reductions below serve fixed and dynamic sizes alike.
+struct Rows<'a, T> {
+    size: usize,
+    data: &'a mut [T],
+}
+
+impl<T> Index<usize> for Rows<'_, T> {
+    type Output = [T];
+
+    fn index(&self, i: usize) -> &[T] {
+        &self.data[i * self.size..(i + 1) * self.size]
+    }
+}
+
+impl<T> IndexMut<usize> for Rows<'_, T> {
+    fn index_mut(&mut self, i: usize) -> &mut [T] {
+        &mut self.data[i * self.size..(i + 1) * self.size]
+    }
+}
+
+// Overwrites the size x size `t` with its real Schur form, accumulating the
+// transformations into `q`, which has to start out as the identity. `ort` is
+// scratch space of `size` entries.
+fn reduce<T: Float>(
+    size: usize,
+    t: &mut [T],
+    q: &mut [T],
+    ort: &mut [T],
+) -> Result<(), LinalgError> {
+    let mut h = Rows { size, data: t };
+    let mut q = Rows { size, data: q };
+    hessenberg(&mut h, &mut q, ort);
+    francis(&mut h, &mut q)
+}
+
+// Reduces A, passed in as `h`, to the Hessenberg form H, accumulating the
+// orthogonal Q with A = Q H Q^T into `q`.
+fn hessenberg<T: Float>(h: &mut Rows<T>, q: &mut Rows<T>, ort: &mut [T]) {
+    let size = h.size;
+    if size < 3 {
+        return;
+    }
+    let high = size - 1;
+
+    for m in 1..high {
+        let scale = (m..=high).fold(T::zero(), |acc, i| acc + h[i][m - 1].abs());
+        if scale == T::zero() {
+            continue;
+        }
+        let mut hh = T::zero();
+        for i in (m..=high).rev() {
+            ort[i] = h[i][m - 1] / scale;
+            hh = hh + ort[i] * ort[i];
+        }
+        let g = if ort[m] > T::zero() {
+            -hh.sqrt()
+        } else {
+            hh.sqrt()
+        };
+        hh = hh - ort[m] * g;
+        ort[m] = ort[m] - g;
+
+        // H = (I - u u^T / hh) H (I - u u^T / hh)
+        for j in m..size {
+            let f = (m..=high).fold(T::zero(), |acc, i| acc + ort[i] * h[i][j]) / hh;
+            for i in m..=high {
+                h[i][j] = h[i][j] - f * ort[i];
+            }
+        }
+        for i in 0..=high {
+            let f = (m..=high).fold(T::zero(), |acc, j| acc + ort[j] * h[i][j]) / hh;
+            for j in m..=high {
+                h[i][j] = h[i][j] - f * ort[j];
+            }
+        }
+        ort[m] = scale * ort[m];
+        h[m][m - 1] = scale * g;
+    }
+
+    // accumulate the reflections, whose vectors are still below the
+    // subdiagonal of H
+    for m in (1..high).rev() {
+        if h[m][m - 1] == T::zero() {
+            continue;
+        }
+        for i in m + 1..=high {
+            ort[i] = h[i][m - 1];
+        }
+        for j in m..=high {
+            let g = (m..=high).fold(T::zero(), |acc, i| acc + ort[i] * q[i][j]);
+            // two divisions avoid underflow
+            let g = (g / ort[m]) / h[m][m - 1];
+            for i in m..=high {
+                q[i][j] = q[i][j] + g * ort[i];
+            }
+        }
+    }
+
+    for i in 2..size {
+        for j in 0..i - 1 {
+            h[i][j] = T::zero();
+        }
+    }
+}
+
+// Reduces the Hessenberg matrix `h` to real Schur form, accumulating the
+// transformations into `q`.
+fn francis<T: Float>(h: &mut Rows<T>, q: &mut Rows<T>) -> Result<(), LinalgError> {
+    let size = h.size;
+    let eps = T::epsilon();
+    let two = T::one() + T::one();
+    let c = |x: f64| T::from(x).unwrap();
+
+    let mut norm = T::zero();
+    for i in 0..size {
+        for j in i.saturating_sub(1)..size {
+            norm = norm + h[i][j].abs();
+        }
+    }
+
+    let mut exshift = T::zero();
+    let mut iter = 0;
+    let mut total = 0;
+    // rows and columns from `end` on have been reduced
+    let mut end = size;
+    while end > 0 {
+        let n = end - 1;
+
+        // look for a single small subdiagonal entry
+        let mut l = n;
+        while l > 0 {
+            let mut s = h[l - 1][l - 1].abs() + h[l][l].abs();
+            if s == T::zero() {
+                s = norm;
+            }
+            if h[l][l - 1].abs() < eps * s {
+                h[l][l - 1] = T::zero();
+                break;
+            }
+            l -= 1;
+        }
+
+        if l == n {
+            // one root found
+            h[n][n] = h[n][n] + exshift;
+            end -= 1;
+            iter = 0;
+            continue;
+        }
+
+        if l == n - 1 {
+            // two roots found
+            let w = h[n][n - 1] * h[n - 1][n];
+            let p = (h[n - 1][n - 1] - h[n][n]) / two;
+            let disc = p * p + w;
+            h[n][n] = h[n][n] + exshift;
+            h[n - 1][n - 1] = h[n - 1][n - 1] + exshift;
+
+            // a real pair is split with a rotation; a complex pair is left
+            // as a 2x2 block
+            if disc >= T::zero() {
+                let z = disc.abs().sqrt();
+                let z = if p >= T::zero() { p + z } else { p - z };
+                let x = h[n][n - 1];
+                let s = x.abs() + z.abs();
+                let (p, qq) = (x / s, z / s);
+                let r = p.hypot(qq);
+                let (p, qq) = (p / r, qq / r);
+
+                for j in n - 1..size {
+                    let z = h[n - 1][j];
+                    h[n - 1][j] = qq * z + p * h[n][j];
+                    h[n][j] = qq * h[n][j] - p * z;
+                }
+                for i in 0..=n {
+                    let z = h[i][n - 1];
+                    h[i][n - 1] = qq * z + p * h[i][n];
+                    h[i][n] = qq * h[i][n] - p * z;
+                }
+                for i in 0..size {
+                    let z = q[i][n - 1];
+                    q[i][n - 1] = qq * z + p * q[i][n];
+                    q[i][n] = qq * q[i][n] - p * z;
+                }
+                h[n][n - 1] = T::zero();
+            }
+            end -= 2;
+            iter = 0;
+            continue;
+        }
+
+        if iter == MAX_ITERATIONS {
+            return Err(LinalgError::NoConvergence { iterations: total });
+        }
+
+        // form the shift
+        let mut x = h[n][n];
+        let mut y = h[n - 1][n - 1];
+        let mut w = h[n][n - 1] * h[n - 1][n];
+
+        // Wilkinson's ad hoc shift
+        if iter == 10 {
+            exshift = exshift + x;
+            for i in 0..=n {
+                h[i][i] = h[i][i] - x;
+            }
+            let s = h[n][n - 1].abs() + h[n - 1][n - 2].abs();
+            x = c(0.75) * s;
+            y = x;
+            w = c(-0.4375) * s * s;
+        }
+
+        // MATLAB's ad hoc shift
+        if iter == 30 {
+            let s = (y - x) / two;
+            let s = s * s + w;
+            if s > T::zero() {
+                let s = if y < x { -s.sqrt() } else { s.sqrt() };
+                let s = x - w / ((y - x) / two + s);
+                for i in 0..=n {
+                    h[i][i] = h[i][i] - s;
+                }
+                exshift = exshift + s;
+                x = c(0.964);
+                y = x;
+                w = x;
+            }
+        }
+
+        iter += 1;
+        total += 1;
+
+        // look for two consecutive small subdiagonal entries
+        let mut m = n - 2;
+        let (mut p, mut qq, mut r);
+        loop {
+            let z = h[m][m];
+            let rr = x - z;
+            let s = y - z;
+            p = (rr * s - w) / h[m + 1][m] + h[m][m + 1];
+            qq = h[m + 1][m + 1] - z - rr - s;
+            r = h[m + 2][m + 1];
+            let s = p.abs() + qq.abs() + r.abs();
+            p = p / s;
+            qq = qq / s;
+            r = r / s;
+            if m == l {
+                break;
+            }
+            if h[m][m - 1].abs() * (qq.abs() + r.abs())
+                < eps * (p.abs() * (h[m - 1][m - 1].abs() + z.abs() + h[m + 1][m + 1].abs()))
+            {
+                break;
+            }
+            m -= 1;
+        }
+
+        for i in m + 2..=n {
+            h[i][i - 2] = T::zero();
+            if i > m + 2 {
+                h[i][i - 3] = T::zero();
+            }
+        }
+
+        // double QR step on rows l..=n and columns m..=n
+        for k in m..n {
+            let notlast = k != n - 1;
+            let mut scale = T::zero();
+            if k != m {
+                p = h[k][k - 1];
+                qq = h[k + 1][k - 1];
+                r = if notlast { h[k + 2][k - 1] } else { T::zero() };
+                scale = p.abs() + qq.abs() + r.abs();
+                if scale == T::zero() {
+                    continue;
+                }
+                p = p / scale;
+                qq = qq / scale;
+                r = r / scale;
+            }
+
+            let mut s = (p * p + qq * qq + r * r).sqrt();
+            if p < T::zero() {
+                s = -s;
+            }
+            if s == T::zero() {
+                continue;
+            }
+            if k != m {
+                h[k][k - 1] = -s * scale;
+            } else if l != m {
+                h[k][k - 1] = -h[k][k - 1];
+            }
+            p = p + s;
+            let (x, y, z) = (p / s, qq / s, r / s);
+            qq = qq / p;
+            r = r / p;
+
+            for j in k..size {
+                let mut p = h[k][j] + qq * h[k + 1][j];
+                if notlast {
+                    p = p + r * h[k + 2][j];
+                    h[k + 2][j] = h[k + 2][j] - p * z;
+                }
+                h[k][j] = h[k][j] - p * x;
+                h[k + 1][j] = h[k + 1][j] - p * y;
+            }
+            for i in 0..=n.min(k + 3) {
+                let mut p = x * h[i][k] + y * h[i][k + 1];
+                if notlast {
+                    p = p + z * h[i][k + 2];
+                    h[i][k + 2] = h[i][k + 2] - p * r;
+                }
+                h[i][k] = h[i][k] - p;
+                h[i][k + 1] = h[i][k + 1] - p * qq;
+            }
+            for i in 0..size {
+                let mut p = x * q[i][k] + y * q[i][k + 1];
+                if notlast {
+                    p = p + z * q[i][k + 2];
+                    q[i][k + 2] = q[i][k + 2] - p * r;
+                }
+                q[i][k] = q[i][k] - p;
+                q[i][k + 1] = q[i][k + 1] - p * qq;
+            }
+        }
+    }
+
+    for i in 2..size {
+        for j in 0..i - 1 {
+            h[i][j] = T::zero();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_abs_diff_eq, mat};
+
+    fn values<const R: usize, const C: usize>(seed: u64) -> Mat<f64, R, C> {
+        let mut state = seed;
+        let mut m = Mat::zero();
+        for x in m.iter_mut() {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            *x = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+        }
+        m
+    }
+
+    fn check<const N: usize>(a: &Mat<f64, N, N>) {
+        let schur = Schur::new(a);
+        let (q, t) = (schur.q(), schur.t());
+        assert_abs_diff_eq!(q.transpose() * *q, Mat::identity(), epsilon = 1e-12);
+        assert_abs_diff_eq!(*q * *t * q.transpose(), *a, epsilon = 1e-12);
+
+        // quasi-triangular, with no two 2x2 blocks touching
+        for i in 0..N {
+            for j in 0..i.saturating_sub(1) {
+                assert_eq!(t[i][j], 0.0);
+            }
+            if i + 2 < N && t[i + 1][i] != 0.0 {
+                assert_eq!(t[i + 2][i + 1], 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn decomposition() {
+        check(&values::<6, 6>(1));
+        check(&values::<9, 9>(2));
+        check(&values::<2, 2>(3));
+        check::<1>(&mat![5]);
+        check::<0>(&Mat::zero());
+
+        // symmetric, so T is diagonal
+        let a = values::<5, 5>(4);
+        let s = a + a.transpose();
+        let t = *Schur::new(&s).t();
+        for i in 1..5 {
+            assert_eq!(t[i][i - 1], 0.0);
+        }
+    }
+
+    #[test]
+    fn eigenvalues() {
+        // a rotation by 90 degrees scaled by 2, next to a real eigenvalue
+        let a: Mat<f64, 3, 3> = mat![0, -2, 0; 2, 0, 0; 0, 0, 3];
+        let q = Schur::new(&values::<3, 3>(5)).q;
+        let schur = Schur::new(&(q * a * q.transpose()));
+        let mut eig = schur.eigenvalues();
+        eig.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for ((re, im), (er, ei)) in eig.iter().zip([(0.0, -2.0), (0.0, 2.0), (3.0, 0.0)]) {
+            assert!(
+                (re - er).abs() < 1e-12 && (im - ei).abs() < 1e-12,
+                "{:?}",
+                eig
+            );
+        }
+
+        let schur = Schur::<f64, 2>::new(&mat![2, 1; 1, 2]);
+        assert_eq!(schur.block_size(0), 1);
+        let mut eig = schur.eigenvalues().map(|(re, _)| re);
+        eig.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_abs_diff_eq!(eig[0], 1.0, epsilon = 1e-14);
+        assert_abs_diff_eq!(eig[1], 3.0, epsilon = 1e-14);
+    }
+
+    #[test]
+    fn non_finite() {
+        assert_eq!(
+            Schur::<f64, 2>::try_new(&mat![1, f64::NAN; 0, 1]).unwrap_err(),
+            LinalgError::NonFinite
+        );
+    }
+}
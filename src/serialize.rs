@@ -0,0 +1,609 @@
+#[cfg(feature = "alloc")]
+use super::dmatrix::DMat;
+use super::mat3a::Mat3A;
+use super::matrix::Mat;
+#[cfg(feature = "alloc")]
+use super::sparse::{csc, CscMatrix};
+use super::vector::{vec2::Vec2, vec3::Vec3, vec3a::Vec3A, vec4::Vec4};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{fmt, marker::PhantomData};
+use num::Float;
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, SeqAccess, Visitor};
+use serde::ser::{SerializeTuple, Serializer};
+use serde::{Deserialize, Serialize};
+
+// Vectors serialize as fixed-length sequences ([1.0, 2.0, 3.0] in JSON) and
+// matrices as arrays of rows ([[1.0, 2.0], [3.0, 4.0]]). Deserialization
+// checks the number of entries against the const-generic shape. To store a
+// vector as a map with named fields instead, annotate the field with
+// `#[serde(with = "linalg::serialize::named")]`.
+//
+// `Mat3A` uses the rows of the matrix it stores, not its padded columns. `DMat`
+// also serializes as rows and takes its shape from them, so a matrix without
+// rows comes back as 0x0. `CscMatrix` serializes its arrays as a struct and is
+// checked like `CscMatrix::new` when deserialized.
+
+struct Row<'a, T, const C: usize>(&'a [T; C]);
+
+impl<T: Serialize, const C: usize> Serialize for Row<'_, T, C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tup = serializer.serialize_tuple(C)?;
+        for x in self.0 {
+            tup.serialize_element(x)?;
+        }
+        tup.end()
+    }
+}
+
+impl<T: Float + Serialize, const R: usize, const C: usize> Serialize for Mat<T, R, C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tup = serializer.serialize_tuple(R)?;
+        for row in &self.0 {
+            tup.serialize_element(&Row(row))?;
+        }
+        tup.end()
+    }
+}
+
+// Reads exactly `N` elements with `next`, rejecting shorter and longer input.
+fn read_exact<'de, A, T, const N: usize>(
+    seq: &mut A,
+    expected: &dyn de::Expected,
+    fill: T,
+    mut next: impl FnMut(&mut A) -> Result<Option<T>, A::Error>,
+) -> Result<[T; N], A::Error>
+where
+    A: SeqAccess<'de>,
+    T: Copy,
+{
+    let mut arr = [fill; N];
+    for (i, x) in arr.iter_mut().enumerate() {
+        *x = next(seq)?.ok_or_else(|| de::Error::invalid_length(i, expected))?;
+    }
+    let mut found = N;
+    while seq.next_element::<IgnoredAny>()?.is_some() {
+        found += 1;
+    }
+    if found != N {
+        return Err(de::Error::invalid_length(found, expected));
+    }
+    Ok(arr)
+}
+
+struct RowSeed<T, const C: usize>(PhantomData<T>);
+
+impl<'de, T: Float + Deserialize<'de>, const C: usize> DeserializeSeed<'de> for RowSeed<T, C> {
+    type Value = [T; C];
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple(C, self)
+    }
+}
+
+impl<'de, T: Float + Deserialize<'de>, const C: usize> Visitor<'de> for RowSeed<T, C> {
+    type Value = [T; C];
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a matrix row of {} entries", C)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        read_exact(&mut seq, &self, T::zero(), |seq| seq.next_element())
+    }
+}
+
+struct MatVisitor<T, const R: usize, const C: usize>(PhantomData<T>);
+
+impl<'de, T: Float + Deserialize<'de>, const R: usize, const C: usize> Visitor<'de>
+    for MatVisitor<T, R, C>
+{
+    type Value = Mat<T, R, C>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a {}x{} matrix as {} rows", R, C, R)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let arr = read_exact(&mut seq, &self, [T::zero(); C], |seq| {
+            seq.next_element_seed(RowSeed(PhantomData))
+        })?;
+        Ok(Mat::new(&arr))
+    }
+}
+
+impl<'de, T: Float + Deserialize<'de>, const R: usize, const C: usize> Deserialize<'de>
+    for Mat<T, R, C>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_tuple(R, MatVisitor(PhantomData))
+    }
+}
+
+// Deserializes a field name to its index in the list of names, without
+// allocating the key.
+struct FieldSeed(&'static [&'static str]);
+
+impl<'de> DeserializeSeed<'de> for FieldSeed {
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de> Visitor<'de> for FieldSeed {
+    type Value = usize;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a field name")
+    }
+
+    fn visit_str<E: de::Error>(self, key: &str) -> Result<usize, E> {
+        self.0
+            .iter()
+            .position(|&n| n == key)
+            .ok_or_else(|| de::Error::unknown_field(key, self.0))
+    }
+}
+
+impl<T: Float + Serialize> Serialize for Mat3A<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Mat::from(*self).serialize(serializer)
+    }
+}
+
+impl<'de, T: Float + Deserialize<'de>> Deserialize<'de> for Mat3A<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Mat::<T, 3, 3>::deserialize(deserializer).map(Mat3A::from)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Float + Serialize> Serialize for DMat<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(self.nrows()))?;
+        for i in 0..self.nrows() {
+            seq.serialize_element(self.row(i))?;
+        }
+        seq.end()
+    }
+}
+
+// Appends the entries of one row of a `DMat` to its data.
+#[cfg(feature = "alloc")]
+struct DynRowSeed<'a, T>(&'a mut Vec<T>);
+
+#[cfg(feature = "alloc")]
+impl<'de, T: Float + Deserialize<'de>> DeserializeSeed<'de> for DynRowSeed<'_, T> {
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, T: Float + Deserialize<'de>> Visitor<'de> for DynRowSeed<'_, T> {
+    type Value = usize;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a matrix row")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<usize, A::Error> {
+        let start = self.0.len();
+        while let Some(x) = seq.next_element()? {
+            self.0.push(x);
+        }
+        Ok(self.0.len() - start)
+    }
+}
+
+#[cfg(feature = "alloc")]
+struct DMatVisitor<T>(PhantomData<T>);
+
+#[cfg(feature = "alloc")]
+impl<'de, T: Float + Deserialize<'de>> Visitor<'de> for DMatVisitor<T> {
+    type Value = DMat<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a matrix as rows of equal length")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<DMat<T>, A::Error> {
+        let mut data = Vec::new();
+        let (mut rows, mut cols) = (0, 0);
+        while let Some(len) = seq.next_element_seed(DynRowSeed(&mut data))? {
+            if rows > 0 && len != cols {
+                return Err(de::Error::custom(format_args!(
+                    "row {} has {} entries, expected {}",
+                    rows, len, cols
+                )));
+            }
+            rows += 1;
+            cols = len;
+        }
+        Ok(DMat::from_vec(rows, cols, data))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, T: Float + Deserialize<'de>> Deserialize<'de> for DMat<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(DMatVisitor(PhantomData))
+    }
+}
+
+#[cfg(feature = "alloc")]
+const CSC_FIELDS: &[&str] = &["nrows", "ncols", "col_ptr", "row_idx", "values"];
+
+#[cfg(feature = "alloc")]
+impl<T: Float + Serialize> Serialize for CscMatrix<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut st = serializer.serialize_struct("CscMatrix", CSC_FIELDS.len())?;
+        st.serialize_field("nrows", &self.nrows())?;
+        st.serialize_field("ncols", &self.ncols())?;
+        st.serialize_field("col_ptr", self.col_ptr())?;
+        st.serialize_field("row_idx", self.row_idx())?;
+        st.serialize_field("values", self.values())?;
+        st.end()
+    }
+}
+
+#[cfg(feature = "alloc")]
+struct CscVisitor<T>(PhantomData<T>);
+
+#[cfg(feature = "alloc")]
+impl<T: Float> CscVisitor<T> {
+    fn build<E: de::Error>(
+        nrows: usize,
+        ncols: usize,
+        col_ptr: Vec<usize>,
+        row_idx: Vec<usize>,
+        values: Vec<T>,
+    ) -> Result<CscMatrix<T>, E> {
+        csc::validate(nrows, ncols, &col_ptr, &row_idx, values.len()).map_err(E::custom)?;
+        Ok(CscMatrix::new(nrows, ncols, col_ptr, row_idx, values))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, T: Float + Deserialize<'de>> Visitor<'de> for CscVisitor<T> {
+    type Value = CscMatrix<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a compressed sparse column matrix")
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let (mut nrows, mut ncols) = (None, None);
+        let (mut col_ptr, mut row_idx, mut values) = (None, None, None);
+        while let Some(i) = map.next_key_seed(FieldSeed(CSC_FIELDS))? {
+            let duplicate = match i {
+                0 => nrows.replace(map.next_value()?).is_some(),
+                1 => ncols.replace(map.next_value()?).is_some(),
+                2 => col_ptr.replace(map.next_value()?).is_some(),
+                3 => row_idx.replace(map.next_value()?).is_some(),
+                _ => values.replace(map.next_value()?).is_some(),
+            };
+            if duplicate {
+                return Err(de::Error::duplicate_field(CSC_FIELDS[i]));
+            }
+        }
+        let missing = |i: usize| de::Error::missing_field(CSC_FIELDS[i]);
+        Self::build(
+            nrows.ok_or_else(|| missing(0))?,
+            ncols.ok_or_else(|| missing(1))?,
+            col_ptr.ok_or_else(|| missing(2))?,
+            row_idx.ok_or_else(|| missing(3))?,
+            values.ok_or_else(|| missing(4))?,
+        )
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let missing = |i: usize| de::Error::invalid_length(i, &"5 fields");
+        Self::build(
+            seq.next_element()?.ok_or_else(|| missing(0))?,
+            seq.next_element()?.ok_or_else(|| missing(1))?,
+            seq.next_element()?.ok_or_else(|| missing(2))?,
+            seq.next_element()?.ok_or_else(|| missing(3))?,
+            seq.next_element()?.ok_or_else(|| missing(4))?,
+        )
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, T: Float + Deserialize<'de>> Deserialize<'de> for CscMatrix<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("CscMatrix", CSC_FIELDS, CscVisitor(PhantomData))
+    }
+}
+
+// Conversion between a vector and its components, shared by the sequence and
+// named-field representations.
+pub trait Components<T>: Sized {
+    const NAMES: &'static [&'static str];
+
+    fn component(&self, i: usize) -> T;
+
+    fn from_components(c: &[T]) -> Self;
+}
+
+macro_rules! impl_serde_vec {
+    ($v:ident, $n:literal, $($c:ident),+) => {
+        impl<T: Float> Components<T> for $v<T> {
+            const NAMES: &'static [&'static str] = &[$(stringify!($c)),+];
+
+            fn component(&self, i: usize) -> T {
+                [$(self.$c),+][i]
+            }
+
+            fn from_components(c: &[T]) -> Self {
+                let [$($c),+] = c.try_into().unwrap();
+                Self::new($($c),+)
+            }
+        }
+
+        impl<T: Float + Serialize> Serialize for $v<T> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut tup = serializer.serialize_tuple($n)?;
+                $(tup.serialize_element(&self.$c)?;)+
+                tup.end()
+            }
+        }
+
+        impl<'de, T: Float + Deserialize<'de>> Deserialize<'de> for $v<T> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_tuple($n, VecVisitor::<T, Self, $n>(PhantomData))
+            }
+        }
+    };
+}
+
+impl_serde_vec!(Vec2, 2, x, y);
+impl_serde_vec!(Vec3, 3, x, y, z);
+impl_serde_vec!(Vec3A, 3, x, y, z);
+impl_serde_vec!(Vec4, 4, x, y, z, w);
+
+struct VecVisitor<T, V, const N: usize>(PhantomData<(T, V)>);
+
+impl<'de, T, V, const N: usize> Visitor<'de> for VecVisitor<T, V, N>
+where
+    T: Float + Deserialize<'de>,
+    V: Components<T>,
+{
+    type Value = V;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a vector of {} components", N)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<V, A::Error> {
+        let arr: [T; N] = read_exact(&mut seq, &self, T::zero(), |seq| seq.next_element())?;
+        Ok(V::from_components(&arr))
+    }
+}
+
+pub mod named {
+    use super::*;
+    use serde::de::MapAccess;
+    use serde::ser::SerializeStruct;
+
+    pub fn serialize<T, V, S>(v: &V, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Float + Serialize,
+        V: Components<T>,
+        S: Serializer,
+    {
+        let mut st = serializer.serialize_struct("Vec", V::NAMES.len())?;
+        for (i, name) in V::NAMES.iter().enumerate() {
+            st.serialize_field(name, &v.component(i))?;
+        }
+        st.end()
+    }
+
+    pub fn deserialize<'de, T, V, D>(deserializer: D) -> Result<V, D::Error>
+    where
+        T: Float + Deserialize<'de>,
+        V: Components<T>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("Vec", V::NAMES, NamedVisitor::<T, V>(PhantomData))
+    }
+
+    struct NamedVisitor<T, V>(PhantomData<(T, V)>);
+
+    impl<'de, T, V> Visitor<'de> for NamedVisitor<T, V>
+    where
+        T: Float + Deserialize<'de>,
+        V: Components<T>,
+    {
+        type Value = V;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a vector with fields ")?;
+            for (i, name) in V::NAMES.iter().enumerate() {
+                write!(f, "{}{}", if i > 0 { ", " } else { "" }, name)?;
+            }
+            Ok(())
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<V, A::Error> {
+            let mut c = [None; 4];
+            while let Some(i) = map.next_key_seed(FieldSeed(V::NAMES))? {
+                if c[i].is_some() {
+                    return Err(de::Error::duplicate_field(V::NAMES[i]));
+                }
+                c[i] = Some(map.next_value()?);
+            }
+
+            let mut arr = [T::zero(); 4];
+            for (i, name) in V::NAMES.iter().enumerate() {
+                arr[i] = c[i].ok_or_else(|| de::Error::missing_field(name))?;
+            }
+            Ok(V::from_components(&arr[..V::NAMES.len()]))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<V, A::Error> {
+            let mut arr = [T::zero(); 4];
+            let n = V::NAMES.len();
+            for (i, x) in arr[..n].iter_mut().enumerate() {
+                *x = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+            }
+            Ok(V::from_components(&arr[..n]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mat_json() {
+        let mat = Mat::new(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let json = serde_json::to_string(&mat).unwrap();
+        assert_eq!(json, "[[1.0,2.0,3.0],[4.0,5.0,6.0]]");
+        assert_eq!(serde_json::from_str::<Mat<f64, 2, 3>>(&json).unwrap(), mat);
+    }
+
+    #[test]
+    fn mat_wrong_rows() {
+        let err = serde_json::from_str::<Mat<f64, 2, 2>>("[[1.0,2.0]]").unwrap_err();
+        assert!(err.to_string().contains("expected a 2x2 matrix"), "{}", err);
+
+        let err = serde_json::from_str::<Mat<f64, 1, 2>>("[[1.0,2.0],[3.0,4.0]]").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid length 2, expected a 1x2 matrix"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn mat_wrong_columns() {
+        let err = serde_json::from_str::<Mat<f64, 2, 2>>("[[1.0,2.0],[3.0]]").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("expected a matrix row of 2 entries"),
+            "{}",
+            err
+        );
+
+        let err = serde_json::from_str::<Mat<f64, 1, 2>>("[[1.0,2.0,3.0]]").unwrap_err();
+        assert!(err.to_string().starts_with("invalid length 3"), "{}", err);
+    }
+
+    #[test]
+    fn vec_json() {
+        let vec = Vec3::new(1.0, 2.0, 3.0);
+        let json = serde_json::to_string(&vec).unwrap();
+        assert_eq!(json, "[1.0,2.0,3.0]");
+        assert_eq!(serde_json::from_str::<Vec3<f64>>(&json).unwrap(), vec);
+        assert_eq!(
+            serde_json::from_str::<Vec4<f32>>("[1,2,3,4]").unwrap(),
+            Vec4::new(1.0, 2.0, 3.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn vec_wrong_length() {
+        assert!(serde_json::from_str::<Vec2<f64>>("[1.0]").is_err());
+        assert!(serde_json::from_str::<Vec2<f64>>("[1.0,2.0,3.0]").is_err());
+    }
+
+    #[test]
+    fn mat3a_json() {
+        let mat = Mat::new(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        let padded = Mat3A::from(mat);
+        let json = serde_json::to_string(&padded).unwrap();
+        assert_eq!(json, serde_json::to_string(&mat).unwrap());
+        assert_eq!(serde_json::from_str::<Mat3A<f64>>(&json).unwrap(), padded);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn dmat_json() {
+        let mat = DMat::from_vec(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let json = serde_json::to_string(&mat).unwrap();
+        assert_eq!(json, "[[1.0,2.0,3.0],[4.0,5.0,6.0]]");
+        assert_eq!(serde_json::from_str::<DMat<f64>>(&json).unwrap(), mat);
+        assert_eq!(
+            serde_json::from_str::<DMat<f64>>("[[],[]]").unwrap(),
+            DMat::zero(2, 0)
+        );
+
+        let err = serde_json::from_str::<DMat<f64>>("[[1.0,2.0],[3.0]]").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("row 1 has 1 entries, expected 2"),
+            "{}",
+            err
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn csc_json() {
+        let mat = CscMatrix::from_triplets(3, 2, &[(0, 0, 1.0), (2, 0, 2.0), (1, 1, 3.0)]);
+        let json = serde_json::to_string(&mat).unwrap();
+        assert_eq!(
+            json,
+            r#"{"nrows":3,"ncols":2,"col_ptr":[0,2,3],"row_idx":[0,2,1],"values":[1.0,2.0,3.0]}"#
+        );
+        assert_eq!(serde_json::from_str::<CscMatrix<f64>>(&json).unwrap(), mat);
+
+        for (bad, msg) in [
+            (r#"[0,2,3],"row_idx":[2,0,1]"#, "strictly increasing"),
+            (r#"[0,4,3],"row_idx":[0,2,1]"#, "nondecreasing"),
+            (r#"[0,2],"row_idx":[0,2,1]"#, "ncols + 1 entries"),
+            (r#"[0,2,3],"row_idx":[0,5,1]"#, "out of bounds"),
+        ] {
+            let json = format!(
+                r#"{{"nrows":3,"ncols":2,"col_ptr":{},"values":[1.0,2.0,3.0]}}"#,
+                bad
+            );
+            let err = serde_json::from_str::<CscMatrix<f64>>(&json).unwrap_err();
+            assert!(err.to_string().contains(msg), "{}", err);
+        }
+        let err = serde_json::from_str::<CscMatrix<f64>>(r#"{"nrows":3,"ncols":2}"#).unwrap_err();
+        assert!(
+            err.to_string().contains("missing field `col_ptr`"),
+            "{}",
+            err
+        );
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Pose {
+        #[serde(with = "named")]
+        position: Vec3<f64>,
+        scale: Vec2<f64>,
+    }
+
+    #[test]
+    fn named_fields() {
+        let pose = Pose {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            scale: Vec2::new(0.5, 0.5),
+        };
+        let json = serde_json::to_string(&pose).unwrap();
+        assert_eq!(
+            json,
+            r#"{"position":{"x":1.0,"y":2.0,"z":3.0},"scale":[0.5,0.5]}"#
+        );
+        assert_eq!(serde_json::from_str::<Pose>(&json).unwrap(), pose);
+
+        let err = serde_json::from_str::<Pose>(r#"{"position":{"x":1,"y":2},"scale":[1,1]}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("missing field `z`"), "{}", err);
+    }
+}
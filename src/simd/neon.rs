@@ -0,0 +1,53 @@
+use super::Lanes;
+use core::arch::aarch64::*;
+
+#[derive(Debug, Clone, Copy)]
+pub struct F32x4(float32x4_t);
+
+// SAFETY (for every block below): NEON is enabled for the whole build, which
+// is checked by the `cfg` on this module, and loads and stores go through
+// `[f32; 4]`.
+impl Lanes for F32x4 {
+    fn load(arr: [f32; 4]) -> Self {
+        Self(unsafe { vld1q_f32(arr.as_ptr()) })
+    }
+
+    fn store(self) -> [f32; 4] {
+        let mut arr = [0.0; 4];
+        unsafe { vst1q_f32(arr.as_mut_ptr(), self.0) };
+        arr
+    }
+
+    fn splat(x: f32) -> Self {
+        Self(unsafe { vdupq_n_f32(x) })
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self(unsafe { vaddq_f32(self.0, other.0) })
+    }
+
+    fn sub(self, other: Self) -> Self {
+        Self(unsafe { vsubq_f32(self.0, other.0) })
+    }
+
+    fn mul(self, other: Self) -> Self {
+        Self(unsafe { vmulq_f32(self.0, other.0) })
+    }
+
+    fn div(self, other: Self) -> Self {
+        Self(unsafe { vdivq_f32(self.0, other.0) })
+    }
+
+    fn sqrt(self) -> Self {
+        Self(unsafe { vsqrtq_f32(self.0) })
+    }
+
+    // NEON has no general two-register shuffle with an immediate, so the
+    // lanes are picked out individually; `MASK` is a constant, so this
+    // compiles down to lane moves.
+    fn shuffle<const MASK: i32>(self, other: Self) -> Self {
+        let (a, b) = (self.store(), other.store());
+        let lane = |i: i32| ((MASK >> (2 * i)) & 3) as usize;
+        Self::load([a[lane(0)], a[lane(1)], b[lane(2)], b[lane(3)]])
+    }
+}
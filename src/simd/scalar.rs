@@ -0,0 +1,57 @@
+use super::Lanes;
+
+// Portable fallback, also used by the tests as the reference for the native
+// backends.
+#[derive(Debug, Clone, Copy)]
+pub struct F32x4([f32; 4]);
+
+impl F32x4 {
+    fn zip(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        Self([0, 1, 2, 3].map(|i| f(self.0[i], other.0[i])))
+    }
+}
+
+impl Lanes for F32x4 {
+    fn load(arr: [f32; 4]) -> Self {
+        Self(arr)
+    }
+
+    fn store(self) -> [f32; 4] {
+        self.0
+    }
+
+    fn splat(x: f32) -> Self {
+        Self([x; 4])
+    }
+
+    fn add(self, other: Self) -> Self {
+        self.zip(other, |a, b| a + b)
+    }
+
+    fn sub(self, other: Self) -> Self {
+        self.zip(other, |a, b| a - b)
+    }
+
+    fn mul(self, other: Self) -> Self {
+        self.zip(other, |a, b| a * b)
+    }
+
+    fn div(self, other: Self) -> Self {
+        self.zip(other, |a, b| a / b)
+    }
+
+    fn sqrt(self) -> Self {
+        // `f32::sqrt` needs std; `num::Float` goes through libm without it
+        Self(self.0.map(num::Float::sqrt))
+    }
+
+    fn shuffle<const MASK: i32>(self, other: Self) -> Self {
+        let lane = |i: i32| ((MASK >> (2 * i)) & 3) as usize;
+        Self([
+            self.0[lane(0)],
+            self.0[lane(1)],
+            other.0[lane(2)],
+            other.0[lane(3)],
+        ])
+    }
+}
@@ -0,0 +1,48 @@
+use super::Lanes;
+use core::arch::x86_64::*;
+
+#[derive(Debug, Clone, Copy)]
+pub struct F32x4(__m128);
+
+// SAFETY (for every block below): SSE2 is enabled for the whole build, which
+// is checked by the `cfg` on this module, and loads and stores go through
+// `[f32; 4]` with the unaligned variants.
+impl Lanes for F32x4 {
+    fn load(arr: [f32; 4]) -> Self {
+        Self(unsafe { _mm_loadu_ps(arr.as_ptr()) })
+    }
+
+    fn store(self) -> [f32; 4] {
+        let mut arr = [0.0; 4];
+        unsafe { _mm_storeu_ps(arr.as_mut_ptr(), self.0) };
+        arr
+    }
+
+    fn splat(x: f32) -> Self {
+        Self(unsafe { _mm_set1_ps(x) })
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self(unsafe { _mm_add_ps(self.0, other.0) })
+    }
+
+    fn sub(self, other: Self) -> Self {
+        Self(unsafe { _mm_sub_ps(self.0, other.0) })
+    }
+
+    fn mul(self, other: Self) -> Self {
+        Self(unsafe { _mm_mul_ps(self.0, other.0) })
+    }
+
+    fn div(self, other: Self) -> Self {
+        Self(unsafe { _mm_div_ps(self.0, other.0) })
+    }
+
+    fn sqrt(self) -> Self {
+        Self(unsafe { _mm_sqrt_ps(self.0) })
+    }
+
+    fn shuffle<const MASK: i32>(self, other: Self) -> Self {
+        Self(unsafe { _mm_shuffle_ps::<MASK>(self.0, other.0) })
+    }
+}
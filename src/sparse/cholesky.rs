@@ -0,0 +1,361 @@
+use super::{invert_permutation, CscMatrix};
+use crate::error::LinalgError;
+use alloc::vec;
+use alloc::vec::Vec;
+use num::Float;
+
+const NONE: usize = usize::MAX;
+
+// Structure of the Cholesky factor L of P A P^T. The analysis only depends on
+// the nonzero pattern of `a`, so it can be kept and reused for any matrix with
+// the same (or a smaller) pattern.
+//
+// The pattern of row k of L is the set of nodes reached by walking up the
+// elimination tree from the nonzeros of row k of A, so the analysis runs in
+// time and memory proportional to the number of nonzeros in L.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolicCholesky {
+    perm: Vec<usize>,
+    pinv: Vec<usize>,
+    parent: Vec<usize>,
+    // pattern of L in compressed columns, diagonal first
+    col_ptr: Vec<usize>,
+    row_idx: Vec<usize>,
+}
+
+impl SymbolicCholesky {
+    // Both triangles of `a` contribute to the pattern. Panics if `a` is not
+    // square or `perm` is not a permutation of its rows.
+    pub fn analyze<T: Float>(a: &CscMatrix<T>, perm: Vec<usize>) -> Self {
+        let n = a.ncols();
+        assert_eq!(a.nrows(), n, "matrix must be square");
+        assert_eq!(perm.len(), n, "permutation has the wrong length");
+        let pinv = invert_permutation(&perm);
+
+        // strict upper triangle of P (A + A^T) P^T, by columns
+        let mut upper = vec![Vec::new(); n];
+        for c in 0..n {
+            for &r in a.col(c).0 {
+                let (i, j) = (pinv[r], pinv[c]);
+                if i != j {
+                    upper[i.max(j)].push(i.min(j));
+                }
+            }
+        }
+
+        let mut parent = vec![NONE; n];
+        let mut ancestor = vec![NONE; n];
+        for (k, rows) in upper.iter().enumerate() {
+            for &i in rows {
+                // follow the path from i to the root of its subtree,
+                // compressing it onto k
+                let mut i = i;
+                while i != NONE && i < k {
+                    let next = ancestor[i];
+                    ancestor[i] = k;
+                    if next == NONE {
+                        parent[i] = k;
+                    }
+                    i = next;
+                }
+            }
+        }
+
+        // count the entries of each column, then fill them in; rows are
+        // visited in increasing order, so every column comes out sorted
+        let mut mark = vec![NONE; n];
+        let mut col_ptr = vec![0; n + 1];
+        for (k, rows) in upper.iter().enumerate() {
+            row_reach(rows, k, &parent, &mut mark, |j| col_ptr[j + 1] += 1);
+        }
+        for j in 0..n {
+            col_ptr[j + 1] += col_ptr[j] + 1;
+        }
+        let mut row_idx = vec![0; col_ptr[n]];
+        let mut next: Vec<usize> = col_ptr[..n].to_vec();
+        for j in 0..n {
+            row_idx[next[j]] = j;
+            next[j] += 1;
+        }
+        mark.fill(NONE);
+        for (k, rows) in upper.iter().enumerate() {
+            row_reach(rows, k, &parent, &mut mark, |j| {
+                row_idx[next[j]] = k;
+                next[j] += 1;
+            });
+        }
+
+        Self {
+            perm,
+            pinv,
+            parent,
+            col_ptr,
+            row_idx,
+        }
+    }
+
+    pub fn perm(&self) -> &[usize] {
+        &self.perm
+    }
+
+    // The elimination tree, with `usize::MAX` for roots.
+    pub fn parent(&self) -> &[usize] {
+        &self.parent
+    }
+
+    // Number of structural nonzeros in L, including the diagonal.
+    pub fn nnz(&self) -> usize {
+        self.row_idx.len()
+    }
+
+    // Panics on the `NotPositiveDefinite` from `try_factor`.
+    pub fn factor<T: Float>(&self, a: &CscMatrix<T>) -> Cholesky<T> {
+        self.try_factor(a).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Left-looking numeric factorization. Only the lower triangle of `a` is
+    // read. Fails with `NotPositiveDefinite` on a pivot that is not positive,
+    // and panics if `a` has the wrong size or a nonzero outside the analyzed
+    // pattern.
+    pub fn try_factor<T: Float>(&self, a: &CscMatrix<T>) -> Result<Cholesky<T>, LinalgError> {
+        let n = self.perm.len();
+        assert!(
+            a.nrows() == n && a.ncols() == n,
+            "matrix does not match the analyzed size"
+        );
+        let (a_ptr, a_idx, a_val) = a.permuted_lower(&self.pinv);
+        let (col_ptr, row_idx) = (&self.col_ptr, &self.row_idx);
+
+        let mut values = vec![T::zero(); row_idx.len()];
+        let mut x = vec![T::zero(); n];
+        let mut mark = vec![NONE; n];
+        // columns k < j are linked into the list of the next row r >= j with
+        // L(r, k) != 0, and pos[k] is the position of that row
+        let mut head = vec![NONE; n];
+        let mut link = vec![NONE; n];
+        let mut pos = vec![0; n];
+
+        for j in 0..n {
+            let column = col_ptr[j]..col_ptr[j + 1];
+            for &i in &row_idx[column.clone()] {
+                mark[i] = j;
+            }
+            for p in a_ptr[j]..a_ptr[j + 1] {
+                let i = a_idx[p];
+                assert!(
+                    mark[i] == j,
+                    "matrix has a nonzero outside the analyzed pattern"
+                );
+                x[i] = x[i] + a_val[p];
+            }
+
+            let mut k = head[j];
+            while k != NONE {
+                let next = link[k];
+                let p = pos[k];
+                let ljk = values[p];
+                for q in p..col_ptr[k + 1] {
+                    x[row_idx[q]] = x[row_idx[q]] - values[q] * ljk;
+                }
+                pos[k] = p + 1;
+                if pos[k] < col_ptr[k + 1] {
+                    let r = row_idx[pos[k]];
+                    link[k] = head[r];
+                    head[r] = k;
+                }
+                k = next;
+            }
+
+            let d = x[j];
+            if d <= T::zero() || d.is_nan() {
+                return Err(LinalgError::NotPositiveDefinite);
+            }
+            let ljj = d.sqrt();
+            for p in column {
+                let i = row_idx[p];
+                values[p] = if i == j { ljj } else { x[i] / ljj };
+                x[i] = T::zero();
+            }
+            pos[j] = col_ptr[j] + 1;
+            if pos[j] < col_ptr[j + 1] {
+                let r = row_idx[pos[j]];
+                link[j] = head[r];
+                head[r] = j;
+            }
+        }
+
+        Ok(Cholesky {
+            perm: self.perm.clone(),
+            l: CscMatrix::new(n, n, col_ptr.clone(), row_idx.clone(), values),
+        })
+    }
+}
+
+// Calls `visit` with every j < k in the pattern of row k of L, given the
+// entries i < k of column k of the permuted matrix.
+fn row_reach(
+    rows: &[usize],
+    k: usize,
+    parent: &[usize],
+    mark: &mut [usize],
+    mut visit: impl FnMut(usize),
+) {
+    mark[k] = k;
+    for &i in rows {
+        let mut i = i;
+        while mark[i] != k {
+            visit(i);
+            mark[i] = k;
+            i = parent[i];
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cholesky<T: Float> {
+    perm: Vec<usize>,
+    l: CscMatrix<T>,
+}
+
+impl<T: Float> Cholesky<T> {
+    // The factor of the permuted matrix, P A P^T = L L^T.
+    pub fn l(&self) -> &CscMatrix<T> {
+        &self.l
+    }
+
+    // Solves A x = b. Panics if b has the wrong length.
+    pub fn solve(&self, b: &[T]) -> Vec<T> {
+        let n = self.perm.len();
+        assert_eq!(b.len(), n, "right-hand side has the wrong length");
+        let mut y: Vec<T> = self.perm.iter().map(|&i| b[i]).collect();
+        for j in 0..n {
+            let (rows, values) = self.l.col(j);
+            y[j] = y[j] / values[0];
+            for (&i, &v) in rows.iter().zip(values).skip(1) {
+                y[i] = y[i] - v * y[j];
+            }
+        }
+        for j in (0..n).rev() {
+            let (rows, values) = self.l.col(j);
+            let mut sum = y[j];
+            for (&i, &v) in rows.iter().zip(values).skip(1) {
+                sum = sum - v * y[i];
+            }
+            y[j] = sum / values[0];
+        }
+        let mut x = vec![T::zero(); n];
+        for (k, &i) in self.perm.iter().enumerate() {
+            x[i] = y[k];
+        }
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::identity_permutation;
+    use super::super::ordering::{approximate_minimum_degree, reverse_cuthill_mckee};
+    use super::super::test_util::{dense_solve, laplacian, random_sparse, residual_norm, Lcg};
+    use super::*;
+    use crate::assert_abs_diff_eq;
+    use crate::matrix::Mat;
+
+    #[test]
+    fn factor_reconstructs() {
+        let a = random_sparse::<6>(&mut Lcg(1), true);
+        let chol = SymbolicCholesky::analyze(&CscMatrix::from_dense(&a), identity_permutation(6))
+            .factor(&CscMatrix::from_dense(&a));
+        let l = chol.l().to_dense::<6, 6>();
+        assert_abs_diff_eq!(l * l.transpose(), a, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn solve_matches_dense() {
+        let mut rng = Lcg(7);
+        for _ in 0..20 {
+            let a = random_sparse::<8>(&mut rng, true);
+            let mut b = Mat::zero();
+            for i in 0..8 {
+                b[i][0] = rng.next();
+            }
+            let expected = dense_solve(a, b);
+            let sparse = CscMatrix::from_dense(&a);
+            for perm in [
+                identity_permutation(8),
+                reverse_cuthill_mckee(&sparse),
+                approximate_minimum_degree(&sparse),
+            ] {
+                let chol = SymbolicCholesky::analyze(&sparse, perm).factor(&sparse);
+                let x = Mat::from_slice(&chol.solve(b.as_slice()));
+                assert_abs_diff_eq!(x, expected, epsilon = 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn reuse_symbolic() {
+        let mut rng = Lcg(3);
+        let a = random_sparse::<7>(&mut rng, true);
+        let sparse = CscMatrix::from_dense(&a);
+        let symbolic = SymbolicCholesky::analyze(&sparse, approximate_minimum_degree(&sparse));
+
+        let mut a2 = a;
+        for i in 0..7 {
+            for j in 0..7 {
+                a2[i][j] = a[i][j] * 0.5;
+            }
+            a2[i][i] = a[i][i] + 1.0;
+        }
+        let b = Mat::fill(1.0);
+        for a in [a, a2] {
+            let x = symbolic
+                .factor(&CscMatrix::from_dense(&a))
+                .solve(b.as_slice());
+            assert_abs_diff_eq!(Mat::from_slice(&x), dense_solve(a, b), epsilon = 1e-10);
+        }
+    }
+
+    #[test]
+    fn reads_lower_triangle_only() {
+        let a = random_sparse::<5>(&mut Lcg(11), true);
+        let mut lower = a;
+        for i in 0..5 {
+            for j in i + 1..5 {
+                lower[i][j] = 0.0;
+            }
+        }
+        let sparse = CscMatrix::from_dense(&a);
+        let b = Mat::fill(1.0);
+        let chol = SymbolicCholesky::analyze(&sparse, reverse_cuthill_mckee(&sparse))
+            .factor(&CscMatrix::from_dense(&lower));
+        let x = Mat::from_slice(&chol.solve(b.as_slice()));
+        assert_abs_diff_eq!(x, dense_solve(a, b), epsilon = 1e-10);
+    }
+
+    #[test]
+    fn large_grid() {
+        let a = laplacian(40);
+        let n = a.ncols();
+        let b: Vec<f64> = (0..n).map(|i| (i % 7) as f64 - 3.0).collect();
+        let chol = SymbolicCholesky::analyze(&a, approximate_minimum_degree(&a)).factor(&a);
+        assert!(residual_norm(&a, &chol.solve(&b), &b) < 1e-10);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside the analyzed pattern")]
+    fn pattern_mismatch() {
+        let a = CscMatrix::from_dense(&Mat::new(&[[4.0, 0.0], [0.0, 4.0]]));
+        let symbolic = SymbolicCholesky::analyze(&a, identity_permutation(2));
+        symbolic.factor(&CscMatrix::from_dense(&Mat::new(&[[4.0, 1.0], [1.0, 4.0]])));
+    }
+
+    #[test]
+    fn not_positive_definite() {
+        let a = CscMatrix::from_dense(&Mat::new(&[[1.0, 2.0], [2.0, 1.0]]));
+        let symbolic = SymbolicCholesky::analyze(&a, identity_permutation(2));
+        assert_eq!(
+            symbolic.try_factor(&a).unwrap_err(),
+            LinalgError::NotPositiveDefinite
+        );
+    }
+}
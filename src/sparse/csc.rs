@@ -0,0 +1,417 @@
+use crate::error::LinalgError;
+use crate::matrix::Mat;
+use alloc::vec;
+use alloc::vec::Vec;
+use num::Float;
+
+// Compressed sparse column storage. The row indices of column j are
+// `row_idx[col_ptr[j]..col_ptr[j + 1]]`, strictly increasing, with the
+// matching entries in `values`. Explicitly stored zeros are allowed and count
+// as part of the pattern.
+//
+// The compressed rows of a matrix are the compressed columns of its transpose,
+// so row-oriented algorithms work on `transpose()` instead of a second type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CscMatrix<T: Float> {
+    nrows: usize,
+    ncols: usize,
+    col_ptr: Vec<usize>,
+    row_idx: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T: Float> CscMatrix<T> {
+    // Panics if the arrays do not describe a valid nrows x ncols matrix:
+    // `col_ptr` must have ncols + 1 nondecreasing entries starting at zero,
+    // and the row indices of each column must be strictly increasing and
+    // below nrows.
+    pub fn new(
+        nrows: usize,
+        ncols: usize,
+        col_ptr: Vec<usize>,
+        row_idx: Vec<usize>,
+        values: Vec<T>,
+    ) -> Self {
+        if let Err(msg) = validate(nrows, ncols, &col_ptr, &row_idx, values.len()) {
+            panic!("{}", msg);
+        }
+        Self {
+            nrows,
+            ncols,
+            col_ptr,
+            row_idx,
+            values,
+        }
+    }
+
+    pub fn zero(nrows: usize, ncols: usize) -> Self {
+        Self {
+            nrows,
+            ncols,
+            col_ptr: vec![0; ncols + 1],
+            row_idx: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        Self {
+            nrows: n,
+            ncols: n,
+            col_ptr: (0..=n).collect(),
+            row_idx: (0..n).collect(),
+            values: vec![T::one(); n],
+        }
+    }
+
+    // Builds the matrix from (row, column, value) entries in any order.
+    // Duplicates are summed, as in the assembly of finite element matrices.
+    // Panics if an index is out of bounds.
+    pub fn from_triplets(nrows: usize, ncols: usize, triplets: &[(usize, usize, T)]) -> Self {
+        let mut count = vec![0; ncols + 1];
+        for &(i, j, _) in triplets {
+            assert!(i < nrows && j < ncols, "triplet index out of bounds");
+            count[j + 1] += 1;
+        }
+        for j in 0..ncols {
+            count[j + 1] += count[j];
+        }
+
+        // bucket by column, then sort each column and merge duplicates
+        let mut entries = vec![(0, T::zero()); triplets.len()];
+        let mut next = count.clone();
+        for &(i, j, v) in triplets {
+            entries[next[j]] = (i, v);
+            next[j] += 1;
+        }
+        let mut col_ptr = Vec::with_capacity(ncols + 1);
+        let mut row_idx = Vec::with_capacity(triplets.len());
+        let mut values = Vec::with_capacity(triplets.len());
+        col_ptr.push(0);
+        for j in 0..ncols {
+            let column = &mut entries[count[j]..count[j + 1]];
+            column.sort_unstable_by_key(|&(i, _)| i);
+            let start = row_idx.len();
+            for &(i, v) in column.iter() {
+                if row_idx.len() > start && row_idx[row_idx.len() - 1] == i {
+                    let last = values.len() - 1;
+                    values[last] = values[last] + v;
+                } else {
+                    row_idx.push(i);
+                    values.push(v);
+                }
+            }
+            col_ptr.push(row_idx.len());
+        }
+        Self {
+            nrows,
+            ncols,
+            col_ptr,
+            row_idx,
+            values,
+        }
+    }
+
+    // The nonzero entries of a dense matrix.
+    pub fn from_dense<const R: usize, const C: usize>(a: &Mat<T, R, C>) -> Self {
+        let mut col_ptr = Vec::with_capacity(C + 1);
+        let (mut row_idx, mut values) = (Vec::new(), Vec::new());
+        col_ptr.push(0);
+        for j in 0..C {
+            for i in 0..R {
+                if a[i][j] != T::zero() {
+                    row_idx.push(i);
+                    values.push(a[i][j]);
+                }
+            }
+            col_ptr.push(row_idx.len());
+        }
+        Self {
+            nrows: R,
+            ncols: C,
+            col_ptr,
+            row_idx,
+            values,
+        }
+    }
+
+    // Panics on the `DimensionMismatch` from `try_to_dense`.
+    pub fn to_dense<const R: usize, const C: usize>(&self) -> Mat<T, R, C> {
+        self.try_to_dense().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_to_dense<const R: usize, const C: usize>(
+        &self,
+    ) -> Result<Mat<T, R, C>, LinalgError> {
+        if (self.nrows, self.ncols) != (R, C) {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (R, C),
+                found: (self.nrows, self.ncols),
+            });
+        }
+        let mut out = Mat::zero();
+        for j in 0..C {
+            for (&i, &v) in self.col(j).0.iter().zip(self.col(j).1) {
+                out[i][j] = v;
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    // Number of stored entries.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn col_ptr(&self) -> &[usize] {
+        &self.col_ptr
+    }
+
+    pub fn row_idx(&self) -> &[usize] {
+        &self.row_idx
+    }
+
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    // The values can be changed in place without touching the pattern, e.g.
+    // to refactor with a reused symbolic analysis.
+    pub fn values_mut(&mut self) -> &mut [T] {
+        &mut self.values
+    }
+
+    // Row indices and values of column j.
+    pub fn col(&self, j: usize) -> (&[usize], &[T]) {
+        let range = self.col_ptr[j]..self.col_ptr[j + 1];
+        (&self.row_idx[range.clone()], &self.values[range])
+    }
+
+    // Entry (i, j), which is zero outside the pattern. Panics if the index is
+    // out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> T {
+        assert!(i < self.nrows && j < self.ncols, "index out of bounds");
+        let (rows, values) = self.col(j);
+        rows.binary_search(&i)
+            .map_or_else(|_| T::zero(), |p| values[p])
+    }
+
+    // The main diagonal, with zeros where it is outside the pattern.
+    pub fn diagonal(&self) -> Vec<T> {
+        (0..self.nrows.min(self.ncols))
+            .map(|i| self.get(i, i))
+            .collect()
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut count = vec![0; self.nrows + 1];
+        for &i in &self.row_idx {
+            count[i + 1] += 1;
+        }
+        for i in 0..self.nrows {
+            count[i + 1] += count[i];
+        }
+        let mut next = count.clone();
+        let mut row_idx = vec![0; self.nnz()];
+        let mut values = vec![T::zero(); self.nnz()];
+        // walking the columns in order keeps the new row indices sorted
+        for j in 0..self.ncols {
+            let (rows, vals) = self.col(j);
+            for (&i, &v) in rows.iter().zip(vals) {
+                row_idx[next[i]] = j;
+                values[next[i]] = v;
+                next[i] += 1;
+            }
+        }
+        Self {
+            nrows: self.ncols,
+            ncols: self.nrows,
+            col_ptr: count,
+            row_idx,
+            values,
+        }
+    }
+
+    // Panics on the `DimensionMismatch` from `try_mul_vec`.
+    pub fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        self.try_mul_vec(x).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // A x, for x with ncols entries.
+    pub fn try_mul_vec(&self, x: &[T]) -> Result<Vec<T>, LinalgError> {
+        if x.len() != self.ncols {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (self.ncols, 1),
+                found: (x.len(), 1),
+            });
+        }
+        let mut y = vec![T::zero(); self.nrows];
+        for (j, &xj) in x.iter().enumerate() {
+            let (rows, values) = self.col(j);
+            for (&i, &v) in rows.iter().zip(values) {
+                y[i] = y[i] + v * xj;
+            }
+        }
+        Ok(y)
+    }
+
+    // The lower triangle of P A P^T, for a square A given by its lower
+    // triangle, with `pinv` the inverse of the permutation. An entry that
+    // lands above the diagonal is mirrored below it. Row indices are not
+    // sorted and may repeat, which the factorizations that scatter the
+    // columns into dense workspaces do not mind.
+    pub(crate) fn permuted_lower(&self, pinv: &[usize]) -> (Vec<usize>, Vec<usize>, Vec<T>) {
+        let n = self.ncols;
+        let mut count = vec![0; n + 1];
+        for c in 0..n {
+            for &r in self.col(c).0 {
+                if r >= c {
+                    count[pinv[r].min(pinv[c]) + 1] += 1;
+                }
+            }
+        }
+        for j in 0..n {
+            count[j + 1] += count[j];
+        }
+        let mut next = count.clone();
+        let mut row_idx = vec![0; count[n]];
+        let mut values = vec![T::zero(); count[n]];
+        for c in 0..n {
+            let (rows, vals) = self.col(c);
+            for (&r, &v) in rows.iter().zip(vals) {
+                if r >= c {
+                    let (i, j) = (pinv[r], pinv[c]);
+                    let col = i.min(j);
+                    row_idx[next[col]] = i.max(j);
+                    values[next[col]] = v;
+                    next[col] += 1;
+                }
+            }
+        }
+        (count, row_idx, values)
+    }
+}
+
+// The checks behind `new`, also used when deserializing.
+pub(crate) fn validate(
+    nrows: usize,
+    ncols: usize,
+    col_ptr: &[usize],
+    row_idx: &[usize],
+    nvalues: usize,
+) -> Result<(), &'static str> {
+    if col_ptr.len() != ncols + 1 {
+        return Err("col_ptr must have ncols + 1 entries");
+    }
+    if col_ptr[0] != 0 {
+        return Err("col_ptr must start at zero");
+    }
+    if col_ptr[ncols] != row_idx.len() {
+        return Err("col_ptr must end at the number of entries");
+    }
+    if row_idx.len() != nvalues {
+        return Err("row_idx and values must have the same length");
+    }
+    if col_ptr.windows(2).any(|w| w[0] > w[1]) {
+        return Err("col_ptr must be nondecreasing");
+    }
+    for j in 0..ncols {
+        let rows = &row_idx[col_ptr[j]..col_ptr[j + 1]];
+        if rows.windows(2).any(|w| w[0] >= w[1]) {
+            return Err("row indices must be strictly increasing within a column");
+        }
+        if rows.last().is_some_and(|&i| i >= nrows) {
+            return Err("row index out of bounds");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_abs_diff_eq;
+
+    fn example() -> Mat<f64, 3, 4> {
+        Mat::new(&[
+            [1.0, 0.0, 2.0, 0.0],
+            [0.0, 0.0, 3.0, 4.0],
+            [5.0, 0.0, 0.0, 6.0],
+        ])
+    }
+
+    #[test]
+    fn dense_round_trip() {
+        let a = CscMatrix::from_dense(&example());
+        assert_eq!(a.nnz(), 6);
+        assert_eq!(a.col_ptr(), &[0, 2, 2, 4, 6]);
+        assert_eq!(a.row_idx(), &[0, 2, 0, 1, 1, 2]);
+        assert_eq!(a.to_dense::<3, 4>(), example());
+        assert!(a.try_to_dense::<4, 3>().is_err());
+    }
+
+    #[test]
+    fn triplets_sum_duplicates() {
+        let a = CscMatrix::from_triplets(
+            3,
+            4,
+            &[
+                (2, 3, 6.0),
+                (0, 0, 1.0),
+                (1, 2, 1.0),
+                (2, 0, 5.0),
+                (1, 3, 4.0),
+                (0, 2, 2.0),
+                (1, 2, 2.0),
+            ],
+        );
+        assert_eq!(a, CscMatrix::from_dense(&example()));
+    }
+
+    #[test]
+    fn get_and_diagonal() {
+        let a = CscMatrix::from_dense(&example());
+        assert_eq!(a.get(1, 3), 4.0);
+        assert_eq!(a.get(1, 1), 0.0);
+        assert_eq!(a.diagonal(), [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn transpose() {
+        let a = CscMatrix::from_dense(&example());
+        assert_eq!(a.transpose().to_dense::<4, 3>(), example().transpose());
+    }
+
+    #[test]
+    fn mul_vec() {
+        let a = CscMatrix::from_dense(&example());
+        let x = [1.0, 2.0, 3.0, 4.0];
+        let expected = example() * Mat::<f64, 4, 1>::from_slice(&x);
+        assert_abs_diff_eq!(
+            Mat::<f64, 3, 1>::from_slice(&a.mul_vec(&x)),
+            expected,
+            epsilon = 1e-12
+        );
+        assert_eq!(
+            a.try_mul_vec(&[1.0]),
+            Err(LinalgError::DimensionMismatch {
+                expected: (4, 1),
+                found: (1, 1)
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly increasing")]
+    fn new_rejects_unsorted_rows() {
+        CscMatrix::new(3, 1, vec![0, 2], vec![2, 0], vec![1.0, 2.0]);
+    }
+}
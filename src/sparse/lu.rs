@@ -0,0 +1,616 @@
+use super::CscMatrix;
+use crate::error::LinalgError;
+use alloc::vec;
+use alloc::vec::Vec;
+use num::Float;
+
+const NONE: usize = usize::MAX;
+
+// Column ordering, pivoting parameters and factor patterns for the LU
+// factorization P A Q = L U. With row pivoting the pattern of the factors
+// depends on the values, so the analysis factors `a` once, which fixes the
+// row order P together with the patterns of L and U. Factorizations of
+// matrices with the same pattern reuse all three and only redo the
+// arithmetic, as long as every pivot of the stored row order stays within the
+// threshold; otherwise the rows are chosen again for that factorization.
+//
+// Rows are chosen by threshold partial pivoting: the diagonal entry of A Q is
+// kept as the pivot when its magnitude is at least `threshold` times the
+// largest candidate in its column, which preserves the fill-reducing order
+// while still avoiding small pivots. A threshold of one is ordinary partial
+// pivoting, and a threshold of zero keeps the diagonal whenever it is
+// nonzero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolicLu {
+    perm: Vec<usize>,
+    threshold: f64,
+    // initial capacity for the factors when the rows are chosen again
+    capacity: usize,
+    // `None` when `a` was singular at the analysis
+    pattern: Option<Pattern>,
+}
+
+// The row order and the patterns of L and U from the analysis, laid out as in
+// `Lu`.
+#[derive(Debug, Clone, PartialEq)]
+struct Pattern {
+    pinv: Vec<usize>,
+    l_ptr: Vec<usize>,
+    l_idx: Vec<usize>,
+    u_ptr: Vec<usize>,
+    u_idx: Vec<usize>,
+}
+
+impl SymbolicLu {
+    // `perm` orders the columns, and a symmetric ordering of A is a good
+    // choice for matrices with a mostly nonzero diagonal. Panics if `a` is not
+    // square or `perm` is not a permutation of its columns.
+    pub fn analyze<T: Float>(a: &CscMatrix<T>, perm: Vec<usize>) -> Self {
+        let n = a.ncols();
+        assert_eq!(a.nrows(), n, "matrix must be square");
+        assert_eq!(perm.len(), n, "permutation has the wrong length");
+        super::invert_permutation(&perm);
+        let mut symbolic = Self {
+            perm,
+            threshold: 0.1,
+            capacity: 4 * a.nnz() + n,
+            pattern: None,
+        };
+        symbolic.pattern = symbolic.pivot(a).ok().map(|lu| Pattern {
+            l_ptr: lu.l.col_ptr().to_vec(),
+            l_idx: lu.l.row_idx().to_vec(),
+            u_ptr: lu.u.col_ptr().to_vec(),
+            u_idx: lu.u.row_idx().to_vec(),
+            pinv: lu.pinv,
+        });
+        symbolic
+    }
+
+    // Panics unless 0 <= threshold <= 1. The default is 0.1. The stored pivots
+    // are checked against the threshold at every factorization.
+    pub fn threshold(self, threshold: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&threshold),
+            "pivot threshold must be between zero and one"
+        );
+        Self { threshold, ..self }
+    }
+
+    // The column order Q.
+    pub fn perm(&self) -> &[usize] {
+        &self.perm
+    }
+
+    // Panics on the `Singular` from `try_factor`.
+    pub fn factor<T: Float>(&self, a: &CscMatrix<T>) -> Lu<T> {
+        self.try_factor(a).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Reuses the row order and patterns from the analysis when they fit `a`,
+    // and otherwise chooses the rows again. Fails with `Singular` when a
+    // column has no nonzero pivot candidate, and panics if `a` has the wrong
+    // size.
+    pub fn try_factor<T: Float>(&self, a: &CscMatrix<T>) -> Result<Lu<T>, LinalgError> {
+        let n = self.perm.len();
+        assert!(
+            a.nrows() == n && a.ncols() == n,
+            "matrix does not match the analyzed size"
+        );
+        if let Some(lu) = self.pattern.as_ref().and_then(|p| self.refactor(a, p)) {
+            return Ok(lu);
+        }
+        self.pivot(a)
+    }
+
+    // The numeric phase on the stored patterns. Column k of U lists the rows
+    // of the triangular solve for column k in increasing order, which is a
+    // topological order for L, so no search is needed. `None` when A has an
+    // entry outside the patterns or a stored pivot is zero or fails the
+    // threshold.
+    fn refactor<T: Float>(&self, a: &CscMatrix<T>, pattern: &Pattern) -> Option<Lu<T>> {
+        let n = self.perm.len();
+        let threshold = T::from(self.threshold).unwrap();
+        let Pattern {
+            pinv,
+            l_ptr,
+            l_idx,
+            u_ptr,
+            u_idx,
+        } = pattern;
+        let mut l_val = vec![T::zero(); l_idx.len()];
+        let mut u_val = vec![T::zero(); u_idx.len()];
+        let mut x = vec![T::zero(); n];
+        let mut mark = vec![NONE; n];
+
+        for (k, &col) in self.perm.iter().enumerate() {
+            // U has its diagonal last and L its unit diagonal first
+            let (us, ls) = (u_ptr[k]..u_ptr[k + 1], l_ptr[k]..l_ptr[k + 1]);
+            for &i in u_idx[us.clone()].iter().chain(&l_idx[ls.clone()]) {
+                mark[i] = k;
+            }
+            let (a_rows, a_vals) = a.col(col);
+            for (&i, &v) in a_rows.iter().zip(a_vals) {
+                if mark[pinv[i]] != k {
+                    return None;
+                }
+                x[pinv[i]] = v;
+            }
+            for &j in &u_idx[us.start..us.end - 1] {
+                for p in l_ptr[j] + 1..l_ptr[j + 1] {
+                    x[l_idx[p]] = x[l_idx[p]] - l_val[p] * x[j];
+                }
+            }
+
+            let pivot = x[k];
+            let largest = l_idx[ls.start + 1..ls.end]
+                .iter()
+                .fold(pivot.abs(), |m, &i| m.max(x[i].abs()));
+            if pivot == T::zero() || pivot.abs() < threshold * largest {
+                return None;
+            }
+            for p in us {
+                u_val[p] = x[u_idx[p]];
+                x[u_idx[p]] = T::zero();
+            }
+            l_val[ls.start] = T::one();
+            for p in ls.start + 1..ls.end {
+                l_val[p] = x[l_idx[p]] / pivot;
+                x[l_idx[p]] = T::zero();
+            }
+        }
+        Some(Lu {
+            pinv: pinv.clone(),
+            q: self.perm.clone(),
+            l: CscMatrix::new(n, n, l_ptr.clone(), l_idx.clone(), l_val),
+            u: CscMatrix::new(n, n, u_ptr.clone(), u_idx.clone(), u_val),
+        })
+    }
+
+    // Left-looking (Gilbert-Peierls) factorization that chooses the rows:
+    // column k of L and U comes from a sparse triangular solve with the first
+    // k columns of L, whose pattern is found by a depth-first search, so the
+    // work is proportional to the arithmetic.
+    fn pivot<T: Float>(&self, a: &CscMatrix<T>) -> Result<Lu<T>, LinalgError> {
+        let n = self.perm.len();
+        let threshold = T::from(self.threshold).unwrap();
+
+        // L keeps original row indices until the end, with the unit diagonal
+        // first in each column; U has permuted row indices and its diagonal
+        // last
+        let mut l_ptr = Vec::with_capacity(n + 1);
+        let mut l_idx = Vec::with_capacity(self.capacity);
+        let mut l_val = Vec::with_capacity(self.capacity);
+        let mut u_ptr = Vec::with_capacity(n + 1);
+        let mut u_idx = Vec::with_capacity(self.capacity);
+        let mut u_val = Vec::with_capacity(self.capacity);
+
+        let mut pinv = vec![NONE; n];
+        let mut x = vec![T::zero(); n];
+        let mut reach = Reach::new(n);
+
+        for (k, &col) in self.perm.iter().enumerate() {
+            l_ptr.push(l_idx.len());
+            u_ptr.push(u_idx.len());
+
+            // x = L \ A(:, col) on the pattern reached from A(:, col)
+            let (a_rows, a_vals) = a.col(col);
+            let top = reach.run(&l_ptr, &l_idx, a_rows, &pinv);
+            for &i in &reach.stack[top..] {
+                x[i] = T::zero();
+            }
+            for (&i, &v) in a_rows.iter().zip(a_vals) {
+                x[i] = v;
+            }
+            for &j in &reach.stack[top..] {
+                let jj = pinv[j];
+                if jj == NONE {
+                    continue;
+                }
+                for p in l_ptr[jj] + 1..l_end(&l_ptr, &l_idx, jj) {
+                    x[l_idx[p]] = x[l_idx[p]] - l_val[p] * x[j];
+                }
+            }
+
+            // entries in pivotal rows belong to U, the rest are candidates
+            let mut pivot_row = NONE;
+            let mut largest = T::zero();
+            for &i in &reach.stack[top..] {
+                if pinv[i] == NONE {
+                    if x[i].abs() > largest {
+                        largest = x[i].abs();
+                        pivot_row = i;
+                    }
+                } else {
+                    u_idx.push(pinv[i]);
+                    u_val.push(x[i]);
+                }
+            }
+            if pivot_row == NONE {
+                return Err(LinalgError::Singular);
+            }
+            if pinv[col] == NONE && x[col].abs() >= threshold * largest && x[col] != T::zero() {
+                pivot_row = col;
+            }
+            let pivot = x[pivot_row];
+            u_idx.push(k);
+            u_val.push(pivot);
+            pinv[pivot_row] = k;
+            l_idx.push(pivot_row);
+            l_val.push(T::one());
+            for &i in &reach.stack[top..] {
+                if pinv[i] == NONE {
+                    l_idx.push(i);
+                    l_val.push(x[i] / pivot);
+                }
+                x[i] = T::zero();
+            }
+        }
+        l_ptr.push(l_idx.len());
+        u_ptr.push(u_idx.len());
+
+        for i in l_idx.iter_mut() {
+            *i = pinv[*i];
+        }
+        Ok(Lu {
+            pinv,
+            q: self.perm.clone(),
+            l: sorted(n, l_ptr, l_idx, l_val),
+            u: sorted(n, u_ptr, u_idx, u_val),
+        })
+    }
+}
+
+// End of column j of the partially built L, whose last column is still open.
+fn l_end(l_ptr: &[usize], l_idx: &[usize], j: usize) -> usize {
+    l_ptr.get(j + 1).copied().unwrap_or(l_idx.len())
+}
+
+// Sorts the row indices within each column, as `CscMatrix` requires.
+fn sorted<T: Float>(n: usize, ptr: Vec<usize>, idx: Vec<usize>, val: Vec<T>) -> CscMatrix<T> {
+    let mut entries: Vec<(usize, T)> = idx.into_iter().zip(val).collect();
+    for j in 0..n {
+        entries[ptr[j]..ptr[j + 1]].sort_unstable_by_key(|&(i, _)| i);
+    }
+    let (idx, val) = entries.into_iter().unzip();
+    CscMatrix::new(n, n, ptr, idx, val)
+}
+
+// Depth-first search through the graph of L for the rows that become nonzero
+// in the solve with a sparse right-hand side, in topological order.
+struct Reach {
+    // the result fills stack[top..] from the back, and the front holds the
+    // search path
+    stack: Vec<usize>,
+    next: Vec<usize>,
+    marked: Vec<bool>,
+}
+
+impl Reach {
+    fn new(n: usize) -> Self {
+        Self {
+            stack: vec![0; n],
+            next: vec![0; n],
+            marked: vec![false; n],
+        }
+    }
+
+    fn run(&mut self, l_ptr: &[usize], l_idx: &[usize], rows: &[usize], pinv: &[usize]) -> usize {
+        let n = self.stack.len();
+        let mut top = n;
+        for &start in rows {
+            if self.marked[start] {
+                continue;
+            }
+            // path[..=depth] lives in the unused front of the stack
+            let mut depth = 0;
+            self.stack[0] = start;
+            loop {
+                let j = self.stack[depth];
+                let jj = pinv[j];
+                if !self.marked[j] {
+                    self.marked[j] = true;
+                    self.next[depth] = if jj == NONE { 0 } else { l_ptr[jj] + 1 };
+                }
+                let end = if jj == NONE {
+                    0
+                } else {
+                    l_end(l_ptr, l_idx, jj)
+                };
+                let mut descended = false;
+                while self.next[depth] < end {
+                    let i = l_idx[self.next[depth]];
+                    self.next[depth] += 1;
+                    if !self.marked[i] {
+                        depth += 1;
+                        self.stack[depth] = i;
+                        descended = true;
+                        break;
+                    }
+                }
+                if !descended {
+                    top -= 1;
+                    self.stack[top] = j;
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                }
+            }
+        }
+        for &j in &self.stack[top..] {
+            self.marked[j] = false;
+        }
+        top
+    }
+}
+
+// Unit lower factor L and upper factor U with P A Q = L U.
+#[derive(Debug, Clone)]
+pub struct Lu<T: Float> {
+    // row i of A is row pinv[i] of P A
+    pinv: Vec<usize>,
+    q: Vec<usize>,
+    l: CscMatrix<T>,
+    u: CscMatrix<T>,
+}
+
+impl<T: Float> Lu<T> {
+    pub fn l(&self) -> &CscMatrix<T> {
+        &self.l
+    }
+
+    pub fn u(&self) -> &CscMatrix<T> {
+        &self.u
+    }
+
+    // The row order P as `perm[k] = i`, meaning row i of A is row k of P A.
+    pub fn row_perm(&self) -> Vec<usize> {
+        let mut perm = vec![0; self.pinv.len()];
+        for (i, &k) in self.pinv.iter().enumerate() {
+            perm[k] = i;
+        }
+        perm
+    }
+
+    pub fn col_perm(&self) -> &[usize] {
+        &self.q
+    }
+
+    // Number of structural nonzeros in L and U together, counting the
+    // diagonal once.
+    pub fn nnz(&self) -> usize {
+        self.l.nnz() + self.u.nnz() - self.q.len()
+    }
+
+    // Solves A x = b. Panics if b has the wrong length.
+    pub fn solve(&self, b: &[T]) -> Vec<T> {
+        let n = self.q.len();
+        assert_eq!(b.len(), n, "right-hand side has the wrong length");
+        let mut y = vec![T::zero(); n];
+        for (i, &k) in self.pinv.iter().enumerate() {
+            y[k] = b[i];
+        }
+        for j in 0..n {
+            let (rows, values) = self.l.col(j);
+            for (&i, &v) in rows.iter().zip(values).skip(1) {
+                y[i] = y[i] - v * y[j];
+            }
+        }
+        for j in (0..n).rev() {
+            let (rows, values) = self.u.col(j);
+            let last = rows.len() - 1;
+            y[j] = y[j] / values[last];
+            for (&i, &v) in rows.iter().zip(values).take(last) {
+                y[i] = y[i] - v * y[j];
+            }
+        }
+        let mut x = vec![T::zero(); n];
+        for (k, &j) in self.q.iter().enumerate() {
+            x[j] = y[k];
+        }
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::identity_permutation;
+    use super::super::ordering::{approximate_minimum_degree, reverse_cuthill_mckee};
+    use super::super::test_util::{dense_solve, laplacian, random_sparse, residual_norm, Lcg};
+    use super::*;
+    use crate::assert_abs_diff_eq;
+    use crate::matrix::Mat;
+
+    fn check_solve<const N: usize>(lu: &Lu<f64>, a: Mat<f64, N, N>, b: Mat<f64, N, 1>) {
+        let x = Mat::from_slice(&lu.solve(b.as_slice()));
+        assert_abs_diff_eq!(x, dense_solve(a, b), epsilon = 1e-10);
+    }
+
+    #[test]
+    fn solve_matches_dense() {
+        let mut rng = Lcg(5);
+        for _ in 0..20 {
+            let a = random_sparse::<8>(&mut rng, false);
+            let mut b = Mat::zero();
+            for i in 0..8 {
+                b[i][0] = rng.next();
+            }
+            let sparse = CscMatrix::from_dense(&a);
+            for perm in [
+                identity_permutation(8),
+                reverse_cuthill_mckee(&sparse),
+                approximate_minimum_degree(&sparse),
+            ] {
+                check_solve(&SymbolicLu::analyze(&sparse, perm).factor(&sparse), a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn factors_reconstruct() {
+        let mut a = random_sparse::<6>(&mut Lcg(2), false);
+        a[2][2] = 0.0;
+        a[2][4] = 3.0;
+        a[4][2] = 3.0;
+        let sparse = CscMatrix::from_dense(&a);
+        let lu = SymbolicLu::analyze(&sparse, approximate_minimum_degree(&sparse)).factor(&sparse);
+        let (p, q) = (lu.row_perm(), lu.col_perm());
+        let mut paq = Mat::<f64, 6, 6>::zero();
+        for i in 0..6 {
+            for j in 0..6 {
+                paq[i][j] = a[p[i]][q[j]];
+            }
+        }
+        let (l, u) = (lu.l().to_dense::<6, 6>(), lu.u().to_dense::<6, 6>());
+        assert_abs_diff_eq!(l * u, paq, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn zero_diagonal_pivot() {
+        let a = Mat::new(&[[0.0, 1.0], [1.0, 1.0]]);
+        let sparse = CscMatrix::from_dense(&a);
+        let lu = SymbolicLu::analyze(&sparse, identity_permutation(2)).factor(&sparse);
+        check_solve(&lu, a, Mat::new(&[[1.0], [2.0]]));
+    }
+
+    #[test]
+    fn zero_diagonal_random() {
+        let mut rng = Lcg(13);
+        for _ in 0..20 {
+            // a dense permutation-like matrix with an empty diagonal, plus
+            // random off-diagonal entries
+            let mut a = random_sparse::<7>(&mut rng, false);
+            for i in 0..7 {
+                a[i][i] = 0.0;
+                a[i][(i + 3) % 7] = 10.0 + rng.next();
+            }
+            let b = Mat::fill(1.0);
+            let sparse = CscMatrix::from_dense(&a);
+            check_solve(
+                &SymbolicLu::analyze(&sparse, identity_permutation(7)).factor(&sparse),
+                a,
+                b,
+            );
+        }
+    }
+
+    #[test]
+    fn threshold_prefers_diagonal() {
+        let a = Mat::new(&[[1.0, 1.0], [4.0, 1.0]]);
+        let sparse = CscMatrix::from_dense(&a);
+        let symbolic = SymbolicLu::analyze(&sparse, identity_permutation(2));
+        let b = Mat::new(&[[1.0], [2.0]]);
+
+        let lu = symbolic.clone().factor(&sparse);
+        assert_eq!(lu.row_perm(), [0, 1]);
+        check_solve(&lu, a, b);
+
+        let lu = symbolic.threshold(1.0).factor(&sparse);
+        assert_eq!(lu.row_perm(), [1, 0]);
+        check_solve(&lu, a, b);
+    }
+
+    #[test]
+    fn reuse_symbolic() {
+        let mut rng = Lcg(9);
+        let a = random_sparse::<6>(&mut rng, false);
+        let sparse = CscMatrix::from_dense(&a);
+        let symbolic = SymbolicLu::analyze(&sparse, reverse_cuthill_mckee(&sparse));
+
+        let mut negated = sparse.clone();
+        for v in negated.values_mut() {
+            *v = -*v;
+        }
+        let b = Mat::fill(1.0);
+        check_solve(&symbolic.factor(&sparse), a, b);
+        check_solve(&symbolic.factor(&negated), -a, b);
+    }
+
+    #[test]
+    fn refactor_reuses_pattern() {
+        let mut rng = Lcg(21);
+        let a = random_sparse::<8>(&mut rng, false);
+        let sparse = CscMatrix::from_dense(&a);
+        let symbolic = SymbolicLu::analyze(&sparse, approximate_minimum_degree(&sparse));
+        let pattern = symbolic.pattern.as_ref().unwrap();
+
+        let mut scaled = sparse.clone();
+        for v in scaled.values_mut() {
+            *v *= 1.0 + rng.next();
+        }
+        let reused = symbolic.refactor(&scaled, pattern).unwrap();
+        let chosen = symbolic.pivot(&scaled).unwrap();
+        assert_eq!(reused.row_perm(), chosen.row_perm());
+        assert_eq!(reused.l().row_idx(), chosen.l().row_idx());
+        assert_eq!(reused.u().row_idx(), chosen.u().row_idx());
+        check_solve(&reused, scaled.to_dense::<8, 8>(), Mat::fill(1.0));
+    }
+
+    #[test]
+    fn refactor_falls_back() {
+        let a = CscMatrix::from_dense(&Mat::new(&[[4.0, 1.0], [1.0, 1.0]]));
+        let symbolic = SymbolicLu::analyze(&a, identity_permutation(2));
+        let pattern = symbolic.pattern.as_ref().unwrap();
+        let b = Mat::new(&[[1.0], [2.0]]);
+
+        // the stored pivot fails the threshold
+        let small = Mat::new(&[[0.01, 1.0], [1.0, 1.0]]);
+        let sparse = CscMatrix::from_dense(&small);
+        assert!(symbolic.refactor(&sparse, pattern).is_none());
+        let lu = symbolic.factor(&sparse);
+        assert_eq!(lu.row_perm(), [1, 0]);
+        check_solve(&lu, small, b);
+
+        // an entry outside the stored pattern
+        let upper = Mat::new(&[[4.0, 1.0], [0.0, 1.0]]);
+        let sparse = CscMatrix::from_dense(&upper);
+        let symbolic = SymbolicLu::analyze(&sparse, identity_permutation(2));
+        let full = CscMatrix::from_dense(&Mat::new(&[[4.0, 1.0], [1.0, 1.0]]));
+        assert!(symbolic
+            .refactor(&full, symbolic.pattern.as_ref().unwrap())
+            .is_none());
+        check_solve(&symbolic.factor(&full), full.to_dense::<2, 2>(), b);
+
+        // no pattern is stored when the analyzed matrix is singular
+        let singular = CscMatrix::from_dense(&Mat::new(&[[1.0, 2.0], [2.0, 4.0]]));
+        let symbolic = SymbolicLu::analyze(&singular, identity_permutation(2));
+        assert!(symbolic.pattern.is_none());
+        check_solve(&symbolic.factor(&full), full.to_dense::<2, 2>(), b);
+    }
+
+    #[test]
+    fn ordering_reduces_fill() {
+        let mut a = Mat::<f64, 5, 5>::diagonal(5.0);
+        for i in 1..5 {
+            a[0][i] = 1.0;
+            a[i][0] = 2.0;
+        }
+        let a = CscMatrix::from_dense(&a);
+        let natural = SymbolicLu::analyze(&a, identity_permutation(5)).factor(&a);
+        let md = SymbolicLu::analyze(&a, approximate_minimum_degree(&a)).factor(&a);
+        assert_eq!(natural.nnz(), 25);
+        assert_eq!(md.nnz(), 13);
+    }
+
+    #[test]
+    fn large_grid() {
+        let mut a = laplacian(30);
+        // make it unsymmetric
+        for (p, v) in a.values_mut().iter_mut().enumerate() {
+            if *v < 0.0 && p % 3 == 0 {
+                *v = -0.5;
+            }
+        }
+        let n = a.ncols();
+        let b: Vec<f64> = (0..n).map(|i| (i % 5) as f64).collect();
+        let lu = SymbolicLu::analyze(&a, approximate_minimum_degree(&a)).factor(&a);
+        assert!(residual_norm(&a, &lu.solve(&b), &b) < 1e-10);
+    }
+
+    #[test]
+    fn singular() {
+        let a = CscMatrix::from_dense(&Mat::new(&[[1.0, 2.0], [2.0, 4.0]]));
+        let symbolic = SymbolicLu::analyze(&a, identity_permutation(2));
+        assert_eq!(symbolic.try_factor(&a).unwrap_err(), LinalgError::Singular);
+    }
+}
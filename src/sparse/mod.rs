@@ -0,0 +1,149 @@
+pub mod cholesky;
+pub mod csc;
+pub mod lu;
+pub mod ordering;
+
+pub use csc::CscMatrix;
+
+use alloc::vec::Vec;
+use num::Float;
+
+// Sparse matrices in compressed column form, with direct solvers and
+// fill-reducing orderings that work in memory proportional to the number of
+// nonzeros. Permutations are given as `perm[k] = i`, meaning row and column
+// `i` of the original matrix become row and column `k` of the permuted one.
+
+pub fn identity_permutation(n: usize) -> Vec<usize> {
+    (0..n).collect()
+}
+
+// perm[k] = i  <=>  inverse[i] = k. Panics if `perm` is not a permutation.
+pub(crate) fn invert_permutation(perm: &[usize]) -> Vec<usize> {
+    let mut inverse = alloc::vec![usize::MAX; perm.len()];
+    for (k, &i) in perm.iter().enumerate() {
+        assert!(
+            i < perm.len() && inverse[i] == usize::MAX,
+            "not a permutation"
+        );
+        inverse[i] = k;
+    }
+    inverse
+}
+
+// Off-diagonal structure of `a + a^T` as sorted adjacency lists.
+pub(crate) fn adjacency<T: Float>(a: &CscMatrix<T>) -> Vec<Vec<usize>> {
+    assert_eq!(a.nrows(), a.ncols(), "matrix must be square");
+    let mut adj = alloc::vec![Vec::new(); a.ncols()];
+    for j in 0..a.ncols() {
+        for &i in a.col(j).0 {
+            if i != j {
+                adj[i].push(j);
+                adj[j].push(i);
+            }
+        }
+    }
+    for list in &mut adj {
+        list.sort_unstable();
+        list.dedup();
+    }
+    adj
+}
+
+#[cfg(test)]
+mod test_util {
+    use super::*;
+    use crate::matrix::Mat;
+
+    pub struct Lcg(pub u64);
+
+    impl Lcg {
+        pub fn next(&mut self) -> f64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 11) as f64 / (1u64 << 53) as f64
+        }
+    }
+
+    // Random sparse matrix with a dominant diagonal, symmetric if requested.
+    pub fn random_sparse<const N: usize>(rng: &mut Lcg, symmetric: bool) -> Mat<f64, N, N> {
+        let mut a = Mat::zero();
+        for i in 0..N {
+            for j in 0..i {
+                if rng.next() < 0.3 {
+                    a[i][j] = rng.next() * 2.0 - 1.0;
+                    a[j][i] = if symmetric {
+                        a[i][j]
+                    } else {
+                        rng.next() * 2.0 - 1.0
+                    };
+                }
+            }
+        }
+        for i in 0..N {
+            a[i][i] = N as f64 + rng.next();
+        }
+        a
+    }
+
+    // The 5-point Laplacian on an n x n grid, with n^2 unknowns.
+    pub fn laplacian(n: usize) -> CscMatrix<f64> {
+        let mut triplets = Vec::new();
+        for x in 0..n {
+            for y in 0..n {
+                let k = x * n + y;
+                triplets.push((k, k, 4.0));
+                if x > 0 {
+                    triplets.push((k, k - n, -1.0));
+                    triplets.push((k - n, k, -1.0));
+                }
+                if y > 0 {
+                    triplets.push((k, k - 1, -1.0));
+                    triplets.push((k - 1, k, -1.0));
+                }
+            }
+        }
+        CscMatrix::from_triplets(n * n, n * n, &triplets)
+    }
+
+    pub fn residual_norm(a: &CscMatrix<f64>, x: &[f64], b: &[f64]) -> f64 {
+        a.mul_vec(x)
+            .iter()
+            .zip(b)
+            .map(|(ax, b)| (ax - b) * (ax - b))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    // Gaussian elimination with partial pivoting, used as the dense reference.
+    pub fn dense_solve<const N: usize>(a: Mat<f64, N, N>, b: Mat<f64, N, 1>) -> Mat<f64, N, 1> {
+        let mut a = a;
+        let mut b = b;
+        for k in 0..N {
+            let mut p = k;
+            for i in k + 1..N {
+                if a[i][k].abs() > a[p][k].abs() {
+                    p = i;
+                }
+            }
+            a.0.swap(k, p);
+            b.0.swap(k, p);
+            for i in k + 1..N {
+                let f = a[i][k] / a[k][k];
+                for j in k..N {
+                    a[i][j] -= f * a[k][j];
+                }
+                b[i][0] -= f * b[k][0];
+            }
+        }
+        for i in (0..N).rev() {
+            let mut sum = b[i][0];
+            for j in i + 1..N {
+                sum -= a[i][j] * b[j][0];
+            }
+            b[i][0] = sum / a[i][i];
+        }
+        b
+    }
+}
@@ -0,0 +1,346 @@
+use super::{adjacency, CscMatrix};
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use num::Float;
+
+const NONE: usize = usize::MAX;
+
+// Bandwidth-reducing ordering of the pattern of a + a^T. Each connected
+// component is traversed breadth-first from its lowest-degree node, visiting
+// neighbours in order of increasing degree, and the final sequence is
+// reversed.
+pub fn reverse_cuthill_mckee<T: Float>(a: &CscMatrix<T>) -> Vec<usize> {
+    let adj = adjacency(a);
+    let n = adj.len();
+    let degree: Vec<usize> = adj.iter().map(Vec::len).collect();
+    // candidate starting nodes, lowest degree first
+    let mut starts: Vec<usize> = (0..n).collect();
+    starts.sort_unstable_by_key(|&i| (degree[i], i));
+
+    let mut order = Vec::with_capacity(n);
+    let mut visited = vec![false; n];
+    for &start in &starts {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        order.push(start);
+
+        let mut head = order.len() - 1;
+        while head < order.len() {
+            let v = order[head];
+            head += 1;
+            let first = order.len();
+            for &u in &adj[v] {
+                if !visited[u] {
+                    visited[u] = true;
+                    order.push(u);
+                }
+            }
+            order[first..].sort_unstable_by_key(|&u| (degree[u], u));
+        }
+    }
+
+    order.reverse();
+    order
+}
+
+// Approximate minimum degree ordering (Amestoy, Davis and Duff) of the
+// pattern of a + a^T. Eliminated nodes become elements of a quotient graph
+// instead of turning their neighbours into a clique: a variable keeps the
+// elements it belongs to and what is left of its original neighbours, and the
+// fill between the members of an element is implied. The graph therefore
+// never grows beyond the pattern of `a`.
+//
+// Degrees are upper bounds that are cheap to update rather than exact
+// degrees. Variables with the same neighbourhood are merged into
+// supervariables and eliminated together, variables that are only reachable
+// through the newest element are eliminated with it, and elements it covers
+// are absorbed into it.
+pub fn approximate_minimum_degree<T: Float>(a: &CscMatrix<T>) -> Vec<usize> {
+    // the remaining original neighbours of a variable, or the variables of an
+    // element
+    let mut vars = adjacency(a);
+    let n = vars.len();
+    // the elements a variable belongs to
+    let mut elems: Vec<Vec<usize>> = vec![Vec::new(); n];
+    // the nodes merged into a supervariable, and their number; zero once the
+    // variable is merged away or eliminated
+    let mut members: Vec<Vec<usize>> = (0..n).map(|i| vec![i]).collect();
+    let mut weight = vec![1; n];
+    let mut element = vec![false; n];
+    // number of variables in an element, counting supervariables by weight
+    let mut size = vec![0; n];
+    let mut degree: Vec<usize> = vars.iter().map(Vec::len).collect();
+    let mut queue: BTreeSet<(usize, usize)> = (0..n).map(|i| (degree[i], i)).collect();
+    // mark[i] == p while i is a variable of the element p being formed
+    let mut mark = vec![NONE; n];
+    // |L_e \ L_p| for the elements e next to L_p, valid when seen[e] == p
+    let mut outside = vec![0; n];
+    let mut seen = vec![NONE; n];
+    let mut order = Vec::with_capacity(n);
+    let mut eliminated = 0;
+
+    while let Some((_, p)) = queue.pop_first() {
+        // L_p: the variables next to p directly or through its elements,
+        // which are absorbed into p
+        let mut lp = Vec::new();
+        mark[p] = p;
+        let mut add = |i: usize, lp: &mut Vec<usize>| {
+            if weight[i] > 0 && mark[i] != p {
+                mark[i] = p;
+                lp.push(i);
+            }
+        };
+        for &i in &vars[p] {
+            add(i, &mut lp);
+        }
+        for e in core::mem::take(&mut elems[p]) {
+            if element[e] {
+                element[e] = false;
+                for i in core::mem::take(&mut vars[e]) {
+                    add(i, &mut lp);
+                }
+            }
+        }
+        eliminated += weight[p];
+        weight[p] = 0;
+        element[p] = true;
+        order.append(&mut members[p]);
+
+        // p replaces the absorbed elements and the neighbours it covers, and
+        // a variable left with p as its only connection is eliminated with it
+        lp.retain(|&i| {
+            queue.remove(&(degree[i], i));
+            elems[i].retain(|&e| element[e]);
+            elems[i].push(p);
+            vars[i].retain(|&j| weight[j] > 0 && mark[j] != p);
+            if vars[i].is_empty() && elems[i].len() == 1 {
+                order.append(&mut members[i]);
+                eliminated += weight[i];
+                weight[i] = 0;
+                false
+            } else {
+                true
+            }
+        });
+
+        for &i in &lp {
+            for &e in &elems[i] {
+                if e != p {
+                    if seen[e] != p {
+                        seen[e] = p;
+                        outside[e] = size[e];
+                    }
+                    outside[e] -= weight[i];
+                }
+            }
+        }
+
+        // d_i <= min(n - k, d_i + |L_p \ i|, |A_i| + |L_p \ i| + sum |L_e \ L_p|),
+        // and an element inside L_p is absorbed
+        let lp_weight: usize = lp.iter().map(|&i| weight[i]).sum();
+        for &i in &lp {
+            elems[i].retain(|&e| {
+                if e != p && outside[e] == 0 {
+                    element[e] = false;
+                    vars[e] = Vec::new();
+                }
+                element[e]
+            });
+            let external = lp_weight - weight[i];
+            let mut d = external + vars[i].iter().map(|&j| weight[j]).sum::<usize>();
+            for &e in &elems[i] {
+                if e != p {
+                    d += outside[e];
+                }
+            }
+            degree[i] = d.min(degree[i] + external).min(n - eliminated - weight[i]);
+        }
+
+        // variables of L_p with the same elements and neighbours are
+        // indistinguishable and merged
+        let mut keyed: Vec<(usize, usize)> = lp
+            .iter()
+            .map(|&i| {
+                elems[i].sort_unstable();
+                vars[i].sort_unstable();
+                let hash = elems[i]
+                    .iter()
+                    .chain(&vars[i])
+                    .fold(0, |h: usize, &x| h.wrapping_add(x));
+                (hash, i)
+            })
+            .collect();
+        keyed.sort_unstable();
+        for run in keyed.chunk_by(|x, y| x.0 == y.0) {
+            for (k, &(_, i)) in run.iter().enumerate() {
+                if weight[i] == 0 {
+                    continue;
+                }
+                for &(_, j) in &run[k + 1..] {
+                    if weight[j] > 0 && elems[i] == elems[j] && vars[i] == vars[j] {
+                        degree[i] = degree[i].saturating_sub(weight[j]);
+                        weight[i] += weight[j];
+                        weight[j] = 0;
+                        let merged = core::mem::take(&mut members[j]);
+                        members[i].extend(merged);
+                        elems[j] = Vec::new();
+                        vars[j] = Vec::new();
+                    }
+                }
+            }
+        }
+
+        lp.retain(|&i| weight[i] > 0);
+        size[p] = lp.iter().map(|&i| weight[i]).sum();
+        for &i in &lp {
+            queue.insert((degree[i], i));
+        }
+        vars[p] = lp;
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cholesky::SymbolicCholesky;
+    use super::super::test_util::laplacian;
+    use super::super::{identity_permutation, invert_permutation};
+    use super::*;
+    use crate::matrix::Mat;
+
+    // Largest distance from the diagonal of an entry of P A P^T.
+    fn bandwidth(a: &CscMatrix<f64>, perm: &[usize]) -> usize {
+        let pinv = invert_permutation(perm);
+        let mut bw = 0;
+        for j in 0..a.ncols() {
+            for &i in a.col(j).0 {
+                bw = bw.max(pinv[i].abs_diff(pinv[j]));
+            }
+        }
+        bw
+    }
+
+    fn is_permutation(perm: &[usize], n: usize) -> bool {
+        let mut seen = vec![false; n];
+        for &p in perm {
+            if p >= n || seen[p] {
+                return false;
+            }
+            seen[p] = true;
+        }
+        perm.len() == n
+    }
+
+    // A path graph 0-1-...-5 with its nodes shuffled.
+    fn shuffled_path() -> CscMatrix<f64> {
+        let labels = [3, 0, 5, 1, 4, 2];
+        let mut a = Mat::<f64, 6, 6>::diagonal(4.0);
+        for k in 0..5 {
+            a[labels[k]][labels[k + 1]] = -1.0;
+            a[labels[k + 1]][labels[k]] = -1.0;
+        }
+        CscMatrix::from_dense(&a)
+    }
+
+    // Node 0 is connected to every other node.
+    fn arrow() -> CscMatrix<f64> {
+        let mut a = Mat::<f64, 5, 5>::diagonal(5.0);
+        for i in 1..5 {
+            a[0][i] = 1.0;
+            a[i][0] = 1.0;
+        }
+        CscMatrix::from_dense(&a)
+    }
+
+    #[test]
+    fn rcm_is_permutation() {
+        assert!(is_permutation(&reverse_cuthill_mckee(&shuffled_path()), 6));
+        assert!(is_permutation(&reverse_cuthill_mckee(&arrow()), 5));
+    }
+
+    #[test]
+    fn rcm_reduces_bandwidth() {
+        let a = shuffled_path();
+        assert!(bandwidth(&a, &identity_permutation(6)) > 1);
+        assert_eq!(bandwidth(&a, &reverse_cuthill_mckee(&a)), 1);
+    }
+
+    #[test]
+    fn rcm_disconnected() {
+        let a = CscMatrix::from_dense(&Mat::new(&[
+            [1.0, 0.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0, 1.0],
+            [1.0, 0.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0, 1.0],
+        ]));
+        let perm = reverse_cuthill_mckee(&a);
+        assert!(is_permutation(&perm, 4));
+        assert_eq!(bandwidth(&a, &perm), 1);
+    }
+
+    #[test]
+    fn amd_is_permutation() {
+        assert!(is_permutation(
+            &approximate_minimum_degree(&shuffled_path()),
+            6
+        ));
+        assert!(is_permutation(&approximate_minimum_degree(&arrow()), 5));
+    }
+
+    #[test]
+    fn amd_arrow_no_fill() {
+        let a = arrow();
+        let natural = SymbolicCholesky::analyze(&a, identity_permutation(5));
+        let md = SymbolicCholesky::analyze(&a, approximate_minimum_degree(&a));
+        assert_eq!(natural.nnz(), 15);
+        assert_eq!(md.nnz(), 9);
+    }
+
+    // The 7-point Laplacian on an n x n x n grid.
+    fn laplacian_3d(n: usize) -> CscMatrix<f64> {
+        let id = |x: usize, y: usize, z: usize| (x * n + y) * n + z;
+        let mut triplets = Vec::new();
+        for x in 0..n {
+            for y in 0..n {
+                for z in 0..n {
+                    let k = id(x, y, z);
+                    triplets.push((k, k, 6.0));
+                    for (dx, dy, dz) in [(1, 0, 0), (0, 1, 0), (0, 0, 1)] {
+                        if x >= dx && y >= dy && z >= dz {
+                            let j = id(x - dx, y - dy, z - dz);
+                            triplets.push((k, j, -1.0));
+                            triplets.push((j, k, -1.0));
+                        }
+                    }
+                }
+            }
+        }
+        CscMatrix::from_triplets(n * n * n, n * n * n, &triplets)
+    }
+
+    #[test]
+    fn amd_on_3d_grid() {
+        let a = laplacian_3d(10);
+        let amd = approximate_minimum_degree(&a);
+        assert!(is_permutation(&amd, 1000));
+        let fill = |perm| SymbolicCholesky::analyze(&a, perm).nnz();
+        let rcm = fill(reverse_cuthill_mckee(&a));
+        assert!(fill(amd) < rcm * 3 / 4);
+    }
+
+    #[test]
+    fn orderings_on_large_grid() {
+        // 2500 unknowns, far more than a dense `Mat` could hold on the stack
+        let a = laplacian(50);
+        let natural = SymbolicCholesky::analyze(&a, identity_permutation(a.ncols())).nnz();
+        let md = approximate_minimum_degree(&a);
+        let rcm = reverse_cuthill_mckee(&a);
+        assert!(is_permutation(&md, a.ncols()));
+        assert!(is_permutation(&rcm, a.ncols()));
+        assert_eq!(bandwidth(&a, &rcm), 50);
+        assert!(SymbolicCholesky::analyze(&a, md).nnz() < natural / 2);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod vec2;
 pub mod vec3;
+pub mod vec3a;
 pub mod vec4;
@@ -1,15 +1,17 @@
+use super::super::error::LinalgError;
 use super::super::matrix::Mat;
+use core::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 use num::Float;
-use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Default)]
+#[repr(C)]
 pub struct Vec2<T: Float> {
     pub x: T,
     pub y: T,
 }
 
 impl<T: Float> Vec2<T> {
-    pub fn new(x: T, y: T) -> Self {
+    pub const fn new(x: T, y: T) -> Self {
         Self { x, y }
     }
 
@@ -20,7 +22,7 @@ impl<T: Float> Vec2<T> {
         }
     }
 
-    pub fn fill(n: T) -> Self {
+    pub const fn fill(n: T) -> Self {
         Self { x: n, y: n }
     }
 
@@ -31,6 +33,36 @@ impl<T: Float> Vec2<T> {
     pub fn dot(self, other: Self) -> T {
         self.x * other.x + self.y * other.y
     }
+
+    pub const fn as_array(&self) -> &[T; 2] {
+        // SAFETY: `#[repr(C)]` with 2 fields of the same type has the same
+        // layout as `[T; 2]`
+        unsafe { &*(self as *const Self as *const [T; 2]) }
+    }
+
+    pub fn as_mut_array(&mut self) -> &mut [T; 2] {
+        // SAFETY: see `as_array`
+        unsafe { &mut *(self as *mut Self as *mut [T; 2]) }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self.as_array()
+    }
+
+    // Panics on the `DimensionMismatch` from `try_from_slice`.
+    pub fn from_slice(slice: &[T]) -> Self {
+        Self::try_from_slice(slice).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_from_slice(slice: &[T]) -> Result<Self, LinalgError> {
+        if slice.len() != 2 {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (2, 1),
+                found: (slice.len(), 1),
+            });
+        }
+        Ok(Self::new(slice[0], slice[1]))
+    }
 }
 
 impl<T: Float> Add<Vec2<T>> for Vec2<T> {
@@ -139,4 +171,23 @@ mod tests {
             Mat::new(&[[4.0, 3.0, 2.0, 1.0], [8.0, 6.0, 4.0, 2.0],])
         )
     }
+
+    #[test]
+    fn as_slice() {
+        let mut vec = Vec2::new(1.0, 2.0);
+        assert_eq!(vec.as_slice(), &[1.0, 2.0]);
+        vec.as_mut_array()[0] = 5.0;
+        assert_eq!(vec.x, 5.0);
+    }
+
+    #[test]
+    fn from_slice() {
+        assert_eq!(Vec2::from_slice(&[1.0, 2.0]), Vec2::new(1.0, 2.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_slice_wrong_length() {
+        Vec2::<f32>::from_slice(&[1.0]);
+    }
 }
@@ -1,8 +1,10 @@
+use super::super::error::LinalgError;
 use super::super::matrix::Mat;
+use core::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 use num::Float;
-use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Default)]
+#[repr(C)]
 pub struct Vec3<T: Float> {
     pub x: T,
     pub y: T,
@@ -10,7 +12,7 @@ pub struct Vec3<T: Float> {
 }
 
 impl<T: Float> Vec3<T> {
-    pub fn new(x: T, y: T, z: T) -> Self {
+    pub const fn new(x: T, y: T, z: T) -> Self {
         Self { x, y, z }
     }
 
@@ -22,7 +24,7 @@ impl<T: Float> Vec3<T> {
         }
     }
 
-    pub fn fill(n: T) -> Self {
+    pub const fn fill(n: T) -> Self {
         Self { x: n, y: n, z: n }
     }
 
@@ -41,6 +43,36 @@ impl<T: Float> Vec3<T> {
             z: self.x * other.y - other.x * self.y,
         }
     }
+
+    pub const fn as_array(&self) -> &[T; 3] {
+        // SAFETY: `#[repr(C)]` with 3 fields of the same type has the same
+        // layout as `[T; 3]`
+        unsafe { &*(self as *const Self as *const [T; 3]) }
+    }
+
+    pub fn as_mut_array(&mut self) -> &mut [T; 3] {
+        // SAFETY: see `as_array`
+        unsafe { &mut *(self as *mut Self as *mut [T; 3]) }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self.as_array()
+    }
+
+    // Panics on the `DimensionMismatch` from `try_from_slice`.
+    pub fn from_slice(slice: &[T]) -> Self {
+        Self::try_from_slice(slice).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_from_slice(slice: &[T]) -> Result<Self, LinalgError> {
+        if slice.len() != 3 {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (3, 1),
+                found: (slice.len(), 1),
+            });
+        }
+        Ok(Self::new(slice[0], slice[1], slice[2]))
+    }
 }
 
 impl<T: Float> Add<Vec3<T>> for Vec3<T> {
@@ -187,4 +219,23 @@ mod tests {
             ])
         )
     }
+
+    #[test]
+    fn as_slice() {
+        let mut vec = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!(vec.as_slice(), &[1.0, 2.0, 3.0]);
+        vec.as_mut_array()[0] = 5.0;
+        assert_eq!(vec.x, 5.0);
+    }
+
+    #[test]
+    fn from_slice() {
+        assert_eq!(Vec3::from_slice(&[1.0, 2.0, 3.0]), Vec3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_slice_wrong_length() {
+        Vec3::<f32>::from_slice(&[1.0]);
+    }
 }
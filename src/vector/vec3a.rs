@@ -0,0 +1,192 @@
+use super::super::error::LinalgError;
+use super::vec3::Vec3;
+use core::fmt;
+use core::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use num::Float;
+
+// A `Vec3` padded to four components and aligned to 16 bytes, matching the
+// layout of a `vec3` in std140/std430 uniform and storage buffers. The
+// padding is an explicit zeroed field rather than implicit padding bytes, so
+// the type can be cast to bytes.
+#[derive(Clone, Copy, Default)]
+#[repr(C, align(16))]
+pub struct Vec3A<T: Float> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pad: T,
+}
+
+impl<T: Float> Vec3A<T> {
+    pub fn new(x: T, y: T, z: T) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            pad: T::zero(),
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::fill(T::zero())
+    }
+
+    pub fn fill(n: T) -> Self {
+        Self::new(n, n, n)
+    }
+
+    pub fn mag(self) -> T {
+        T::sqrt(self.x * self.x + self.y * self.y + self.z * self.z)
+    }
+
+    pub fn dot(self, other: Self) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Self) -> Self {
+        Self::new(
+            self.y * other.z - other.y * self.z,
+            other.x * self.z - self.x * other.z,
+            self.x * other.y - other.x * self.y,
+        )
+    }
+
+    pub fn as_array(&self) -> &[T; 3] {
+        // SAFETY: `#[repr(C)]` starting with 3 fields of the same type
+        unsafe { &*(self as *const Self as *const [T; 3]) }
+    }
+
+    pub fn as_mut_array(&mut self) -> &mut [T; 3] {
+        // SAFETY: see `as_array`
+        unsafe { &mut *(self as *mut Self as *mut [T; 3]) }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self.as_array()
+    }
+
+    // Panics on the `DimensionMismatch` from `try_from_slice`.
+    pub fn from_slice(slice: &[T]) -> Self {
+        Self::try_from_slice(slice).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_from_slice(slice: &[T]) -> Result<Self, LinalgError> {
+        if slice.len() != 3 {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (3, 1),
+                found: (slice.len(), 1),
+            });
+        }
+        Ok(Self::new(slice[0], slice[1], slice[2]))
+    }
+}
+
+// The padding is not part of the value, so it is skipped when comparing and
+// printing.
+impl<T: Float> PartialEq for Vec3A<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.y == other.y && self.z == other.z
+    }
+}
+
+impl<T: Float + fmt::Debug> fmt::Debug for Vec3A<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Vec3A")
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .field("z", &self.z)
+            .finish()
+    }
+}
+
+impl<T: Float> From<Vec3<T>> for Vec3A<T> {
+    fn from(vec: Vec3<T>) -> Self {
+        Self::new(vec.x, vec.y, vec.z)
+    }
+}
+
+impl<T: Float> From<Vec3A<T>> for Vec3<T> {
+    fn from(vec: Vec3A<T>) -> Self {
+        Self::new(vec.x, vec.y, vec.z)
+    }
+}
+
+impl<T: Float> Add<Vec3A<T>> for Vec3A<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Vec3A<T>) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl<T: Float> Sub<Vec3A<T>> for Vec3A<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Vec3A<T>) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl<T: Float> Neg for Vec3A<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl<T: Float> AddAssign for Vec3A<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: Float> SubAssign for Vec3A<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{align_of, size_of};
+
+    #[test]
+    fn layout() {
+        assert_eq!(size_of::<Vec3A<f32>>(), 16);
+        assert_eq!(align_of::<Vec3A<f32>>(), 16);
+        assert_eq!(size_of::<[Vec3A<f32>; 2]>(), 32);
+        assert_eq!(size_of::<Vec3A<f64>>(), 32);
+    }
+
+    #[test]
+    fn new() {
+        let vec = Vec3A::new(1.0, 2.0, 3.0);
+        assert_eq!(vec.as_slice(), &[1.0, 2.0, 3.0]);
+        assert_eq!(Vec3A::from_slice(&[1.0, 2.0, 3.0]), vec);
+    }
+
+    #[test]
+    fn ops() {
+        let vec_a = Vec3A::new(5.0, 5.0, 5.0);
+        let vec_b = Vec3A::new(1.0, 2.0, 3.0);
+        assert_eq!(vec_a + vec_b, Vec3A::new(6.0, 7.0, 8.0));
+        assert_eq!(vec_a - vec_b, Vec3A::new(4.0, 3.0, 2.0));
+        assert_eq!(-vec_b, Vec3A::new(-1.0, -2.0, -3.0));
+        assert_eq!(vec_a.dot(vec_b), 30.0);
+    }
+
+    #[test]
+    fn cross() {
+        let vec_a = Vec3A::new(3.0, -3.0, 1.0);
+        let vec_b = Vec3A::new(4.0, 9.0, 2.0);
+        assert_eq!(vec_a.cross(vec_b), Vec3A::new(-15.0, -2.0, 39.0));
+    }
+
+    #[test]
+    fn convert() {
+        let vec = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!(Vec3::from(Vec3A::from(vec)), vec);
+    }
+}
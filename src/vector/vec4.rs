@@ -1,8 +1,10 @@
+use super::super::error::LinalgError;
 use super::super::matrix::Mat;
+use core::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 use num::Float;
-use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Default)]
+#[repr(C)]
 pub struct Vec4<T: Float> {
     pub x: T,
     pub y: T,
@@ -11,7 +13,7 @@ pub struct Vec4<T: Float> {
 }
 
 impl<T: Float> Vec4<T> {
-    pub fn new(x: T, y: T, z: T, w: T) -> Self {
+    pub const fn new(x: T, y: T, z: T, w: T) -> Self {
         Self { x, y, z, w }
     }
 
@@ -24,7 +26,7 @@ impl<T: Float> Vec4<T> {
         }
     }
 
-    pub fn fill(n: T) -> Self {
+    pub const fn fill(n: T) -> Self {
         Self {
             x: n,
             y: n,
@@ -40,6 +42,36 @@ impl<T: Float> Vec4<T> {
     pub fn dot(self, other: Self) -> T {
         self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
     }
+
+    pub const fn as_array(&self) -> &[T; 4] {
+        // SAFETY: `#[repr(C)]` with 4 fields of the same type has the same
+        // layout as `[T; 4]`
+        unsafe { &*(self as *const Self as *const [T; 4]) }
+    }
+
+    pub fn as_mut_array(&mut self) -> &mut [T; 4] {
+        // SAFETY: see `as_array`
+        unsafe { &mut *(self as *mut Self as *mut [T; 4]) }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self.as_array()
+    }
+
+    // Panics on the `DimensionMismatch` from `try_from_slice`.
+    pub fn from_slice(slice: &[T]) -> Self {
+        Self::try_from_slice(slice).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_from_slice(slice: &[T]) -> Result<Self, LinalgError> {
+        if slice.len() != 4 {
+            return Err(LinalgError::DimensionMismatch {
+                expected: (4, 1),
+                found: (slice.len(), 1),
+            });
+        }
+        Ok(Self::new(slice[0], slice[1], slice[2], slice[3]))
+    }
 }
 
 impl<T: Float> Add<Vec4<T>> for Vec4<T> {
@@ -165,4 +197,26 @@ mod tests {
             ])
         )
     }
+
+    #[test]
+    fn as_slice() {
+        let mut vec = Vec4::new(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(vec.as_slice(), &[1.0, 2.0, 3.0, 4.0]);
+        vec.as_mut_array()[0] = 5.0;
+        assert_eq!(vec.x, 5.0);
+    }
+
+    #[test]
+    fn from_slice() {
+        assert_eq!(
+            Vec4::from_slice(&[1.0, 2.0, 3.0, 4.0]),
+            Vec4::new(1.0, 2.0, 3.0, 4.0)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_slice_wrong_length() {
+        Vec4::<f32>::from_slice(&[1.0]);
+    }
 }